@@ -0,0 +1,202 @@
+//! Times the parser on flat, moderately nested and deeply nested
+//! inputs, `validate` against it on some of them, and
+//! `validate_quick` against `validate` on lines that are mostly
+//! not tson at all. Run it
+//! with `cargo bench --bench parse`, optionally with the name of
+//! a case to run only that one.
+//!
+//! There is no benchmarking framework to lean on, so each case
+//! is parsed until a second has passed and the fastest run is
+//! reported, which is the one least disturbed by everything else
+//! running at the time.
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use tson::{
+    parse_value, parse_value_with, validate, validate_quick,
+    validate_with, ParserOptions,
+};
+
+fn flat_list() -> String {
+    let numbers: Vec<String> =
+        (0..100_000).map(|number| number.to_string()).collect();
+    format!("[{}]", numbers.join(", "))
+}
+
+fn flat_object() -> String {
+    let entries: Vec<String> = (0..20_000)
+        .map(|idx| format!("\"key{idx}\": \"value {idx}\""))
+        .collect();
+    format!("{{{}}}", entries.join(", "))
+}
+
+/// Records much like a configuration file or a log of `{:?}`
+/// output holds, a handful of levels deep.
+fn records() -> String {
+    let records: Vec<String> = (0..5_000)
+        .map(|idx| {
+            format!(
+                "Record {{ id: {idx}, name: \"record {idx}\", \
+                 tags: [\"a\", \"b\"], owner: Some(User {{ id: \
+                 {idx}, roles: {{Admin, Reader}} }}), limits: \
+                 {{1: (0..10, 1.5), 2: (10..=20, -2.5)}}, state: \
+                 Ok(Active) }}"
+            )
+        })
+        .collect();
+    format!("[{}]", records.join(",\n"))
+}
+
+/// Lines much like a log holds, a few of which are values, as
+/// when sniffing which ones are.
+fn log_lines() -> String {
+    let lines: Vec<String> = (0..10_000)
+        .map(|idx| match idx % 5 {
+            0 => format!("GET /users/{idx} HTTP/1.1 200"),
+            1 => format!("user{idx}@example.com logged in"),
+            2 => format!("retries={idx} timeout=30s"),
+            3 => format!("<li class=\"item\">{idx}</li>"),
+            _ => format!("Event {{ id: {idx}, ok: true }}"),
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn nested_lists(depth: usize) -> String {
+    "[".repeat(depth) + &"]".repeat(depth)
+}
+
+/// Checks a whole input, as `parse` or `validate`, or each of
+/// its lines, with `validate` or `validate_quick`.
+#[derive(Clone, Copy)]
+enum Check {
+    Parse,
+    Validate,
+    Lines,
+    QuickLines,
+}
+
+fn bench(
+    name: &str,
+    input: &str,
+    options: ParserOptions,
+    check: Check,
+) {
+    let mut fastest = Duration::MAX;
+    let mut runs = 0;
+    let started = Instant::now();
+    while runs < 3 || started.elapsed() < Duration::from_secs(1)
+    {
+        let start = Instant::now();
+        match check {
+            Check::Parse => {
+                let result =
+                    parse_value_with(black_box(input), options);
+                let elapsed = start.elapsed();
+                assert!(matches!(result, Ok(("", _))), "{name}");
+                drop(black_box(result));
+                fastest = fastest.min(elapsed);
+            }
+            Check::Validate => {
+                let result =
+                    validate_with(black_box(input), options);
+                fastest = fastest.min(start.elapsed());
+                assert_eq!(result, Ok(()), "{name}");
+            }
+            Check::Lines => {
+                let valid = black_box(input)
+                    .lines()
+                    .filter(|line| validate(line).is_ok())
+                    .count();
+                fastest = fastest.min(start.elapsed());
+                assert_eq!(valid, 2_000, "{name}");
+            }
+            Check::QuickLines => {
+                let valid = black_box(input)
+                    .lines()
+                    .filter(|line| validate_quick(line).is_ok())
+                    .count();
+                fastest = fastest.min(start.elapsed());
+                assert_eq!(valid, 2_000, "{name}");
+            }
+        }
+
+        runs += 1;
+    }
+
+    println!(
+        "{name:<24} {:>10.3} ms {:>8.1} MB/s",
+        fastest.as_secs_f64() * 1e3,
+        input.len() as f64 / fastest.as_secs_f64() / 1e6
+    );
+}
+
+fn main() {
+    let filter = std::env::args()
+        .skip(1)
+        .find(|arg| !arg.starts_with('-'));
+    let unlimited =
+        ParserOptions::default().max_depth(usize::MAX);
+    let default = ParserOptions::default();
+    let cases = [
+        ("flat list", flat_list(), default, Check::Parse),
+        (
+            "flat list (validate)",
+            flat_list(),
+            default,
+            Check::Validate,
+        ),
+        (
+            "flat object",
+            flat_object(),
+            default,
+            Check::Parse,
+        ),
+        (
+            "flat object (validate)",
+            flat_object(),
+            default,
+            Check::Validate,
+        ),
+        ("records", records(), default, Check::Parse),
+        (
+            "records (validate)",
+            records(),
+            default,
+            Check::Validate,
+        ),
+        ("log lines", log_lines(), default, Check::Lines),
+        (
+            "log lines (quick)",
+            log_lines(),
+            default,
+            Check::QuickLines,
+        ),
+        (
+            "nested lists",
+            nested_lists(100),
+            default,
+            Check::Parse,
+        ),
+        (
+            "deeply nested lists",
+            nested_lists(10_000),
+            unlimited,
+            Check::Parse,
+        ),
+    ];
+
+    // Checks that the parser works at all before timing it
+    assert!(parse_value("[1, {2}]").is_ok());
+    for (name, input, options, check) in cases {
+        if filter
+            .as_ref()
+            .is_none_or(|filter| name.contains(filter.as_str()))
+        {
+            bench(name, &input, options, check);
+        }
+    }
+}
@@ -0,0 +1,517 @@
+use alloc::boxed::Box;
+
+use crate::{display::sorted, Path, PathSegment, Value};
+
+/// How far apart floats may be for [`Value::approx_eq`] to count
+/// them as equal.
+///
+/// Two floats are equal if they are within `absolute` of each
+/// other, or within `relative` times the larger of their
+/// magnitudes, so that both small and large floats can be
+/// compared. By default neither is allowed, so floats are only
+/// equal if they are exactly, and `NaN` is equal to `NaN`, as it
+/// is for `Value`'s `==`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Tolerance {
+    /// How far apart two floats may be.
+    pub absolute: f64,
+    /// How far apart two floats may be, as a fraction of the
+    /// larger of their magnitudes.
+    pub relative: f64,
+    /// Let `NaN` be equal to `NaN`. Otherwise it is equal to
+    /// nothing, as with `f64`.
+    pub nan_eq: bool,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            absolute: 0.0,
+            relative: 0.0,
+            nan_eq: true,
+        }
+    }
+}
+
+impl Tolerance {
+    pub fn absolute(mut self, absolute: f64) -> Self {
+        self.absolute = absolute;
+        self
+    }
+
+    pub fn relative(mut self, relative: f64) -> Self {
+        self.relative = relative;
+        self
+    }
+
+    pub fn nan_eq(mut self, nan_eq: bool) -> Self {
+        self.nan_eq = nan_eq;
+        self
+    }
+
+    /// Returns `true` if `left` and `right` are within this
+    /// tolerance of each other.
+    fn floats_eq(self, left: f64, right: f64) -> bool {
+        if left.is_nan() || right.is_nan() {
+            return self.nan_eq
+                && left.is_nan()
+                && right.is_nan();
+        }
+        // Infinities are only equal to themselves, as their
+        // difference is `NaN` or infinite
+        if left == right {
+            return true;
+        }
+
+        let difference = (left - right).abs();
+        difference <= self.absolute
+            || difference
+                <= self.relative * left.abs().max(right.abs())
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Returns `true` if this value is equal to `other` but for
+    /// floats that are within `tolerance` of each other, as when
+    /// checking floats that went through a calculation or were
+    /// written and read back.
+    ///
+    /// Everything else has to be equal: values have to be the
+    /// same kind, so `1` isn't approximately `1.0`, lists of the
+    /// same length, and objects, structs and maps have to have
+    /// the same keys. Raw numbers are compared as the floats
+    /// they are closest to, so they can be compared with floats
+    /// too.
+    pub fn approx_eq(
+        &self,
+        other: &Value<'_>,
+        tolerance: Tolerance,
+    ) -> bool {
+        self.approx_mismatch(other, tolerance).is_none()
+    }
+
+    /// Returns the path to the first value in this one that
+    /// isn't approximately equal to the one in `other` at the
+    /// same path, as [`Value::approx_eq`] compares them, or
+    /// `None` if they are approximately equal.
+    ///
+    /// Where one value has an element or entry that the other
+    /// doesn't, the path leads to it. Where no path could lead
+    /// to what differs, such as in a `Result`, a range or under
+    /// a map key that isn't a string, it leads to the value
+    /// that holds it.
+    pub fn approx_mismatch<'v>(
+        &'v self,
+        other: &'v Value<'_>,
+        tolerance: Tolerance,
+    ) -> Option<Path<'v>> {
+        let mut path = Path::default();
+        mismatch(self, other, &mut path, tolerance)
+            .then_some(path)
+    }
+}
+
+/// Returns `true` if `left` isn't approximately equal to
+/// `right`, leaving `path` at the value where they first differ.
+fn mismatch<'v>(
+    left: &'v Value<'_>,
+    right: &'v Value<'_>,
+    path: &mut Path<'v>,
+    tolerance: Tolerance,
+) -> bool {
+    match (left, right) {
+        (Value::Float(left), Value::Float(right)) => {
+            !tolerance.floats_eq(*left, *right)
+        }
+        #[cfg(feature = "arbitrary-precision")]
+        (
+            Value::Float(_) | Value::RawNumber(_),
+            Value::Float(_) | Value::RawNumber(_),
+        ) => match (left.as_f64(), right.as_f64()) {
+            (Some(left), Some(right)) => {
+                !tolerance.floats_eq(left, right)
+            }
+            _ => left != right,
+        },
+        (Value::List(left), Value::List(right))
+        | (Value::Tuple(left), Value::Tuple(right))
+        | (Value::Set(left), Value::Set(right)) => {
+            elements(left, right, path, tolerance)
+        }
+        (
+            Value::TupleStruct { name, fields: left },
+            Value::TupleStruct {
+                name: other_name,
+                fields: right,
+            },
+        ) => {
+            name != other_name
+                || elements(left, right, path, tolerance)
+        }
+        (
+            Value::Optional(Some(left)),
+            Value::Optional(Some(right)),
+        ) => mismatch(left, right, path, tolerance),
+        (Value::Result(Ok(left)), Value::Result(Ok(right)))
+        | (
+            Value::Result(Err(left)),
+            Value::Result(Err(right)),
+        ) => differs(left, right, tolerance),
+        (
+            Value::Range {
+                start,
+                end,
+                inclusive,
+            },
+            Value::Range {
+                start: other_start,
+                end: other_end,
+                inclusive: other_inclusive,
+            },
+        ) => {
+            inclusive != other_inclusive
+                || bounds_differ(start, other_start, tolerance)
+                || bounds_differ(end, other_end, tolerance)
+        }
+        (Value::Object(left), Value::Object(right)) => {
+            let mut left = sorted(left).into_iter().peekable();
+            let mut right = sorted(right).into_iter().peekable();
+            loop {
+                let (key, left, right) =
+                    match (left.peek(), right.peek()) {
+                        (None, None) => return false,
+                        (Some((key, _)), Some((other, _)))
+                            if key == other =>
+                        {
+                            let (key, left) =
+                                left.next().unwrap();
+                            let (_, right) =
+                                right.next().unwrap();
+                            (key, left, right)
+                        }
+                        // The smaller key is the one only one of
+                        // them has
+                        (Some((key, _)), Some((other, _))) => {
+                            path.push(PathSegment::Key(
+                                key.min(other),
+                            ));
+                            return true;
+                        }
+                        (Some((key, _)), None)
+                        | (None, Some((key, _))) => {
+                            path.push(PathSegment::Key(key));
+                            return true;
+                        }
+                    };
+                if at(
+                    PathSegment::Key(key),
+                    left,
+                    right,
+                    path,
+                    tolerance,
+                ) {
+                    return true;
+                }
+            }
+        }
+        (
+            Value::Struct {
+                name,
+                fields: left,
+                non_exhaustive,
+            },
+            Value::Struct {
+                name: other_name,
+                fields: right,
+                non_exhaustive: other_non_exhaustive,
+            },
+        ) => {
+            if name != other_name
+                || non_exhaustive != other_non_exhaustive
+            {
+                return true;
+            }
+
+            for idx in 0..left.len().max(right.len()) {
+                match (left.get(idx), right.get(idx)) {
+                    (
+                        Some((field, left)),
+                        Some((other, right)),
+                    ) if field == other => {
+                        let segment = PathSegment::Key(field);
+                        if at(
+                            segment, left, right, path,
+                            tolerance,
+                        ) {
+                            return true;
+                        }
+                    }
+                    (Some((field, _)), _)
+                    | (None, Some((field, _))) => {
+                        path.push(PathSegment::Key(field));
+                        return true;
+                    }
+                    (None, None) => {
+                        unreachable!("within both lengths")
+                    }
+                }
+            }
+            false
+        }
+        (Value::Map(left), Value::Map(right)) => {
+            for idx in 0..left.len().max(right.len()) {
+                let (key, left, right) =
+                    match (left.get(idx), right.get(idx)) {
+                        (
+                            Some((key, left)),
+                            Some((other, right)),
+                        ) if key == other => (key, left, right),
+                        (Some((key, _)), _)
+                        | (None, Some((key, _))) => {
+                            if let Some(key) = key.as_str() {
+                                path.push(PathSegment::Key(key));
+                            }
+                            return true;
+                        }
+                        (None, None) => {
+                            unreachable!("within both lengths")
+                        }
+                    };
+                let differ = match key.as_str() {
+                    Some(key) => at(
+                        PathSegment::Key(key),
+                        left,
+                        right,
+                        path,
+                        tolerance,
+                    ),
+                    None => differs(left, right, tolerance),
+                };
+                if differ {
+                    return true;
+                }
+            }
+            false
+        }
+        _ => left != right,
+    }
+}
+
+/// Returns `true` if `left` and `right` differ at `segment`,
+/// leaving `path` there or where in them they differ.
+fn at<'v>(
+    segment: PathSegment<'v>,
+    left: &'v Value<'_>,
+    right: &'v Value<'_>,
+    path: &mut Path<'v>,
+    tolerance: Tolerance,
+) -> bool {
+    let len = path.segments().len();
+    path.push(segment);
+    if mismatch(left, right, path, tolerance) {
+        return true;
+    }
+    path.truncate(len);
+    false
+}
+
+/// Compares the elements of two lists, tuples, sets or tuple
+/// structs in order.
+fn elements<'v>(
+    left: &'v [Value<'_>],
+    right: &'v [Value<'_>],
+    path: &mut Path<'v>,
+    tolerance: Tolerance,
+) -> bool {
+    for (idx, (left, right)) in
+        left.iter().zip(right).enumerate()
+    {
+        if at(
+            PathSegment::Index(idx),
+            left,
+            right,
+            path,
+            tolerance,
+        ) {
+            return true;
+        }
+    }
+    if left.len() != right.len() {
+        path.push(PathSegment::Index(
+            left.len().min(right.len()),
+        ));
+        return true;
+    }
+    false
+}
+
+/// Returns `true` if `left` and `right` differ where no path
+/// leads.
+fn differs(
+    left: &Value<'_>,
+    right: &Value<'_>,
+    tolerance: Tolerance,
+) -> bool {
+    mismatch(left, right, &mut Path::default(), tolerance)
+}
+
+fn bounds_differ(
+    left: &Option<Box<Value<'_>>>,
+    right: &Option<Box<Value<'_>>>,
+    tolerance: Tolerance,
+) -> bool {
+    match (left, right) {
+        (Some(left), Some(right)) => {
+            differs(left, right, tolerance)
+        }
+        (left, right) => left.is_some() != right.is_some(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+
+    use crate::{from_str, tson, Tolerance};
+
+    /// Returns where `left` and `right` first differ, written
+    /// out.
+    fn mismatch(
+        left: &str,
+        right: &str,
+        tolerance: Tolerance,
+    ) -> Option<String> {
+        let (left, right) =
+            (from_str(left).unwrap(), from_str(right).unwrap());
+        assert_eq!(
+            left.approx_eq(&right, tolerance),
+            left.approx_mismatch(&right, tolerance).is_none()
+        );
+        left.approx_mismatch(&right, tolerance)
+            .map(|path| path.to_string())
+    }
+
+    #[test]
+    fn compares_floats_within_tolerance() {
+        let sum = 0.1 + 0.2;
+        let measured = tson!({
+            "host": "a",
+            "readings": [
+                Reading { at: 1, value: 0.5 },
+                Reading { at: 2, value: sum },
+            ],
+        });
+        let expected = from_str(
+            r#"{"host": "a", "readings": [
+                Reading { at: 1, value: 0.5 },
+                Reading { at: 2, value: 0.3 },
+            ]}"#,
+        )
+        .unwrap();
+
+        assert!(
+            !measured.approx_eq(&expected, Tolerance::default())
+        );
+        assert_eq!(
+            measured
+                .approx_mismatch(&expected, Tolerance::default())
+                .unwrap()
+                .to_string(),
+            "readings.1.value"
+        );
+        assert!(measured.approx_eq(
+            &expected,
+            Tolerance::default().absolute(1e-9)
+        ));
+
+        let relative = Tolerance::default().relative(1e-9);
+        assert_eq!(
+            mismatch("1e20", "1.0000000001e20", relative),
+            None
+        );
+        assert_eq!(
+            mismatch("1e-20", "2e-20", relative),
+            Some("".to_string())
+        );
+        assert_eq!(mismatch("inf", "inf", relative), None);
+        assert_eq!(
+            mismatch(
+                "inf",
+                "-inf",
+                Tolerance::default().absolute(1e300)
+            ),
+            Some("".to_string())
+        );
+    }
+
+    #[test]
+    fn treats_nan_as_configured() {
+        let nan = f64::NAN;
+        let (nan, other) =
+            (tson!([1.5, nan]), tson!([1.5, nan]));
+
+        assert!(nan.approx_eq(&other, Tolerance::default()));
+        let strict =
+            Tolerance::default().absolute(1.0).nan_eq(false);
+        assert!(!nan.approx_eq(&other, strict));
+        assert_eq!(
+            nan.approx_mismatch(&other, strict)
+                .unwrap()
+                .to_string(),
+            "1"
+        );
+    }
+
+    #[test]
+    fn finds_where_the_structure_differs() {
+        let loose = Tolerance::default().absolute(0.5);
+        for (left, right, at) in [
+            ("[1.0, 2.0]", "[1.1, 2.1, 3.0]", Some("2")),
+            ("[1.0, 2.0, 3.0]", "[1.1]", Some("1")),
+            (
+                "{\"a\": 1.0, \"c\": 2.0}",
+                "{\"a\": 1.2, \"b\": 2.0}",
+                Some("b"),
+            ),
+            (
+                "{\"a\": 1.0}",
+                "{\"a\": 1.2, \"b\": 2.0}",
+                Some("b"),
+            ),
+            (
+                "P { x: 1.0, y: 2.0 }",
+                "P { x: 1.1, z: 2.0 }",
+                Some("y"),
+            ),
+            ("P { x: 1.0 }", "Q { x: 1.0 }", Some("")),
+            (
+                "[Some(1.0), None]",
+                "[Some(1.2), Some(1.0)]",
+                Some("1"),
+            ),
+            ("[1]", "[1.0]", Some("0")),
+            ("{\"id\": Ok(1.0)}", "{\"id\": Ok(1.2)}", None),
+            (
+                "{\"id\": Ok(1.0)}",
+                "{\"id\": Ok([1.0])}",
+                Some("id"),
+            ),
+            (
+                "{1: 1.0, \"a\": [2.0]}",
+                "{1: 1.2, \"a\": [2.9]}",
+                Some("a.0"),
+            ),
+            ("{1: 1.0}", "{1: 2.0}", Some("")),
+            ("0.0..=1.0", "0.1..=1.1", None),
+            ("0.0..1.0", "0.1..=1.1", Some("")),
+            ("\"a\"", "\"b\"", Some("")),
+        ] {
+            assert_eq!(
+                mismatch(left, right, loose).as_deref(),
+                at,
+                "{left} and {right}"
+            );
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+use core::fmt::Write;
+
+use crate::Value;
+
+impl Value<'_> {
+    /// Writes this value in its canonical form: the same text
+    /// for any two equal values, and text that will stay the
+    /// same across versions, for use as a cache key or in
+    /// golden files.
+    ///
+    /// The canonical form is what `Display` writes, except that:
+    ///
+    /// - There is no whitespace outside of strings, chars and
+    ///   byte strings: `Foo{a:[1,2],..}`.
+    /// - The entries of a `Value::Object` or a `Value::Map`, and
+    ///   the elements of a `Value::Set`, are sorted by their
+    ///   canonical text, compared byte by byte. The entries of a
+    ///   map with equal keys are then sorted by their values'.
+    ///   Lists, tuples and the fields of structs keep their
+    ///   order, since it is part of what they are.
+    /// - Floats are written in their shortest form that reads
+    ///   back exactly (`0.1`, `1e300`, `NaN`, `inf`), with
+    ///   `-0.0` written as `0.0`, since the two compare equal.
+    /// - Strings and chars only escape what they have to: their
+    ///   own quote, the backslash, and control characters, which
+    ///   are written as `\n`, `\r`, `\t`, `\0` or `\u{..}` with
+    ///   lowercase hex digits. Everything else, including
+    ///   non-ASCII text, is written as is.
+    ///
+    /// Like the output of `Display`, this parses back to an
+    /// equal value, up to the order of maps and sets.
+    pub fn to_canonical_string(&self) -> String {
+        let mut out = String::new();
+        write_canonical(&mut out, self);
+
+        out
+    }
+}
+
+fn canonical(value: &Value<'_>) -> String {
+    value.to_canonical_string()
+}
+
+fn write_canonical(out: &mut String, value: &Value<'_>) {
+    match value {
+        Value::Float(float) if *float == 0. => {
+            out.push_str("0.0")
+        }
+        Value::Float(float) => {
+            let _ = write!(out, "{float:?}");
+        }
+        Value::String(string) => write_quoted(out, string, '"'),
+        Value::Char(chr) => {
+            write_quoted(out, chr.encode_utf8(&mut [0; 4]), '\'')
+        }
+        Value::List(elements) => write_joined(
+            out,
+            "[",
+            elements.iter().map(canonical),
+            "]",
+        ),
+        Value::Tuple(elements) => {
+            // `(1)` would read as a parenthesized value
+            let close =
+                if elements.len() == 1 { ",)" } else { ")" };
+            write_joined(
+                out,
+                "(",
+                elements.iter().map(canonical),
+                close,
+            )
+        }
+        Value::Optional(Some(value)) => {
+            write_joined(out, "Some(", [canonical(value)], ")")
+        }
+        Value::Result(Ok(value)) => {
+            write_joined(out, "Ok(", [canonical(value)], ")")
+        }
+        Value::Result(Err(value)) => {
+            write_joined(out, "Err(", [canonical(value)], ")")
+        }
+        Value::Object(object) => write_entries(
+            out,
+            object.iter().map(|(key, value)| {
+                (
+                    canonical(&Value::String(key.clone())),
+                    canonical(value),
+                )
+            }),
+        ),
+        Value::Map(entries) => write_entries(
+            out,
+            entries.iter().map(|(key, value)| {
+                (canonical(key), canonical(value))
+            }),
+        ),
+        Value::Set(elements) => {
+            let mut elements: Vec<_> =
+                elements.iter().map(canonical).collect();
+            elements.sort_unstable();
+            write_joined(out, "{", elements, "}")
+        }
+        Value::Struct {
+            name,
+            fields,
+            non_exhaustive,
+        } => {
+            let fields = fields
+                .iter()
+                .map(|(field, value)| {
+                    format!("{field}:{}", canonical(value))
+                })
+                .chain(non_exhaustive.then(|| "..".to_owned()));
+            write_joined(out, &format!("{name}{{"), fields, "}")
+        }
+        Value::TupleStruct { name, fields } => write_joined(
+            out,
+            &format!("{name}("),
+            fields.iter().map(canonical),
+            ")",
+        ),
+        Value::Range {
+            start,
+            end,
+            inclusive,
+        } => {
+            if let Some(start) = start {
+                write_canonical(out, start);
+            }
+            out.push_str(if *inclusive { "..=" } else { ".." });
+            if let Some(end) = end {
+                write_canonical(out, end);
+            }
+        }
+        // The rest are written the one way `Display` writes them
+        value => {
+            let _ = write!(out, "{value}");
+        }
+    }
+}
+
+/// Writes `parts` separated by commas, between `open` and
+/// `close`.
+fn write_joined(
+    out: &mut String,
+    open: &str,
+    parts: impl IntoIterator<Item = String>,
+    close: &str,
+) {
+    out.push_str(open);
+    for (idx, part) in parts.into_iter().enumerate() {
+        if idx > 0 {
+            out.push(',');
+        }
+        out.push_str(&part);
+    }
+    out.push_str(close);
+}
+
+/// Writes the canonical texts of a map's keys and values as
+/// `{key:value,..}`, sorted by key and then by value.
+fn write_entries(
+    out: &mut String,
+    entries: impl Iterator<Item = (String, String)>,
+) {
+    let mut entries: Vec<_> = entries.collect();
+    entries.sort_unstable();
+
+    let entries = entries
+        .into_iter()
+        .map(|(key, value)| format!("{key}:{value}"));
+    write_joined(out, "{", entries, "}")
+}
+
+/// Writes `text` between `quote`s, escaping only `quote`, the
+/// backslash and control characters.
+fn write_quoted(out: &mut String, text: &str, quote: char) {
+    out.push(quote);
+    for chr in text.chars() {
+        match chr {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\0' => out.push_str("\\0"),
+            chr if chr == quote => {
+                out.push('\\');
+                out.push(chr);
+            }
+            chr if chr.is_control() => {
+                let _ =
+                    write!(out, "\\u{{{:x}}}", u32::from(chr));
+            }
+            chr => out.push(chr),
+        }
+    }
+    out.push(quote);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        display::tests::{parse_f64s, Generator},
+        ObjectMap, Value,
+    };
+
+    fn canonical(text: &str) -> String {
+        let (rest, value) = parse_f64s(text).unwrap();
+        assert_eq!(rest, "", "{text}");
+
+        value.to_canonical_string()
+    }
+
+    #[test]
+    fn writes_canonical_values() {
+        let cases = [
+            ("[ 1, 2.50, -0.0 ]", "[1,2.5,0.0]"),
+            ("( 1 , )", "(1,)"),
+            (
+                r#"{"b": 1, "a": 2, "ab": 3}"#,
+                r#"{"a":2,"ab":3,"b":1}"#,
+            ),
+            (
+                "{2: 'x', 1: 'y', 10: 'z'}",
+                "{1:'y',10:'z',2:'x'}",
+            ),
+            ("{3, 1, 2}", "{1,2,3}"),
+            ("Foo { b: Some(1), a: () }", "Foo{b:Some(1),a:()}"),
+            ("Foo { a: 1, .. }", "Foo{a:1,..}"),
+            ("Foo {}", "Foo{}"),
+            ("shape::Move( 1 , Red )", "shape::Move(1,Red)"),
+            ("1 ..= 5", "1..=5"),
+            ("1e300", "1e300"),
+            ("NaN", "NaN"),
+            (
+                r#""é😀\u{7f}\x01'\"\\\n""#,
+                r#""é😀\u{7f}\u{1}'\"\\\n""#,
+            ),
+            (r#"'"'"#, r#"'"'"#),
+            (r"'\''", r"'\''"),
+            (r#"b"a \xff""#, r#"b"a \xff""#),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(canonical(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn writes_equal_values_the_same() {
+        let keys: Vec<_> =
+            (0..64).map(|key| key.to_string()).collect();
+        let forwards: ObjectMap = keys
+            .iter()
+            .map(|key| (key.as_str().into(), Value::Integer(1)))
+            .collect();
+        let backwards: ObjectMap = keys
+            .iter()
+            .rev()
+            .map(|key| (key.as_str().into(), Value::Integer(1)))
+            .collect();
+        assert_eq!(
+            Value::Object(forwards).to_canonical_string(),
+            Value::Object(backwards).to_canonical_string()
+        );
+
+        assert_eq!(
+            Value::Float(-0.).to_canonical_string(),
+            Value::Float(0.).to_canonical_string()
+        );
+        assert_eq!(canonical("{1, 2}"), canonical("{2, 1}"));
+        assert_eq!(
+            canonical("{'a': 1, 'b': 2}"),
+            canonical("{'b': 2, 'a': 1}")
+        );
+    }
+
+    #[test]
+    fn reads_back_canonical_values() {
+        let mut generator = Generator(0xca11);
+        for _ in 0..2000 {
+            let text = generator.value(4).to_canonical_string();
+            assert_eq!(canonical(&text), text);
+        }
+    }
+}
@@ -0,0 +1,1223 @@
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::{ObjectMap, Value};
+
+/// Types that can be built from a [`Value`], such as the value
+/// `parse_value` reads from their `Debug` output.
+///
+/// This is implemented for the integer and float types, `bool`,
+/// `char`, `String`, `Vec<T>`, `Option<T>`, `Box<T>` and tuples
+/// of up to eight elements, and can be derived for structs and
+/// enums with the `tson-derive` crate. Those types can also be
+/// converted with `TryFrom`, from a value or a borrowed one, and
+/// a string's text borrowed as a `&str`.
+pub trait FromTson: Sized {
+    fn from_tson(
+        value: &Value<'_>,
+    ) -> Result<Self, ConvertError>;
+
+    /// Returns what a missing field of this type stands for, if
+    /// it may be left out at all. Only `Option`s may, and are
+    /// `None` when they are.
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+/// Types that can be turned into a [`Value`] that writes like
+/// their `Debug` output.
+///
+/// Integers become the variant `parse_value` would read them
+/// as, so that `i64` and smaller types are `Value::Integer`.
+pub trait ToTson {
+    fn to_tson(&self) -> Value<'static>;
+}
+
+/// An error converting a [`Value`] with [`FromTson`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConvertError {
+    /// The value isn't of the `expected` type, but is `found`,
+    /// as in `a string`.
+    Mismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The number `value` doesn't fit in the `expected` type.
+    OutOfRange {
+        expected: &'static str,
+        value: String,
+    },
+    /// A tuple or tuple struct has `found` elements instead of
+    /// the `expected` number.
+    WrongLength { expected: usize, found: usize },
+    /// A struct is missing this field.
+    MissingField(&'static str),
+    /// An identifier that looks like a misspelling of the
+    /// keyword `suggestion`, such as `True` where a `bool` is
+    /// `expected`, or `Nne` where an `Option` is.
+    Misspelled {
+        expected: &'static str,
+        found: String,
+        suggestion: &'static str,
+    },
+    /// An enum has no variant named `found`.
+    UnknownVariant {
+        expected: &'static str,
+        found: String,
+    },
+    /// Converting the named field of a struct failed.
+    InField {
+        field: String,
+        error: Box<ConvertError>,
+    },
+    /// Converting the element at `index` of a list or tuple
+    /// failed.
+    InElement {
+        index: usize,
+        error: Box<ConvertError>,
+    },
+}
+
+impl ConvertError {
+    /// Marks this as an error converting `field`.
+    pub fn in_field(self, field: impl Into<String>) -> Self {
+        ConvertError::InField {
+            field: field.into(),
+            error: Box::new(self),
+        }
+    }
+
+    /// Marks this as an error converting the element at
+    /// `index`.
+    pub fn in_element(self, index: usize) -> Self {
+        ConvertError::InElement {
+            index,
+            error: Box::new(self),
+        }
+    }
+
+    /// Returns the keyword that the value which failed to
+    /// convert was likely meant to be, if it looks like a
+    /// misspelling of one.
+    pub fn suggestion(&self) -> Option<&'static str> {
+        match self {
+            ConvertError::Misspelled { suggestion, .. } => {
+                Some(suggestion)
+            }
+            ConvertError::InField { error, .. }
+            | ConvertError::InElement { error, .. } => {
+                error.suggestion()
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn mismatch(
+        expected: &'static str,
+        found: &Value<'_>,
+    ) -> Self {
+        ConvertError::Mismatch {
+            expected,
+            found: found.type_name(),
+        }
+    }
+}
+
+/// Writes the error along with the path to where it happened,
+/// as in `servers[1].port: 70000 is out of range for u16`.
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut error = self;
+        let mut path = String::new();
+        loop {
+            match error {
+                ConvertError::InField {
+                    field,
+                    error: inner,
+                } => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(field);
+                    error = inner;
+                }
+                ConvertError::InElement {
+                    index,
+                    error: inner,
+                } => {
+                    path.push_str(&format!("[{index}]"));
+                    error = inner;
+                }
+                _ => break,
+            }
+        }
+        if !path.is_empty() {
+            write!(f, "{path}: ")?;
+        }
+
+        match error {
+            ConvertError::Mismatch { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            ConvertError::OutOfRange { expected, value } => {
+                write!(
+                    f,
+                    "{value} is out of range for {expected}"
+                )
+            }
+            ConvertError::WrongLength { expected, found } => {
+                write!(
+                f,
+                "expected {expected} elements, found {found}"
+            )
+            }
+            ConvertError::MissingField(field) => {
+                write!(f, "missing field `{field}`")
+            }
+            ConvertError::Misspelled {
+                expected,
+                found,
+                suggestion,
+            } => write!(
+                f,
+                "expected {expected}, found `{found}`; help: did \
+                 you mean `{suggestion}`?"
+            ),
+            ConvertError::UnknownVariant { expected, found } => {
+                write!(
+                    f,
+                    "unknown variant `{found}` of {expected}"
+                )
+            }
+            ConvertError::InField { .. }
+            | ConvertError::InElement { .. } => unreachable!(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConvertError {}
+
+/// Returns the error for an identifier where `expected` is, if
+/// it looks like a misspelling of one of `keywords`.
+fn misspelled(
+    expected: &'static str,
+    value: &Value<'_>,
+    keywords: &[&'static str],
+) -> Option<ConvertError> {
+    let Value::Ident(word) = value else {
+        return None;
+    };
+
+    resembles(word, keywords).map(|suggestion| {
+        ConvertError::Misspelled {
+            expected,
+            found: word.to_string(),
+            suggestion,
+        }
+    })
+}
+
+/// Returns the first of `keywords` that `word` differs from only
+/// in case or by a single typo: a letter added, left out,
+/// changed or swapped with the next one. Words any further off
+/// are taken to be meant as they are.
+fn resembles(
+    word: &str,
+    keywords: &[&'static str],
+) -> Option<&'static str> {
+    let word: Vec<char> = word.to_lowercase().chars().collect();
+    keywords.iter().copied().find(|keyword| {
+        let keyword: Vec<char> =
+            keyword.to_lowercase().chars().collect();
+        typos(&word, &keyword) <= 1
+    })
+}
+
+/// Returns how many letters must be added, left out, changed or
+/// swapped with the next one to turn `a` into `b`.
+fn typos(a: &[char], b: &[char]) -> usize {
+    if a.len().abs_diff(b.len()) > 1 {
+        return a.len().abs_diff(b.len());
+    }
+
+    // `rows[i][j]` is the number of typos between the first `i`
+    // chars of `a` and the first `j` of `b`
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, typos) in rows[0].iter_mut().enumerate() {
+        *typos = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let changed = usize::from(a[i - 1] != b[j - 1]);
+            let mut least = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + changed);
+            if i > 1
+                && j > 1
+                && a[i - 1] == b[j - 2]
+                && a[i - 2] == b[j - 1]
+            {
+                least = least.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = least;
+        }
+    }
+
+    rows[a.len()][b.len()]
+}
+
+/// Converts integers to any integer type they fit in. A float is
+/// never converted, even one holding a whole number, since it
+/// may have been rounded when parsed, as [`Value::as_i64`]
+/// doesn't convert one either.
+macro_rules! integers {
+    ($($ty:ident: $to_tson:ident)*) => {$(
+        impl FromTson for $ty {
+            fn from_tson(
+                value: &Value<'_>,
+            ) -> Result<Self, ConvertError> {
+                let integer: Option<i128> = match value {
+                    Value::Integer(integer) => Some((*integer).into()),
+                    Value::UInt(integer) => Some((*integer).into()),
+                    Value::BigInt(integer) => Some(*integer),
+                    Value::BigUInt(integer) => {
+                        let converted = $ty::try_from(*integer);
+                        return converted.map_err(|_| out_of_range(stringify!($ty), value));
+                    }
+                    #[cfg(feature = "arbitrary-precision")]
+                    Value::RawNumber(number)
+                        if number.replace('_', "").parse::<i128>().is_ok() =>
+                    {
+                        number.replace('_', "").parse().ok()
+                    }
+                    value => {
+                        return Err(ConvertError::mismatch(
+                            stringify!($ty),
+                            value,
+                        ))
+                    }
+                };
+
+                integer
+                    .and_then(|integer| $ty::try_from(integer).ok())
+                    .ok_or_else(|| out_of_range(stringify!($ty), value))
+            }
+        }
+
+        impl ToTson for $ty {
+            fn to_tson(&self) -> Value<'static> {
+                $to_tson(*self as _)
+            }
+        }
+    )*};
+}
+
+integers! {
+    i8: signed i16: signed i32: signed i64: signed i128: signed
+    isize: signed
+    u8: unsigned u16: unsigned u32: unsigned u64: unsigned
+    u128: unsigned usize: unsigned
+}
+
+/// Turns an integer into the variant `parse_value` reads it as.
+fn signed(integer: i128) -> Value<'static> {
+    match i64::try_from(integer) {
+        Ok(integer) => Value::Integer(integer),
+        Err(_) => match u64::try_from(integer) {
+            Ok(integer) => Value::UInt(integer),
+            Err(_) => Value::BigInt(integer),
+        },
+    }
+}
+
+fn unsigned(integer: u128) -> Value<'static> {
+    match i128::try_from(integer) {
+        Ok(integer) => signed(integer),
+        Err(_) => Value::BigUInt(integer),
+    }
+}
+
+fn out_of_range(
+    expected: &'static str,
+    value: &Value<'_>,
+) -> ConvertError {
+    ConvertError::OutOfRange {
+        expected,
+        value: value.to_string(),
+    }
+}
+
+impl FromTson for f64 {
+    /// Accepts any number, rounding integers too large to be
+    /// represented exactly.
+    fn from_tson(
+        value: &Value<'_>,
+    ) -> Result<Self, ConvertError> {
+        value
+            .as_f64()
+            .ok_or_else(|| ConvertError::mismatch("f64", value))
+    }
+}
+
+impl FromTson for f32 {
+    fn from_tson(
+        value: &Value<'_>,
+    ) -> Result<Self, ConvertError> {
+        value
+            .as_f64()
+            .map(|float| float as f32)
+            .ok_or_else(|| ConvertError::mismatch("f32", value))
+    }
+}
+
+impl ToTson for f64 {
+    fn to_tson(&self) -> Value<'static> {
+        Value::Float(*self)
+    }
+}
+
+impl ToTson for f32 {
+    fn to_tson(&self) -> Value<'static> {
+        Value::Float((*self).into())
+    }
+}
+
+impl FromTson for bool {
+    fn from_tson(
+        value: &Value<'_>,
+    ) -> Result<Self, ConvertError> {
+        match value {
+            Value::Boolean(boolean) => Ok(*boolean),
+            value => Err(misspelled(
+                "bool",
+                value,
+                &["true", "false"],
+            )
+            .unwrap_or_else(|| {
+                ConvertError::mismatch("bool", value)
+            })),
+        }
+    }
+}
+
+impl ToTson for bool {
+    fn to_tson(&self) -> Value<'static> {
+        Value::Boolean(*self)
+    }
+}
+
+impl FromTson for char {
+    /// Accepts a char, or a string of a single char.
+    fn from_tson(
+        value: &Value<'_>,
+    ) -> Result<Self, ConvertError> {
+        match value {
+            Value::Char(chr) => Ok(*chr),
+            Value::String(string) => {
+                let mut chars = string.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(chr), None) => Ok(chr),
+                    _ => Err(ConvertError::mismatch(
+                        "char", value,
+                    )),
+                }
+            }
+            value => Err(ConvertError::mismatch("char", value)),
+        }
+    }
+}
+
+impl ToTson for char {
+    fn to_tson(&self) -> Value<'static> {
+        Value::Char(*self)
+    }
+}
+
+impl FromTson for String {
+    /// Accepts a string or a char.
+    fn from_tson(
+        value: &Value<'_>,
+    ) -> Result<Self, ConvertError> {
+        match value {
+            Value::String(string) => {
+                Ok(string.clone().into_owned())
+            }
+            Value::Char(chr) => Ok(chr.to_string()),
+            value => {
+                Err(ConvertError::mismatch("String", value))
+            }
+        }
+    }
+}
+
+impl ToTson for String {
+    fn to_tson(&self) -> Value<'static> {
+        self.as_str().to_tson()
+    }
+}
+
+impl ToTson for str {
+    fn to_tson(&self) -> Value<'static> {
+        Value::String(Cow::Owned(self.to_owned()))
+    }
+}
+
+impl FromTson for () {
+    fn from_tson(
+        value: &Value<'_>,
+    ) -> Result<Self, ConvertError> {
+        match value {
+            Value::Tuple(elements) if elements.is_empty() => {
+                Ok(())
+            }
+            value => Err(ConvertError::mismatch("()", value)),
+        }
+    }
+}
+
+impl ToTson for () {
+    fn to_tson(&self) -> Value<'static> {
+        Value::Tuple(vec![])
+    }
+}
+
+impl<T: FromTson> FromTson for Vec<T> {
+    /// Accepts a list, a tuple or a set.
+    fn from_tson(
+        value: &Value<'_>,
+    ) -> Result<Self, ConvertError> {
+        match value {
+            Value::List(elements)
+            | Value::Tuple(elements)
+            | Value::Set(elements) => elements
+                .iter()
+                .enumerate()
+                .map(|(idx, element)| {
+                    T::from_tson(element)
+                        .map_err(|err| err.in_element(idx))
+                })
+                .collect(),
+            value => {
+                Err(ConvertError::mismatch("a list", value))
+            }
+        }
+    }
+}
+
+impl<T: ToTson> ToTson for Vec<T> {
+    fn to_tson(&self) -> Value<'static> {
+        self[..].to_tson()
+    }
+}
+
+impl<T: ToTson> ToTson for [T] {
+    fn to_tson(&self) -> Value<'static> {
+        Value::List(self.iter().map(ToTson::to_tson).collect())
+    }
+}
+
+impl<T: FromTson> FromTson for Option<T> {
+    /// Accepts `None` and `Some(..)`, and any other value as
+    /// what `Some(..)` would wrap, so that `null` in JSON and
+    /// plain values work too.
+    fn from_tson(
+        value: &Value<'_>,
+    ) -> Result<Self, ConvertError> {
+        match value {
+            Value::Optional(None) => Ok(None),
+            Value::Optional(Some(value)) => {
+                T::from_tson(value).map(Some)
+            }
+            value => {
+                T::from_tson(value).map(Some).map_err(|err| {
+                    // `Sme(1)` may have been meant as `Some(1)`,
+                    // and `Nne` or `null` as
+                    // `None`
+                    let meant = match value {
+                        Value::TupleStruct { name, fields }
+                            if fields.len() == 1 =>
+                        {
+                            resembles(name, &["Some"]).map(
+                                |keyword| (&**name, keyword),
+                            )
+                        }
+                        Value::Ident(word) => {
+                            resembles(word, &["None", "null"])
+                                .map(|_| (&**word, "None"))
+                        }
+                        _ => None,
+                    };
+                    match (err, meant) {
+                        (
+                            ConvertError::Mismatch {
+                                expected,
+                                ..
+                            },
+                            Some((found, suggestion)),
+                        ) => ConvertError::Misspelled {
+                            expected,
+                            found: found.to_string(),
+                            suggestion,
+                        },
+                        (err, _) => err,
+                    }
+                })
+            }
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: ToTson> ToTson for Option<T> {
+    fn to_tson(&self) -> Value<'static> {
+        Value::Optional(
+            self.as_ref().map(|value| Box::new(value.to_tson())),
+        )
+    }
+}
+
+impl<T: FromTson> FromTson for Box<T> {
+    fn from_tson(
+        value: &Value<'_>,
+    ) -> Result<Self, ConvertError> {
+        T::from_tson(value).map(Box::new)
+    }
+}
+
+impl<T: ToTson + ?Sized> ToTson for Box<T> {
+    fn to_tson(&self) -> Value<'static> {
+        (**self).to_tson()
+    }
+}
+
+impl<T: ToTson + ?Sized> ToTson for &T {
+    fn to_tson(&self) -> Value<'static> {
+        (**self).to_tson()
+    }
+}
+
+/// Converts tuples from tuples or lists of the same length.
+macro_rules! tuples {
+    ($(($($idx:tt $ty:ident)+))*) => {$(
+        impl<$($ty: FromTson),+> FromTson for ($($ty,)+) {
+            fn from_tson(
+                value: &Value<'_>,
+            ) -> Result<Self, ConvertError> {
+                const LEN: usize = [$($idx),+].len();
+                let elements =
+                    __private::tuple_fields(value, "a tuple", LEN)?;
+
+                Ok(($(__private::element(elements, $idx)?,)+))
+            }
+        }
+
+        impl<$($ty: ToTson),+> ToTson for ($($ty,)+) {
+            fn to_tson(&self) -> Value<'static> {
+                Value::Tuple(vec![$(self.$idx.to_tson()),+])
+            }
+        }
+    )*};
+}
+
+tuples! {
+    (0 A)
+    (0 A 1 B)
+    (0 A 1 B 2 C)
+    (0 A 1 B 2 C 3 D)
+    (0 A 1 B 2 C 3 D 4 E)
+    (0 A 1 B 2 C 3 D 4 E 5 F)
+    (0 A 1 B 2 C 3 D 4 E 5 F 6 G)
+    (0 A 1 B 2 C 3 D 4 E 5 F 6 G 7 H)
+}
+
+/// Implements `TryFrom` a value and a borrowed value for types
+/// that implement [`FromTson`], converting them the same way.
+macro_rules! try_from {
+    ($([$($generics:tt)*] $ty:ty,)*) => {$(
+        impl<$($generics)*> TryFrom<Value<'_>> for $ty {
+            type Error = ConvertError;
+
+            fn try_from(
+                value: Value<'_>,
+            ) -> Result<Self, ConvertError> {
+                <$ty>::from_tson(&value)
+            }
+        }
+
+        impl<$($generics)*> TryFrom<&Value<'_>> for $ty {
+            type Error = ConvertError;
+
+            fn try_from(
+                value: &Value<'_>,
+            ) -> Result<Self, ConvertError> {
+                <$ty>::from_tson(value)
+            }
+        }
+    )*};
+}
+
+try_from! {
+    [] i8, [] i16, [] i32, [] i64, [] i128, [] isize,
+    [] u8, [] u16, [] u32, [] u64, [] u128, [] usize,
+    [] f32, [] f64, [] bool, [] char, [] String, [] (),
+    [T: FromTson] Vec<T>,
+    [T: FromTson] Option<T>,
+    [A: FromTson] (A,),
+    [A: FromTson, B: FromTson] (A, B),
+    [A: FromTson, B: FromTson, C: FromTson] (A, B, C),
+    [A: FromTson, B: FromTson, C: FromTson, D: FromTson]
+        (A, B, C, D),
+    [A: FromTson, B: FromTson, C: FromTson, D: FromTson,
+        E: FromTson] (A, B, C, D, E),
+    [A: FromTson, B: FromTson, C: FromTson, D: FromTson,
+        E: FromTson, F: FromTson] (A, B, C, D, E, F),
+    [A: FromTson, B: FromTson, C: FromTson, D: FromTson,
+        E: FromTson, F: FromTson, G: FromTson]
+        (A, B, C, D, E, F, G),
+    [A: FromTson, B: FromTson, C: FromTson, D: FromTson,
+        E: FromTson, F: FromTson, G: FromTson, H: FromTson]
+        (A, B, C, D, E, F, G, H),
+}
+
+/// Borrows the text of a string, which unlike converting to a
+/// `String` can't be done from a char.
+impl<'v> TryFrom<&'v Value<'_>> for &'v str {
+    type Error = ConvertError;
+
+    fn try_from(
+        value: &'v Value<'_>,
+    ) -> Result<Self, ConvertError> {
+        match value {
+            Value::String(string) => Ok(string),
+            value => Err(ConvertError::mismatch("&str", value)),
+        }
+    }
+}
+
+/// Implements `From` for types whose [`ToTson`] value holds
+/// nothing borrowed.
+macro_rules! from_scalars {
+    ($($ty:ty),*) => {$(
+        impl From<$ty> for Value<'_> {
+            fn from(value: $ty) -> Self {
+                value.to_tson()
+            }
+        }
+    )*};
+}
+
+from_scalars! {
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize,
+    f32, f64, bool, char, ()
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(string: &'a str) -> Self {
+        Value::String(Cow::Borrowed(string))
+    }
+}
+
+impl From<String> for Value<'_> {
+    fn from(string: String) -> Self {
+        Value::String(Cow::Owned(string))
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for Value<'a> {
+    fn from(string: Cow<'a, str>) -> Self {
+        Value::String(string)
+    }
+}
+
+impl<'a, T: Into<Value<'a>>> From<Option<T>> for Value<'a> {
+    fn from(option: Option<T>) -> Self {
+        Value::Optional(
+            option.map(|value| Box::new(value.into())),
+        )
+    }
+}
+
+impl<'a, T: Into<Value<'a>>> From<Vec<T>> for Value<'a> {
+    /// Builds a list.
+    fn from(elements: Vec<T>) -> Self {
+        elements.into_iter().collect()
+    }
+}
+
+impl<'a> From<ObjectMap<'a>> for Value<'a> {
+    fn from(object: ObjectMap<'a>) -> Self {
+        Value::Object(object)
+    }
+}
+
+/// Builds a list, so that values can be put together without
+/// naming their variants:
+///
+/// ```
+/// use tson::{ObjectMap, Value};
+///
+/// let mut server = ObjectMap::new();
+/// server.insert("host".into(), "localhost".into());
+/// server.insert("port".into(), 8080u16.into());
+/// server.insert("weights".into(), (1..4).map(f64::from).collect());
+/// server.insert("backup".into(), None::<&str>.into());
+///
+/// let servers = Value::from(vec![Value::from(server)]);
+/// assert_eq!(servers.get_path("0.port"), Some(&Value::Integer(8080)));
+/// assert_eq!(servers.get_path("0.weights.2"), Some(&Value::Float(3.)));
+/// assert_eq!(
+///     Value::from(vec![Some('a'), None]).to_string(),
+///     "[Some('a'), None]",
+/// );
+/// ```
+impl<'a, T: Into<Value<'a>>> FromIterator<T> for Value<'a> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Value::List(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+/// What the impls generated by `tson-derive` are built on. Not
+/// part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::{borrow::Cow, boxed::Box, vec, vec::Vec};
+
+    use super::*;
+
+    /// The named fields of a struct or of an object.
+    pub enum StructFields<'v, 'a> {
+        Struct(&'v [(Cow<'a, str>, Value<'a>)]),
+        Object(&'v ObjectMap<'a>),
+    }
+
+    impl StructFields<'_, '_> {
+        /// Converts the field named `field`, or uses
+        /// [`FromTson::from_missing`] if it is missing.
+        pub fn get<T: FromTson>(
+            &self,
+            field: &'static str,
+        ) -> Result<T, ConvertError> {
+            let value = match self {
+                StructFields::Struct(fields) => fields
+                    .iter()
+                    .find(|(name, _)| *name == field)
+                    .map(|(_, value)| value),
+                StructFields::Object(object) => {
+                    object.get(field)
+                }
+            };
+
+            match value {
+                Some(value) => T::from_tson(value)
+                    .map_err(|err| err.in_field(field)),
+                None => T::from_missing()
+                    .ok_or(ConvertError::MissingField(field)),
+            }
+        }
+    }
+
+    /// Returns the fields of a struct with named fields, which
+    /// may be written as a struct of any name or as an object.
+    pub fn struct_fields<'v, 'a>(
+        value: &'v Value<'a>,
+        expected: &'static str,
+    ) -> Result<StructFields<'v, 'a>, ConvertError> {
+        match value {
+            Value::Struct { fields, .. } => {
+                Ok(StructFields::Struct(fields))
+            }
+            Value::Object(object) => {
+                Ok(StructFields::Object(object))
+            }
+            value => {
+                Err(ConvertError::mismatch(expected, value))
+            }
+        }
+    }
+
+    /// Returns the `len` fields of a tuple struct, which may be
+    /// written as a tuple struct of any name, a tuple or a list.
+    pub fn tuple_fields<'v, 'a>(
+        value: &'v Value<'a>,
+        expected: &'static str,
+        len: usize,
+    ) -> Result<&'v [Value<'a>], ConvertError> {
+        match value {
+            Value::TupleStruct { fields, .. }
+            | Value::Tuple(fields)
+            | Value::List(fields) => match fields.len() {
+                found if found == len => Ok(fields),
+                found => Err(ConvertError::WrongLength {
+                    expected: len,
+                    found,
+                }),
+            },
+            value => {
+                Err(ConvertError::mismatch(expected, value))
+            }
+        }
+    }
+
+    /// Converts the element at `idx` of `elements`, which must
+    /// have one.
+    pub fn element<T: FromTson>(
+        elements: &[Value<'_>],
+        idx: usize,
+    ) -> Result<T, ConvertError> {
+        T::from_tson(&elements[idx])
+            .map_err(|err| err.in_element(idx))
+    }
+
+    /// Checks that a unit struct or unit variant is written as
+    /// its name, or as `()`.
+    pub fn unit(
+        value: &Value<'_>,
+        expected: &'static str,
+    ) -> Result<(), ConvertError> {
+        match value {
+            Value::Ident(_) => Ok(()),
+            Value::Tuple(elements) if elements.is_empty() => {
+                Ok(())
+            }
+            value => {
+                Err(ConvertError::mismatch(expected, value))
+            }
+        }
+    }
+
+    /// Returns the name of the enum variant `value` is, without
+    /// any path before it, so that `Shape::Circle { .. }` is a
+    /// `Circle`.
+    pub fn variant_name<'v>(
+        value: &'v Value<'_>,
+        expected: &'static str,
+    ) -> Result<&'v str, ConvertError> {
+        let name = match value {
+            Value::Ident(name)
+            | Value::TupleStruct { name, .. }
+            | Value::Struct { name, .. } => &**name,
+            value => {
+                return Err(ConvertError::mismatch(
+                    expected, value,
+                ))
+            }
+        };
+
+        Ok(name.rsplit("::").next().unwrap_or(name))
+    }
+
+    pub fn unknown_variant(
+        expected: &'static str,
+        found: &str,
+    ) -> ConvertError {
+        ConvertError::UnknownVariant {
+            expected,
+            found: found.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::Cow;
+
+    use crate::{
+        display::tests::parse_f64s, from_str, parse_value,
+        ConvertError, FromTson, ToTson, Value,
+    };
+
+    fn convert<T: FromTson>(
+        text: &str,
+    ) -> Result<T, ConvertError> {
+        let (rest, value) = parse_value(text).unwrap();
+        assert_eq!(rest, "", "{text}");
+
+        T::from_tson(&value)
+    }
+
+    #[test]
+    fn converts_scalars() {
+        assert_eq!(convert::<u8>("255"), Ok(255));
+        assert_eq!(
+            convert::<i128>("-100_000_000_000_000_000_000"),
+            Ok(-100_000_000_000_000_000_000)
+        );
+        assert_eq!(
+            convert::<u128>(&u128::MAX.to_string()),
+            Ok(u128::MAX)
+        );
+        assert_eq!(convert::<f64>("2"), Ok(2.));
+        assert_eq!(convert::<f32>("0.5"), Ok(0.5));
+        assert_eq!(convert::<bool>("true"), Ok(true));
+        assert_eq!(convert::<char>("'x'"), Ok('x'));
+        assert_eq!(convert::<char>("\"x\""), Ok('x'));
+        assert_eq!(
+            convert::<String>(r#""a\nb""#),
+            Ok("a\nb".into())
+        );
+        assert_eq!(convert::<()>("()"), Ok(()));
+
+        assert_eq!(
+            convert::<u8>("256").unwrap_err().to_string(),
+            "256 is out of range for u8"
+        );
+        assert_eq!(
+            convert::<u8>("2.5").unwrap_err().to_string(),
+            "expected u8, found a float"
+        );
+        assert_eq!(
+            convert::<u8>("3.0").unwrap_err().to_string(),
+            "expected u8, found a float"
+        );
+        assert_eq!(
+            convert::<i128>("-1e20").unwrap_err().to_string(),
+            "expected i128, found a float"
+        );
+        assert_eq!(
+            convert::<i32>("\"1\"").unwrap_err().to_string(),
+            "expected i32, found a string"
+        );
+        assert!(convert::<char>("\"xy\"").is_err());
+    }
+
+    #[test]
+    fn suggests_keywords_for_misspellings() {
+        assert_eq!(
+            convert::<bool>("True"),
+            Err(ConvertError::Misspelled {
+                expected: "bool",
+                found: "True".into(),
+                suggestion: "true",
+            })
+        );
+        assert_eq!(
+            convert::<bool>("ture")
+                .map_err(|err| err.suggestion()),
+            Err(Some("true"))
+        );
+        assert_eq!(
+            convert::<bool>("FALSE")
+                .map_err(|err| err.suggestion()),
+            Err(Some("false"))
+        );
+        for text in ["nul", "NULL", "Nne", "none"] {
+            assert_eq!(
+                convert::<Option<u8>>(text)
+                    .map_err(|err| err.suggestion()),
+                Err(Some("None")),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            convert::<Option<u8>>("Sme(1)")
+                .map_err(|err| err.suggestion()),
+            Err(Some("Some"))
+        );
+
+        // Words that aren't near a keyword are left alone
+        for text in ["banana", "yes", "Trust", "Nothing"] {
+            assert_eq!(
+                convert::<Option<bool>>(text),
+                Err(ConvertError::Mismatch {
+                    expected: "bool",
+                    found: "an identifier",
+                }),
+                "{text}"
+            );
+        }
+
+        assert_eq!(
+            convert::<Vec<Option<bool>>>("[None, Nne]")
+                .unwrap_err()
+                .to_string(),
+            "[1]: expected bool, found `Nne`; help: did you mean \
+             `None`?"
+        );
+    }
+
+    #[test]
+    fn converts_containers() {
+        assert_eq!(
+            convert::<Vec<Option<f64>>>("[Some(1.5), None, 2]"),
+            Ok(vec![Some(1.5), None, Some(2.)])
+        );
+        assert_eq!(
+            convert::<(u8, String, Vec<char>)>(
+                r#"(1, "a", ['b'])"#
+            ),
+            Ok((1, "a".into(), vec!['b']))
+        );
+        assert_eq!(
+            convert::<Vec<(u8, u8)>>("[(1, 2), [3, 4]]"),
+            Ok(vec![(1, 2), (3, 4)])
+        );
+        assert_eq!(
+            convert::<Vec<Vec<u16>>>("[[1], [2, 70000]]")
+                .unwrap_err()
+                .to_string(),
+            "[1][1]: 70000 is out of range for u16"
+        );
+        assert_eq!(
+            convert::<(u8, u8)>("(1, 2, 3)"),
+            Err(ConvertError::WrongLength {
+                expected: 2,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn converts_back_and_forth() {
+        let value = (
+            -1i8,
+            u64::MAX,
+            i128::MIN,
+            vec![Some('x'), None],
+            "text".to_owned(),
+            (true, 0.25f32),
+        );
+        let tson = value.to_tson();
+        assert_eq!(
+            tson.to_string(),
+            r#"(-1, 18446744073709551615, -170141183460469231731687303715884105728, [Some('x'), None], "text", (true, 0.25))"#
+        );
+        assert_eq!(FromTson::from_tson(&tson), Ok(value));
+        assert_eq!(
+            parse_f64s(&tson.to_string()),
+            Ok(("", tson))
+        );
+    }
+
+    #[test]
+    fn converts_with_try_from() {
+        let value = from_str(
+            r#"[Some((1.5, "a")), None, Some((2, "b"))]"#,
+        )
+        .unwrap();
+        let expected = vec![
+            Some((1.5, "a".into())),
+            None,
+            Some((2., "b".into())),
+        ];
+        assert_eq!(
+            Vec::<Option<(f64, String)>>::try_from(&value),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            Vec::<Option<(f64, String)>>::try_from(value),
+            Ok(expected)
+        );
+
+        let value =
+            from_str(r#"("text", 'c', true, -3)"#).unwrap();
+        assert_eq!(
+            <(String, char, bool, i64)>::try_from(&value),
+            Ok(("text".into(), 'c', true, -3))
+        );
+        let text: &str =
+            value.get(0).unwrap().try_into().unwrap();
+        assert_eq!(text, "text");
+        assert_eq!(i64::try_from(Value::Integer(7)), Ok(7));
+        assert_eq!(
+            i64::try_from(&Value::Float(1.)),
+            Err(ConvertError::mismatch(
+                "i64",
+                &Value::Float(1.)
+            ))
+        );
+        assert_eq!(Value::Float(1.).as_i64(), None);
+        assert_eq!(
+            String::try_from(Value::Char('x')),
+            Ok("x".into())
+        );
+    }
+
+    #[test]
+    fn fails_to_convert_with_try_from_at_paths() {
+        let message = |err: ConvertError| err.to_string();
+
+        let value = from_str(r#"[1, 2, "x"]"#).unwrap();
+        assert_eq!(
+            Vec::<f64>::try_from(&value).map_err(message),
+            Err("[2]: expected f64, found a string".into())
+        );
+
+        let value = from_str(r#"[None, Some((1, 2))]"#).unwrap();
+        assert_eq!(
+            Vec::<Option<(f64, String)>>::try_from(value)
+                .map_err(message),
+            Err("[1][1]: expected String, found an integer"
+                .into())
+        );
+
+        let value = from_str("('a', 1)").unwrap();
+        assert_eq!(
+            <&str>::try_from(value.get(0).unwrap()),
+            Err(ConvertError::Mismatch {
+                expected: "&str",
+                found: "a char"
+            })
+        );
+        assert_eq!(
+            bool::try_from(&value).map_err(message),
+            Err("expected bool, found a tuple".into())
+        );
+        assert_eq!(
+            <(char, u8, u8)>::try_from(&value),
+            Err(ConvertError::WrongLength {
+                expected: 3,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn builds_values_with_from() {
+        assert_eq!(Value::from(-1i8), Value::Integer(-1));
+        assert_eq!(Value::from(u64::MAX), Value::UInt(u64::MAX));
+        assert_eq!(Value::from(0.5), Value::Float(0.5));
+        assert_eq!(Value::from(()), Value::Tuple(vec![]));
+        assert_eq!(
+            Value::from("a"),
+            Value::String(Cow::Borrowed("a"))
+        );
+        assert_eq!(
+            Value::from(String::from("a")),
+            Value::String(Cow::Owned("a".into()))
+        );
+        assert_eq!(
+            Value::from(Some(Some(true))),
+            Value::Optional(Some(Box::new(Value::Optional(
+                Some(Box::new(Value::Boolean(true)))
+            ))))
+        );
+        assert_eq!(
+            Value::from(vec![vec![1u8], vec![]]).to_string(),
+            "[[1], []]"
+        );
+        assert_eq!(
+            "ab".chars().collect::<Value<'_>>().to_string(),
+            "['a', 'b']"
+        );
+        assert_eq!(
+            core::iter::empty::<bool>().collect::<Value<'_>>(),
+            Value::List(vec![])
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use std::{borrow::Cow, fmt, io};
+
+use crate::Value;
+
+/// An error from [`Value::to_csv`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CsvError {
+    /// Only a `Value::List` can be written as CSV.
+    NotAList,
+    /// The element at `row` of the list isn't a struct, an
+    /// object or a map with string keys.
+    NotARecord { row: usize },
+    /// Writing to the writer failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::NotAList => {
+                f.write_str("only a list of records can be written as CSV")
+            }
+            CsvError::NotARecord { row } => write!(
+                f,
+                "row {row} isn't a struct, an object or a map with string keys"
+            ),
+            CsvError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {
+    fn source(
+        &self,
+    ) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CsvError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CsvError {
+    fn from(err: io::Error) -> Self {
+        CsvError::Io(err)
+    }
+}
+
+/// The fields of a record, by name.
+type Record<'v, 'a> = Vec<(Cow<'v, str>, &'v Value<'a>)>;
+
+impl Value<'_> {
+    /// Writes a list of records, such as
+    /// `[Row { id: 1, name: "a" }, Row { id: 2, name: "b" }]`,
+    /// to `writer` as CSV, with a header row naming the fields.
+    ///
+    /// Each record may be a struct, an object or a map whose
+    /// keys are strings. The columns are every field any record
+    /// has, in the order they first appear, with the keys of
+    /// each object sorted since objects have no order. A
+    /// record without one of the fields leaves that cell
+    /// empty.
+    ///
+    /// Strings, chars and identifiers are written as their text,
+    /// `None` as an empty cell and `Some(value)` as the value.
+    /// Anything else, including nested lists and structs, is
+    /// written as its tson text, as `Display` writes it. Cells
+    /// holding a comma, a quote or a line break are quoted, with
+    /// quotes doubled, and rows end in `\n`.
+    ///
+    /// An empty list writes nothing, having no fields to name.
+    pub fn to_csv<W: io::Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<(), CsvError> {
+        let Value::List(rows) = self else {
+            return Err(CsvError::NotAList);
+        };
+
+        let records = rows
+            .iter()
+            .enumerate()
+            .map(|(row, value)| {
+                record(value).ok_or(CsvError::NotARecord { row })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut columns: Vec<&str> = vec![];
+        for (name, _) in records.iter().flatten() {
+            if !columns.contains(&&**name) {
+                columns.push(name);
+            }
+        }
+        if columns.is_empty() {
+            return Ok(());
+        }
+
+        write_row(
+            writer,
+            columns.iter().map(|&name| name.into()),
+        )?;
+        for record in &records {
+            let cells = columns.iter().map(|&column| {
+                record
+                    .iter()
+                    .find(|(name, _)| name == column)
+                    .map_or(Cow::Borrowed(""), |(_, value)| {
+                        cell(value)
+                    })
+            });
+            write_row(writer, cells)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the fields of a struct, object or map with string
+/// keys.
+fn record<'v, 'a>(
+    value: &'v Value<'a>,
+) -> Option<Record<'v, 'a>> {
+    match value {
+        Value::Struct { fields, .. } => Some(
+            fields
+                .iter()
+                .map(|(name, value)| {
+                    (Cow::Borrowed(&**name), value)
+                })
+                .collect(),
+        ),
+        Value::Object(object) => {
+            let mut fields: Record = object
+                .iter()
+                .map(|(name, value)| {
+                    (Cow::Borrowed(&**name), value)
+                })
+                .collect();
+            fields.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+            Some(fields)
+        }
+        Value::Map(entries) => entries
+            .iter()
+            .map(|(key, value)| {
+                let name = match key {
+                    Value::String(key) => Cow::Borrowed(&**key),
+                    Value::Char(key) => {
+                        Cow::Owned(key.to_string())
+                    }
+                    _ => return None,
+                };
+
+                Some((name, value))
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Returns the text of the cell holding `value`.
+fn cell<'v>(value: &'v Value<'_>) -> Cow<'v, str> {
+    match value {
+        Value::String(string) => Cow::Borrowed(string),
+        Value::Char(chr) => Cow::Owned(chr.to_string()),
+        Value::Ident(ident) => Cow::Borrowed(ident),
+        Value::Optional(None) => Cow::Borrowed(""),
+        Value::Optional(Some(value)) => cell(value),
+        value => Cow::Owned(value.to_string()),
+    }
+}
+
+fn write_row<'c, W: io::Write>(
+    w: &mut W,
+    cells: impl Iterator<Item = Cow<'c, str>>,
+) -> io::Result<()> {
+    for (idx, cell) in cells.enumerate() {
+        if idx > 0 {
+            w.write_all(b",")?;
+        }
+
+        if cell.contains([',', '"', '\n', '\r']) {
+            write!(w, "\"{}\"", cell.replace('"', "\"\""))?;
+        } else {
+            w.write_all(cell.as_bytes())?;
+        }
+    }
+
+    w.write_all(b"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_value, CsvError};
+
+    fn csv(text: &str) -> Result<String, CsvError> {
+        let (rest, value) = parse_value(text).unwrap();
+        assert_eq!(rest, "", "{text}");
+
+        let mut out = Vec::new();
+        value.to_csv(&mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn writes_records() {
+        let cases = [
+            (
+                r#"[Row { id: 1, name: "a" }, Row { id: 2, name: "b" }]"#,
+                "id,name\n1,a\n2,b\n",
+            ),
+            (
+                r#"[{"b": 1, "a": 'x'}, {"c": None, "a": Some(2.5)}]"#,
+                "a,b,c\nx,1,\n2.5,,\n",
+            ),
+            (
+                r#"[Row { id: 1 }, Row { name: "b", extra: Ok(Red) }, { "id": 3 }]"#,
+                "id,name,extra\n1,,\n,b,Ok(Red)\n3,,\n",
+            ),
+            (
+                r#"[Row { tags: ["x", "y"], point: Point(1, 2) }]"#,
+                "tags,point\n\"[\"\"x\"\", \"\"y\"\"]\",\"Point(1, 2)\"\n",
+            ),
+            (
+                r#"[Row { kind: Warning, at: 1..5, flag: true }]"#,
+                "kind,at,flag\nWarning,1..5,true\n",
+            ),
+            ("[]", ""),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(csv(text).unwrap(), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn quotes_cells() {
+        assert_eq!(
+            csv(r#"[Row { text: "a, b", quote: "say \"hi\"", lines: "1\n2\r\n3", plain: "a b" }]"#)
+                .unwrap(),
+            "text,quote,lines,plain\n\"a, b\",\"say \"\"hi\"\"\",\"1\n2\r\n3\",a b\n"
+        );
+        assert_eq!(
+            csv(r#"[{"a,b": 1}]"#).unwrap(),
+            "\"a,b\"\n1\n"
+        );
+    }
+
+    #[test]
+    fn rejects_values_that_arent_records() {
+        assert!(matches!(
+            csv("Row { id: 1 }"),
+            Err(CsvError::NotAList)
+        ));
+        assert!(matches!(
+            csv("[Row { id: 1 }, 2]"),
+            Err(CsvError::NotARecord { row: 1 })
+        ));
+        assert!(matches!(
+            csv("[{1: 'x'}]"),
+            Err(CsvError::NotARecord { row: 0 })
+        ));
+        assert_eq!(
+            CsvError::NotARecord { row: 1 }.to_string(),
+            "row 1 isn't a struct, an object or a map with string keys"
+        );
+    }
+}
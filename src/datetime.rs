@@ -0,0 +1,328 @@
+use core::{fmt, str::FromStr};
+
+use crate::Value;
+
+/// A date and time with an offset from UTC, in the RFC 3339
+/// form `chrono` and `time` print them in, such as
+/// `2024-01-02T03:04:05.5+01:00` or `2024-01-02T03:04:05Z`.
+///
+/// Two datetimes are equal when they were written with the same
+/// fields, so `10:00:00Z` and `11:00:00+01:00` are not, and
+/// neither are `Z` and `-00:00`, which RFC 3339 uses for an
+/// unknown offset.
+///
+/// Datetimes are ordered by their fields in turn, from the year
+/// down to the offset, which is in time order only for datetimes
+/// with the same offset.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+pub struct DateTime {
+    pub year: u16,
+    /// From 1 to 12.
+    pub month: u8,
+    /// From 1 to the number of days in the month.
+    pub day: u8,
+    /// From 0 to 23.
+    pub hour: u8,
+    /// From 0 to 59.
+    pub minute: u8,
+    /// From 0 to 59, or 60 for a leap second.
+    pub second: u8,
+    /// The fraction of the second, from 0 to 999,999,999.
+    pub nanosecond: u32,
+    /// The offset from UTC in minutes, or `None` for `Z`.
+    pub offset: Option<i16>,
+}
+
+/// An error parsing a [`DateTime`] from text that isn't one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidDateTime;
+
+impl fmt::Display for InvalidDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid RFC 3339 datetime")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidDateTime {}
+
+impl FromStr for DateTime {
+    type Err = InvalidDateTime;
+
+    /// Parses an RFC 3339 datetime, also accepting a lowercase
+    /// `t` and `z` and a space between the date and the time, as
+    /// RFC 3339 allows. The fraction of a second may have up to
+    /// nine digits.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let bytes = text.as_bytes();
+        if bytes.len() < 20
+            || bytes[4] != b'-'
+            || bytes[7] != b'-'
+            || !matches!(bytes[10], b'T' | b't' | b' ')
+            || bytes[13] != b':'
+            || bytes[16] != b':'
+        {
+            return Err(InvalidDateTime);
+        }
+
+        let year = digits(&bytes[0..4])?;
+        let month = digits(&bytes[5..7])? as u8;
+        let day = digits(&bytes[8..10])? as u8;
+        let hour = digits(&bytes[11..13])? as u8;
+        let minute = digits(&bytes[14..16])? as u8;
+        let second = digits(&bytes[17..19])? as u8;
+
+        let mut rest = &bytes[19..];
+        let mut nanosecond = 0;
+        if let [b'.', fraction @ ..] = rest {
+            let len = fraction
+                .iter()
+                .take_while(|byte| byte.is_ascii_digit())
+                .count();
+            if !(1..=9).contains(&len) {
+                return Err(InvalidDateTime);
+            }
+            nanosecond = digits(&fraction[..len])?
+                * 10u32.pow(9 - len as u32);
+            rest = &fraction[len..];
+        }
+
+        let offset = match rest {
+            [b'Z' | b'z'] => None,
+            [sign @ (b'+' | b'-'), hours @ .., b':', m1, m2]
+                if hours.len() == 2 =>
+            {
+                let hours = digits(hours)?;
+                let minutes = digits(&[*m1, *m2])?;
+                if hours > 23 || minutes > 59 {
+                    return Err(InvalidDateTime);
+                }
+                let offset = (hours * 60 + minutes) as i16;
+                Some(if *sign == b'-' {
+                    -offset
+                } else {
+                    offset
+                })
+            }
+            _ => return Err(InvalidDateTime),
+        };
+
+        let datetime = DateTime {
+            year: year as u16,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            nanosecond,
+            offset,
+        };
+        if !(1..=12).contains(&month)
+            || day == 0
+            || day > datetime.days_in_month()
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return Err(InvalidDateTime);
+        }
+
+        Ok(datetime)
+    }
+}
+
+/// Reads ASCII digits as a number.
+fn digits(bytes: &[u8]) -> Result<u32, InvalidDateTime> {
+    bytes.iter().try_fold(0, |number, byte| match byte {
+        b'0'..=b'9' => Ok(number * 10 + u32::from(byte - b'0')),
+        _ => Err(InvalidDateTime),
+    })
+}
+
+impl DateTime {
+    fn days_in_month(&self) -> u8 {
+        let leap = self.year.is_multiple_of(4)
+            && (!self.year.is_multiple_of(100)
+                || self.year.is_multiple_of(400));
+        match self.month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+}
+
+/// Writes the datetime in RFC 3339 form, with an uppercase `T`
+/// and `Z`, and the fraction of the second only when it isn't
+/// zero, without trailing zeros.
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year,
+            self.month,
+            self.day,
+            self.hour,
+            self.minute,
+            self.second
+        )?;
+
+        if self.nanosecond > 0 {
+            let mut fraction = self.nanosecond;
+            let mut width = 9;
+            while fraction.is_multiple_of(10) {
+                fraction /= 10;
+                width -= 1;
+            }
+            write!(f, ".{fraction:0width$}")?;
+        }
+
+        match self.offset {
+            None => f.write_str("Z"),
+            Some(offset) => write!(
+                f,
+                "{}{:02}:{:02}",
+                if offset < 0 { '-' } else { '+' },
+                offset.unsigned_abs() / 60,
+                offset.unsigned_abs() % 60
+            ),
+        }
+    }
+}
+
+impl Value<'_> {
+    /// Returns the datetime this value holds, whether it was
+    /// parsed as a `Value::DateTime` or is a `Value::String`
+    /// holding one.
+    pub fn as_datetime(&self) -> Option<DateTime> {
+        match self {
+            Value::DateTime(datetime) => Some(*datetime),
+            Value::String(string) => string.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{DateTime, InvalidDateTime};
+    use crate::{
+        parse_value, parse_value_with, ParserOptions, Value,
+    };
+
+    #[test]
+    fn parses_datetimes() {
+        let cases = [
+            (
+                "2024-01-02T03:04:05Z",
+                DateTime {
+                    year: 2024,
+                    month: 1,
+                    day: 2,
+                    hour: 3,
+                    minute: 4,
+                    second: 5,
+                    nanosecond: 0,
+                    offset: None,
+                },
+                "2024-01-02T03:04:05Z",
+            ),
+            (
+                "2024-02-29t23:59:60.250z",
+                DateTime {
+                    year: 2024,
+                    month: 2,
+                    day: 29,
+                    hour: 23,
+                    minute: 59,
+                    second: 60,
+                    nanosecond: 250_000_000,
+                    offset: None,
+                },
+                "2024-02-29T23:59:60.25Z",
+            ),
+            (
+                "1999-12-31 00:00:00.000000001-05:30",
+                DateTime {
+                    year: 1999,
+                    month: 12,
+                    day: 31,
+                    hour: 0,
+                    minute: 0,
+                    second: 0,
+                    nanosecond: 1,
+                    offset: Some(-330),
+                },
+                "1999-12-31T00:00:00.000000001-05:30",
+            ),
+        ];
+
+        for (text, datetime, written) in cases {
+            assert_eq!(text.parse(), Ok(datetime), "{text}");
+            assert_eq!(datetime.to_string(), written);
+            assert_eq!(written.parse(), Ok(datetime));
+        }
+
+        for text in [
+            "2023-02-29T00:00:00Z",
+            "2024-13-01T00:00:00Z",
+            "2024-01-01T24:00:00Z",
+            "2024-01-01T00:00:00",
+            "2024-01-01T00:00:00.Z",
+            "2024-01-01T00:00:00.1234567890Z",
+            "2024-01-01T00:00:00+1:00",
+            "2024-01-01T00:00:00+01:60",
+            "2024-1-01T00:00:00Z",
+            "2024-01-01T00:00:00Z ",
+            "not a datetime at all",
+        ] {
+            assert_eq!(
+                text.parse::<DateTime>(),
+                Err(InvalidDateTime),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_datetime_strings_when_asked() {
+        let options = ParserOptions::default().datetimes(true);
+        let text = r#"Event { at: "2024-01-02T03:04:05.5+01:00", note: "2024-02-30T00:00:00Z", tags: ["x"] }"#;
+
+        let (_, value) =
+            parse_value_with(text, options).unwrap();
+        let Value::Struct { fields, .. } = &value else {
+            panic!("{value:?}");
+        };
+        assert!(matches!(fields[0].1, Value::DateTime(_)));
+        assert_eq!(
+            fields[1].1,
+            Value::String("2024-02-30T00:00:00Z".into())
+        );
+        assert_eq!(value.to_string(), text);
+        assert_eq!(
+            parse_value_with(&value.to_string(), options),
+            Ok(("", value.clone()))
+        );
+
+        let (_, plain) = parse_value(text).unwrap();
+        let Value::Struct { fields: plain, .. } = &plain else {
+            panic!("{plain:?}");
+        };
+        assert_eq!(
+            plain[0].1,
+            Value::String("2024-01-02T03:04:05.5+01:00".into())
+        );
+        assert_eq!(
+            plain[0].1.as_datetime(),
+            fields[0].1.as_datetime()
+        );
+        assert_eq!(plain[2].1.as_datetime(), None);
+    }
+}
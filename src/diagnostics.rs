@@ -0,0 +1,311 @@
+use alloc::string::{String, ToString};
+use core::fmt::{self, Write};
+
+use crate::{document::parse_key, Error, ErrorKind};
+
+impl Error {
+    /// Renders the error for a person to read, with the line of
+    /// `source` it is on and a caret under what is wrong, as
+    /// `rustc` and `miette` do:
+    ///
+    /// ```text
+    /// error: unexpected character '2'
+    ///  --> 1:4
+    ///   |
+    /// 1 | [1 2]
+    ///   |    ^ expected ',' or ']'
+    /// ```
+    ///
+    /// `source` should be the text that was parsed. A string or
+    /// comment that is never closed is pointed at by its opening
+    /// delimiter, however many lines up that is. The source line
+    /// is left out when `source` doesn't reach the error, such
+    /// as for bytes that aren't UTF-8.
+    pub fn render(&self, source: &str) -> String {
+        let mut out = String::new();
+        self.write_rendered(&mut out, source)
+            .expect("writing to a String can't fail");
+
+        out
+    }
+
+    fn write_rendered(
+        &self,
+        out: &mut String,
+        source: &str,
+    ) -> fmt::Result {
+        let gutter = " ".repeat(self.line().to_string().len());
+        writeln!(out, "error: {}", self.kind())?;
+        write!(
+            out,
+            "{gutter}--> {}:{}",
+            self.line(),
+            self.column()
+        )?;
+
+        let Some(before) = source.get(..self.offset()) else {
+            return Ok(());
+        };
+        let line_start =
+            before.rfind('\n').map_or(0, |idx| idx + 1);
+        let line = source[line_start..]
+            .split('\n')
+            .next()
+            .unwrap_or_default();
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let start = (self.offset() - line_start).min(line.len());
+        let span =
+            &line[start..][..self.span_len(&line[start..])];
+
+        let pad: String = line[..start]
+            .chars()
+            .map(|chr| match chr {
+                '\t' => "\t".into(),
+                chr => " ".repeat(width(chr)),
+            })
+            .collect();
+        let carets = "^".repeat(
+            span.chars().map(width).sum::<usize>().max(1),
+        );
+
+        writeln!(out)?;
+        writeln!(out, "{gutter} |")?;
+        let space = if line.is_empty() { "" } else { " " };
+        writeln!(out, "{} |{space}{line}", self.line())?;
+        write!(out, "{gutter} | {pad}{carets} ")?;
+        if self.expected().is_empty() {
+            out.write_str(self.label())
+        } else {
+            self.write_expected(out)
+        }
+    }
+
+    /// Returns how many bytes of `rest`, the rest of the line
+    /// the error starts at, to underline.
+    fn span_len(&self, rest: &str) -> usize {
+        let first =
+            rest.chars().next().map_or(0, char::len_utf8);
+        match self.kind() {
+            ErrorKind::UnterminatedString
+            | ErrorKind::StringLimitExceeded => rest
+                .find(['"', '\''])
+                .map_or(first, |idx| idx + 1),
+            ErrorKind::EmptyChar
+            | ErrorKind::UnterminatedComment => {
+                rest.len().min(2)
+            }
+            ErrorKind::InvalidEscape => {
+                let braced = rest
+                    .strip_prefix("\\u{")
+                    .and_then(|_| rest.find('}'))
+                    .map(|idx| idx + 1);
+                braced.unwrap_or_else(|| {
+                    let mut chars = rest.char_indices().skip(1);
+                    chars.next().map_or(first, |(idx, chr)| {
+                        idx + chr.len_utf8()
+                    })
+                })
+            }
+            ErrorKind::NumberTooLarge => {
+                let digits =
+                    rest.strip_prefix('-').unwrap_or(rest);
+                rest.len() - digits.len()
+                    + digits
+                        .find(|chr: char| {
+                            !(chr.is_alphanumeric()
+                                || chr == '_')
+                        })
+                        .unwrap_or(digits.len())
+            }
+            ErrorKind::TrailingInput => rest.trim_end().len(),
+            ErrorKind::DuplicateKey => parse_key(rest)
+                .map_or(first, |(after, _)| {
+                    rest.len() - after.len()
+                }),
+            _ => first,
+        }
+    }
+
+    /// Returns a short note on what is wrong, for errors that
+    /// don't say what was expected instead.
+    fn label(&self) -> &'static str {
+        match self.kind() {
+            ErrorKind::UnexpectedChar(_) => "not expected here",
+            ErrorKind::UnexpectedEnd => "the input ends here",
+            ErrorKind::UnterminatedString => {
+                "this is never closed"
+            }
+            ErrorKind::EmptyChar => "a char can't be empty",
+            ErrorKind::UnterminatedComment => {
+                "this comment is never closed"
+            }
+            ErrorKind::InvalidEscape => "not a valid escape",
+            ErrorKind::InvalidNumber => {
+                "not valid in this number"
+            }
+            ErrorKind::NumberTooLarge => {
+                "too large for its type"
+            }
+            ErrorKind::DepthLimitExceeded => {
+                "the depth limit is reached here"
+            }
+            ErrorKind::NodeLimitExceeded => {
+                "the value limit is reached here"
+            }
+            ErrorKind::StringLimitExceeded => {
+                "this string is too long"
+            }
+            ErrorKind::ElementLimitExceeded => {
+                "the element limit is reached here"
+            }
+            ErrorKind::TrailingInput => {
+                "the value ended before this"
+            }
+            ErrorKind::InvalidUtf8 => "not UTF-8",
+            ErrorKind::DuplicateKey => "this key is already set",
+            #[cfg(feature = "std")]
+            ErrorKind::Io(_) => "reading failed here",
+        }
+    }
+}
+
+/// Returns how many columns `chr` takes up in a terminal: two
+/// for the wide characters of East Asian scripts and for emoji,
+/// and one for anything else.
+fn width(chr: char) -> usize {
+    match chr as u32 {
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{from_slice, parse_document};
+
+    fn render(text: &str) -> String {
+        from_slice(text.as_bytes()).unwrap_err().render(text)
+    }
+
+    #[test]
+    fn renders_errors() {
+        let cases = [
+            (
+                "[1 2]",
+                "\
+error: unexpected character '2'
+ --> 1:4
+  |
+1 | [1 2]
+  |    ^ expected ',' or ']'",
+            ),
+            (
+                "Config {\n    name: \"tson\",\n    port: 300u8,\n}",
+                "\
+error: number too large
+ --> 3:11
+  |
+3 |     port: 300u8,
+  |           ^^^^^ too large for its type",
+            ),
+            (
+                "[\n    \"a\\qb\",\n]",
+                "\
+error: invalid escape sequence
+ --> 2:7
+  |
+2 |     \"a\\qb\",
+  |       ^^ not a valid escape",
+            ),
+            (
+                "Point(1, 2) Point(3, 4)\r\n",
+                "\
+error: unexpected input after the value
+ --> 1:13
+  |
+1 | Point(1, 2) Point(3, 4)
+  |             ^^^^^^^^^^^ the value ended before this",
+            ),
+            (
+                "{\n\t\"名前\": 1 2\n}",
+                "\
+error: unexpected character '2'
+ --> 2:10
+  |
+2 | \t\"名前\": 1 2
+  | \t          ^ expected ',' or '}'",
+            ),
+            (
+                "[1,\n 2,\n",
+                "\
+error: unexpected end of input
+ --> 3:1
+  |
+3 |
+  | ^ expected a value or ']'",
+            ),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(render(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn points_at_the_opening_delimiter() {
+        assert_eq!(
+            render("Doc {\n    body: r#\"one\n        two\n        three\"\n}"),
+            "\
+error: unterminated string
+ --> 2:11
+  |
+2 |     body: r#\"one
+  |           ^^^ this is never closed"
+        );
+        assert_eq!(
+            render("1 /* a\n\n\n"),
+            "\
+error: unterminated block comment
+ --> 1:3
+  |
+1 | 1 /* a
+  |   ^^ this comment is never closed"
+        );
+    }
+
+    #[test]
+    fn underlines_duplicate_keys() {
+        let text = "port = 1\n\"port\" = 2 // again\n";
+        assert_eq!(
+            parse_document(text).unwrap_err().render(text),
+            "\
+error: duplicate key
+ --> 2:1
+  |
+2 | \"port\" = 2 // again
+  | ^^^^^^ this key is already set"
+        );
+    }
+
+    #[test]
+    fn renders_errors_past_the_source() {
+        let err = from_slice(b"[\"\xff\"]").unwrap_err();
+        assert_eq!(
+            err.render("["),
+            "error: invalid UTF-8\n --> 1:3"
+        );
+    }
+}
@@ -0,0 +1,644 @@
+use alloc::{borrow::Cow, vec, vec::Vec};
+use core::{cmp::Ordering, fmt, ops::Range};
+
+use crate::{display::sorted, Path, PathSegment, Value};
+
+/// Options for [`Value::diff_with`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct DiffOptions {
+    /// How far apart two floats may be and still count as the
+    /// same. Defaults to 0, so that only equal floats do, but
+    /// `NaN` always counts as the same as `NaN`.
+    pub epsilon: f64,
+    /// Line up the elements of lists, tuples and sets by their
+    /// longest common subsequence, so that an element added or
+    /// removed in the middle shows up as just that, rather than
+    /// as each element after it changing. This takes as long as
+    /// comparing every element of one with every element of the
+    /// other.
+    pub align_lists: bool,
+}
+
+impl DiffOptions {
+    pub fn epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn align_lists(mut self, align_lists: bool) -> Self {
+        self.align_lists = align_lists;
+        self
+    }
+}
+
+/// A difference found by [`Value::diff`], at the end of `path`
+/// in both values.
+///
+/// It displays as the path followed by what changed, as in
+/// `items.2.name: "a" != "b"`, `items.3: added 'x'` or
+/// `retries: removed 3`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difference<'v, 'a> {
+    pub path: Path<'v>,
+    pub change: Change<'v, 'a>,
+}
+
+/// What differs between two values at a [`Difference`]'s path.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Change<'v, 'a> {
+    /// Only the value compared with has this.
+    Added(&'v Value<'a>),
+    /// Only the value compared has this.
+    Removed(&'v Value<'a>),
+    /// The value compared has `left` where the one compared
+    /// with has `right`.
+    Changed {
+        left: &'v Value<'a>,
+        right: &'v Value<'a>,
+    },
+}
+
+impl fmt::Display for Difference<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.path.segments().is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+
+        match self.change {
+            Change::Added(value) => write!(f, "added {value}"),
+            Change::Removed(value) => {
+                write!(f, "removed {value}")
+            }
+            Change::Changed { left, right } => {
+                write!(f, "{left} != {right}")
+            }
+        }
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Returns where this value and `other` differ, and how, so
+    /// that a test can show what changed rather than two whole
+    /// values.
+    ///
+    /// Objects, structs of the same name and maps keyed by
+    /// strings are compared key by key, finding what only one of
+    /// them has, in the order of the keys for objects and in the
+    /// order they were written otherwise. Lists, tuples, sets
+    /// and tuple structs of the same name are compared element
+    /// by element, and two `Some`s by what they hold, at the
+    /// same path as the `Some`, as [`Value::get_path`] reads it.
+    /// Anything else that isn't equal is changed as a whole,
+    /// such as two maps that differ under a key that isn't a
+    /// string, which no path could lead to.
+    ///
+    /// An element added or removed is at its position in the
+    /// value it is in, and one that changed at its position in
+    /// this value.
+    pub fn diff<'v>(
+        &'v self,
+        other: &'v Value<'a>,
+    ) -> Vec<Difference<'v, 'a>> {
+        self.diff_with(other, DiffOptions::default())
+    }
+
+    /// Returns where this value and `other` differ like
+    /// [`Value::diff`] does, with `options` deciding how floats
+    /// and lists are compared.
+    pub fn diff_with<'v>(
+        &'v self,
+        other: &'v Value<'a>,
+        options: DiffOptions,
+    ) -> Vec<Difference<'v, 'a>> {
+        let mut differences = Vec::new();
+        diff(
+            self,
+            other,
+            &Path::default(),
+            options,
+            &mut differences,
+        );
+        differences
+    }
+}
+
+fn diff<'v, 'a>(
+    left: &'v Value<'a>,
+    right: &'v Value<'a>,
+    path: &Path<'v>,
+    options: DiffOptions,
+    out: &mut Vec<Difference<'v, 'a>>,
+) {
+    match (left, right) {
+        (
+            Value::Optional(Some(left)),
+            Value::Optional(Some(right)),
+        ) => diff(left, right, path, options, out),
+        (Value::Object(left), Value::Object(right)) => objects(
+            sorted(left),
+            sorted(right),
+            path,
+            options,
+            out,
+        ),
+        (
+            Value::Struct {
+                name,
+                fields: left,
+                non_exhaustive,
+            },
+            Value::Struct {
+                name: other_name,
+                fields: right,
+                non_exhaustive: other_non_exhaustive,
+            },
+        ) if name == other_name
+            && non_exhaustive == other_non_exhaustive =>
+        {
+            let entries =
+                |fields: &'v [(Cow<'a, str>, Value<'a>)]| {
+                    fields
+                        .iter()
+                        .map(|(field, value)| (&**field, value))
+                        .collect()
+                };
+            keyed(
+                entries(left),
+                entries(right),
+                path,
+                options,
+                out,
+            )
+        }
+        (Value::Map(left), Value::Map(right))
+            if string_keyed(left) && string_keyed(right) =>
+        {
+            let entries = |entries: &'v [(
+                Value<'a>,
+                Value<'a>,
+            )]|
+             -> Vec<_> {
+                entries
+                    .iter()
+                    .filter_map(|(key, value)| {
+                        Some((key.as_str()?, value))
+                    })
+                    .collect()
+            };
+            keyed(
+                entries(left),
+                entries(right),
+                path,
+                options,
+                out,
+            )
+        }
+        (Value::List(left), Value::List(right))
+        | (Value::Tuple(left), Value::Tuple(right))
+        | (Value::Set(left), Value::Set(right)) => {
+            elements(left, right, path, options, out)
+        }
+        (
+            Value::TupleStruct { name, fields: left },
+            Value::TupleStruct {
+                name: other_name,
+                fields: right,
+            },
+        ) if name == other_name => {
+            elements(left, right, path, options, out)
+        }
+        _ if same_scalar(left, right, options) => {}
+        _ => out.push(Difference {
+            path: path.clone(),
+            change: Change::Changed { left, right },
+        }),
+    }
+}
+
+/// Returns `true` if there is no difference between `left` and
+/// `right`.
+fn same(
+    left: &Value<'_>,
+    right: &Value<'_>,
+    options: DiffOptions,
+) -> bool {
+    let mut differences = Vec::new();
+    diff(
+        left,
+        right,
+        &Path::default(),
+        options,
+        &mut differences,
+    );
+    differences.is_empty()
+}
+
+fn same_scalar(
+    left: &Value<'_>,
+    right: &Value<'_>,
+    options: DiffOptions,
+) -> bool {
+    if left == right {
+        return true;
+    }
+
+    match (float(left), float(right)) {
+        (Some(left), Some(right)) => {
+            (left.is_nan() && right.is_nan())
+                || (left - right).abs() <= options.epsilon
+        }
+        _ => false,
+    }
+}
+
+fn float(value: &Value<'_>) -> Option<f64> {
+    match value {
+        Value::Float(float) => Some(*float),
+        #[cfg(feature = "arbitrary-precision")]
+        Value::RawNumber(_) => value.as_f64(),
+        _ => None,
+    }
+}
+
+fn string_keyed(entries: &[(Value<'_>, Value<'_>)]) -> bool {
+    entries.iter().all(|(key, _)| key.as_str().is_some())
+}
+
+/// Compares the entries of two objects, each sorted by key.
+fn objects<'v, 'a>(
+    left: Vec<(&'v Cow<'a, str>, &'v Value<'a>)>,
+    right: Vec<(&'v Cow<'a, str>, &'v Value<'a>)>,
+    path: &Path<'v>,
+    options: DiffOptions,
+    out: &mut Vec<Difference<'v, 'a>>,
+) {
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    loop {
+        let order = match (left.peek(), right.peek()) {
+            (Some((left, _)), Some((right, _))) => {
+                left.cmp(right)
+            }
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+        match order {
+            Ordering::Less => {
+                let (key, value) = left.next().unwrap();
+                out.push(Difference {
+                    path: path.join(PathSegment::Key(key)),
+                    change: Change::Removed(value),
+                });
+            }
+            Ordering::Greater => {
+                let (key, value) = right.next().unwrap();
+                out.push(Difference {
+                    path: path.join(PathSegment::Key(key)),
+                    change: Change::Added(value),
+                });
+            }
+            Ordering::Equal => {
+                let (key, left) = left.next().unwrap();
+                let (_, right) = right.next().unwrap();
+                let path = path.join(PathSegment::Key(key));
+                diff(left, right, &path, options, out);
+            }
+        }
+    }
+}
+
+/// Compares the entries of two structs or maps, matching the
+/// first of each key in one with the first in the other.
+fn keyed<'v, 'a>(
+    left: Vec<(&'v str, &'v Value<'a>)>,
+    right: Vec<(&'v str, &'v Value<'a>)>,
+    path: &Path<'v>,
+    options: DiffOptions,
+    out: &mut Vec<Difference<'v, 'a>>,
+) {
+    let first = |entries: &[(&str, &'v Value<'a>)],
+                 key: &str| {
+        entries
+            .iter()
+            .find(|(other, _)| *other == key)
+            .map(|(_, value)| *value)
+    };
+
+    for (idx, &(key, value)) in left.iter().enumerate() {
+        if first(&left[..idx], key).is_some() {
+            continue;
+        }
+        let path = path.join(PathSegment::Key(key));
+        match first(&right, key) {
+            Some(right) => {
+                diff(value, right, &path, options, out)
+            }
+            None => out.push(Difference {
+                path,
+                change: Change::Removed(value),
+            }),
+        }
+    }
+    for (idx, &(key, value)) in right.iter().enumerate() {
+        if first(&right[..idx], key).is_none()
+            && first(&left, key).is_none()
+        {
+            out.push(Difference {
+                path: path.join(PathSegment::Key(key)),
+                change: Change::Added(value),
+            });
+        }
+    }
+}
+
+/// Compares the elements of two lists, tuples, sets or tuple
+/// structs.
+fn elements<'v, 'a>(
+    left: &'v [Value<'a>],
+    right: &'v [Value<'a>],
+    path: &Path<'v>,
+    options: DiffOptions,
+    out: &mut Vec<Difference<'v, 'a>>,
+) {
+    let mut run = |left_run: Range<usize>,
+                   right_run: Range<usize>| {
+        unaligned(
+            (&left[left_run.clone()], left_run.start),
+            (&right[right_run.clone()], right_run.start),
+            path,
+            options,
+            out,
+        )
+    };
+    if !options.align_lists {
+        return run(0..left.len(), 0..right.len());
+    }
+
+    // `common[i * width + j]` is the length of the longest
+    // common subsequence of `left[i..]` and `right[j..]`
+    let width = right.len() + 1;
+    let mut common = vec![0; (left.len() + 1) * width];
+    let mut matches = vec![false; left.len() * right.len()];
+    for i in (0..left.len()).rev() {
+        for j in (0..right.len()).rev() {
+            let matched = same(&left[i], &right[j], options);
+            matches[i * right.len() + j] = matched;
+            common[i * width + j] = if matched {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j]
+                    .max(common[i * width + j + 1])
+            };
+        }
+    }
+
+    // Everything between two matched elements has changed
+    let (mut i, mut j) = (0, 0);
+    let (mut left_start, mut right_start) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if matches[i * right.len() + j] {
+            run(left_start..i, right_start..j);
+            i += 1;
+            j += 1;
+            (left_start, right_start) = (i, j);
+        } else if common[(i + 1) * width + j]
+            >= common[i * width + j + 1]
+        {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    run(left_start..left.len(), right_start..right.len());
+}
+
+/// Compares two runs of elements starting at the given
+/// positions by position, the ones only one of them has having
+/// been added or removed.
+fn unaligned<'v, 'a>(
+    (left, left_start): (&'v [Value<'a>], usize),
+    (right, right_start): (&'v [Value<'a>], usize),
+    path: &Path<'v>,
+    options: DiffOptions,
+    out: &mut Vec<Difference<'v, 'a>>,
+) {
+    for (idx, (left, right)) in
+        left.iter().zip(right).enumerate()
+    {
+        let path =
+            path.join(PathSegment::Index(left_start + idx));
+        diff(left, right, &path, options, out);
+    }
+
+    let paired = left.len().min(right.len());
+    for (idx, value) in left.iter().enumerate().skip(paired) {
+        out.push(Difference {
+            path: path
+                .join(PathSegment::Index(left_start + idx)),
+            change: Change::Removed(value),
+        });
+    }
+    for (idx, value) in right.iter().enumerate().skip(paired) {
+        out.push(Difference {
+            path: path
+                .join(PathSegment::Index(right_start + idx)),
+            change: Change::Added(value),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        string::{String, ToString},
+        vec::Vec,
+    };
+
+    use crate::{from_str, tson, Change, DiffOptions, Value};
+
+    fn written(
+        left: &str,
+        right: &str,
+        options: DiffOptions,
+    ) -> Vec<String> {
+        let left = from_str(left).unwrap();
+        let right = from_str(right).unwrap();
+        left.diff_with(&right, options)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn diff(left: &str, right: &str) -> Vec<String> {
+        written(left, right, DiffOptions::default())
+    }
+
+    #[test]
+    fn finds_differences_by_path() {
+        let expected = r#"State {
+            items: [Item { name: "a" }, Item { name: "b" }, Item { name: "c" }],
+            owner: Some(User { id: 1, roles: {admin} }),
+            counts: {"x": 1, "y": 2, "z": 3},
+            weights: {"light": 1, "heavy": 9},
+            point: Point(1, 2),
+            status: Ok(()),
+        }"#;
+        let actual = r#"State {
+            items: [Item { name: "a" }, Item { name: "b" }, Item { name: "d" }, Item { name: "e" }],
+            owner: Some(User { id: 2, roles: {admin, guest} }),
+            counts: {"w": 0, "x": 1, "z": 4},
+            weights: {"heavy": 9, "light": 1},
+            point: Point(1, 2, 3),
+            status: Err(()),
+            extra: true,
+        }"#;
+
+        assert_eq!(
+            diff(expected, actual),
+            [
+                r#"items.2.name: "c" != "d""#,
+                r#"items.3: added Item { name: "e" }"#,
+                "owner.id: 1 != 2",
+                "owner.roles.1: added guest",
+                "counts.w: added 0",
+                "counts.y: removed 2",
+                "counts.z: 3 != 4",
+                "point.2: added 3",
+                "status: Ok(()) != Err(())",
+                "extra: added true",
+            ]
+        );
+        assert_eq!(
+            diff(actual, expected)[..2],
+            [
+                r#"items.2.name: "d" != "c""#,
+                r#"items.3: removed Item { name: "e" }"#
+            ]
+        );
+        assert_eq!(
+            diff(expected, expected),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn changes_values_of_other_kinds_as_a_whole() {
+        assert_eq!(diff("1", "1.0"), ["1 != 1.0"]);
+        assert_eq!(diff("Some(1)", "None"), ["Some(1) != None"]);
+        assert_eq!(
+            diff("[Some(1)]", "[Some(2)]"),
+            ["0: 1 != 2"]
+        );
+        assert_eq!(
+            diff("A { x: 1 }", "B { x: 2 }"),
+            ["A { x: 1 } != B { x: 2 }"]
+        );
+        assert_eq!(
+            diff("A { x: 1 }", "A { x: 1, .. }"),
+            ["A { x: 1 } != A { x: 1, .. }"]
+        );
+        assert_eq!(diff("P(1)", "Q(1)"), ["P(1) != Q(1)"]);
+        assert_eq!(diff("[1]", "(1,)"), ["[1] != (1,)"]);
+        // No path leads past a key that isn't a string
+        assert_eq!(
+            diff("{1: 'a', \"b\": 'b'}", "{1: 'x', \"b\": 'b'}"),
+            ["{1: 'a', \"b\": 'b'} != {1: 'x', \"b\": 'b'}"]
+        );
+        assert_eq!(
+            diff("{1: 'a'}", "{1: 'a'}"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn keeps_what_differences_hold() {
+        let left = tson!({"a": [1, 2], "b": "x"});
+        let right = tson!({"a": [1], "b": "y"});
+        let differences = left.diff(&right);
+
+        assert_eq!(differences.len(), 2);
+        assert_eq!(differences[0].path.to_string(), "a.1");
+        assert_eq!(
+            differences[0].change,
+            Change::Removed(&Value::Integer(2))
+        );
+        assert_eq!(
+            left.get_at(differences[1].path.segments()),
+            Some(&left["b"])
+        );
+        assert_eq!(
+            differences[1].change,
+            Change::Changed {
+                left: &left["b"],
+                right: &right["b"]
+            }
+        );
+    }
+
+    #[test]
+    fn aligns_lists_on_request() {
+        let aligned = DiffOptions::default().align_lists(true);
+        assert_eq!(
+            diff("[1, 2, 3, 4]", "[1, 3, 4]"),
+            ["1: 2 != 3", "2: 3 != 4", "3: removed 4"]
+        );
+        assert_eq!(
+            written("[1, 2, 3, 4]", "[1, 3, 4]", aligned),
+            ["1: removed 2"]
+        );
+        assert_eq!(
+            written("[1, 3]", "[0, 1, 2, 3, 4]", aligned),
+            ["0: added 0", "2: added 2", "4: added 4"]
+        );
+        // Elements between the ones that match are compared with
+        // each other
+        assert_eq!(
+            written(
+                "[A { x: 1 }, B, C, D]",
+                "[A { x: 2 }, C, E, F]",
+                aligned
+            ),
+            [
+                "0.x: 1 != 2",
+                "1: removed B",
+                "3: D != E",
+                "3: added F"
+            ]
+        );
+        assert_eq!(
+            written("[]", "[]", aligned),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            written("[1]", "[]", aligned),
+            ["0: removed 1"]
+        );
+    }
+
+    #[test]
+    fn compares_floats_within_epsilon() {
+        let close = DiffOptions::default().epsilon(1e-9);
+        assert_eq!(
+            diff("[0.30000000000000004]", "[0.3]"),
+            ["0: 0.30000000000000004 != 0.3"]
+        );
+        assert_eq!(
+            written("[0.30000000000000004]", "[0.3]", close),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            written("[0.31]", "[0.3]", close),
+            ["0: 0.31 != 0.3"]
+        );
+        assert_eq!(diff("NaN", "NaN"), Vec::<String>::new());
+        assert_eq!(diff("inf", "inf"), Vec::<String>::new());
+        assert_eq!(
+            written("inf", "-inf", close),
+            ["inf != -inf"]
+        );
+        // Integers aren't floats, however close
+        assert_eq!(written("1", "1.0", close), ["1 != 1.0"]);
+    }
+}
@@ -0,0 +1,969 @@
+use alloc::{borrow::Cow, vec::Vec};
+use core::fmt::{self, Display, Formatter, LowerExp, Write};
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::{pretty::write_single_line, ObjectMap, Value};
+
+/// How deeply values are nested before `Display` stops recursing
+/// and writes the rest with an explicit stack, which is slower
+/// but can't overflow the call stack.
+const MAX_RECURSION: usize = 64;
+
+/// Writes a value in the form `parse_value` reads back, which is
+/// the form `Debug` prints it in.
+///
+/// Parsing the output gives back an equal value, as long as it
+/// is a value parsing could have given, and writing the value
+/// parsed from any document gives output that is parsed as the
+/// same value and written the same way again. The exceptions
+/// are the values no document is parsed as:
+///
+/// - Integers are parsed as the narrowest of `Integer`, `UInt`,
+///   `BigInt` and `BigUInt` that holds them, as the `From`
+///   conversions make them, so `Value::UInt(5)` is read back as
+///   `Value::Integer(5)`.
+/// - An empty `Value::Map` or `Value::Set` is written as `{}`,
+///   which is parsed as an empty `Value::Object`, and a
+///   `Value::Map` with only string keys is parsed as a
+///   `Value::Object`.
+/// - A range can't be the start or end of another one, as
+///   `1..2..3` could be read either way.
+/// - Identifiers and the names of structs and their fields are
+///   written as they are, so they must be ones the parser reads
+///   as names: not `true`, `None`, `inf` or `NaN`, nor `Some`,
+///   `Ok` or `Err` for a tuple struct.
+/// - UUIDs, datetimes and addresses are only read back with the
+///   [`ParserOptions`](crate::ParserOptions) that parse them,
+///   which also parse strings and identifiers that look like
+///   them as them.
+///
+/// Any `NaN` is read back as `NaN`, which it is equal to. The
+/// entries of an object are written sorted by key, since objects
+/// have no order of their own, so that equal values are always
+/// written the same way.
+///
+/// Floats are written in their shortest form that reads back
+/// exactly, unless a precision is given: `{:.2}` writes them
+/// with [`FloatFormat::Fixed`]`(2)`.
+impl Display for Value<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let floats = match f.precision() {
+            Some(places) => FloatFormat::Fixed(
+                places.try_into().unwrap_or(u8::MAX),
+            ),
+            None => FloatFormat::Shortest,
+        };
+
+        self.formatted(Style {
+            floats,
+            ..Style::default()
+        })
+        .fmt(f)
+    }
+}
+
+/// Writes a value like `Display` does, with its floats in
+/// scientific notation ([`FloatFormat::Exponent`]).
+impl LowerExp for Value<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.formatted(Style {
+            floats: FloatFormat::Exponent,
+            ..Style::default()
+        })
+        .fmt(f)
+    }
+}
+
+/// How floats are written by `Display` and the pretty printer.
+///
+/// Whatever the format, a float is always written with a
+/// fraction or an exponent so that it reads back as a float,
+/// and `NaN`, `inf` and `-inf` are always spelled out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FloatFormat {
+    /// The shortest text that reads back as exactly the same
+    /// float, as written by `f64`'s `Debug`: `0.1`, `1e300`.
+    #[default]
+    Shortest,
+    /// Rounded to this many decimal places: `Fixed(2)` writes
+    /// `3.14`. `Fixed(0)` writes `3.0`, so floats stay floats.
+    Fixed(u8),
+    /// Scientific notation, as written by `{:e}`: `1.5e3`.
+    Exponent,
+}
+
+/// How strings and chars are written by `Display` and the
+/// pretty printer.
+///
+/// Either way, only the contents of strings and chars are
+/// escaped, so identifiers, including the names of structs and
+/// their fields, are always written as they are.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EscapeMode {
+    /// Non-ASCII characters are written as UTF-8, escaping only
+    /// what `Debug` escapes: `"café 😀"`.
+    #[default]
+    Utf8,
+    /// Everything that isn't printable ASCII is escaped, so
+    /// that the output is pure ASCII: `"caf\u{e9}
+    /// \u{1f600}"`.
+    AsciiOnly,
+}
+
+/// How the floats and strings of a value are written.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Style {
+    pub(crate) floats: FloatFormat,
+    pub(crate) escapes: EscapeMode,
+}
+
+/// A value to write in the given style.
+pub(crate) struct Formatted<'v, 'a> {
+    value: &'v Value<'a>,
+    style: Style,
+    /// How many values this one is nested in.
+    depth: usize,
+}
+
+impl<'a> Value<'a> {
+    pub(crate) fn formatted(
+        &self,
+        style: Style,
+    ) -> Formatted<'_, 'a> {
+        Formatted {
+            value: self,
+            style,
+            depth: 0,
+        }
+    }
+}
+
+impl<'v, 'a> Formatted<'v, 'a> {
+    fn nested(&self, value: &'v Value<'a>) -> Self {
+        Formatted {
+            value,
+            style: self.style,
+            depth: self.depth + 1,
+        }
+    }
+}
+
+impl Display for Formatted<'_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let style = self.style;
+        if self.depth == MAX_RECURSION {
+            return write_single_line(f, self.value, style);
+        }
+
+        match self.value {
+            Value::Integer(integer) => write!(f, "{integer}"),
+            Value::UInt(integer) => write!(f, "{integer}"),
+            Value::BigInt(integer) => write!(f, "{integer}"),
+            Value::BigUInt(integer) => write!(f, "{integer}"),
+            Value::Float(float) => {
+                write_float(f, *float, style.floats)
+            }
+            #[cfg(feature = "arbitrary-precision")]
+            Value::RawNumber(number) => f.write_str(number),
+            Value::Boolean(boolean) => write!(f, "{boolean}"),
+            Value::String(string) => {
+                write_string(f, string, style.escapes)
+            }
+            Value::Char(chr) => {
+                write_char(f, *chr, style.escapes)
+            }
+            Value::Bytes(bytes) => write_bytes(f, bytes),
+            Value::List(elements) => {
+                f.write_char('[')?;
+                write_separated(f, elements, self)?;
+                f.write_char(']')
+            }
+            Value::Tuple(elements) => {
+                f.write_char('(')?;
+                write_separated(f, elements, self)?;
+                // `(1)` would read as a parenthesized value
+                if elements.len() == 1 {
+                    f.write_char(',')?;
+                }
+                f.write_char(')')
+            }
+            Value::Optional(None) => f.write_str("None"),
+            Value::Optional(Some(value)) => {
+                write!(f, "Some({})", self.nested(value))
+            }
+            Value::Result(Ok(value)) => {
+                write!(f, "Ok({})", self.nested(value))
+            }
+            Value::Result(Err(value)) => {
+                write!(f, "Err({})", self.nested(value))
+            }
+            Value::Object(object) => {
+                f.write_char('{')?;
+                for (idx, (key, value)) in
+                    sorted(object).into_iter().enumerate()
+                {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write_string(f, key, style.escapes)?;
+                    write!(f, ": {}", self.nested(value))?;
+                }
+                f.write_char('}')
+            }
+            Value::Struct {
+                name,
+                fields,
+                non_exhaustive,
+            } => {
+                write!(f, "{name} {{")?;
+                for (idx, (field, value)) in
+                    fields.iter().enumerate()
+                {
+                    let separator =
+                        if idx == 0 { " " } else { ", " };
+                    write!(
+                        f,
+                        "{separator}{field}: {}",
+                        self.nested(value)
+                    )?;
+                }
+                if *non_exhaustive {
+                    let separator = if fields.is_empty() {
+                        " "
+                    } else {
+                        ", "
+                    };
+                    write!(f, "{separator}..")?;
+                }
+                if !fields.is_empty() || *non_exhaustive {
+                    f.write_char(' ')?;
+                }
+                f.write_char('}')
+            }
+            Value::TupleStruct { name, fields } => {
+                write!(f, "{name}(")?;
+                write_separated(f, fields, self)?;
+                f.write_char(')')
+            }
+            Value::Map(entries) => {
+                f.write_char('{')?;
+                for (idx, (key, value)) in
+                    entries.iter().enumerate()
+                {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(
+                        f,
+                        "{}: {}",
+                        self.nested(key),
+                        self.nested(value)
+                    )?;
+                }
+                f.write_char('}')
+            }
+            Value::Set(elements) => {
+                f.write_char('{')?;
+                write_separated(f, elements, self)?;
+                f.write_char('}')
+            }
+            Value::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                if let Some(start) = start {
+                    write!(f, "{}", self.nested(start))?;
+                }
+                f.write_str(if *inclusive {
+                    "..="
+                } else {
+                    ".."
+                })?;
+                if let Some(end) = end {
+                    write!(f, "{}", self.nested(end))?;
+                }
+                Ok(())
+            }
+            #[cfg(feature = "duration")]
+            Value::Duration(duration) => {
+                write!(f, "{duration:?}")
+            }
+            #[cfg(feature = "datetime")]
+            Value::DateTime(datetime) => {
+                write!(f, "\"{datetime}\"")
+            }
+            #[cfg(feature = "uuid")]
+            Value::Uuid(uuid) => write!(f, "\"{uuid}\""),
+            #[cfg(feature = "net")]
+            Value::IpAddr(addr) => write!(f, "{addr}"),
+            #[cfg(feature = "net")]
+            Value::SocketAddr(addr) => write!(f, "{addr}"),
+            Value::Ident(ident) => f.write_str(ident),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Value<'_> {
+    /// Writes this value to `writer` the way `Display` does,
+    /// without building the whole output in memory first.
+    ///
+    /// The value is written in many small pieces, so a
+    /// `BufWriter` is worth wrapping around unbuffered writers.
+    /// Use `write!(writer, "{value:.2}")` instead to choose how
+    /// floats are written, as with `Display`.
+    pub fn write_to<W: io::Write>(
+        &self,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        write!(writer, "{self}")
+    }
+}
+
+/// Returns the entries of an object sorted by key, which is the
+/// order they're written in, as objects have none of their own.
+pub(crate) fn sorted<'v, 'a>(
+    object: &'v ObjectMap<'a>,
+) -> Vec<(&'v Cow<'a, str>, &'v Value<'a>)> {
+    let mut entries: Vec<_> = object.iter().collect();
+    entries.sort_unstable_by_key(|&(key, _)| key);
+    entries
+}
+
+/// Writes `values`, nested in `parent`, separated by `, `.
+fn write_separated<'v, 'a>(
+    f: &mut Formatter<'_>,
+    values: &'v [Value<'a>],
+    parent: &Formatted<'v, 'a>,
+) -> fmt::Result {
+    for (idx, value) in values.iter().enumerate() {
+        if idx > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{}", parent.nested(value))?;
+    }
+
+    Ok(())
+}
+
+/// Writes a string literal, escaped as `escapes` says.
+pub(crate) fn write_string(
+    f: &mut impl Write,
+    string: &str,
+    escapes: EscapeMode,
+) -> fmt::Result {
+    match escapes {
+        EscapeMode::Utf8 => write!(f, "{string:?}"),
+        EscapeMode::AsciiOnly => write_ascii(f, string, '"'),
+    }
+}
+
+/// Writes a char literal, escaped as `escapes` says.
+fn write_char(
+    f: &mut impl Write,
+    chr: char,
+    escapes: EscapeMode,
+) -> fmt::Result {
+    match escapes {
+        EscapeMode::Utf8 => write!(f, "{chr:?}"),
+        EscapeMode::AsciiOnly => {
+            write_ascii(f, chr.encode_utf8(&mut [0; 4]), '\'')
+        }
+    }
+}
+
+/// Writes `text` between `quote`s, escaping everything but
+/// printable ASCII with the escapes `unescape_char` knows.
+fn write_ascii(
+    f: &mut impl Write,
+    text: &str,
+    quote: char,
+) -> fmt::Result {
+    f.write_char(quote)?;
+    for chr in text.chars() {
+        match chr {
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '\0' => f.write_str("\\0")?,
+            '\\' => f.write_str("\\\\")?,
+            chr if chr == quote => write!(f, "\\{chr}")?,
+            ' '..='~' => f.write_char(chr)?,
+            chr => write!(f, "\\u{{{:x}}}", u32::from(chr))?,
+        }
+    }
+    f.write_char(quote)
+}
+
+/// Writes `float` in the given format.
+fn write_float(
+    f: &mut impl Write,
+    float: f64,
+    format: FloatFormat,
+) -> fmt::Result {
+    // `Debug` spells out `NaN`, `inf` and `-inf`, which no
+    // other format does
+    if !float.is_finite() {
+        return write!(f, "{float:?}");
+    }
+
+    match format {
+        // `Debug` always writes a fraction or an exponent
+        FloatFormat::Shortest => write!(f, "{float:?}"),
+        FloatFormat::Fixed(0) => write!(f, "{float:.0}.0"),
+        FloatFormat::Fixed(places) => {
+            write!(f, "{float:.*}", usize::from(places))
+        }
+        FloatFormat::Exponent => write!(f, "{float:e}"),
+    }
+}
+
+/// Writes a byte string literal, escaping the bytes that aren't
+/// printable ASCII with the escapes `parse_byte_string` knows.
+fn write_bytes(
+    f: &mut Formatter<'_>,
+    bytes: &[u8],
+) -> fmt::Result {
+    f.write_str("b\"")?;
+    for &byte in bytes {
+        match byte {
+            b'\n' => f.write_str("\\n")?,
+            b'\r' => f.write_str("\\r")?,
+            b'\t' => f.write_str("\\t")?,
+            b'\0' => f.write_str("\\0")?,
+            b'\\' => f.write_str("\\\\")?,
+            b'"' => f.write_str("\\\"")?,
+            b' '..=b'~' => f.write_char(char::from(byte))?,
+            _ => write!(f, "\\x{byte:02x}")?,
+        }
+    }
+    f.write_char('"')
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::borrow::Cow;
+
+    use super::Style;
+    use crate::{
+        parse_value, pretty::write_single_line,
+        tests::with_f64_floats, EscapeMode, FloatFormat,
+        IResult, PrettyConfig, Value,
+    };
+
+    /// Parses `text` like `parse_value`, with floats as `f64`s
+    /// whatever the features.
+    pub(crate) fn parse_f64s(
+        text: &str,
+    ) -> IResult<&str, Value<'_>> {
+        parse_value(text)
+            .map(|(rest, value)| (rest, with_f64_floats(value)))
+    }
+
+    fn assert_round_trips(value: &Value<'_>) {
+        let text = value.to_string();
+        assert_eq!(
+            parse_f64s(&text),
+            Ok(("", value.clone())),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn writes_values() {
+        let cases = [
+            (Value::Integer(-7), "-7"),
+            (Value::BigUInt(u128::MAX), &*u128::MAX.to_string()),
+            (Value::Float(1.), "1.0"),
+            (Value::Float(-0.5), "-0.5"),
+            (Value::Float(f64::INFINITY), "inf"),
+            (Value::String("".into()), "\"\""),
+            (
+                Value::String("say \"hi\"\n".into()),
+                r#""say \"hi\"\n""#,
+            ),
+            (Value::Char('\''), r"'\''"),
+            (
+                Value::Bytes(Cow::Borrowed(b"a\"\x00\xff")),
+                r#"b"a\"\0\xff""#,
+            ),
+            (Value::List(vec![]), "[]"),
+            (Value::Tuple(vec![]), "()"),
+            (Value::Tuple(vec![Value::Integer(1)]), "(1,)"),
+            (
+                Value::Optional(Some(Box::new(
+                    Value::Optional(None),
+                ))),
+                "Some(None)",
+            ),
+            (
+                Value::Struct {
+                    name: "Foo".into(),
+                    fields: vec![],
+                    non_exhaustive: false,
+                },
+                "Foo {}",
+            ),
+            (
+                Value::Struct {
+                    name: "Foo".into(),
+                    fields: vec![(
+                        "a".into(),
+                        Value::Integer(1),
+                    )],
+                    non_exhaustive: true,
+                },
+                "Foo { a: 1, .. }",
+            ),
+            (
+                Value::Range {
+                    start: None,
+                    end: Some(Box::new(Value::Integer(5))),
+                    inclusive: true,
+                },
+                "..=5",
+            ),
+        ];
+
+        for (value, text) in cases {
+            assert_eq!(value.to_string(), text);
+            assert_round_trips(&value);
+        }
+
+        let nan = Value::Float(f64::NAN);
+        assert_eq!(nan.to_string(), "NaN");
+        assert!(parse_value(&nan.to_string())
+            .unwrap()
+            .1
+            .is_nan());
+    }
+
+    #[test]
+    fn writes_floats_in_each_format() {
+        let floats = [
+            3.,
+            -0.,
+            0.1,
+            2.5,
+            -1234.5678,
+            1e300,
+            -1.7976931348623157e308,
+            5e-324,
+            2.2250738585072014e-308,
+        ];
+        let formats = [
+            FloatFormat::Shortest,
+            FloatFormat::Fixed(0),
+            FloatFormat::Fixed(3),
+            FloatFormat::Exponent,
+        ];
+
+        for float in floats {
+            for format in formats {
+                let value = Value::Float(float);
+                let text = value.to_string_pretty_with(
+                    &PrettyConfig::default()
+                        .float_format(format),
+                );
+                let Ok(("", Value::Float(parsed))) =
+                    parse_f64s(&text)
+                else {
+                    panic!("{text} didn't parse as a float");
+                };
+                assert_eq!(
+                    parsed.is_sign_negative(),
+                    float.is_sign_negative(),
+                    "{text}"
+                );
+                if format != FloatFormat::Fixed(0)
+                    && format != FloatFormat::Fixed(3)
+                {
+                    assert_eq!(parsed, float, "{text}");
+                }
+            }
+        }
+
+        let cases = [
+            (3., FloatFormat::Fixed(0), "3.0"),
+            (2.5, FloatFormat::Fixed(0), "2.0"),
+            (-0., FloatFormat::Fixed(0), "-0.0"),
+            (-0., FloatFormat::Fixed(2), "-0.00"),
+            (0.125, FloatFormat::Fixed(2), "0.12"),
+            (5e-324, FloatFormat::Fixed(3), "0.000"),
+            (5e-324, FloatFormat::Shortest, "5e-324"),
+            (1e300, FloatFormat::Shortest, "1e300"),
+            (3., FloatFormat::Exponent, "3e0"),
+            (-1234.5, FloatFormat::Exponent, "-1.2345e3"),
+            (f64::NEG_INFINITY, FloatFormat::Fixed(2), "-inf"),
+            (f64::NAN, FloatFormat::Exponent, "NaN"),
+        ];
+        for (float, format, text) in cases {
+            let config =
+                PrettyConfig::default().float_format(format);
+            assert_eq!(
+                Value::Float(float)
+                    .to_string_pretty_with(&config),
+                text
+            );
+        }
+
+        let list = Value::List(vec![
+            Value::Float(1.),
+            Value::Optional(Some(Box::new(Value::Float(-0.25)))),
+            Value::Integer(7),
+        ]);
+        assert_eq!(format!("{list}"), "[1.0, Some(-0.25), 7]");
+        assert_eq!(format!("{list:.1}"), "[1.0, Some(-0.2), 7]");
+        assert_eq!(format!("{list:.0}"), "[1.0, Some(-0.0), 7]");
+        assert_eq!(
+            format!("{list:e}"),
+            "[1e0, Some(-2.5e-1), 7]"
+        );
+    }
+
+    #[test]
+    fn escapes_strings_in_each_mode() {
+        let text =
+            "tea: e\u{301}, caf\u{e9} \u{1f375}\n\"\u{7f}";
+        let input = format!(
+            r#"[{text:?}, '\u{{1f600}}', {{"cl\u{{e9}}": '\''}}]"#
+        );
+        let (_, value) = parse_value(&input).unwrap();
+
+        let utf8 = value.to_string();
+        assert_eq!(
+            utf8,
+            "[\"tea: e\\u{301}, caf\u{e9} \u{1f375}\\n\\\"\\u{7f}\", \
+             '\u{1f600}', {\"cl\u{e9}\": '\\''}]"
+        );
+
+        let config = PrettyConfig::default()
+            .indent("")
+            .escape_mode(EscapeMode::AsciiOnly);
+        let ascii = value.to_string_pretty_with(&config);
+        assert_eq!(
+            ascii,
+            r#"["tea: e\u{301}, caf\u{e9} \u{1f375}\n\"\u{7f}", '\u{1f600}', {"cl\u{e9}": '\''}]"#
+        );
+        assert!(ascii.is_ascii());
+
+        for text in [utf8, ascii] {
+            assert_eq!(
+                parse_value(&text),
+                Ok(("", value.clone()))
+            );
+        }
+    }
+
+    /// A tiny deterministic generator of pseudo-random values,
+    /// so that the round trip is checked over a varied corpus.
+    pub(crate) struct Generator(pub(crate) u64);
+
+    impl Generator {
+        fn next(&mut self) -> u64 {
+            // Knuth's MMIX linear congruential generator
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            self.0 >> 33
+        }
+
+        fn below(&mut self, bound: u64) -> usize {
+            (self.next() % bound) as usize
+        }
+
+        fn string(&mut self) -> String {
+            const CHARS: &[char] = &[
+                'a', 'Z', ' ', '"', '\'', '\\', '\n', '\t',
+                '\0', 'é', '😀', '\u{7f}',
+            ];
+            (0..self.below(6))
+                .map(|_| CHARS[self.below(CHARS.len() as u64)])
+                .collect()
+        }
+
+        fn values(
+            &mut self,
+            depth: usize,
+        ) -> Vec<Value<'static>> {
+            (0..self.below(4))
+                .map(|_| self.value(depth - 1))
+                .collect()
+        }
+
+        /// Returns a value that isn't a range, to bound one.
+        fn bound(
+            &mut self,
+            depth: usize,
+        ) -> Box<Value<'static>> {
+            loop {
+                match self.value(depth) {
+                    Value::Range { .. } => {}
+                    value => return Box::new(value),
+                }
+            }
+        }
+
+        /// Returns a value that parsing the way it is written
+        /// gives back, which every integer is written in the
+        /// narrowest variant that holds it for.
+        pub(crate) fn value(
+            &mut self,
+            depth: usize,
+        ) -> Value<'static> {
+            let kinds = if depth == 0 { 10 } else { 20 };
+            match self.below(kinds) {
+                0 => Value::Integer(
+                    self.next() as i64 - (1 << 30),
+                ),
+                1 => Value::BigInt(
+                    -(self.next() as i128 + 1) << 70,
+                ),
+                2 => Value::Float(match self.below(4) {
+                    // Any float's bits, as every `NaN` is equal
+                    // to the one read back
+                    0 => f64::from_bits(
+                        self.next() << 33
+                            ^ self.next() << 2
+                            ^ self.next(),
+                    ),
+                    _ => {
+                        (self.next() as f64 - 1e9)
+                            / (self.below(1000) + 1) as f64
+                    }
+                }),
+                3 => Value::Boolean(self.below(2) == 0),
+                4 => Value::String(self.string().into()),
+                5 => Value::Char(match self.below(2) {
+                    0 => char::from_u32(
+                        (self.next() % 0x11_0000) as u32,
+                    )
+                    .unwrap_or('\u{fffd}'),
+                    _ => self
+                        .string()
+                        .chars()
+                        .next()
+                        .unwrap_or('x'),
+                }),
+                6 => Value::Bytes(
+                    (0..self.below(5))
+                        .map(|_| self.next() as u8)
+                        .collect(),
+                ),
+                7 => Value::Ident(
+                    ["Red", "a::B", "_x"][self.below(3)].into(),
+                ),
+                8 => Value::UInt(
+                    self.next() << 32 | self.next() | 1 << 63,
+                ),
+                9 => Value::BigUInt(
+                    u128::MAX - u128::from(self.next()),
+                ),
+                10 => Value::List(self.values(depth)),
+                11 => Value::Tuple(self.values(depth)),
+                12 => Value::Optional(match self.below(2) {
+                    0 => None,
+                    _ => Some(Box::new(self.value(depth - 1))),
+                }),
+                13 => Value::Result(match self.below(2) {
+                    0 => Ok(Box::new(self.value(depth - 1))),
+                    _ => Err(Box::new(self.value(depth - 1))),
+                }),
+                14 => Value::Object(
+                    (0..self.below(3))
+                        .map(|_| {
+                            (
+                                self.string().into(),
+                                self.value(depth - 1),
+                            )
+                        })
+                        .collect(),
+                ),
+                15 => Value::Struct {
+                    name: "Point".into(),
+                    fields: ["x", "y", "z"][..self.below(4)]
+                        .iter()
+                        .map(|&field| {
+                            (field.into(), self.value(depth - 1))
+                        })
+                        .collect(),
+                    non_exhaustive: self.below(2) == 0,
+                },
+                16 => Value::TupleStruct {
+                    name: "shape::Move".into(),
+                    fields: self.values(depth),
+                },
+                17 => {
+                    // Integer keys, so that this isn't an
+                    // object, and at least
+                    // one entry, so it isn't `{}`
+                    Value::Map(
+                        (0..=self.below(3))
+                            .map(|key| {
+                                (
+                                    Value::Integer(key as i64),
+                                    self.value(depth - 1),
+                                )
+                            })
+                            .collect(),
+                    )
+                }
+                18 => Value::Set(
+                    (0..=self.below(3))
+                        .map(|_| self.value(depth - 1))
+                        .collect(),
+                ),
+                _ => {
+                    // An inclusive range always has an end
+                    let inclusive = self.below(2) == 0;
+                    Value::Range {
+                        start: (self.below(3) > 0)
+                            .then(|| self.bound(depth - 1)),
+                        end: (inclusive || self.below(3) > 0)
+                            .then(|| self.bound(depth - 1)),
+                        inclusive,
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_generated_values() {
+        let mut generator = Generator(0x7e57);
+        for _ in 0..2000 {
+            let value = generator.value(4);
+            assert_round_trips(&value);
+
+            let text = format!("{value:e}");
+            assert_eq!(
+                parse_f64s(&text),
+                Ok(("", value.clone())),
+                "{text}"
+            );
+        }
+    }
+
+    /// Checks that the value `text` is parsed as is written as
+    /// text that parses as the same value and is written the
+    /// same way again.
+    fn assert_fixed_point(text: &str) {
+        let (_, value) = parse_value(text).unwrap();
+        let written = value.to_string();
+        let (rest, again) = parse_value(&written).unwrap();
+        assert_eq!(rest, "", "{text}");
+        assert_eq!(again.to_string(), written, "{text}");
+        assert_eq!(again, value, "{text}");
+    }
+
+    #[test]
+    fn writes_parsed_values_the_same_way_again() {
+        // Everything the parser accepts but `Display` writes
+        // another way
+        let spellings = [
+            "5.",
+            ".5",
+            "-0.0",
+            "1E5",
+            "1_000.5",
+            "0x1F",
+            "0o17",
+            "0b101",
+            "+1_000",
+            "1u8",
+            "2.5f32",
+            "9223372036854775808",
+            "-170141183460469231731687303715884105728",
+            "NaN",
+            "-inf",
+            r##"r#"a "quote""#"##,
+            r#""\x41\u{e9}\t""#,
+            r#"b"\x00\xFF""#,
+            r#"br"raw""#,
+            r"'\u{1F600}'",
+            "[1, 2,]",
+            "[ ]",
+            "(1 ,)",
+            "( 1 , )",
+            r#"{ "a" : 1 , }"#,
+            "{1: 2, 3: 4,}",
+            "{1, 2,}",
+            "{ }",
+            "Foo{a:1,}",
+            "Foo {..}",
+            "shape::Shape::Circle { r: 1 }",
+            "Point (1, 2)",
+            "Some (1)",
+            "Ok(Err /* x */ (1))",
+            "1 .. 2",
+            "..= 5",
+            "'a'..'z'",
+            " /* a */ [1 // b\n, /* c /* d */ */ 2]  ",
+        ];
+        for text in spellings {
+            assert_fixed_point(text);
+        }
+
+        let mut generator = Generator(0xf1);
+        let ascii = PrettyConfig::default()
+            .escape_mode(EscapeMode::AsciiOnly)
+            .float_format(FloatFormat::Fixed(2));
+        for _ in 0..500 {
+            let value = generator.value(4);
+            for text in [
+                value.to_string(),
+                value.to_string_pretty(),
+                value.to_string_pretty_with(&ascii),
+                format!("{value:.1}"),
+            ] {
+                assert_fixed_point(&text);
+            }
+        }
+    }
+
+    #[test]
+    fn writes_the_same_without_recursing() {
+        let mut generator = Generator(0x5eed);
+        for _ in 0..500 {
+            let value = generator.value(5);
+            let mut text = String::new();
+            write_single_line(
+                &mut text,
+                &value,
+                Style::default(),
+            )
+            .unwrap();
+            assert_eq!(text, value.to_string());
+        }
+    }
+
+    #[test]
+    fn writes_deeply_nested_values() {
+        let depth = 1_000_000;
+        let mut value = Value::List(vec![]);
+        for _ in 0..depth {
+            value = Value::Optional(Some(Box::new(
+                Value::List(vec![value]),
+            )));
+        }
+
+        let text = value.to_string();
+        assert_eq!(text.len(), 8 * depth + 2);
+        assert!(
+            text.starts_with("Some([Some([")
+                && text.ends_with("])])")
+        );
+
+        #[cfg(feature = "std")]
+        {
+            let mut out = Vec::new();
+            value.write_to(&mut out).unwrap();
+            assert_eq!(out, text.as_bytes());
+        }
+    }
+}
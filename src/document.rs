@@ -0,0 +1,513 @@
+use alloc::{borrow::Cow, vec, vec::Vec};
+use core::{fmt, ops::Index};
+
+use nom::{bytes::complete::take_while1, IResult, Parser};
+
+use crate::{
+    display::write_string, error::Located, parse_string,
+    parse_tracked, parse_ws, trailing, ConvertError, Error,
+    ErrorKind, EscapeMode, Expected, FromTson, Mode, ObjectMap,
+    ParserOptions, State, Value,
+};
+
+/// Options for [`parse_document_with`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DocumentOptions {
+    /// Let a key be set more than once, with the last value it
+    /// is set to replacing the ones before it, rather than that
+    /// being an [`ErrorKind::DuplicateKey`] error. The entry
+    /// stays where the key was first set.
+    pub duplicate_keys: bool,
+}
+
+impl DocumentOptions {
+    pub fn duplicate_keys(
+        mut self,
+        duplicate_keys: bool,
+    ) -> Self {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+}
+
+/// The `key = value` entries of a document, as parsed by
+/// [`parse_document`], in the order they were written.
+///
+/// It displays as one entry per line, with each key bare if it
+/// can be and in quotes otherwise, so that what it writes parses
+/// back to the same document.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Document<'a> {
+    entries: Vec<(Cow<'a, str>, Value<'a>)>,
+}
+
+/// Parses a document of top-level `key = value` entries, one
+/// after the other, as a config file is written:
+///
+/// ```text
+/// // Where to listen
+/// port = 8080
+/// hosts = [
+///     "a.example.com",
+///     "b.example.com",
+/// ]
+/// "log level" = Info
+/// ```
+///
+/// A key is a bare word of ASCII letters, digits, `_` and `-`,
+/// or a string. A value may span as many lines as it needs, but
+/// the next entry must start on a line after it ends, and
+/// whitespace and comments may go between and around entries.
+/// A key set twice is an [`ErrorKind::DuplicateKey`] error
+/// pointing at the second, unless [`parse_document_with`] is
+/// told to allow it.
+pub fn parse_document(
+    input: &str,
+) -> Result<Document<'_>, Error> {
+    parse_document_with(input, DocumentOptions::default())
+}
+
+/// Parses a document like [`parse_document`] does, but with
+/// `options` applied to it.
+pub fn parse_document_with(
+    input: &str,
+    options: DocumentOptions,
+) -> Result<Document<'_>, Error> {
+    let mut document = Document::new();
+    let (mut rest, _) = parse_ws(input)
+        .map_err(|err| Error::from_nom(input, err, None))?;
+    while !rest.is_empty() {
+        let start = input.len() - rest.len();
+        let (key, value, after) =
+            parse_entry(rest).map_err(|err| {
+                err.shifted(start).into_error(input)
+            })?;
+
+        match document.get_mut(&key) {
+            Some(_) if !options.duplicate_keys => {
+                return Err(Error::new(
+                    ErrorKind::DuplicateKey,
+                    input,
+                    start,
+                ))
+            }
+            Some(existing) => *existing = value,
+            None => document.entries.push((key, value)),
+        }
+        rest = after;
+    }
+
+    Ok(document)
+}
+
+/// Parses the entry at the start of `input`, returning its key
+/// and value along with the input after the whitespace and
+/// comments that follow it, with any error located in `input`.
+fn parse_entry(
+    input: &str,
+) -> Result<(Cow<'_, str>, Value<'_>, &str), Located> {
+    let (rest, key) =
+        parse_key(input).map_err(|err| match err {
+            nom::Err::Failure(_) => {
+                Located::from_nom(input, err, None)
+            }
+            _ => Located::unexpected(input, 0)
+                .expecting(vec![Expected::Label("a key")]),
+        })?;
+    let (rest, _) = parse_ws(rest)
+        .map_err(|err| Located::from_nom(input, err, None))?;
+    let Some(rest) = rest.strip_prefix('=') else {
+        let offset = input.len() - rest.len();
+        return Err(Located::unexpected(input, offset)
+            .expecting(vec![Expected::Char('=')]));
+    };
+
+    let value_start = input.len() - rest.len();
+    let (after, value) = match parse_tracked(
+        rest,
+        ParserOptions::default(),
+        Mode::Build,
+        true,
+    ) {
+        (Ok((after, value)), furthest) => {
+            let (next, skipped) =
+                parse_ws(after).map_err(|err| {
+                    Located::from_nom(input, err, None)
+                })?;
+            // The next entry must start on a line of its own
+            if !next.is_empty() && !skipped.contains('\n') {
+                let err = trailing(rest, after, furthest)
+                    .err()
+                    .unwrap_or_else(|| {
+                        Located::new(
+                            ErrorKind::TrailingInput,
+                            rest.len() - next.len(),
+                        )
+                    });
+                return Err(err.shifted(value_start));
+            }
+            (next, value)
+        }
+        (Err(err), furthest) => {
+            return Err(Located::from_nom(rest, err, furthest)
+                .shifted(value_start))
+        }
+    };
+
+    Ok((key, value, after))
+}
+
+/// Parses a key: a bare word or a string.
+pub(crate) fn parse_key(
+    input: &str,
+) -> IResult<&str, Cow<'_, str>> {
+    let state = State::new(ParserOptions::default(), false);
+    take_while1(is_bare_key_char)
+        .map(Cow::Borrowed)
+        .or(|input| parse_string(&state, input))
+        .parse(input)
+}
+
+fn is_bare_key_char(chr: char) -> bool {
+    chr.is_ascii_alphanumeric() || chr == '_' || chr == '-'
+}
+
+impl<'a> Document<'a> {
+    /// Makes a document with no entries.
+    pub fn new() -> Self {
+        Document::default()
+    }
+
+    /// Returns the value of `key`, or `None` if it isn't set.
+    pub fn get(&self, key: &str) -> Option<&Value<'a>> {
+        self.entries
+            .iter()
+            .find(|(entry, _)| entry == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the value of `key` so that it can be changed, or
+    /// `None` if it isn't set.
+    pub fn get_mut(
+        &mut self,
+        key: &str,
+    ) -> Option<&mut Value<'a>> {
+        self.entries
+            .iter_mut()
+            .find(|(entry, _)| entry == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Sets `key` to `value`, returning the value it had, if
+    /// any. A key that wasn't set is added as the last entry,
+    /// and one that was keeps its place.
+    pub fn insert(
+        &mut self,
+        key: impl Into<Cow<'a, str>>,
+        value: Value<'a>,
+    ) -> Option<Value<'a>> {
+        let key = key.into();
+        match self.get_mut(&key) {
+            Some(existing) => {
+                Some(core::mem::replace(existing, value))
+            }
+            None => {
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes `key`, returning the value it had, if any, and
+    /// keeping the other entries in order.
+    pub fn remove(&mut self, key: &str) -> Option<Value<'a>> {
+        let idx = self
+            .entries
+            .iter()
+            .position(|(entry, _)| entry == key)?;
+        Some(self.entries.remove(idx).1)
+    }
+
+    /// Returns an iterator over the keys and values of the
+    /// entries, in order.
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (&str, &Value<'a>)> {
+        self.entries.iter().map(|(key, value)| (&**key, value))
+    }
+
+    /// Returns an iterator over the keys, in order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entries, in order.
+    pub fn into_entries(self) -> Vec<(Cow<'a, str>, Value<'a>)> {
+        self.entries
+    }
+
+    /// Returns the entries as a `Value::Object`, which leaves
+    /// out their order.
+    pub fn into_value(self) -> Value<'a> {
+        Value::Object(
+            self.entries.into_iter().collect::<ObjectMap<'a>>(),
+        )
+    }
+
+    /// Converts the document to a `T` that is read from an
+    /// object or struct, as types with a derived [`FromTson`]
+    /// are, taking each entry as a field, so that a config file
+    /// can be read straight into the type it configures.
+    ///
+    /// This clones the values, which
+    /// [`into_value`](Document::into_value) and
+    /// [`FromTson::from_tson`] don't need to.
+    pub fn convert<T: FromTson>(
+        &self,
+    ) -> Result<T, ConvertError> {
+        T::from_tson(&self.clone().into_value())
+    }
+
+    /// Returns the document with everything it borrows from its
+    /// input copied, so that it can outlive the input.
+    pub fn into_owned(self) -> Document<'static> {
+        Document {
+            entries: self
+                .entries
+                .into_iter()
+                .map(|(key, value)| {
+                    (
+                        Cow::Owned(key.into_owned()),
+                        value.into_owned(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a> FromIterator<(Cow<'a, str>, Value<'a>)>
+    for Document<'a>
+{
+    /// Collects entries into a document, with the last value of
+    /// a key given more than once replacing the ones before it.
+    fn from_iter<I>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (Cow<'a, str>, Value<'a>)>,
+    {
+        let mut document = Document::new();
+        for (key, value) in entries {
+            document.insert(key, value);
+        }
+        document
+    }
+}
+
+impl<'a> From<Document<'a>> for Value<'a> {
+    fn from(document: Document<'a>) -> Self {
+        document.into_value()
+    }
+}
+
+/// Returns the value of a key like [`Document::get`] does, or
+/// `Value::Optional(None)` if it isn't set, as indexing a
+/// `Value` does.
+impl<'a> Index<&str> for Document<'a> {
+    type Output = Value<'a>;
+
+    fn index(&self, key: &str) -> &Value<'a> {
+        self.get(key).unwrap_or(&crate::MISSING)
+    }
+}
+
+impl fmt::Display for Document<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.entries {
+            if !key.is_empty()
+                && key.chars().all(is_bare_key_char)
+            {
+                f.write_str(key)?;
+            } else {
+                write_string(f, key, EscapeMode::Utf8)?;
+            }
+            writeln!(f, " = {value}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec, vec::Vec};
+
+    use crate::{
+        from_str, parse_document, parse_document_with, Document,
+        DocumentOptions, ErrorKind, Expected, Value,
+    };
+
+    const CONFIG: &str = r#"
+        // Where to listen
+        port = 8080
+        hosts = [
+            "a.example.com", // the primary
+            "b.example.com",
+        ]
+
+        /* How much to say */
+        "log level" = Info
+        retry-after = Some(1.5) // seconds
+        limits = Limits { open: 10, idle: 2 }
+    "#;
+
+    #[test]
+    fn parses_entries_in_order() {
+        let document = parse_document(CONFIG).unwrap();
+        assert_eq!(
+            document.keys().collect::<Vec<_>>(),
+            [
+                "port",
+                "hosts",
+                "log level",
+                "retry-after",
+                "limits"
+            ]
+        );
+        assert_eq!(
+            document.get("port"),
+            Some(&Value::Integer(8080))
+        );
+        assert_eq!(
+            document["hosts"],
+            from_str(r#"["a.example.com", "b.example.com"]"#)
+                .unwrap()
+        );
+        assert_eq!(
+            document["limits"]["idle"],
+            Value::Integer(2)
+        );
+        assert_eq!(document["missing"], Value::Optional(None));
+
+        assert_eq!(parse_document(""), Ok(Document::new()));
+        assert_eq!(
+            parse_document(" // nothing\n").unwrap().len(),
+            0
+        );
+    }
+
+    #[test]
+    fn writes_what_parses_back() {
+        let mut document = parse_document(CONFIG).unwrap();
+        document.insert("", Value::String("empty".into()));
+        document.insert("a b", Value::Ident("Foo".into()));
+        let written = document.to_string();
+        assert!(written.starts_with("port = 8080\nhosts = ["));
+        assert!(written.contains("\n\"log level\" = Info\n"));
+        assert!(written.contains("\n\"\" = \"empty\"\n"));
+        assert_eq!(parse_document(&written), Ok(document));
+    }
+
+    #[test]
+    fn changes_entries_in_place() {
+        let mut document =
+            parse_document("a = 1\nb = 2\nc = 3").unwrap();
+        assert_eq!(
+            document.insert("a", Value::Integer(10)),
+            Some(Value::Integer(1))
+        );
+        assert_eq!(
+            document.remove("b"),
+            Some(Value::Integer(2))
+        );
+        assert_eq!(document.remove("b"), None);
+        *document.get_mut("c").unwrap() = Value::Boolean(true);
+        document.insert("d", Value::Char('x'));
+        assert_eq!(
+            document.to_string(),
+            "a = 10\nc = true\nd = 'x'\n"
+        );
+        assert_eq!(
+            document.into_value(),
+            from_str(r#"{"a": 10, "c": true, "d": 'x'}"#)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_keys_unless_allowed() {
+        let input = "a = 1\nb = 2\n\"a\" = 3\n";
+        let err = parse_document(input).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::DuplicateKey);
+        assert_eq!((err.line(), err.column()), (3, 1));
+        assert_eq!(
+            err.to_string(),
+            "duplicate key at line 3, column 1"
+        );
+
+        let document = parse_document_with(
+            input,
+            DocumentOptions::default().duplicate_keys(true),
+        )
+        .unwrap();
+        assert_eq!(document.to_string(), "a = 3\nb = 2\n");
+    }
+
+    #[test]
+    fn points_at_what_is_wrong() {
+        let error = |input| {
+            let err = parse_document(input).unwrap_err();
+            (
+                err.kind(),
+                err.line(),
+                err.column(),
+                err.expected().to_vec(),
+            )
+        };
+
+        assert_eq!(
+            error("a = 1\n= 2"),
+            (
+                ErrorKind::UnexpectedChar('='),
+                2,
+                1,
+                vec![Expected::Label("a key")]
+            )
+        );
+        assert_eq!(
+            error("a = 1\nb: 2"),
+            (
+                ErrorKind::UnexpectedChar(':'),
+                2,
+                2,
+                vec![Expected::Char('=')]
+            )
+        );
+        // Entries must be on lines of their own
+        assert_eq!(
+            error("a = 1 b = 2"),
+            (ErrorKind::TrailingInput, 1, 7, vec![])
+        );
+        assert_eq!(error("x = 1\ny = [1,\n  2 3]").1, 3);
+        assert_eq!(
+            error("x = 1\ny = \"open").0,
+            ErrorKind::UnterminatedString
+        );
+        assert_eq!(
+            error("\"key = 1").0,
+            ErrorKind::UnterminatedString
+        );
+        assert_eq!(
+            error("a = 1 /* open").0,
+            ErrorKind::UnterminatedComment
+        );
+    }
+}
@@ -0,0 +1,594 @@
+use alloc::{
+    borrow::Cow,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{cmp::Ordering, fmt, mem};
+
+use crate::{
+    path::{index, segments},
+    ObjectMap, Path, PathSegment, Value,
+};
+
+/// An error from [`Value::set`] or [`Value::set_creating`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SetError {
+    /// Nothing is at `path`, on the way to where the value was
+    /// to be set.
+    Missing { path: String },
+    /// The value at `path` is `found`, as in `an integer`,
+    /// which has no field, entry or element `segment` to be
+    /// set.
+    WrongKind {
+        path: String,
+        segment: String,
+        found: &'static str,
+    },
+    /// The position `index` is past the end of the `len`
+    /// elements at `path`. Only the position one past the last
+    /// element can be set, which adds an element.
+    OutOfBounds {
+        path: String,
+        index: usize,
+        len: usize,
+    },
+}
+
+impl fmt::Display for SetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = |path: &str| {
+            if path.is_empty() {
+                "the value".to_string()
+            } else {
+                format!("`{path}`")
+            }
+        };
+
+        match self {
+            SetError::Missing { path } => {
+                write!(f, "nothing is at `{path}`")
+            }
+            SetError::WrongKind {
+                path,
+                segment,
+                found,
+            } => write!(
+                f,
+                "can't set `{segment}` in {}, which is {found}",
+                at(path)
+            ),
+            SetError::OutOfBounds { path, index, len } => write!(
+                f,
+                "position {index} is past the end of {}, which has \
+                 {len} elements",
+                at(path)
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SetError {}
+
+impl<'a> Value<'a> {
+    /// Adds `element` to the end of a list, tuple, set or tuple
+    /// struct, or gives it back if this is anything else.
+    pub fn push(
+        &mut self,
+        element: impl Into<Value<'a>>,
+    ) -> Result<(), Value<'a>> {
+        match self.elements_mut() {
+            Some(elements) => {
+                elements.push(element.into());
+                Ok(())
+            }
+            None => Err(element.into()),
+        }
+    }
+
+    /// Puts `element` at `idx` in a list, tuple, set or tuple
+    /// struct, moving the ones after it along, or gives it back
+    /// if this is anything else or `idx` is past the end.
+    pub fn insert(
+        &mut self,
+        idx: usize,
+        element: impl Into<Value<'a>>,
+    ) -> Result<(), Value<'a>> {
+        match self.elements_mut() {
+            Some(elements) if idx <= elements.len() => {
+                elements.insert(idx, element.into());
+                Ok(())
+            }
+            _ => Err(element.into()),
+        }
+    }
+
+    /// Takes the element at `idx` out of a list, tuple, set or
+    /// tuple struct, moving the ones after it back, or returns
+    /// `None` if there is no such element.
+    pub fn remove(&mut self, idx: usize) -> Option<Value<'a>> {
+        let elements = self.elements_mut()?;
+        (idx < elements.len()).then(|| elements.remove(idx))
+    }
+
+    /// Takes the value of `key` out of an object, struct or map
+    /// with string keys, the one [`Value::get_key`] returns,
+    /// leaving the other fields and entries in their order, or
+    /// returns `None` if there is no such key.
+    pub fn remove_key(
+        &mut self,
+        key: &str,
+    ) -> Option<Value<'a>> {
+        match self {
+            Value::Object(object) => object.remove(key),
+            Value::Struct { fields, .. } => {
+                let idx = fields
+                    .iter()
+                    .position(|(field, _)| field == key)?;
+                Some(fields.remove(idx).1)
+            }
+            Value::Map(entries) => {
+                let idx =
+                    entries.iter().position(|(entry, _)| {
+                        entry.as_str() == Some(key)
+                    })?;
+                Some(entries.remove(idx).1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Sets the value at the end of `path`, which is read like
+    /// [`Value::get_path`] reads it, returning the value that
+    /// was there before, if any.
+    ///
+    /// Everything but the last segment of the path has to be
+    /// there already, and any `Some(..)` along the way is looked
+    /// inside of, so `config.retries` sets the field of the
+    /// struct in `Some(Config { retries: 3 })`, while `config`
+    /// replaces the `Some` itself. The last segment adds an
+    /// entry to an object or map, a field to a struct, or an
+    /// element at the end if it is the position one past the
+    /// last one. An empty path replaces this whole value.
+    pub fn set(
+        &mut self,
+        path: &str,
+        value: impl Into<Value<'a>>,
+    ) -> Result<Option<Value<'a>>, SetError> {
+        set(self, path, value.into(), false)
+    }
+
+    /// Sets the value at the end of `path` like [`Value::set`]
+    /// does, but puts an empty object wherever something is
+    /// missing along the way, as [`Value::set`] would put any
+    /// other value there.
+    ///
+    /// `a.b.0` then creates `{"a": {"b": {"0": ..}}}` in an
+    /// empty object, which [`Value::get_path`] reads back, since
+    /// the keys of an object may be made up of digits.
+    pub fn set_creating(
+        &mut self,
+        path: &str,
+        value: impl Into<Value<'a>>,
+    ) -> Result<Option<Value<'a>>, SetError> {
+        set(self, path, value.into(), true)
+    }
+
+    /// Returns the elements of a list, tuple, set or tuple
+    /// struct.
+    pub(crate) fn elements_mut(
+        &mut self,
+    ) -> Option<&mut Vec<Value<'a>>> {
+        match self {
+            Value::List(elements)
+            | Value::Tuple(elements)
+            | Value::Set(elements)
+            | Value::TupleStruct {
+                fields: elements, ..
+            } => Some(elements),
+            _ => None,
+        }
+    }
+}
+
+fn set<'a>(
+    root: &mut Value<'a>,
+    path: &str,
+    value: Value<'a>,
+    create: bool,
+) -> Result<Option<Value<'a>>, SetError> {
+    let segments: Vec<Cow<'_, str>> = segments(path).collect();
+    let Some((last, parents)) = segments.split_last() else {
+        return Ok(Some(mem::replace(root, value)));
+    };
+
+    let mut at = Path::default();
+    let mut current = root;
+    for segment in parents {
+        let parent = current.unwrapped_mut();
+        let step = step(parent, segment);
+        let found = match step {
+            PathSegment::Index(idx) => parent.get(idx).is_some(),
+            PathSegment::Key(key) => {
+                parent.get_key(key).is_some()
+            }
+        };
+        if !found {
+            // Only values with elements are indexed, so a step
+            // that doesn't fit is a key in something without
+            // keys, which `put` fails for
+            let fits = match step {
+                PathSegment::Index(_) => true,
+                PathSegment::Key(_) => matches!(
+                    parent,
+                    Value::Object(_)
+                        | Value::Struct { .. }
+                        | Value::Map(_)
+                ),
+            };
+            if fits && !create {
+                at.push(step);
+                return Err(SetError::Missing {
+                    path: at.to_string(),
+                });
+            }
+            put(
+                parent,
+                step,
+                Value::Object(ObjectMap::new()),
+                &at,
+            )?;
+        }
+
+        at.push(step);
+        current = match step {
+            PathSegment::Index(idx) => parent.get_mut(idx),
+            PathSegment::Key(key) => parent.get_key_mut(key),
+        }
+        .expect("it was found or put there");
+    }
+
+    let parent = current.unwrapped_mut();
+    let step = step(parent, last);
+    put(parent, step, value, &at)
+}
+
+/// Returns what `segment` picks out of `parent`, as
+/// [`Value::get_path`] reads it.
+fn step<'s>(
+    parent: &Value<'_>,
+    segment: &'s str,
+) -> PathSegment<'s> {
+    match index(parent, segment) {
+        Some(idx) => PathSegment::Index(idx),
+        None => PathSegment::Key(segment),
+    }
+}
+
+/// Sets what `step` picks out of `parent`, the value at `at`, to
+/// `value`, adding it if it isn't there.
+fn put<'a>(
+    parent: &mut Value<'a>,
+    step: PathSegment<'_>,
+    value: Value<'a>,
+    at: &Path<'_>,
+) -> Result<Option<Value<'a>>, SetError> {
+    let key = match step {
+        PathSegment::Key(key) => key,
+        PathSegment::Index(idx) => {
+            let elements = parent
+                .elements_mut()
+                .expect("only values with elements are indexed");
+            return match idx.cmp(&elements.len()) {
+                Ordering::Less => Ok(Some(mem::replace(
+                    &mut elements[idx],
+                    value,
+                ))),
+                Ordering::Equal => {
+                    elements.push(value);
+                    Ok(None)
+                }
+                Ordering::Greater => {
+                    Err(SetError::OutOfBounds {
+                        path: at.to_string(),
+                        index: idx,
+                        len: elements.len(),
+                    })
+                }
+            };
+        }
+    };
+
+    match parent {
+        Value::Object(object) => {
+            Ok(object.insert(Cow::Owned(key.into()), value))
+        }
+        Value::Struct { fields, .. } => {
+            match fields
+                .iter_mut()
+                .find(|(field, _)| field == key)
+            {
+                Some((_, old)) => {
+                    Ok(Some(mem::replace(old, value)))
+                }
+                None => {
+                    fields.push((Cow::Owned(key.into()), value));
+                    Ok(None)
+                }
+            }
+        }
+        Value::Map(entries) => match entries
+            .iter_mut()
+            .find(|(entry, _)| entry.as_str() == Some(key))
+        {
+            Some((_, old)) => Ok(Some(mem::replace(old, value))),
+            None => {
+                let key = Value::String(Cow::Owned(key.into()));
+                entries.push((key, value));
+                Ok(None)
+            }
+        },
+        parent => Err(SetError::WrongKind {
+            path: at.to_string(),
+            segment: key.into(),
+            found: parent.type_name(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::{
+        from_str,
+        tests::{float, with_f64_floats},
+        tson, SetError, Value,
+    };
+
+    const CONFIG: &str = r#"Config {
+        name: "service",
+        retries: 3,
+        servers: [Server { host: "a", port: 80 }],
+        backup: Some(Server { host: "b", port: 81 }),
+        fallback: None,
+        limits: {"cpu": 2},
+        weights: {1: 0.5, "x": 1.5},
+        pair: (1, 2),
+    }"#;
+
+    #[test]
+    fn edits_a_document_in_place() {
+        let mut value = from_str(CONFIG).unwrap();
+
+        let Some(Value::Integer(retries)) =
+            value.get_key_mut("retries")
+        else {
+            panic!("no retries");
+        };
+        *retries += 1;
+        let servers = value.get_key_mut("servers").unwrap();
+        servers
+            .push(tson!(Server {
+                host: "c",
+                port: 82
+            }))
+            .unwrap();
+        servers
+            .insert(
+                0,
+                tson!(Server {
+                    host: "z",
+                    port: 79
+                }),
+            )
+            .unwrap();
+        assert_eq!(
+            servers.remove(1).unwrap().get_key("host"),
+            Some(&Value::String("a".into()))
+        );
+        *value.get_path_mut("servers.1.port").unwrap() =
+            Value::Integer(8080);
+
+        assert_eq!(
+            value.set("backup.port", 8081),
+            Ok(Some(Value::Integer(81)))
+        );
+        assert_eq!(
+            value.set("fallback", tson!(Some("d"))),
+            Ok(Some(Value::Optional(None)))
+        );
+        assert_eq!(value.set("limits.memory", 512), Ok(None));
+        assert_eq!(
+            value.set("weights.x", 2.5),
+            Ok(Some(float("1.5")))
+        );
+        assert_eq!(value.set("weights.y", 3.5), Ok(None));
+        assert_eq!(
+            value.set("pair.1", "two"),
+            Ok(Some(Value::Integer(2)))
+        );
+        assert_eq!(value.set("pair.2", 'c'), Ok(None));
+        assert_eq!(
+            value.set("version", tson!((1, 0))),
+            Ok(None)
+        );
+
+        let mut expected = with_f64_floats(
+            from_str(
+                r#"Config {
+                name: "service",
+                retries: 4,
+                servers: [
+                    Server { host: "z", port: 79 },
+                    Server { host: "c", port: 8080 },
+                ],
+                backup: Some(Server { host: "b", port: 8081 }),
+                fallback: Some("d"),
+                limits: {"cpu": 2, "memory": 512},
+                weights: {1: 0.5, "x": 2.5, "y": 3.5},
+                pair: (1, "two", 'c'),
+                version: (1, 0),
+            }"#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(with_f64_floats(value.clone()), expected);
+        assert_eq!(value.to_string(), expected.to_string());
+
+        assert!(expected.set("", 1).unwrap().is_some());
+        assert_eq!(expected, Value::Integer(1));
+    }
+
+    #[test]
+    fn removes_keys_keeping_the_order_of_the_rest() {
+        let mut value = from_str(CONFIG).unwrap();
+
+        assert_eq!(
+            value.remove_key("retries"),
+            Some(Value::Integer(3))
+        );
+        assert_eq!(value.remove_key("retries"), None);
+        let weights = value.get_key_mut("weights").unwrap();
+        assert_eq!(weights.remove_key("1"), None);
+        assert_eq!(weights.remove_key("x"), Some(float("1.5")));
+        let limits = value.get_key_mut("limits").unwrap();
+        assert_eq!(
+            limits.remove_key("cpu"),
+            Some(Value::Integer(2))
+        );
+        assert_eq!(
+            value.get_key_mut("pair").unwrap().remove_key("0"),
+            None
+        );
+        assert_eq!(
+            value.remove_key("pair"),
+            Some(tson!((1, 2)))
+        );
+
+        assert_eq!(
+            value.to_string(),
+            from_str(
+                r#"Config {
+                name: "service",
+                servers: [Server { host: "a", port: 80 }],
+                backup: Some(Server { host: "b", port: 81 }),
+                fallback: None,
+                limits: {},
+                weights: {1: 0.5},
+            }"#,
+            )
+            .unwrap()
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn replaces_what_options_hold() {
+        let mut value =
+            from_str("{\"a\": Some(Some([1]))}").unwrap();
+        assert_eq!(
+            value.set("a.0", 2),
+            Ok(Some(Value::Integer(1)))
+        );
+        assert_eq!(
+            value.to_string(),
+            r#"{"a": Some(Some([2]))}"#
+        );
+
+        // The `Some` at the end of a path is replaced as a whole
+        assert!(value
+            .set("a", tson!(Some(3)))
+            .unwrap()
+            .is_some());
+        assert_eq!(value.to_string(), r#"{"a": Some(3)}"#);
+        assert_eq!(
+            value.set("a", tson!(None)),
+            Ok(Some(tson!(Some(3))))
+        );
+    }
+
+    #[test]
+    fn creates_what_is_missing_on_request() {
+        let mut value = tson!({"list": [1], "name": "x"});
+        assert_eq!(
+            value.set("config.retries", 1),
+            Err(SetError::Missing {
+                path: "config".into()
+            })
+        );
+        assert_eq!(
+            value.set_creating("config.retries", 1),
+            Ok(None)
+        );
+        assert_eq!(value.set_creating("a.b.0", true), Ok(None));
+        assert_eq!(
+            value.set_creating("list.1.x", 'x'),
+            Ok(None)
+        );
+        assert_eq!(
+            value,
+            tson!({
+                "list": [1, {"x": 'x'}],
+                "name": "x",
+                "config": {"retries": 1},
+                "a": {"b": {"0": true}},
+            })
+        );
+        assert_eq!(
+            value.get_path("a.b.0"),
+            Some(&Value::Boolean(true))
+        );
+    }
+
+    #[test]
+    fn fails_where_paths_do_not_fit() {
+        let mut value = from_str(CONFIG).unwrap();
+        let before = value.clone();
+        let fail = |value: &mut Value<'_>, path| {
+            value.set_creating(path, 1).unwrap_err().to_string()
+        };
+
+        assert_eq!(
+            fail(&mut value, "retries.x"),
+            "can't set `x` in `retries`, which is an integer"
+        );
+        assert_eq!(
+            fail(&mut value, "servers.x.y"),
+            "can't set `x` in `servers`, which is a list"
+        );
+        assert_eq!(
+            fail(&mut value, "servers.5"),
+            "position 5 is past the end of `servers`, which has 1 \
+             elements"
+        );
+        assert_eq!(
+            fail(&mut value, "fallback.port"),
+            "can't set `port` in `fallback`, which is an option"
+        );
+        assert_eq!(
+            value.set("servers.3.port", 1),
+            Err(SetError::Missing {
+                path: "servers.3".into()
+            })
+        );
+        assert_eq!(
+            Value::Integer(1)
+                .set("a", 2)
+                .unwrap_err()
+                .to_string(),
+            "can't set `a` in the value, which is an integer"
+        );
+        assert_eq!(value, before);
+
+        let mut number = Value::Integer(1);
+        assert_eq!(number.push(2), Err(Value::Integer(2)));
+        assert_eq!(number.remove(0), None);
+        let mut list = tson!([1]);
+        assert_eq!(list.insert(2, 3), Err(Value::Integer(3)));
+        assert_eq!(list.remove(1), None);
+        assert_eq!(list, tson!([1]));
+    }
+}
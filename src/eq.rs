@@ -0,0 +1,555 @@
+use core::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    mem,
+};
+
+use crate::{display::sorted, Value};
+
+/// Values are equal if they are of the same kind and hold equal
+/// values, like a derived `PartialEq`, except that floats are
+/// compared so that equality is total: every `NaN` is equal to
+/// every other, whatever its sign and payload, and `-0.0` is
+/// equal to `0.0`, as it is for `f64`. Any other floats are
+/// equal exactly when they are as `f64`s.
+///
+/// Sets and maps are compared in order, as they were written;
+/// use [`Value::set_eq`] to compare sets regardless of order.
+impl PartialEq for Value<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Integer(left), Value::Integer(right)) => {
+                left == right
+            }
+            (Value::UInt(left), Value::UInt(right)) => {
+                left == right
+            }
+            (Value::BigInt(left), Value::BigInt(right)) => {
+                left == right
+            }
+            (Value::BigUInt(left), Value::BigUInt(right)) => {
+                left == right
+            }
+            (Value::Float(left), Value::Float(right)) => {
+                float_bits(*left) == float_bits(*right)
+            }
+            #[cfg(feature = "arbitrary-precision")]
+            (
+                Value::RawNumber(left),
+                Value::RawNumber(right),
+            ) => left == right,
+            (Value::Boolean(left), Value::Boolean(right)) => {
+                left == right
+            }
+            (Value::String(left), Value::String(right)) => {
+                left == right
+            }
+            (Value::Char(left), Value::Char(right)) => {
+                left == right
+            }
+            (Value::Bytes(left), Value::Bytes(right)) => {
+                left == right
+            }
+            (Value::List(left), Value::List(right)) => {
+                left == right
+            }
+            (Value::Tuple(left), Value::Tuple(right)) => {
+                left == right
+            }
+            (Value::Optional(left), Value::Optional(right)) => {
+                left == right
+            }
+            (Value::Result(left), Value::Result(right)) => {
+                left == right
+            }
+            (Value::Object(left), Value::Object(right)) => {
+                left == right
+            }
+            (
+                Value::Struct {
+                    name,
+                    fields,
+                    non_exhaustive,
+                },
+                Value::Struct {
+                    name: other_name,
+                    fields: other_fields,
+                    non_exhaustive: other_non_exhaustive,
+                },
+            ) => {
+                name == other_name
+                    && fields == other_fields
+                    && non_exhaustive == other_non_exhaustive
+            }
+            (
+                Value::TupleStruct { name, fields },
+                Value::TupleStruct {
+                    name: other_name,
+                    fields: other_fields,
+                },
+            ) => name == other_name && fields == other_fields,
+            (Value::Map(left), Value::Map(right)) => {
+                left == right
+            }
+            (Value::Set(left), Value::Set(right)) => {
+                left == right
+            }
+            (
+                Value::Range {
+                    start,
+                    end,
+                    inclusive,
+                },
+                Value::Range {
+                    start: other_start,
+                    end: other_end,
+                    inclusive: other_inclusive,
+                },
+            ) => {
+                start == other_start
+                    && end == other_end
+                    && inclusive == other_inclusive
+            }
+            #[cfg(feature = "duration")]
+            (Value::Duration(left), Value::Duration(right)) => {
+                left == right
+            }
+            #[cfg(feature = "datetime")]
+            (Value::DateTime(left), Value::DateTime(right)) => {
+                left == right
+            }
+            #[cfg(feature = "uuid")]
+            (Value::Uuid(left), Value::Uuid(right)) => {
+                left == right
+            }
+            #[cfg(feature = "net")]
+            (Value::IpAddr(left), Value::IpAddr(right)) => {
+                left == right
+            }
+            #[cfg(feature = "net")]
+            (
+                Value::SocketAddr(left),
+                Value::SocketAddr(right),
+            ) => left == right,
+            (Value::Ident(left), Value::Ident(right)) => {
+                left == right
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value<'_> {}
+
+/// Hashes a value so that values that are equal hash the same,
+/// with every `NaN` hashed as one and `-0.0` as `0.0`. An
+/// object's entries are hashed in key order, so two objects with
+/// the same entries hash the same however they were built.
+impl Hash for Value<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            Value::Integer(integer) => integer.hash(state),
+            Value::UInt(integer) => integer.hash(state),
+            Value::BigInt(integer) => integer.hash(state),
+            Value::BigUInt(integer) => integer.hash(state),
+            Value::Float(float) => {
+                float_bits(*float).hash(state)
+            }
+            #[cfg(feature = "arbitrary-precision")]
+            Value::RawNumber(number) => number.hash(state),
+            Value::Boolean(boolean) => boolean.hash(state),
+            Value::String(string) => string.hash(state),
+            Value::Char(char) => char.hash(state),
+            Value::Bytes(bytes) => bytes.hash(state),
+            Value::List(elements)
+            | Value::Tuple(elements)
+            | Value::Set(elements) => elements.hash(state),
+            Value::Optional(inner) => inner.hash(state),
+            Value::Result(result) => result.hash(state),
+            Value::Object(object) => {
+                state.write_usize(object.len());
+                for entry in sorted(object) {
+                    entry.hash(state);
+                }
+            }
+            Value::Struct {
+                name,
+                fields,
+                non_exhaustive,
+            } => {
+                name.hash(state);
+                fields.hash(state);
+                non_exhaustive.hash(state);
+            }
+            Value::TupleStruct { name, fields } => {
+                name.hash(state);
+                fields.hash(state);
+            }
+            Value::Map(entries) => entries.hash(state),
+            Value::Range {
+                start,
+                end,
+                inclusive,
+            } => {
+                start.hash(state);
+                end.hash(state);
+                inclusive.hash(state);
+            }
+            #[cfg(feature = "duration")]
+            Value::Duration(duration) => duration.hash(state),
+            #[cfg(feature = "datetime")]
+            Value::DateTime(datetime) => datetime.hash(state),
+            #[cfg(feature = "uuid")]
+            Value::Uuid(uuid) => uuid.hash(state),
+            #[cfg(feature = "net")]
+            Value::IpAddr(address) => address.hash(state),
+            #[cfg(feature = "net")]
+            Value::SocketAddr(address) => address.hash(state),
+            Value::Ident(ident) => ident.hash(state),
+        }
+    }
+}
+
+/// Orders values first by their kind, in the order
+/// [`ValueKind`](crate::ValueKind) lists them: integers (`i64`,
+/// `u64`, `i128` and then `u128`), floats, raw numbers,
+/// booleans, strings, chars, byte strings, lists, tuples,
+/// options, results, objects, structs, tuple structs, maps,
+/// sets, ranges, durations, datetimes, UUIDs, IP addresses,
+/// socket addresses and then identifiers. So `Value::Integer(2)`
+/// comes before `Value::UInt(1)`, as they are of different
+/// kinds.
+///
+/// Values of the same kind are ordered by what they hold:
+///
+/// - Floats by `f64::total_cmp`, but so that this is consistent
+///   with equality: `-0.0` is equal to `0.0`, and every `NaN` is
+///   equal to every other and after `inf`.
+/// - Strings, byte strings, identifiers and raw numbers by their
+///   bytes.
+/// - Lists, tuples, sets and maps element by element, in order,
+///   with a shorter one before any it starts.
+/// - `None` before `Some`, and `Ok` before `Err`.
+/// - Objects by their entries sorted by key, element by element.
+/// - Structs and tuple structs by their name, then their fields,
+///   in order, and a struct without `..` before one with.
+/// - Ranges by their starts, with no start first, then their
+///   ends, with no end first, and then an exclusive one before
+///   an inclusive one.
+impl Ord for Value<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Integer(left), Value::Integer(right)) => {
+                left.cmp(right)
+            }
+            (Value::UInt(left), Value::UInt(right)) => {
+                left.cmp(right)
+            }
+            (Value::BigInt(left), Value::BigInt(right)) => {
+                left.cmp(right)
+            }
+            (Value::BigUInt(left), Value::BigUInt(right)) => {
+                left.cmp(right)
+            }
+            (Value::Float(left), Value::Float(right)) => {
+                let total =
+                    |float| f64::from_bits(float_bits(float));
+                total(*left).total_cmp(&total(*right))
+            }
+            #[cfg(feature = "arbitrary-precision")]
+            (
+                Value::RawNumber(left),
+                Value::RawNumber(right),
+            ) => left.cmp(right),
+            (Value::Boolean(left), Value::Boolean(right)) => {
+                left.cmp(right)
+            }
+            (Value::String(left), Value::String(right))
+            | (Value::Ident(left), Value::Ident(right)) => {
+                left.cmp(right)
+            }
+            (Value::Char(left), Value::Char(right)) => {
+                left.cmp(right)
+            }
+            (Value::Bytes(left), Value::Bytes(right)) => {
+                left.cmp(right)
+            }
+            (Value::List(left), Value::List(right))
+            | (Value::Tuple(left), Value::Tuple(right))
+            | (Value::Set(left), Value::Set(right)) => {
+                left.cmp(right)
+            }
+            (Value::Optional(left), Value::Optional(right)) => {
+                left.cmp(right)
+            }
+            (Value::Result(left), Value::Result(right)) => {
+                left.cmp(right)
+            }
+            (Value::Object(left), Value::Object(right)) => {
+                sorted(left).cmp(&sorted(right))
+            }
+            (
+                Value::Struct {
+                    name,
+                    fields,
+                    non_exhaustive,
+                },
+                Value::Struct {
+                    name: other_name,
+                    fields: other_fields,
+                    non_exhaustive: other_non_exhaustive,
+                },
+            ) => name
+                .cmp(other_name)
+                .then_with(|| fields.cmp(other_fields))
+                .then(non_exhaustive.cmp(other_non_exhaustive)),
+            (
+                Value::TupleStruct { name, fields },
+                Value::TupleStruct {
+                    name: other_name,
+                    fields: other_fields,
+                },
+            ) => name
+                .cmp(other_name)
+                .then_with(|| fields.cmp(other_fields)),
+            (Value::Map(left), Value::Map(right)) => {
+                left.cmp(right)
+            }
+            (
+                Value::Range {
+                    start,
+                    end,
+                    inclusive,
+                },
+                Value::Range {
+                    start: other_start,
+                    end: other_end,
+                    inclusive: other_inclusive,
+                },
+            ) => start
+                .cmp(other_start)
+                .then_with(|| end.cmp(other_end))
+                .then(inclusive.cmp(other_inclusive)),
+            #[cfg(feature = "duration")]
+            (Value::Duration(left), Value::Duration(right)) => {
+                left.cmp(right)
+            }
+            #[cfg(feature = "datetime")]
+            (Value::DateTime(left), Value::DateTime(right)) => {
+                left.cmp(right)
+            }
+            #[cfg(feature = "uuid")]
+            (Value::Uuid(left), Value::Uuid(right)) => {
+                left.cmp(right)
+            }
+            #[cfg(feature = "net")]
+            (Value::IpAddr(left), Value::IpAddr(right)) => {
+                left.cmp(right)
+            }
+            #[cfg(feature = "net")]
+            (
+                Value::SocketAddr(left),
+                Value::SocketAddr(right),
+            ) => left.cmp(right),
+            _ => self.kind().cmp(&other.kind()),
+        }
+    }
+}
+
+impl PartialOrd for Value<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Returns the bits `float` is compared and hashed by: those of
+/// `f64::NAN` for any `NaN`, those of `0.0` for `-0.0`, and its
+/// own otherwise.
+fn float_bits(float: f64) -> u64 {
+    if float.is_nan() {
+        f64::NAN.to_bits()
+    } else if float == 0.0 {
+        0.0f64.to_bits()
+    } else {
+        float.to_bits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::Cow, boxed::Box, string::String, vec};
+    use core::{
+        cmp::Ordering,
+        hash::{Hash, Hasher},
+        mem,
+    };
+    use std::collections::{
+        hash_map::DefaultHasher, BTreeSet, HashMap, HashSet,
+    };
+
+    use crate::{
+        from_str, tests::with_f64_floats, ObjectMap, OwnedValue,
+        Value,
+    };
+
+    fn hash_of(value: &Value<'_>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn hashes_equal_values_alike() {
+        let mut object = ObjectMap::new();
+        object.insert(
+            Cow::Owned(String::from("b")),
+            Value::Float(-0.0),
+        );
+        object.insert(
+            Cow::Borrowed("a"),
+            Value::List(vec![Value::Optional(Some(Box::new(
+                Value::Float(f64::NAN),
+            )))]),
+        );
+        let built = Value::Object(object);
+        let parsed = with_f64_floats(
+            from_str("{\"a\": [Some(NaN)], \"b\": 0.0}")
+                .unwrap(),
+        );
+
+        assert_eq!(built, parsed);
+        assert_eq!(hash_of(&built), hash_of(&parsed));
+
+        // A `NaN` of another sign and payload is still `NaN`
+        let other_nan = Value::Float(-f64::from_bits(
+            f64::NAN.to_bits() | 1,
+        ));
+        assert_eq!(other_nan, Value::Float(f64::NAN));
+        assert_eq!(
+            hash_of(&other_nan),
+            hash_of(&Value::Float(f64::NAN))
+        );
+
+        for (left, right) in [
+            ("[1, Some(2)]", "[1, Some(3)]"),
+            ("[1, 2]", "(1, 2)"),
+            ("{1, 2}", "{2, 1}"),
+            ("Foo { x: 1 }", "Foo { x: 1, .. }"),
+            ("1", "1.0"),
+            ("Some(None)", "None"),
+        ] {
+            let (left, right) = (
+                from_str(left).unwrap(),
+                from_str(right).unwrap(),
+            );
+            assert_ne!(left, right);
+            assert_ne!(hash_of(&left), hash_of(&right));
+        }
+    }
+
+    #[test]
+    fn dedupes_in_hash_sets() {
+        let values: HashSet<OwnedValue> = [
+            "[1, Some(2.5)]",
+            "[ 1 , Some( 2.5 ) ]",
+            "{\"x\": NaN, \"y\": -0.0}",
+            "{\"y\": 0.0, \"x\": NaN}",
+            "Point(1, 2)",
+            "Point(2, 1)",
+            "\"a\\u{62}c\"",
+            "\"abc\"",
+        ]
+        .into_iter()
+        .map(|text| with_f64_floats(text.parse().unwrap()))
+        .collect();
+
+        assert_eq!(values.len(), 5);
+        assert!(
+            values.contains(&from_str("Point(2, 1)").unwrap())
+        );
+
+        let mut counts = HashMap::new();
+        for text in ["Red", "Green", "Red"] {
+            *counts
+                .entry(from_str(text).unwrap())
+                .or_insert(0) += 1;
+        }
+        assert_eq!(counts[&Value::Ident("Red".into())], 2);
+    }
+
+    #[test]
+    fn sorts_mixed_lists() {
+        let list = |text| {
+            let mut value =
+                with_f64_floats(from_str(text).unwrap());
+            let Value::List(elements) = &mut value else {
+                panic!("expected a list");
+            };
+            mem::take(elements)
+        };
+        let mut values = list(
+            r#"[
+                Red, (), {"b": 1}, [2], NaN, "b", Some(1), Err(0),
+                Foo { x: 1, .. }, 0.0, 'a', [1, 2], -inf, Foo { x: 1 },
+                1..2, None, {"a": 2}, b"a", -0.0, Ok(9), [], true, 3,
+                Bar(1), "a", -1, 1e300, ..2, Blue, {1: 2}, {3},
+                18446744073709551615,
+            ]"#,
+        );
+        values.sort();
+
+        assert_eq!(
+            values,
+            list(
+                r#"[
+                    -1, 3, 18446744073709551615, -inf, 0.0, -0.0,
+                    1e300, NaN, true, "a", "b", 'a', b"a", [], [1, 2],
+                    [2], (), None, Some(1), Ok(9), Err(0), {"a": 2},
+                    {"b": 1}, Foo { x: 1 }, Foo { x: 1, .. }, Bar(1),
+                    {1: 2}, {3}, ..2, 1..2, Blue, Red,
+                ]"#
+            )
+        );
+        // `-0.0` and `0.0` are equal, so they stay in the order
+        // they were in
+        assert!(values[4].as_f64().unwrap().is_sign_positive());
+        assert!(values[5].as_f64().unwrap().is_sign_negative());
+    }
+
+    #[test]
+    fn orders_consistently_with_equality() {
+        let values: Vec<OwnedValue> = [
+            "NaN",
+            "0.0",
+            "-0.0",
+            "{\"a\": 1, \"b\": 2}",
+            "{\"b\": 2, \"a\": 1}",
+            "[1, NaN]",
+            "[1, NaN]",
+            "1",
+            "18446744073709551615",
+            "1.0",
+        ]
+        .into_iter()
+        .map(|text| with_f64_floats(text.parse().unwrap()))
+        .collect();
+        for left in &values {
+            for right in &values {
+                assert_eq!(
+                    left.cmp(right) == Ordering::Equal,
+                    left == right,
+                    "{left} and {right}"
+                );
+                assert_eq!(
+                    left.cmp(right),
+                    right.cmp(left).reverse()
+                );
+            }
+        }
+
+        let keys: BTreeSet<&OwnedValue> =
+            values.iter().collect();
+        assert_eq!(keys.len(), 7);
+    }
+}
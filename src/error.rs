@@ -0,0 +1,622 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use nom::error::ErrorKind as NomErrorKind;
+
+/// An error parsing tson, from [`from_str`],
+/// [`from_str_partial`], [`from_slice`], [`from_slice_partial`],
+/// [`from_reader`] or [`fmt`].
+///
+/// [`from_str`]: crate::from_str
+/// [`from_str_partial`]: crate::from_str_partial
+/// [`from_slice`]: crate::from_slice
+/// [`from_slice_partial`]: crate::from_slice_partial
+/// [`from_reader`]: crate::from_reader
+/// [`fmt`]: crate::fmt()
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    kind: ErrorKind,
+    offset: usize,
+    line: usize,
+    column: usize,
+    expected: Vec<Expected>,
+}
+
+/// What went wrong, as returned by [`Error::kind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A character that can't start or continue a value where
+    /// it is.
+    UnexpectedChar(char),
+    /// The input ended in the middle of a value.
+    UnexpectedEnd,
+    /// A string, raw string or char was never closed.
+    UnterminatedString,
+    /// A char literal with nothing between its quotes, `''`.
+    EmptyChar,
+    /// A `/*` comment was never closed.
+    UnterminatedComment,
+    /// A backslash escape that isn't supported, such as `\q`,
+    /// or that names something that isn't a `char`, such as
+    /// `\u{d800}`.
+    InvalidEscape,
+    /// A number with a bad digit or suffix, such as `0b2` or
+    /// `1u7`, or one [`ParserOptions::strict_numbers`]
+    /// rejects.
+    ///
+    /// [`ParserOptions::strict_numbers`]:
+    ///     crate::ParserOptions::strict_numbers
+    InvalidNumber,
+    /// An integer too large for any integer type, or for the
+    /// type its suffix names.
+    NumberTooLarge,
+    /// A value nested deeper in other values than
+    /// [`ParserOptions::max_depth`] allows.
+    ///
+    /// [`ParserOptions::max_depth`]:
+    ///     crate::ParserOptions::max_depth
+    DepthLimitExceeded,
+    /// One value more than [`ParserOptions::max_nodes`] allows.
+    ///
+    /// [`ParserOptions::max_nodes`]:
+    ///     crate::ParserOptions::max_nodes
+    NodeLimitExceeded,
+    /// A string or byte string longer than
+    /// [`ParserOptions::max_string_len`] allows.
+    ///
+    /// [`ParserOptions::max_string_len`]:
+    ///     crate::ParserOptions::max_string_len
+    StringLimitExceeded,
+    /// One element more than [`ParserOptions::max_elements`]
+    /// allows.
+    ///
+    /// [`ParserOptions::max_elements`]:
+    ///     crate::ParserOptions::max_elements
+    ElementLimitExceeded,
+    /// Something other than whitespace and comments after the
+    /// value of a document.
+    TrailingInput,
+    /// Bytes that aren't valid UTF-8.
+    InvalidUtf8,
+    /// A key set a second time in a document, which
+    /// [`DocumentOptions::duplicate_keys`] can allow.
+    ///
+    /// [`DocumentOptions::duplicate_keys`]:
+    ///     crate::DocumentOptions::duplicate_keys
+    DuplicateKey,
+    /// Reading the input failed, as [`from_reader`] can,
+    /// for the reason given.
+    ///
+    /// [`from_reader`]: crate::from_reader
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+}
+
+/// Something that could have been where an [`Error`] is, as
+/// returned by [`Error::expected`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Expected {
+    /// A character, such as the `,` or `]` after an element of
+    /// a list.
+    Char(char),
+    /// A fixed piece of text, such as the `..` ending the
+    /// fields of a non-exhaustive struct.
+    Tag(&'static str),
+    /// Anything of a kind, such as "a value", described by
+    /// name.
+    Label(&'static str),
+}
+
+impl Error {
+    /// Makes an error `offset` bytes into `input`, which must be
+    /// on a char boundary.
+    pub(crate) fn new(
+        kind: ErrorKind,
+        input: &str,
+        offset: usize,
+    ) -> Self {
+        let before = &input[..offset];
+        let line_start =
+            before.rfind('\n').map_or(0, |idx| idx + 1);
+
+        Error {
+            kind,
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            expected: Vec::new(),
+        }
+    }
+
+    /// Returns the error saying that `expected` could have been
+    /// where it is.
+    pub(crate) fn expecting(
+        mut self,
+        expected: Vec<Expected>,
+    ) -> Self {
+        self.expected = expected;
+        self
+    }
+
+    /// Turns an error from the parsers into an `Error`, given
+    /// the `input` that was being parsed, which the error
+    /// points into, as [`Located::from_nom`] does.
+    pub(crate) fn from_nom(
+        input: &str,
+        err: nom::Err<nom::error::Error<&str>>,
+        furthest: Option<crate::Failure>,
+    ) -> Self {
+        Located::from_nom(input, err, furthest).into_error(input)
+    }
+
+    /// Returns the error with its offset moved `by` bytes
+    /// further, for input that was parsed after skipping a
+    /// prefix of it, such as a byte order mark.
+    pub(crate) fn shifted(mut self, by: usize) -> Self {
+        self.offset += by;
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Returns how many bytes into the input the error is.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the line the error is on, counting from 1. Lines
+    /// end in `\n`, so `\r\n` line endings are counted once.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Returns what could have been where the error is instead,
+    /// in the order the parsers tried them, such as `','` and
+    /// `']'` after an element of a list. This is empty when the
+    /// error isn't about something missing, such as an invalid
+    /// escape.
+    pub fn expected(&self) -> &[Expected] {
+        &self.expected
+    }
+
+    /// Returns the column the error is at, counting from 1, in
+    /// `char`s rather than bytes, so `é` and `日` are a column
+    /// each, as is a tab.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Writes what was expected, as `expected ',' or ']'`, or
+    /// nothing if the error doesn't say.
+    pub(crate) fn write_expected(
+        &self,
+        w: &mut impl fmt::Write,
+    ) -> fmt::Result {
+        let Some((last, rest)) = self.expected.split_last()
+        else {
+            return Ok(());
+        };
+
+        w.write_str("expected ")?;
+        for (idx, expected) in rest.iter().enumerate() {
+            if idx > 0 {
+                w.write_str(", ")?;
+            }
+            write!(w, "{expected}")?;
+        }
+        if !rest.is_empty() {
+            w.write_str(" or ")?;
+        }
+        write!(w, "{last}")
+    }
+}
+
+/// What went wrong and where, without the line and column an
+/// [`Error`] has, which take scanning the input before it to
+/// find.
+#[derive(Debug)]
+pub(crate) struct Located {
+    pub(crate) kind: ErrorKind,
+    pub(crate) offset: usize,
+    expected: Vec<Expected>,
+}
+
+impl Located {
+    pub(crate) fn new(kind: ErrorKind, offset: usize) -> Self {
+        Located {
+            kind,
+            offset,
+            expected: Vec::new(),
+        }
+    }
+
+    /// Returns the error saying that `expected` could have been
+    /// where it is.
+    pub(crate) fn expecting(
+        mut self,
+        expected: Vec<Expected>,
+    ) -> Self {
+        self.expected = expected;
+        self
+    }
+
+    /// Returns the error with its offset moved `by` bytes
+    /// further, for input that was parsed from part of the way
+    /// into a larger one.
+    pub(crate) fn shifted(mut self, by: usize) -> Self {
+        self.offset += by;
+        self
+    }
+
+    /// Makes an error for the character `offset` bytes into
+    /// `input`, which can't be there, or for the end of the
+    /// input.
+    pub(crate) fn unexpected(
+        input: &str,
+        offset: usize,
+    ) -> Self {
+        let kind = match input[offset..].chars().next() {
+            Some(chr) => ErrorKind::UnexpectedChar(chr),
+            None => ErrorKind::UnexpectedEnd,
+        };
+
+        Located::new(kind, offset)
+    }
+
+    /// Turns an error from the parsers into a `Located`, given
+    /// the `input` that was being parsed, which the error
+    /// points into.
+    ///
+    /// An error that another parser could have recovered from,
+    /// unlike a failure, only says where the last alternative
+    /// gave up, so it is reported at the `furthest` offset that
+    /// any parser failed at instead, along with what the parsers
+    /// that failed there expected.
+    pub(crate) fn from_nom(
+        input: &str,
+        err: nom::Err<nom::error::Error<&str>>,
+        furthest: Option<crate::Failure>,
+    ) -> Self {
+        let (err, fatal) = match err {
+            nom::Err::Incomplete(_) => {
+                return Located::unexpected(input, input.len())
+            }
+            nom::Err::Error(err) => (err, false),
+            nom::Err::Failure(err) => (err, true),
+        };
+
+        let offset = input.len() - err.input.len();
+        if !fatal {
+            return match furthest {
+                Some((furthest, expected))
+                    if furthest >= offset =>
+                {
+                    Located::unexpected(input, furthest)
+                        .expecting(expected)
+                }
+                _ => Located::unexpected(input, offset),
+            };
+        }
+
+        let kind = match err.code {
+            NomErrorKind::TakeUntil
+                if err.input.starts_with("/*") =>
+            {
+                ErrorKind::UnterminatedComment
+            }
+            NomErrorKind::TakeUntil => {
+                ErrorKind::UnterminatedString
+            }
+            NomErrorKind::NonEmpty => ErrorKind::EmptyChar,
+            crate::DEPTH_LIMIT_EXCEEDED => {
+                ErrorKind::DepthLimitExceeded
+            }
+            crate::NODE_LIMIT_EXCEEDED => {
+                ErrorKind::NodeLimitExceeded
+            }
+            crate::STRING_LIMIT_EXCEEDED => {
+                ErrorKind::StringLimitExceeded
+            }
+            crate::ELEMENT_LIMIT_EXCEEDED => {
+                ErrorKind::ElementLimitExceeded
+            }
+            NomErrorKind::Escaped => ErrorKind::InvalidEscape,
+            NomErrorKind::TooLarge => ErrorKind::NumberTooLarge,
+            NomErrorKind::Digit
+            | NomErrorKind::HexDigit
+            | NomErrorKind::OctDigit
+            | NomErrorKind::AlphaNumeric
+            | NomErrorKind::Float => ErrorKind::InvalidNumber,
+            _ => {
+                let expected = furthest
+                    .filter(|&(furthest, _)| furthest == offset)
+                    .map_or_else(Vec::new, |(_, expected)| {
+                        expected
+                    });
+                return Located::unexpected(input, offset)
+                    .expecting(expected);
+            }
+        };
+
+        Located::new(kind, offset)
+    }
+
+    /// Returns the `Error` for this in `input`, working out its
+    /// line and column.
+    pub(crate) fn into_error(self, input: &str) -> Error {
+        Error::new(self.kind, input, self.offset)
+            .expecting(self.expected)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::UnexpectedChar(chr) => {
+                write!(f, "unexpected character {chr:?}")
+            }
+            ErrorKind::UnexpectedEnd => {
+                f.write_str("unexpected end of input")
+            }
+            ErrorKind::UnterminatedString => {
+                f.write_str("unterminated string")
+            }
+            ErrorKind::EmptyChar => {
+                f.write_str("empty char literal")
+            }
+            ErrorKind::UnterminatedComment => {
+                f.write_str("unterminated block comment")
+            }
+            ErrorKind::InvalidEscape => {
+                f.write_str("invalid escape sequence")
+            }
+            ErrorKind::InvalidNumber => {
+                f.write_str("invalid number")
+            }
+            ErrorKind::NumberTooLarge => {
+                f.write_str("number too large")
+            }
+            ErrorKind::DepthLimitExceeded => {
+                f.write_str("values nested too deeply")
+            }
+            ErrorKind::NodeLimitExceeded => {
+                f.write_str("too many values")
+            }
+            ErrorKind::StringLimitExceeded => {
+                f.write_str("string too long")
+            }
+            ErrorKind::ElementLimitExceeded => {
+                f.write_str("too many elements")
+            }
+            ErrorKind::TrailingInput => {
+                f.write_str("unexpected input after the value")
+            }
+            ErrorKind::InvalidUtf8 => {
+                f.write_str("invalid UTF-8")
+            }
+            ErrorKind::DuplicateKey => {
+                f.write_str("duplicate key")
+            }
+            #[cfg(feature = "std")]
+            ErrorKind::Io(kind) => {
+                write!(f, "failed to read the input: {kind}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.kind, self.line, self.column
+        )?;
+
+        if !self.expected.is_empty() {
+            f.write_str(", ")?;
+            self.write_expected(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Char(chr) => write!(f, "'{chr}'"),
+            Expected::Tag(tag) => write!(f, "'{tag}'"),
+            Expected::Label(label) => f.write_str(label),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{ErrorKind, Expected};
+    use crate::{from_slice, from_str_partial, Value};
+
+    /// Returns the kind, offset, line and column of the error
+    /// parsing `text` as a document.
+    fn error(text: &str) -> (ErrorKind, usize, usize, usize) {
+        let err = from_slice(text.as_bytes()).unwrap_err();
+        (err.kind(), err.offset(), err.line(), err.column())
+    }
+
+    #[test]
+    fn classifies_errors() {
+        let cases = [
+            ("\"never closed", ErrorKind::UnterminatedString, 0),
+            ("[r#\"raw\"]", ErrorKind::UnterminatedString, 1),
+            ("[b\"bytes]", ErrorKind::UnterminatedString, 1),
+            (
+                "[b\"d\u{e9}j\u{e0}\"]",
+                ErrorKind::UnexpectedChar('\u{e9}'),
+                4,
+            ),
+            ("['a]", ErrorKind::UnterminatedString, 1),
+            ("['']", ErrorKind::EmptyChar, 1),
+            ("['ab']", ErrorKind::UnexpectedChar('b'), 3),
+            ("/* open", ErrorKind::UnterminatedComment, 0),
+            ("1 /* open", ErrorKind::UnterminatedComment, 2),
+            ("[1, \"a\\qb\"]", ErrorKind::InvalidEscape, 6),
+            ("0b102", ErrorKind::InvalidNumber, 4),
+            ("300u8", ErrorKind::NumberTooLarge, 0),
+            ("1 2", ErrorKind::TrailingInput, 2),
+            ("@", ErrorKind::UnexpectedChar('@'), 0),
+            ("  ", ErrorKind::UnexpectedEnd, 2),
+        ];
+
+        for (text, kind, offset) in cases {
+            assert_eq!(
+                error(text),
+                (kind, offset, 1, offset + 1),
+                "{text}"
+            );
+        }
+
+        assert_eq!(
+            from_slice(b"\xef\xbb\xbf[\"\xff\"]").map_err(
+                |err| (err.kind(), err.offset(), err.column())
+            ),
+            Err((ErrorKind::InvalidUtf8, 5, 3))
+        );
+        assert_eq!(
+            from_slice(b"\xef\xbb\xbf1 x").map_err(|err| (
+                err.kind(),
+                err.offset(),
+                err.column()
+            )),
+            Err((ErrorKind::TrailingInput, 5, 3))
+        );
+    }
+
+    #[test]
+    fn points_at_the_furthest_failure() {
+        let cases = [
+            ("[1 2]", '2', 3),
+            ("[1, @]", '@', 4),
+            ("{\"a\": {\"b\": 1 \"c\": 2}}", '"', 14),
+            ("Foo { x: 1 y }", 'y', 11),
+            ("Some(1 2)", '2', 7),
+            ("(1, 2 3)", '3', 6),
+        ];
+
+        for (text, chr, offset) in cases {
+            assert_eq!(
+                error(text),
+                (
+                    ErrorKind::UnexpectedChar(chr),
+                    offset,
+                    1,
+                    offset + 1
+                ),
+                "{text}"
+            );
+        }
+
+        assert_eq!(
+            error("[1,\n 2,\n"),
+            (ErrorKind::UnexpectedEnd, 8, 3, 1)
+        );
+    }
+
+    #[test]
+    fn counts_lines_and_columns() {
+        let fixture =
+            include_str!("../tests/fixtures/broken.tson");
+        let offset = fixture.find("80 80").unwrap() + 3;
+        assert_eq!(
+            error(fixture),
+            (ErrorKind::UnexpectedChar('8'), offset, 37, 41)
+        );
+
+        let crlf = fixture.replace('\n', "\r\n");
+        assert_eq!(
+            error(&crlf),
+            (
+                ErrorKind::UnexpectedChar('8'),
+                offset + 36,
+                37,
+                41
+            )
+        );
+
+        assert_eq!(
+            error("\"日本\" é\r\n\"\\q\""),
+            (ErrorKind::TrailingInput, 9, 1, 6)
+        );
+        assert_eq!(
+            error("[\"日本\",\r\n\t\"é\\q\"]"),
+            (ErrorKind::InvalidEscape, 16, 2, 4)
+        );
+    }
+
+    #[test]
+    fn lists_expected_tokens() {
+        use Expected::{Char, Label, Tag};
+
+        let cases: [(&str, &[Expected]); 13] = [
+            ("[1 2]", &[Char(','), Char(']')]),
+            ("(1)", &[Char(',')]),
+            ("[1, 2", &[Char(','), Char(']')]),
+            ("[1, @]", &[Label("a value"), Char(']')]),
+            ("Some(1", &[Char(','), Char(')')]),
+            ("{\"a\" 1}", &[Char(':'), Char(','), Char('}')]),
+            (
+                "Foo { x: 1 y }",
+                &[Char(','), Tag(".."), Char('}')],
+            ),
+            (
+                "Foo { 1: 2 }",
+                &[Label("a field name"), Tag(".."), Char('}')],
+            ),
+            ("1..=", &[Label("a value")]),
+            ("-", &[Label("a number")]),
+            ("[1, -]", &[Label("a number")]),
+            ("-x", &[Label("a number")]),
+            ("", &[Label("a value")]),
+        ];
+
+        for (text, expected) in cases {
+            let err = from_slice(text.as_bytes()).unwrap_err();
+            assert_eq!(err.expected(), expected, "{text}");
+        }
+
+        let err = from_slice(b"\"a\\q\"").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidEscape);
+        assert_eq!(err.expected(), []);
+    }
+
+    #[test]
+    fn writes_errors() {
+        let err =
+            from_str_partial("[1,\n \"a\\qb\"]").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid escape sequence at line 2, column 4"
+        );
+        assert_eq!(
+            from_str_partial("[1 2]").unwrap_err().to_string(),
+            "unexpected character '2' at line 1, column 4, expected ',' or ']'"
+        );
+        assert_eq!(
+            from_str_partial("").unwrap_err().to_string(),
+            "unexpected end of input at line 1, column 1, expected a value"
+        );
+        assert_eq!(
+            from_str_partial("true garbage"),
+            Ok((Value::Boolean(true), " garbage"))
+        );
+    }
+}
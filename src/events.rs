@@ -0,0 +1,1385 @@
+use alloc::{borrow::Cow, vec::Vec};
+use core::mem;
+
+use nom::{
+    combinator::{opt, verify},
+    sequence::{pair, preceded, terminated},
+    IResult, Parser,
+};
+
+use crate::{
+    char, labelled, limit_exceeded, parse_boolean,
+    parse_byte_string, parse_char, parse_float, parse_ident,
+    parse_identifier, parse_integer, parse_keyword,
+    parse_nested, parse_range_operator, parse_special_value,
+    parse_string, parse_ws, skip_ws, string_value, tag,
+    trailing, Counts, Error, Located, Mode, ParserOptions,
+    State, Value, DEPTH_LIMIT_EXCEEDED, ELEMENT_LIMIT_EXCEEDED,
+    NODE_LIMIT_EXCEEDED, WRAPPER_KEYWORDS,
+};
+
+/// Something an [`EventReader`] read: a value that holds no
+/// others, or the start or end of one that does.
+///
+/// The values a value holds come between its start and its end
+/// as events of their own, so `Some([1, 'a'])` is read as
+/// `SomeStart`, `ListStart`, `Integer(1)`, `Char('a')`,
+/// `ListEnd` and `SomeEnd`. Strings, byte strings and names
+/// borrow from the input unless they have escapes to undo.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Event<'a> {
+    Integer(i64),
+    UInt(u64),
+    BigInt(i128),
+    BigUInt(u128),
+    Float(f64),
+    #[cfg(feature = "arbitrary-precision")]
+    RawNumber(Cow<'a, str>),
+    Boolean(bool),
+    String(Cow<'a, str>),
+    Char(char),
+    Bytes(Cow<'a, [u8]>),
+    /// `None`, or `null` with [`ParserOptions::json_compat`].
+    None,
+    #[cfg(feature = "duration")]
+    Duration(core::time::Duration),
+    #[cfg(feature = "datetime")]
+    DateTime(crate::DateTime),
+    #[cfg(feature = "uuid")]
+    Uuid(crate::Uuid),
+    #[cfg(feature = "net")]
+    IpAddr(core::net::IpAddr),
+    #[cfg(feature = "net")]
+    SocketAddr(core::net::SocketAddr),
+    Ident(&'a str),
+    ListStart,
+    ListEnd,
+    TupleStart,
+    TupleEnd,
+    SetStart,
+    SetEnd,
+    /// The start of an object or a map, whose entries are each
+    /// read as the events of a key followed by those of its
+    /// value. Which of the two it is is only known once every
+    /// key has been read: it is an object if they are all
+    /// strings, `{}` included.
+    MapStart,
+    MapEnd,
+    SomeStart,
+    SomeEnd,
+    OkStart,
+    OkEnd,
+    ErrStart,
+    ErrEnd,
+    /// The start of a tuple struct, with its name.
+    TupleStructStart(&'a str),
+    TupleStructEnd,
+    /// The start of a struct with named fields, with its name,
+    /// each of whose fields is read as a `Field` with its name
+    /// followed by the events of its value.
+    StructStart(&'a str),
+    Field(&'a str),
+    StructEnd {
+        /// Whether the fields end in `..`.
+        non_exhaustive: bool,
+    },
+    /// The `..` or `..=` of a range, which comes after the
+    /// events of its start, if it has one, since a value isn't
+    /// known to start a range until it has been read. The
+    /// events of its end, if it has one, follow, and then
+    /// `RangeEnd`.
+    RangeOperator {
+        inclusive: bool,
+        /// Whether the range has a start, whose events came
+        /// just before, as they do in `[1..]` but not `[1,
+        /// ..]`.
+        has_start: bool,
+    },
+    RangeEnd,
+}
+
+/// A pull parser, which reads a document as a series of
+/// [`Event`]s, one at a time, rather than as a whole [`Value`],
+/// so that a document too large to build can be looked through
+/// for the values that are wanted, as made by
+/// [`EventReader::new`].
+///
+/// It reads the same documents [`from_str`](crate::from_str)
+/// does, with the same parsers: values that hold others are
+/// kept track of on a stack rather than built, so memory goes
+/// only to how deeply they are nested.
+/// [`EventReader::skip_value`] goes over a whole value without
+/// returning its events, and [`EventReader::read_value`] builds
+/// one, as when only a few fields of each element of a long list
+/// are needed.
+///
+/// Braces are read ahead as far as their first element, to tell
+/// a map from a set. A document that isn't valid is read up to
+/// its first error, which is returned from then on; the events
+/// before it have been returned already, so it may be found
+/// well into the document.
+#[derive(Clone, Debug)]
+pub struct EventReader<'a> {
+    input: &'a str,
+    rest: &'a str,
+    options: ParserOptions,
+    /// The values being read that hold others, from the
+    /// outermost in.
+    stack: Vec<Open<'a>>,
+    /// How many of the values in `stack` aren't ranges.
+    depth: usize,
+    next: Next,
+    /// How many values have been read, towards
+    /// [`ParserOptions::max_nodes`].
+    nodes: usize,
+    failed: Option<Error>,
+}
+
+/// A value an [`EventReader`] is inside of.
+#[derive(Clone, Debug)]
+struct Open<'a> {
+    container: Container,
+    /// How many elements, entries or fields were read.
+    count: usize,
+    /// Where the element being read starts.
+    element: &'a str,
+    /// Whether the value may be the start of a range.
+    ranged: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Container {
+    List,
+    Tuple,
+    Set,
+    /// An object or map, and whether the key of the entry being
+    /// read was read.
+    Map {
+        keyed: bool,
+    },
+    TupleStruct,
+    Struct,
+    Some,
+    Ok,
+    Err,
+    /// A range, whose end is being read.
+    Range,
+}
+
+impl Container {
+    fn end(self, non_exhaustive: bool) -> Event<'static> {
+        match self {
+            Container::List => Event::ListEnd,
+            Container::Tuple => Event::TupleEnd,
+            Container::Set => Event::SetEnd,
+            Container::Map { .. } => Event::MapEnd,
+            Container::TupleStruct => Event::TupleStructEnd,
+            Container::Struct => {
+                Event::StructEnd { non_exhaustive }
+            }
+            Container::Some => Event::SomeEnd,
+            Container::Ok => Event::OkEnd,
+            Container::Err => Event::ErrEnd,
+            Container::Range => Event::RangeEnd,
+        }
+    }
+
+    fn closing(self) -> char {
+        match self {
+            Container::List => ']',
+            Container::Tuple | Container::TupleStruct => ')',
+            _ => '}',
+        }
+    }
+}
+
+/// What an [`EventReader`] reads next.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Next {
+    /// A value, which must be there.
+    Value,
+    /// An element of the innermost value, or its end.
+    Element,
+    /// The end of a range, which may be left out unless the
+    /// range is inclusive.
+    RangeEnd {
+        inclusive: bool,
+    },
+    /// Whatever is after a value that was read, which may start
+    /// a range if `ranged` is set.
+    AfterValue {
+        ranged: bool,
+    },
+    /// The end of the innermost value, after its elements.
+    Close,
+    /// Nothing but whitespace and comments.
+    End,
+    Done,
+}
+
+type Failed<'a> = nom::Err<nom::error::Error<&'a str>>;
+
+/// The start of a value, found by [`EventReader::start`].
+enum Start<'a> {
+    /// A value holding no others.
+    Scalar(Value<'a>),
+    /// The start of a value holding others.
+    Open(Container, Event<'a>),
+}
+
+impl<'a> EventReader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        EventReader::with_options(
+            input,
+            ParserOptions::default(),
+        )
+    }
+
+    /// Makes a reader that reads `input` with `options`, as
+    /// [`parse_value_with`](crate::parse_value_with) would.
+    pub fn with_options(
+        input: &'a str,
+        options: ParserOptions,
+    ) -> Self {
+        EventReader {
+            input,
+            rest: input,
+            options,
+            stack: Vec::new(),
+            depth: 0,
+            next: Next::Value,
+            nodes: 0,
+            failed: None,
+        }
+    }
+
+    /// Returns how many bytes into the input the reader is,
+    /// which is just after the last event it read.
+    pub fn offset(&self) -> usize {
+        self.input.len() - self.rest.len()
+    }
+
+    /// Returns how many values the reader is inside of, not
+    /// counting ranges, so that it is 1 just after a `ListStart`
+    /// at the top of the document.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Reads the next event, or returns `None` once the
+    /// document has been read, checking that only whitespace
+    /// and comments follow its value.
+    pub fn next_event(
+        &mut self,
+    ) -> Result<Option<Event<'a>>, Error> {
+        let event =
+            self.run(|reader, state| reader.step(state))?;
+        if event.is_some() || self.next == Next::Done {
+            return Ok(event);
+        }
+
+        let input = self.input;
+        match trailing(input, self.rest, None) {
+            Ok(()) => {
+                self.next = Next::Done;
+                self.rest = &input[input.len()..];
+                Ok(None)
+            }
+            Err(err) => Err(self.fail(err)),
+        }
+    }
+
+    /// Skips over the value whose events would come next, along
+    /// with every value in it, without building any, returning
+    /// whether there was one.
+    ///
+    /// There is none when the next event would end the value
+    /// the reader is in, or the document, or would be a `Field`
+    /// naming the value rather than the value itself, or the
+    /// `RangeOperator` of a range that the last value read
+    /// starts. The end of a range is only ever read as events,
+    /// and a range is skipped as a whole from its start. The
+    /// events of a value that is skipped are never read, but it
+    /// counts towards the limits of the reader's options like
+    /// the values that are.
+    pub fn skip_value(&mut self) -> Result<bool, Error> {
+        self.take_value(Mode::Validate)
+            .map(|value| value.is_some())
+    }
+
+    /// Builds the value whose events would come next, as
+    /// [`from_str`](crate::from_str) would build it, returning
+    /// `None` where [`EventReader::skip_value`] would skip
+    /// nothing.
+    pub fn read_value(
+        &mut self,
+    ) -> Result<Option<Value<'a>>, Error> {
+        self.take_value(Mode::Build)
+    }
+
+    fn take_value(
+        &mut self,
+        mode: Mode<'_>,
+    ) -> Result<Option<Value<'a>>, Error> {
+        self.run(|reader, state| {
+            if let Next::AfterValue { ranged } = reader.next {
+                let elements = matches!(
+                    reader.stack.last(),
+                    Some(Open {
+                        container: Container::List
+                            | Container::Tuple
+                            | Container::Set
+                            | Container::TupleStruct
+                            | Container::Map { keyed: true },
+                        ..
+                    })
+                );
+                let operator = ranged
+                    && opt(skip_ws(parse_range_operator))(
+                        reader.rest,
+                    )?
+                    .1
+                    .is_some();
+                if !elements
+                    || operator
+                    || !reader.separate(state)?
+                {
+                    return Ok(None);
+                }
+            }
+
+            // An element may be left out before the end of the
+            // value it is in
+            let mut end = false;
+            match (reader.next, reader.stack.last_mut()) {
+                (Next::Value, _) => {}
+                (Next::Element, Some(open))
+                    if open.container != Container::Struct =>
+                {
+                    open.element = parse_ws(reader.rest)?.0;
+                    end = open
+                        .element
+                        .starts_with(open.container.closing());
+                }
+                _ => return Ok(None),
+            }
+            match parse_nested(state, reader.rest, mode) {
+                Ok((rest, value)) => {
+                    reader.rest = rest;
+                    reader.next =
+                        Next::AfterValue { ranged: false };
+                    Ok(Some(value))
+                }
+                Err(nom::Err::Error(_)) if end => Ok(None),
+                Err(err) => Err(err),
+            }
+        })
+    }
+
+    /// Runs `step` with a parse state holding the reader's
+    /// options and counts, keeping the error it fails with, if
+    /// any, to be returned from then on.
+    fn run<T>(
+        &mut self,
+        step: impl FnOnce(&mut Self, &State) -> Result<T, Failed<'a>>,
+    ) -> Result<T, Error> {
+        if let Some(err) = &self.failed {
+            return Err(err.clone());
+        }
+
+        let input = self.input;
+        let state = State::new(self.options, true);
+        state.counts.set(Counts {
+            depth: self.depth(),
+            nodes: self.nodes,
+        });
+        let result = step(self, &state);
+        self.nodes = state.counts.get().nodes;
+
+        result.map_err(|err| {
+            let furthest = state.take_failure(input);
+            self.fail(Located::from_nom(input, err, furthest))
+        })
+    }
+
+    fn fail(&mut self, err: Located) -> Error {
+        let err = err.into_error(self.input);
+        self.failed = Some(err.clone());
+        err
+    }
+
+    /// Reads the next event, or returns `None` when only
+    /// whitespace and comments should be left.
+    fn step(
+        &mut self,
+        state: &State,
+    ) -> Result<Option<Event<'a>>, Failed<'a>> {
+        let (rest, _) = parse_ws(self.rest)?;
+        match self.next {
+            Next::Value => {
+                self.value(state, rest, true).map(Some)
+            }
+            Next::Element => self.element(state, rest).map(Some),
+            Next::RangeEnd { inclusive } => {
+                match self.value(state, rest, false) {
+                    Err(nom::Err::Error(err)) if inclusive => {
+                        Err(nom::Err::Failure(err))
+                    }
+                    Err(nom::Err::Error(_)) => {
+                        self.stack.pop();
+                        self.next =
+                            Next::AfterValue { ranged: false };
+                        Ok(Some(Event::RangeEnd))
+                    }
+                    result => result.map(Some),
+                }
+            }
+            Next::Close => self.close(state, rest).map(Some),
+            Next::AfterValue { ranged } => {
+                self.after_value(state, ranged)
+            }
+            Next::End | Next::Done => Ok(None),
+        }
+    }
+
+    /// Reads the start of the value at `input`, or a range with
+    /// no start if `ranged` is set.
+    fn value(
+        &mut self,
+        state: &State,
+        input: &'a str,
+        ranged: bool,
+    ) -> Result<Event<'a>, Failed<'a>> {
+        let depth = self.depth() + 1;
+        if depth > self.options.max_depth {
+            return Err(limit_exceeded(
+                input,
+                DEPTH_LIMIT_EXCEEDED,
+            ));
+        }
+        let nodes = state.with_counts(|counts| {
+            counts.nodes += 1;
+            counts.nodes
+        });
+        if self
+            .options
+            .max_nodes
+            .is_some_and(|max_nodes| nodes > max_nodes)
+        {
+            return Err(limit_exceeded(
+                input,
+                NODE_LIMIT_EXCEEDED,
+            ));
+        }
+
+        let key = matches!(
+            self.stack.last(),
+            Some(Open {
+                container: Container::Map { keyed: false },
+                ..
+            })
+        );
+        let before = state.furthest_so_far();
+        let (rest, start) = match self.start(state, input, key) {
+            Ok(started) => started,
+            Err(nom::Err::Error(err)) => {
+                state.relabel(input, before, "a value");
+                // A range may have no start
+                let operator = match ranged {
+                    true => parse_range_operator(input).ok(),
+                    false => None,
+                };
+                let Some((rest, inclusive)) = operator else {
+                    state
+                        .with_counts(|counts| counts.nodes -= 1);
+                    return Err(nom::Err::Error(err));
+                };
+                self.rest = rest;
+                self.open(Container::Range, rest, false);
+                self.next = Next::RangeEnd { inclusive };
+                return Ok(Event::RangeOperator {
+                    inclusive,
+                    has_start: false,
+                });
+            }
+            Err(err) => {
+                state.relabel(input, before, "a value");
+                return Err(err);
+            }
+        };
+
+        self.rest = rest;
+        match start {
+            Start::Scalar(value) => {
+                self.next = Next::AfterValue { ranged };
+                Ok(scalar_event(value))
+            }
+            Start::Open(container, event) => {
+                self.open(container, rest, ranged);
+                self.next = match container {
+                    Container::Some
+                    | Container::Ok
+                    | Container::Err => Next::Value,
+                    _ => Next::Element,
+                };
+                Ok(event)
+            }
+        }
+    }
+
+    fn open(
+        &mut self,
+        container: Container,
+        element: &'a str,
+        ranged: bool,
+    ) {
+        if container != Container::Range {
+            self.depth += 1;
+        }
+        self.stack.push(Open {
+            container,
+            count: 0,
+            element,
+            ranged,
+        });
+    }
+
+    /// Finds what value starts at `input`, and where it goes on,
+    /// trying each kind of value in the order
+    /// [`from_str`](crate::from_str) does. The key of a map
+    /// entry, as told by `key`, is a string when it is written
+    /// as one, as it would be in an object.
+    fn start(
+        &self,
+        state: &State,
+        input: &'a str,
+        key: bool,
+    ) -> IResult<&'a str, Start<'a>> {
+        type Scalar<'a> =
+            fn(&State, &'a str) -> IResult<&'a str, Value<'a>>;
+
+        if let Some(result) =
+            parse_special_value(input, &state.options)
+        {
+            return result.map(|(rest, value)| {
+                (rest, Start::Scalar(value))
+            });
+        }
+        for (opening, container, event) in [
+            ('[', Container::List, Event::ListStart),
+            ('(', Container::Tuple, Event::TupleStart),
+        ] {
+            if let Ok((rest, _)) = char(state, opening)(input) {
+                return Ok((
+                    rest,
+                    Start::Open(container, event),
+                ));
+            }
+        }
+        if let Ok((rest, _)) = char(state, '{')(input) {
+            return Ok((rest, brace(state, rest)));
+        }
+        if let Ok((rest, _)) = parse_keyword("None")(input) {
+            return Ok((
+                rest,
+                Start::Scalar(Value::Optional(None)),
+            ));
+        }
+        for (keyword, container, event) in [
+            ("Some", Container::Some, Event::SomeStart),
+            ("Ok", Container::Ok, Event::OkStart),
+            ("Err", Container::Err, Event::ErrStart),
+        ] {
+            if let Ok((rest, _)) = pair(
+                tag(state, keyword),
+                skip_ws(char(state, '(')),
+            )(input)
+            {
+                return Ok((
+                    rest,
+                    Start::Open(container, event),
+                ));
+            }
+        }
+
+        let scalars: [Scalar<'a>; 6] = [
+            |state, input| parse_integer(state, input),
+            |_, input| parse_float(input),
+            |state, input| {
+                let (rest, chr) = parse_char(state, input)?;
+                Ok((rest, Value::Char(chr)))
+            },
+            if key {
+                |state, input| {
+                    let (rest, string) =
+                        parse_string(state, input)?;
+                    Ok((rest, Value::String(string)))
+                }
+            } else {
+                |state, input| {
+                    let (rest, string) =
+                        parse_string(state, input)?;
+                    Ok((
+                        rest,
+                        string_value(&state.options, string),
+                    ))
+                }
+            },
+            |state, input| {
+                let (rest, bytes) =
+                    parse_byte_string(state, input)?;
+                Ok((rest, Value::Bytes(bytes)))
+            },
+            |_, input| {
+                parse_boolean.map(Value::Boolean).parse(input)
+            },
+        ];
+        for scalar in scalars {
+            match scalar(state, input) {
+                Ok((rest, value)) => {
+                    return Ok((rest, Start::Scalar(value)))
+                }
+                Err(nom::Err::Error(_)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        let name = verify(
+            |input| parse_ident(state, input),
+            |name: &str| !WRAPPER_KEYWORDS.contains(&name),
+        );
+        if let Ok((rest, (name, _))) =
+            pair(name, skip_ws(char(state, '(')))(input)
+        {
+            let event = Event::TupleStructStart(name);
+            return Ok((
+                rest,
+                Start::Open(Container::TupleStruct, event),
+            ));
+        }
+        if let Ok((rest, (name, _))) = pair(
+            |input| parse_ident(state, input),
+            skip_ws(char(state, '{')),
+        )(input)
+        {
+            let event = Event::StructStart(name);
+            return Ok((
+                rest,
+                Start::Open(Container::Struct, event),
+            ));
+        }
+        let (rest, ident) = parse_ident(state, input)?;
+        Ok((
+            rest,
+            Start::Scalar(Value::Ident(Cow::Borrowed(ident))),
+        ))
+    }
+
+    /// Reads the next element of the innermost value, starting
+    /// at `input`, or its end.
+    fn element(
+        &mut self,
+        state: &State,
+        input: &'a str,
+    ) -> Result<Event<'a>, Failed<'a>> {
+        let open = self
+            .stack
+            .last_mut()
+            .expect("elements are in a value");
+        open.element = input;
+        if open.container == Container::Struct {
+            let field = pair(
+                labelled(
+                    state,
+                    "a field name",
+                    parse_identifier,
+                ),
+                skip_ws(char(state, ':')),
+            )(input);
+            return match field {
+                Ok((rest, (name, _))) => {
+                    self.rest = rest;
+                    self.next = Next::Value;
+                    Ok(Event::Field(name))
+                }
+                Err(nom::Err::Error(_)) => {
+                    self.close(state, input)
+                }
+                Err(err) => Err(err),
+            };
+        }
+
+        match self.value(state, input, true) {
+            Err(nom::Err::Error(_)) => self.close(state, input),
+            result => result,
+        }
+    }
+
+    /// Reads what follows a value that was read, starting at
+    /// `input`.
+    fn after_value(
+        &mut self,
+        state: &State,
+        ranged: bool,
+    ) -> Result<Option<Event<'a>>, Failed<'a>> {
+        if ranged {
+            if let (rest, Some(inclusive)) =
+                opt(skip_ws(parse_range_operator))(self.rest)?
+            {
+                self.rest = rest;
+                self.open(Container::Range, rest, false);
+                self.next = Next::RangeEnd { inclusive };
+                return Ok(Some(Event::RangeOperator {
+                    inclusive,
+                    has_start: true,
+                }));
+            }
+        }
+
+        let Some(open) = self.stack.last_mut() else {
+            self.next = Next::End;
+            return Ok(None);
+        };
+        let rest = self.rest;
+        match open.container {
+            Container::Range => {
+                self.stack.pop();
+                self.next = Next::AfterValue { ranged: false };
+                Ok(Some(Event::RangeEnd))
+            }
+            container @ (Container::Some
+            | Container::Ok
+            | Container::Err) => {
+                let (rest, _) = preceded(
+                    opt(skip_ws(char(state, ','))),
+                    skip_ws(char(state, ')')),
+                )(rest)?;
+                self.closed(rest);
+                Ok(Some(container.end(false)))
+            }
+            Container::Map { keyed: false } => {
+                let (rest, _) = skip_ws(char(state, ':'))(rest)?;
+                open.container = Container::Map { keyed: true };
+                self.rest = rest;
+                self.next = Next::Value;
+                self.step(state)
+            }
+            _ => {
+                self.separate(state)?;
+                self.step(state)
+            }
+        }
+    }
+
+    /// Counts the element of the innermost value that was read,
+    /// and reads the comma after it, returning whether there was
+    /// one. If there wasn't, the end of the value comes next,
+    /// unless this is the one element of a tuple, which is read
+    /// as `(1,)`.
+    fn separate(
+        &mut self,
+        state: &State,
+    ) -> Result<bool, Failed<'a>> {
+        let max_elements = self.options.max_elements;
+        let open = self
+            .stack
+            .last_mut()
+            .expect("elements are in a value");
+        if let Container::Map { keyed } = &mut open.container {
+            *keyed = false;
+        }
+        open.count += 1;
+        if max_elements.is_some_and(|max| open.count > max) {
+            return Err(limit_exceeded(
+                open.element,
+                ELEMENT_LIMIT_EXCEEDED,
+            ));
+        }
+
+        match skip_ws(char(state, ','))(self.rest) {
+            Ok((rest, _)) => {
+                self.rest = rest;
+                self.next = Next::Element;
+                Ok(true)
+            }
+            Err(nom::Err::Error(_))
+                if open.container != Container::Tuple
+                    || open.count != 1 =>
+            {
+                self.next = Next::Close;
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Reads the end of the innermost value at `input`, after
+    /// its last element and any comma after that.
+    fn close(
+        &mut self,
+        state: &State,
+        input: &'a str,
+    ) -> Result<Event<'a>, Failed<'a>> {
+        let open =
+            self.stack.last().expect("elements are in a value");
+        let container = open.container;
+        let mut rest = input;
+        let mut non_exhaustive = false;
+        if container == Container::Struct {
+            let mut marker = opt(terminated(
+                skip_ws(tag(state, "..")),
+                opt(skip_ws(char(state, ','))),
+            ));
+            let (after, marker) = marker(rest)?;
+            rest = after;
+            non_exhaustive = marker.is_some();
+        }
+
+        let (rest, _) =
+            skip_ws(char(state, container.closing()))(rest)?;
+        self.closed(rest);
+        Ok(container.end(non_exhaustive))
+    }
+
+    /// Leaves the innermost value, which ended before `rest`.
+    fn closed(&mut self, rest: &'a str) {
+        let open = self.stack.pop().expect("a value was closed");
+        self.depth -= 1;
+        self.rest = rest;
+        self.next = Next::AfterValue {
+            ranged: open.ranged,
+        };
+    }
+}
+
+/// Finds out what the braces whose contents start at `rest`
+/// hold: a map or object if their first element is followed by
+/// a `:`, and a set if it isn't.
+fn brace<'a>(state: &State, rest: &'a str) -> Start<'a> {
+    let (inner, _) = parse_ws(rest).unwrap_or((rest, ""));
+    // The first element is counted towards the limits when it
+    // is read
+    let counts = state.with_counts(|counts| *counts);
+    let map = inner.starts_with('}')
+        || match parse_nested(state, inner, Mode::Validate) {
+            Ok((after, _)) => parse_ws(after)
+                .is_ok_and(|(after, _)| after.starts_with(':')),
+            Err(_) => false,
+        };
+    state.with_counts(|current| *current = counts);
+
+    match map {
+        true => Start::Open(
+            Container::Map { keyed: false },
+            Event::MapStart,
+        ),
+        false => Start::Open(Container::Set, Event::SetStart),
+    }
+}
+
+/// Returns the event for a value that holds no others.
+fn scalar_event(mut value: Value<'_>) -> Event<'_> {
+    match value {
+        Value::Integer(integer) => Event::Integer(integer),
+        Value::UInt(integer) => Event::UInt(integer),
+        Value::BigInt(integer) => Event::BigInt(integer),
+        Value::BigUInt(integer) => Event::BigUInt(integer),
+        Value::Float(float) => Event::Float(float),
+        #[cfg(feature = "arbitrary-precision")]
+        Value::RawNumber(ref mut number) => {
+            Event::RawNumber(mem::take(number))
+        }
+        Value::Boolean(boolean) => Event::Boolean(boolean),
+        Value::String(ref mut string) => {
+            Event::String(mem::take(string))
+        }
+        Value::Char(chr) => Event::Char(chr),
+        Value::Bytes(ref mut bytes) => {
+            Event::Bytes(mem::take(bytes))
+        }
+        #[cfg(feature = "duration")]
+        Value::Duration(duration) => Event::Duration(duration),
+        #[cfg(feature = "datetime")]
+        Value::DateTime(datetime) => Event::DateTime(datetime),
+        #[cfg(feature = "uuid")]
+        Value::Uuid(uuid) => Event::Uuid(uuid),
+        #[cfg(feature = "net")]
+        Value::IpAddr(address) => Event::IpAddr(address),
+        #[cfg(feature = "net")]
+        Value::SocketAddr(address) => Event::SocketAddr(address),
+        Value::Ident(Cow::Borrowed(ident)) => {
+            Event::Ident(ident)
+        }
+        _ => Event::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::Cow, boxed::Box, vec::Vec};
+    use core::mem;
+
+    use crate::{
+        from_str, validate_with, Error, ErrorKind, Event,
+        EventReader, ParserOptions, Value,
+    };
+
+    /// Builds the value `reader` reads from its events, the way
+    /// the tree parser would build it.
+    fn build<'a>(
+        mut reader: EventReader<'a>,
+    ) -> Result<Value<'a>, Error> {
+        // Each value being built, with the start of any range it
+        // is and the values in it so far
+        let mut stack: Vec<(
+            Event<'a>,
+            Option<Value<'a>>,
+            Vec<Value<'a>>,
+        )> = Vec::new();
+        // The last value built, which may yet start a range
+        let mut built: Option<Value<'a>> = None;
+        while let Some(event) = reader.next_event()? {
+            if let Event::RangeOperator {
+                has_start: true, ..
+            } = event
+            {
+                stack.push((event, built.take(), Vec::new()));
+                continue;
+            }
+            if let Some(value) = built.take() {
+                stack.last_mut().unwrap().2.push(value);
+            }
+
+            let value = match event {
+                Event::RangeOperator { .. } => {
+                    stack.push((event, None, Vec::new()));
+                    continue;
+                }
+                Event::Field(name) => {
+                    stack.last_mut().unwrap().2.push(
+                        Value::String(Cow::Borrowed(name)),
+                    );
+                    continue;
+                }
+                Event::ListEnd
+                | Event::TupleEnd
+                | Event::SetEnd
+                | Event::MapEnd
+                | Event::SomeEnd
+                | Event::OkEnd
+                | Event::ErrEnd
+                | Event::TupleStructEnd
+                | Event::StructEnd { .. }
+                | Event::RangeEnd => {
+                    let (start, range_start, mut values) =
+                        stack.pop().unwrap();
+                    let mut only =
+                        || Box::new(values.pop().unwrap());
+                    let fields = |values: Vec<Value<'a>>| {
+                        let mut values = values.into_iter();
+                        let mut fields = Vec::new();
+                        while let Some(Value::String(key)) =
+                            values.next().as_mut()
+                        {
+                            fields.push((
+                                mem::take(key),
+                                values.next().unwrap(),
+                            ));
+                        }
+                        fields
+                    };
+                    match (start, event) {
+                        (
+                            Event::RangeOperator {
+                                inclusive,
+                                ..
+                            },
+                            _,
+                        ) => Value::Range {
+                            start: range_start.map(Box::new),
+                            end: values.pop().map(Box::new),
+                            inclusive,
+                        },
+                        (_, Event::ListEnd) => {
+                            Value::List(values)
+                        }
+                        (_, Event::TupleEnd) => {
+                            Value::Tuple(values)
+                        }
+                        (_, Event::SetEnd) => Value::Set(values),
+                        (_, Event::SomeEnd) => {
+                            Value::Optional(Some(only()))
+                        }
+                        (_, Event::OkEnd) => {
+                            Value::Result(Ok(only()))
+                        }
+                        (_, Event::ErrEnd) => {
+                            Value::Result(Err(only()))
+                        }
+                        (_, Event::MapEnd)
+                            if values.iter().step_by(2).all(
+                                |key| {
+                                    matches!(
+                                        key,
+                                        Value::String(_)
+                                    )
+                                },
+                            ) =>
+                        {
+                            Value::Object(
+                                fields(values)
+                                    .into_iter()
+                                    .collect(),
+                            )
+                        }
+                        (_, Event::MapEnd) => {
+                            let mut values = values.into_iter();
+                            let mut entries = Vec::new();
+                            while let Some(key) = values.next() {
+                                entries.push((
+                                    key,
+                                    values.next().unwrap(),
+                                ));
+                            }
+                            Value::Map(entries)
+                        }
+                        (Event::TupleStructStart(name), _) => {
+                            Value::TupleStruct {
+                                name: Cow::Borrowed(name),
+                                fields: values,
+                            }
+                        }
+                        (
+                            Event::StructStart(name),
+                            Event::StructEnd { non_exhaustive },
+                        ) => Value::Struct {
+                            name: Cow::Borrowed(name),
+                            fields: fields(values),
+                            non_exhaustive,
+                        },
+                        ends => unreachable!("{ends:?}"),
+                    }
+                }
+                Event::ListStart
+                | Event::TupleStart
+                | Event::SetStart
+                | Event::MapStart
+                | Event::SomeStart
+                | Event::OkStart
+                | Event::ErrStart
+                | Event::TupleStructStart(_)
+                | Event::StructStart(_) => {
+                    stack.push((event, None, Vec::new()));
+                    continue;
+                }
+                Event::Integer(integer) => {
+                    Value::Integer(integer)
+                }
+                Event::UInt(integer) => Value::UInt(integer),
+                Event::BigInt(integer) => Value::BigInt(integer),
+                Event::BigUInt(integer) => {
+                    Value::BigUInt(integer)
+                }
+                Event::Float(float) => Value::Float(float),
+                #[cfg(feature = "arbitrary-precision")]
+                Event::RawNumber(number) => {
+                    Value::RawNumber(number)
+                }
+                Event::Boolean(boolean) => {
+                    Value::Boolean(boolean)
+                }
+                Event::String(string) => Value::String(string),
+                Event::Char(chr) => Value::Char(chr),
+                Event::Bytes(bytes) => Value::Bytes(bytes),
+                Event::None => Value::Optional(None),
+                #[cfg(feature = "duration")]
+                Event::Duration(duration) => {
+                    Value::Duration(duration)
+                }
+                #[cfg(feature = "datetime")]
+                Event::DateTime(datetime) => {
+                    Value::DateTime(datetime)
+                }
+                #[cfg(feature = "uuid")]
+                Event::Uuid(uuid) => Value::Uuid(uuid),
+                #[cfg(feature = "net")]
+                Event::IpAddr(address) => Value::IpAddr(address),
+                #[cfg(feature = "net")]
+                Event::SocketAddr(address) => {
+                    Value::SocketAddr(address)
+                }
+                Event::Ident(ident) => {
+                    Value::Ident(Cow::Borrowed(ident))
+                }
+            };
+            built = Some(value);
+        }
+
+        Ok(built.unwrap())
+    }
+
+    /// Reads the events of `text` up to its first error.
+    fn first_error(reader: &mut EventReader<'_>) -> Error {
+        loop {
+            match reader.next_event() {
+                Ok(Some(_)) => {}
+                Ok(None) => panic!("no error"),
+                Err(err) => return err,
+            }
+        }
+    }
+
+    #[test]
+    fn reads_what_the_tree_parser_builds() {
+        for text in [
+            "1",
+            "-5",
+            "18446744073709551615",
+            "2.5e3",
+            "\"a\\nb\"",
+            "r#\"raw\"#",
+            "'c'",
+            "b\"by\\x00\"",
+            "true",
+            "None",
+            "Some(1)",
+            "Some(1,)",
+            "Ok([1,])",
+            "Err(\"e\")",
+            "Unit",
+            "(1,)",
+            "[1, [2, (3,)], {}]",
+            "{\"a\": 1, \"b\": [true]}",
+            "{1: 2, \"x\": [3]}",
+            "{1, 2, Some(3)}",
+            "Point { x: 1, y: Some(2), .. }",
+            "Point { x: 1 .. }",
+            "Foo { .. }",
+            "Pair(1, 'a',)",
+            "1..2",
+            "..=5",
+            "'a'..",
+            "..",
+            "[1..2, .., [3]..=[4]]",
+            "Some(1..)",
+            "{\"k\": 1..=3, \"j\": ..}",
+            "// lead\n[ /* x */ 1, // y\n ]  // end",
+        ] {
+            assert_eq!(
+                build(EventReader::new(text)),
+                Ok(from_str(text).unwrap()),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn reads_events_in_order() {
+        let mut reader =
+            EventReader::new("Some([1, 'a']..) // done");
+        let mut events = Vec::new();
+        while let Some(event) = reader.next_event().unwrap() {
+            events.push(event);
+        }
+        assert_eq!(
+            events,
+            [
+                Event::SomeStart,
+                Event::ListStart,
+                Event::Integer(1),
+                Event::Char('a'),
+                Event::ListEnd,
+                Event::RangeOperator {
+                    inclusive: false,
+                    has_start: true
+                },
+                Event::RangeEnd,
+                Event::SomeEnd,
+            ]
+        );
+        assert_eq!(reader.next_event(), Ok(None));
+        assert_eq!(reader.offset(), 24);
+    }
+
+    #[test]
+    fn counts_depth_without_ranges() {
+        let mut reader =
+            EventReader::new("[Some(1..[2]), (3,)]");
+        let mut depths = Vec::new();
+        while let Some(event) = reader.next_event().unwrap() {
+            depths.push((event, reader.depth()));
+        }
+        assert_eq!(
+            depths,
+            [
+                (Event::ListStart, 1),
+                (Event::SomeStart, 2),
+                (Event::Integer(1), 2),
+                (
+                    Event::RangeOperator {
+                        inclusive: false,
+                        has_start: true,
+                    },
+                    2
+                ),
+                (Event::ListStart, 3),
+                (Event::Integer(2), 3),
+                (Event::ListEnd, 2),
+                (Event::RangeEnd, 2),
+                (Event::SomeEnd, 1),
+                (Event::TupleStart, 2),
+                (Event::Integer(3), 2),
+                (Event::TupleEnd, 1),
+                (Event::ListEnd, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_and_reads_whole_values() {
+        let mut reader = EventReader::new(
+            "[{\"big\": [1, 2, 3]}, Point { x: 1, y: [2] }, 3..4, 5]",
+        );
+        assert_eq!(
+            reader.next_event(),
+            Ok(Some(Event::ListStart))
+        );
+        assert_eq!(reader.skip_value(), Ok(true));
+        assert_eq!(reader.depth(), 1);
+        assert_eq!(
+            reader.next_event(),
+            Ok(Some(Event::StructStart("Point")))
+        );
+        // A field's name comes before its value
+        assert_eq!(reader.skip_value(), Ok(false));
+        assert_eq!(
+            reader.next_event(),
+            Ok(Some(Event::Field("x")))
+        );
+        assert_eq!(
+            reader.read_value(),
+            Ok(Some(Value::Integer(1)))
+        );
+        assert_eq!(
+            reader.next_event(),
+            Ok(Some(Event::Field("y")))
+        );
+        assert_eq!(reader.skip_value(), Ok(true));
+        assert_eq!(
+            reader.next_event(),
+            Ok(Some(Event::StructEnd {
+                non_exhaustive: false
+            }))
+        );
+        assert_eq!(reader.skip_value(), Ok(true));
+        assert_eq!(
+            reader.read_value(),
+            Ok(Some(Value::Integer(5)))
+        );
+        assert_eq!(reader.skip_value(), Ok(false));
+        assert_eq!(
+            reader.next_event(),
+            Ok(Some(Event::ListEnd))
+        );
+        assert_eq!(reader.skip_value(), Ok(false));
+        assert_eq!(reader.next_event(), Ok(None));
+
+        let mut reader = EventReader::new("[1, [2 3], 4]");
+        reader.next_event().unwrap();
+        assert_eq!(reader.skip_value(), Ok(true));
+        let err = reader.skip_value().unwrap_err();
+        assert_eq!(
+            err,
+            first_error(&mut EventReader::new("[1, [2 3], 4]"))
+        );
+        assert_eq!(reader.next_event(), Err(err));
+    }
+
+    #[test]
+    fn borrows_from_the_input() {
+        let mut reader = EventReader::new(
+            "[\"plain\", \"esc\\u{41}\", Name, b\"raw\"]",
+        );
+        reader.next_event().unwrap();
+        let mut next = || reader.next_event().unwrap().unwrap();
+        assert!(matches!(
+            next(),
+            Event::String(Cow::Borrowed("plain"))
+        ));
+        assert_eq!(
+            next(),
+            Event::String(Cow::Owned("escA".into()))
+        );
+        assert_eq!(next(), Event::Ident("Name"));
+        assert!(matches!(
+            next(),
+            Event::Bytes(Cow::Borrowed(b"raw"))
+        ));
+    }
+
+    #[test]
+    fn fails_where_the_tree_parser_does() {
+        for text in [
+            "",
+            "[1, 2",
+            "[1 2]",
+            "[,]",
+            "{1: 2, 3}",
+            "Foo { x: 1 y }",
+            "Foo { 1: 2 }",
+            "Some(1 2)",
+            "(1)",
+            "1..=",
+            "[\"\\q\"]",
+            "[1] x",
+            "@",
+        ] {
+            let err = first_error(&mut EventReader::new(text));
+            assert_eq!(
+                err,
+                from_str(text).unwrap_err(),
+                "{text}"
+            );
+        }
+
+        // Which the braces hold was settled by an earlier event,
+        // so a `:` is no longer expected
+        let err =
+            first_error(&mut EventReader::new("{\"a\" 1}"));
+        assert_eq!(
+            err.to_string(),
+            "unexpected character '1' at line 1, column 6, \
+             expected ',' or '}'"
+        );
+
+        let mut reader = EventReader::new("[1,\n  2 3]");
+        let err = first_error(&mut reader);
+        assert_eq!(
+            (err.kind(), err.line(), err.column()),
+            (ErrorKind::UnexpectedChar('3'), 2, 5)
+        );
+        assert_eq!(reader.next_event(), Err(err));
+
+        for (text, options) in [
+            ("[[[1]]]", ParserOptions::default().max_depth(2)),
+            (
+                "[1, 2, 3]",
+                ParserOptions::default().max_elements(2),
+            ),
+            (
+                "[1, [2, 3]]",
+                ParserOptions::default().max_nodes(3),
+            ),
+        ] {
+            let err = first_error(
+                &mut EventReader::with_options(text, options),
+            );
+            assert_eq!(
+                Err(err),
+                validate_with(text, options),
+                "{text}"
+            );
+        }
+    }
+}
@@ -0,0 +1,769 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{from_slice, Error};
+
+/// How [`fmt`] lays out a document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FmtOptions {
+    /// What each level of nesting is indented with.
+    pub indent: String,
+    /// The column that lines should stay within. A bracketed
+    /// value is kept on one line when it fits, and spread
+    /// across lines, one element per line, when it doesn't.
+    pub max_width: usize,
+}
+
+impl Default for FmtOptions {
+    fn default() -> Self {
+        Self {
+            indent: "    ".into(),
+            max_width: 100,
+        }
+    }
+}
+
+impl FmtOptions {
+    pub fn indent(mut self, indent: impl Into<String>) -> Self {
+        self.indent = indent.into();
+        self
+    }
+
+    pub fn max_width(mut self, max_width: usize) -> Self {
+        self.max_width = max_width;
+        self
+    }
+}
+
+/// Reformats a tson document the way `rustfmt` formats Rust:
+/// re-indenting it, normalizing the spacing around commas,
+/// colons and brackets, and spreading values that don't fit
+/// within `options.max_width` across lines.
+///
+/// Comments are kept with the values they precede, and a comment
+/// on the same line as the end of a value stays at the end of
+/// that line. Any bracketed value holding a comment is spread
+/// across lines, and single blank lines between the elements of
+/// a value spread across lines are kept.
+///
+/// The reformatted document holds exactly the same value, and
+/// formatting it again changes nothing. A document that isn't
+/// valid is returned as the error [`from_slice`] would return.
+pub fn fmt(
+    input: &str,
+    options: &FmtOptions,
+) -> Result<String, Error> {
+    from_slice(input.as_bytes())?;
+
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let tokens = tokenize(input);
+    let (document, _) = parse_elements(&tokens, 0);
+
+    let mut out = Output {
+        text: String::new(),
+        column: 0,
+        options,
+    };
+    out.write_document(&document);
+
+    Ok(out.text)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TokenKind {
+    Open(char),
+    Close(char),
+    Comma,
+    Colon,
+    Comment,
+    /// Any other run of text, such as `1..=5`, `"a b"` or
+    /// `Foo`.
+    Atom,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    /// How many line breaks come between this token and the one
+    /// before it.
+    newlines: usize,
+}
+
+/// Splits a valid document into tokens.
+fn tokenize(input: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut newlines = 0;
+    let mut idx = 0;
+
+    while let Some(chr) = input[idx..].chars().next() {
+        let start = idx;
+        let kind = match chr {
+            chr if chr.is_whitespace() => {
+                newlines += usize::from(chr == '\n');
+                idx += chr.len_utf8();
+                continue;
+            }
+            '[' | '(' | '{' => TokenKind::Open(chr),
+            ']' | ')' | '}' => TokenKind::Close(chr),
+            ',' => TokenKind::Comma,
+            ':' if !input[idx..].starts_with("::") => {
+                TokenKind::Colon
+            }
+            _ if input[idx..].starts_with("//")
+                || input[idx..].starts_with("/*") =>
+            {
+                TokenKind::Comment
+            }
+            _ => TokenKind::Atom,
+        };
+
+        idx = match kind {
+            TokenKind::Comment => comment_end(input, idx),
+            TokenKind::Atom => atom_end(input, idx),
+            _ => idx + 1,
+        };
+
+        tokens.push(Token {
+            kind,
+            text: &input[start..idx],
+            newlines,
+        });
+        newlines = 0;
+    }
+
+    tokens
+}
+
+/// Returns where the comment starting at `start` ends, leaving
+/// the line break after a line comment out.
+fn comment_end(input: &str, start: usize) -> usize {
+    if input[start..].starts_with("//") {
+        return input[start..]
+            .find('\n')
+            .map_or(input.len(), |len| start + len);
+    }
+
+    // Block comments nest
+    let mut depth = 0;
+    let mut idx = start;
+    while idx < input.len() {
+        if input[idx..].starts_with("/*") {
+            depth += 1;
+            idx += 2;
+        } else if input[idx..].starts_with("*/") {
+            depth -= 1;
+            idx += 2;
+            if depth == 0 {
+                break;
+            }
+        } else {
+            idx +=
+                input[idx..].chars().next().unwrap().len_utf8();
+        }
+    }
+
+    idx
+}
+
+/// Returns where the atom starting at `start` ends, skipping
+/// over the contents of any string or char in it.
+fn atom_end(input: &str, start: usize) -> usize {
+    let rest = &input[start..];
+
+    // Raw strings can hold anything but their own end
+    let hashes = rest.strip_prefix('r').map(|rest| {
+        rest.len() - rest.trim_start_matches('#').len()
+    });
+    if let Some(hashes) = hashes {
+        if rest[1 + hashes..].starts_with('"') {
+            let body = 2 + hashes;
+            let end = format!("\"{}", "#".repeat(hashes));
+            let len = rest[body..].find(&end).unwrap();
+            return start + body + len + end.len();
+        }
+    }
+
+    let mut chars = rest.char_indices();
+    while let Some((idx, chr)) = chars.next() {
+        match chr {
+            '"' | '\'' => {
+                // Skip to the closing quote, along with every
+                // escaped char
+                while let Some((_, inner)) = chars.next() {
+                    match inner {
+                        '\\' => {
+                            chars.next();
+                        }
+                        inner if inner == chr => break,
+                        _ => {}
+                    }
+                }
+            }
+            ':' if rest[idx..].starts_with("::") => {
+                chars.next();
+            }
+            '[' | ']' | '(' | ')' | '{' | '}' | ',' | ':' => {
+                return start + idx
+            }
+            '/' if rest[idx..].starts_with("//")
+                || rest[idx..].starts_with("/*") =>
+            {
+                return start + idx
+            }
+            chr if chr.is_whitespace() => return start + idx,
+            _ => {}
+        }
+    }
+
+    input.len()
+}
+
+/// One element of a bracketed value, such as `1`, `"a": [2]` or
+/// `Foo { x: 1 }`, along with the comments around it.
+#[derive(Debug, Default)]
+struct Element<'a> {
+    /// The comments on the lines before the element.
+    leading: Vec<&'a str>,
+    /// Whether there was a blank line before the element.
+    blank_line: bool,
+    parts: Vec<Part<'a>>,
+    /// The comments after the element on the same line.
+    trailing: Vec<&'a str>,
+}
+
+impl Element<'_> {
+    /// Moves the comments the element ends in, from the first
+    /// line comment on, to after the comma that follows it,
+    /// which would otherwise be written inside that line
+    /// comment.
+    fn trail_line_comments(&mut self) {
+        let comments = self
+            .parts
+            .iter()
+            .rev()
+            .take_while(|part| matches!(part, Part::Comment(_)))
+            .count();
+        let start = self.parts.len() - comments;
+        let Some(line) = self.parts[start..].iter().position(
+            |part| matches!(part, Part::Comment(comment) if comment.starts_with("//")),
+        ) else {
+            return;
+        };
+
+        let moved =
+            self.parts.drain(start + line..).map(|part| {
+                match part {
+                    Part::Comment(comment) => comment,
+                    _ => unreachable!("only comments are moved"),
+                }
+            });
+        self.trailing.extend(moved);
+    }
+}
+
+#[derive(Debug)]
+enum Part<'a> {
+    Atom(&'a str),
+    Colon,
+    /// A comment in the middle of an element.
+    Comment(&'a str),
+    Group(Group<'a>),
+}
+
+/// A bracketed value, such as a list or the fields of a struct.
+#[derive(Debug)]
+struct Group<'a> {
+    open: char,
+    close: char,
+    elements: Vec<Element<'a>>,
+    /// The comments after the last element.
+    dangling: Vec<&'a str>,
+}
+
+/// Groups `tokens`, starting at `pos`, into comma-separated
+/// elements until a closing bracket or the end of the tokens.
+/// Returns the elements and where the tokens after the closing
+/// bracket begin.
+fn parse_elements<'a>(
+    tokens: &[Token<'a>],
+    mut pos: usize,
+) -> (Group<'a>, usize) {
+    let mut elements = Vec::new();
+    let mut pending: Vec<&'a str> = Vec::new();
+    let mut blank_line = false;
+    let mut element = Element::default();
+
+    let close = loop {
+        let Some(token) = tokens.get(pos) else {
+            break None;
+        };
+        pos += 1;
+
+        match token.kind {
+            TokenKind::Comment if element.parts.is_empty() => {
+                if pending.is_empty() {
+                    blank_line = token.newlines > 1;
+                }
+                pending.push(token.text);
+            }
+            TokenKind::Comment => {
+                let next = tokens[pos..].iter().find(|token| {
+                    token.kind != TokenKind::Comment
+                });
+                let ends_element = matches!(
+                    next.map(|token| token.kind),
+                    None | Some(TokenKind::Close(_))
+                );
+
+                // Once a comment is on a line of its own, so are
+                // the ones after it
+                if ends_element
+                    && token.newlines == 0
+                    && pending.is_empty()
+                {
+                    element.trailing.push(token.text);
+                } else if ends_element {
+                    pending.push(token.text);
+                } else {
+                    element
+                        .parts
+                        .push(Part::Comment(token.text));
+                }
+            }
+            TokenKind::Close(chr) => break Some(chr),
+            TokenKind::Comma => {
+                element.trail_line_comments();
+                // Comments after the comma on the same line
+                while let Some(token) = tokens.get(pos) {
+                    match token.kind {
+                        TokenKind::Comment
+                            if token.newlines == 0 =>
+                        {
+                            element.trailing.push(token.text);
+                            pos += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                elements.push(core::mem::take(&mut element));
+            }
+            kind => {
+                if element.parts.is_empty() {
+                    element.leading =
+                        core::mem::take(&mut pending);
+                    element.blank_line = match element.leading[..]
+                    {
+                        [] => token.newlines > 1,
+                        _ => blank_line,
+                    };
+                }
+
+                let part = match kind {
+                    TokenKind::Open(open) => {
+                        let (group, next) =
+                            parse_elements(tokens, pos);
+                        pos = next;
+                        Part::Group(Group { open, ..group })
+                    }
+                    TokenKind::Colon => Part::Colon,
+                    _ => Part::Atom(token.text),
+                };
+                element.parts.push(part);
+            }
+        }
+    };
+
+    if !element.parts.is_empty() {
+        elements.push(element);
+    } else if let Some(last) = elements.last_mut() {
+        // Comments left over after the last comma that are on
+        // its line
+        last.trailing.append(&mut element.trailing);
+    }
+
+    let group = Group {
+        open: ' ',
+        close: close.unwrap_or(' '),
+        elements,
+        dangling: pending,
+    };
+    (group, pos)
+}
+
+impl Group<'_> {
+    /// Whether the group has text between braces and its
+    /// elements, as in `Foo { x: 1 }`, rather than none, as in
+    /// `{"x": 1}`.
+    fn is_padded(&self, named: bool) -> bool {
+        named && self.open == '{'
+    }
+
+    fn has_comments(&self) -> bool {
+        !self.dangling.is_empty()
+            || self.elements.iter().any(|element| {
+                !element.leading.is_empty()
+                    || !element.trailing.is_empty()
+                    || element.parts.iter().any(
+                        |part| match part {
+                            Part::Comment(_) => true,
+                            Part::Group(group) => {
+                                group.has_comments()
+                            }
+                            _ => false,
+                        },
+                    )
+            })
+    }
+}
+
+/// Whether no space goes between `before` and `after`.
+fn is_glued(before: &Part<'_>, after: &Part<'_>) -> bool {
+    match (before, after) {
+        (_, Part::Colon) => true,
+        (Part::Atom(_), Part::Group(group)) => group.open != '{',
+        (Part::Atom(atom), _) if atom.ends_with("..") => true,
+        (Part::Atom(atom), _) if atom.ends_with("..=") => true,
+        (_, Part::Atom(atom)) => atom.starts_with(".."),
+        _ => false,
+    }
+}
+
+struct Output<'o> {
+    text: String,
+    column: usize,
+    options: &'o FmtOptions,
+}
+
+impl Output<'_> {
+    fn write(&mut self, text: &str) {
+        self.column = match text.rfind('\n') {
+            Some(idx) => text[idx + 1..].chars().count(),
+            None => self.column + text.chars().count(),
+        };
+        self.text.push_str(text);
+    }
+
+    fn line(&mut self, depth: usize) {
+        self.text.push('\n');
+        self.column = 0;
+        for _ in 0..depth {
+            let indent = &self.options.indent;
+            self.column += indent.chars().count();
+            self.text.push_str(indent);
+        }
+    }
+
+    fn write_document(&mut self, document: &Group<'_>) {
+        for element in &document.elements {
+            for comment in &element.leading {
+                self.write(comment);
+                self.line(0);
+            }
+            self.write_parts(&element.parts, 0);
+            for comment in &element.trailing {
+                self.write(" ");
+                self.write(comment);
+            }
+        }
+        for comment in &document.dangling {
+            self.line(0);
+            self.write(comment);
+        }
+        self.text.push('\n');
+    }
+
+    fn write_parts(&mut self, parts: &[Part<'_>], depth: usize) {
+        let mut before: Option<&Part<'_>> = None;
+        for part in parts {
+            if let Some(before) = before {
+                if matches!(before, Part::Comment(comment) if comment.starts_with("//"))
+                {
+                    self.line(depth + 1);
+                } else if !is_glued(before, part) {
+                    self.write(" ");
+                }
+            }
+
+            match part {
+                Part::Atom(atom) => self.write(atom),
+                Part::Colon => self.write(":"),
+                Part::Comment(comment) => self.write(comment),
+                Part::Group(group) => {
+                    let named =
+                        matches!(before, Some(Part::Atom(_)));
+                    self.write_group(group, named, depth)
+                }
+            }
+            before = Some(part);
+        }
+    }
+
+    fn write_group(
+        &mut self,
+        group: &Group<'_>,
+        named: bool,
+        depth: usize,
+    ) {
+        if group.elements.is_empty() && group.dangling.is_empty()
+        {
+            self.write(&format!(
+                "{}{}",
+                group.open, group.close
+            ));
+            return;
+        }
+
+        // Leave room for the comma that follows an element
+        let budget = self.options.max_width.saturating_sub(
+            self.column + usize::from(depth > 0),
+        );
+        if !group.has_comments() {
+            let mut flat = String::new();
+            if write_flat(&mut flat, group, named, budget) {
+                self.write(&flat);
+                return;
+            }
+        }
+
+        self.write(&group.open.to_string());
+        for (idx, element) in group.elements.iter().enumerate() {
+            if idx > 0 && element.blank_line {
+                self.text.push('\n');
+            }
+            for comment in &element.leading {
+                self.line(depth + 1);
+                self.write(comment);
+            }
+            self.line(depth + 1);
+            self.write_parts(&element.parts, depth + 1);
+            self.write(",");
+            for comment in &element.trailing {
+                self.write(" ");
+                self.write(comment);
+            }
+        }
+        for comment in &group.dangling {
+            self.line(depth + 1);
+            self.write(comment);
+        }
+        self.line(depth);
+        self.write(&group.close.to_string());
+    }
+}
+
+/// Writes `group` on a single line, returning whether it fit in
+/// `budget` chars.
+fn write_flat(
+    out: &mut String,
+    group: &Group<'_>,
+    named: bool,
+    budget: usize,
+) -> bool {
+    let start = out.len();
+    let padded = group.is_padded(named);
+
+    out.push(group.open);
+    if padded && !group.elements.is_empty() {
+        out.push(' ');
+    }
+    for (idx, element) in group.elements.iter().enumerate() {
+        if idx > 0 {
+            out.push_str(", ");
+        }
+        let mut before: Option<&Part<'_>> = None;
+        for part in &element.parts {
+            if before
+                .is_some_and(|before| !is_glued(before, part))
+            {
+                out.push(' ');
+            }
+            match part {
+                Part::Atom(atom) if atom.contains('\n') => {
+                    return false
+                }
+                Part::Atom(atom) => out.push_str(atom),
+                Part::Colon => out.push(':'),
+                Part::Comment(_) => return false,
+                Part::Group(inner) => {
+                    let used = out[start..].chars().count();
+                    let named =
+                        matches!(before, Some(Part::Atom(_)));
+                    let Some(budget) = budget.checked_sub(used)
+                    else {
+                        return false;
+                    };
+                    if !write_flat(out, inner, named, budget) {
+                        return false;
+                    }
+                }
+            }
+            before = Some(part);
+
+            if out[start..].chars().count() > budget {
+                return false;
+            }
+        }
+    }
+    // `(1)` is the same as `(1,)`, but `{:?}` writes the latter
+    if group.open == '(' && !named && group.elements.len() == 1 {
+        out.push(',');
+    }
+    if padded && !group.elements.is_empty() {
+        out.push(' ');
+    }
+    out.push(group.close);
+
+    out[start..].chars().count() <= budget
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        display::tests::{parse_f64s, Generator},
+        fmt, from_str, parse_value, FmtOptions,
+    };
+
+    fn assert_formats(
+        input: &str,
+        expected: &str,
+        options: &FmtOptions,
+    ) {
+        let formatted = fmt(input, options).unwrap();
+        assert_eq!(formatted, expected, "{input}");
+
+        // The value is unchanged, and formatting again is a
+        // no-op
+        assert_eq!(
+            parse_value(&formatted).map(|(_, value)| value),
+            parse_value(input).map(|(_, value)| value)
+        );
+        assert_eq!(fmt(&formatted, options).unwrap(), formatted);
+    }
+
+    #[test]
+    fn formats_documents() {
+        let options = FmtOptions::default().max_width(24);
+        let cases = [
+            ("[1,2 ,  3]", "[1, 2, 3]\n"),
+            ("  Foo{a:1,b :( 2 ,)}", "Foo { a: 1, b: (2,) }\n"),
+            ("Foo { }", "Foo {}\n"),
+            ("{ 'a' : Some( 1 ) , 1 .. 5 : x::Y }", "{\n    'a': Some(1),\n    1..5: x::Y,\n}\n"),
+            (
+                "[[1, 2, 3], [4, 5, 6], [7, 8, 9]]",
+                "[\n    [1, 2, 3],\n    [4, 5, 6],\n    [7, 8, 9],\n]\n",
+            ),
+            (
+                "Point{x:1.0,y:-2.5,z:r#\"a\" b\"#}",
+                "Point {\n    x: 1.0,\n    y: -2.5,\n    z: r#\"a\" b\"#,\n}\n",
+            ),
+            ("[\"a, [b]\" , '\\'' ,b\"//\"]", "[\"a, [b]\", '\\'', b\"//\"]\n"),
+        ];
+
+        for (input, expected) in cases {
+            assert_formats(input, expected, &options);
+        }
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let options = FmtOptions::default();
+        let input = "// The config
+[ 1, // one
+  /* two */ 2 ,
+
+  // three
+  3 // the last
+  // done
+]
+// end";
+        let expected = "// The config
+[
+    1, // one
+    /* two */
+    2,
+
+    // three
+    3, // the last
+    // done
+]
+// end
+";
+        assert_formats(input, expected, &options);
+
+        let input = "{\"a\": /* why */ [1 /* first */, 2], \"b\": // note\n 3}";
+        let expected = "{
+    \"a\": /* why */ [
+        1 /* first */,
+        2,
+    ],
+    \"b\": // note
+        3,
+}
+";
+        assert_formats(input, expected, &options);
+
+        // A line comment before a comma is moved after it
+        for (input, expected) in [
+            ("[1\n// c\n, 2]", "[\n    1, // c\n    2,\n]\n"),
+            (
+                "Foo { a: 1 // c\n, b: 2 }",
+                "Foo {\n    a: 1, // c\n    b: 2,\n}\n",
+            ),
+            (
+                "{1: 2 /* b */ // c\n // d\n, 3: 4}",
+                "{\n    1: 2 /* b */, // c // d\n    3: 4,\n}\n",
+            ),
+            (
+                "{1: 2 // c\n, 3: 4}",
+                "{\n    1: 2, // c\n    3: 4,\n}\n",
+            ),
+        ] {
+            assert_formats(input, expected, &options);
+            let formatted = fmt(input, &options).unwrap();
+            assert_eq!(from_str(&formatted), from_str(input));
+        }
+
+        assert_formats("[] // empty", "[] // empty\n", &options);
+        assert_formats(
+            "[ /* nothing */ ]",
+            "[\n    /* nothing */\n]\n",
+            &options,
+        );
+    }
+
+    #[test]
+    fn reformats_generated_values_idempotently() {
+        let mut generator = Generator(0xf0f0);
+        for width in [0, 20, 80] {
+            let options = FmtOptions::default().max_width(width);
+            for _ in 0..300 {
+                let value = generator.value(4);
+                let text = value.to_string();
+                let formatted = fmt(&text, &options).unwrap();
+
+                assert_eq!(
+                    parse_f64s(&formatted),
+                    Ok(("\n", value)),
+                    "{formatted}"
+                );
+                assert_eq!(
+                    fmt(&formatted, &options).unwrap(),
+                    formatted
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        let options = FmtOptions::default();
+        assert!(fmt("[1, 2", &options).is_err());
+        assert!(fmt("[1] [2]", &options).is_err());
+        assert!(fmt("/* unclosed", &options).is_err());
+    }
+}
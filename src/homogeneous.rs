@@ -0,0 +1,229 @@
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::mem;
+
+use crate::{ConvertError, FromTson, Value};
+
+/// The elements of a list whose elements are all of one kind,
+/// as returned by [`Value::try_into_homogeneous`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Homogeneous<'a> {
+    /// The list has no elements, so they are of no kind.
+    Empty,
+    Integers(Vec<i64>),
+    Floats(Vec<f64>),
+    Booleans(Vec<bool>),
+    Chars(Vec<char>),
+    Strings(Vec<Cow<'a, str>>),
+}
+
+impl<'a> Value<'a> {
+    /// Converts each element of a list, tuple or set with
+    /// `TryFrom`, as when a list is known to hold only floats or
+    /// only strings, returning the first error marked with the
+    /// index of the element that caused it, as in `[2]: expected
+    /// f64, found a string`.
+    ///
+    /// A list holds values rather than the type they convert to,
+    /// so there is no slice of them to borrow: converting them
+    /// takes making a `Vec`, whose elements can borrow from this
+    /// value, as with `as_vec_of::<&str>()`.
+    pub fn as_vec_of<'v, T>(
+        &'v self,
+    ) -> Result<Vec<T>, ConvertError>
+    where
+        T: TryFrom<&'v Value<'a>, Error = ConvertError>,
+    {
+        match self {
+            Value::List(elements)
+            | Value::Tuple(elements)
+            | Value::Set(elements) => elements
+                .iter()
+                .enumerate()
+                .map(|(idx, element)| {
+                    T::try_from(element)
+                        .map_err(|err| err.in_element(idx))
+                })
+                .collect(),
+            value => {
+                Err(ConvertError::mismatch("a list", value))
+            }
+        }
+    }
+
+    /// Takes the elements of a list, tuple or set out as a `Vec`
+    /// of the type they all convert to, when which that is isn't
+    /// known beforehand.
+    ///
+    /// The first element decides the type: integers are taken as
+    /// `i64`s, unless any element is a float, when they are all
+    /// taken as `f64`s, and floats, booleans, chars and strings
+    /// as themselves. Each element is then converted like
+    /// [`Value::as_vec_of`] converts it, so that a char is taken
+    /// as a string among strings, and the first that doesn't
+    /// convert is the error, marked with its index, as in `[3]:
+    /// expected bool, found an integer`.
+    pub fn try_into_homogeneous(
+        mut self,
+    ) -> Result<Homogeneous<'a>, ConvertError> {
+        let elements = match self {
+            Value::List(ref mut elements)
+            | Value::Tuple(ref mut elements)
+            | Value::Set(ref mut elements) => {
+                mem::take(elements)
+            }
+            value => {
+                return Err(ConvertError::mismatch(
+                    "a list", &value,
+                ))
+            }
+        };
+        let Some(first) = elements.first() else {
+            return Ok(Homogeneous::Empty);
+        };
+
+        Ok(match first {
+            first if first.is_number() => {
+                if elements.iter().any(is_float) {
+                    Homogeneous::Floats(convert(&elements)?)
+                } else {
+                    Homogeneous::Integers(convert(&elements)?)
+                }
+            }
+            Value::Boolean(_) => {
+                Homogeneous::Booleans(convert(&elements)?)
+            }
+            Value::Char(_) => {
+                Homogeneous::Chars(convert(&elements)?)
+            }
+            Value::String(_) => Homogeneous::Strings(
+                elements
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, mut element)| match element {
+                        Value::String(ref mut string) => {
+                            Ok(mem::take(string))
+                        }
+                        element => String::from_tson(&element)
+                            .map(Cow::Owned)
+                            .map_err(|err| err.in_element(idx)),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            first => {
+                return Err(ConvertError::mismatch(
+                    "a number, boolean, char or string",
+                    first,
+                )
+                .in_element(0))
+            }
+        })
+    }
+}
+
+/// Converts every element, marking the first error with its
+/// index.
+fn convert<T: FromTson>(
+    elements: &[Value<'_>],
+) -> Result<Vec<T>, ConvertError> {
+    elements
+        .iter()
+        .enumerate()
+        .map(|(idx, element)| {
+            T::from_tson(element)
+                .map_err(|err| err.in_element(idx))
+        })
+        .collect()
+}
+
+fn is_float(value: &Value<'_>) -> bool {
+    match value {
+        Value::Float(_) => true,
+        #[cfg(feature = "arbitrary-precision")]
+        Value::RawNumber(_) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec, vec::Vec};
+
+    use crate::{from_str, Homogeneous, Value};
+
+    #[test]
+    fn takes_out_vecs_of_one_type() {
+        let floats = from_str("[1.5, 2, -0.25]").unwrap();
+        assert_eq!(
+            floats.as_vec_of::<f64>(),
+            Ok(vec![1.5, 2.0, -0.25])
+        );
+
+        let names = from_str("(\"a\", \"b\")").unwrap();
+        let borrowed: Vec<&str> = names.as_vec_of().unwrap();
+        assert_eq!(borrowed, ["a", "b"]);
+
+        let error = from_str("[1.5, 2, \"3\"]")
+            .unwrap()
+            .as_vec_of::<f64>()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "[2]: expected f64, found a string"
+        );
+        assert_eq!(
+            Value::Integer(1)
+                .as_vec_of::<i64>()
+                .unwrap_err()
+                .to_string(),
+            "expected a list, found an integer"
+        );
+    }
+
+    #[test]
+    fn finds_the_type_elements_share() {
+        let homogeneous = |text| {
+            from_str(text).unwrap().try_into_homogeneous()
+        };
+
+        assert_eq!(homogeneous("[]"), Ok(Homogeneous::Empty));
+        assert_eq!(
+            homogeneous("[1, 2, 3]"),
+            Ok(Homogeneous::Integers(vec![1, 2, 3]))
+        );
+        assert_eq!(
+            homogeneous("{1, 2.5}"),
+            Ok(Homogeneous::Floats(vec![1.0, 2.5]))
+        );
+        assert_eq!(
+            homogeneous("[true, false]"),
+            Ok(Homogeneous::Booleans(vec![true, false]))
+        );
+        assert_eq!(
+            homogeneous("['a', \"b\"]"),
+            Ok(Homogeneous::Chars(vec!['a', 'b']))
+        );
+        assert_eq!(
+            homogeneous("[\"ab\", 'c']"),
+            Ok(Homogeneous::Strings(vec![
+                "ab".into(),
+                "c".into()
+            ]))
+        );
+
+        for (text, error) in [
+            ("[true, false, true, 1]", "[3]: expected bool, found an integer"),
+            ("[1, 18446744073709551615]", "[1]: 18446744073709551615 is out of range for i64"),
+            ("[1, 2.5, None]", "[2]: expected f64, found an option"),
+            ("[\"a\", [1]]", "[1]: expected String, found a list"),
+            ("[[1], [2]]", "[0]: expected a number, boolean, char or string, found a list"),
+            ("\"abc\"", "expected a list, found a string"),
+        ] {
+            assert_eq!(
+                homogeneous(text).unwrap_err().to_string(),
+                error,
+                "{text}"
+            );
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use alloc::vec::{self, Vec};
+use core::{mem, slice};
+
+use crate::{display::sorted, Value};
+
+impl<'a> Value<'a> {
+    /// Returns an iterator over the elements of a list, tuple,
+    /// set or tuple struct, which for anything else is empty, so
+    /// that code that goes through a value's elements doesn't
+    /// need to match on it first.
+    pub fn iter(&self) -> slice::Iter<'_, Value<'a>> {
+        self.elements().iter()
+    }
+
+    /// Returns an iterator over the elements of a list, tuple,
+    /// set or tuple struct like [`Value::iter`] does, but so
+    /// that they can be changed.
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, Value<'a>> {
+        match self.elements_mut() {
+            Some(elements) => elements.iter_mut(),
+            None => [].iter_mut(),
+        }
+    }
+
+    /// Returns an iterator over the entries of an object, in the
+    /// order of their keys, the fields of a struct, or the
+    /// entries of a map whose keys are strings, in the order
+    /// they were written, which for anything else is empty.
+    ///
+    /// Like a [`Path`](crate::Path), this leaves out the entries
+    /// of a map whose keys aren't strings; use [`Value::as_map`]
+    /// for those.
+    pub fn entries<'v>(
+        &'v self,
+    ) -> impl Iterator<Item = (&'v str, &'v Value<'a>)> {
+        let (object, fields, map) = match self {
+            Value::Object(object) => {
+                (sorted(object), &[][..], &[][..])
+            }
+            Value::Struct { fields, .. } => {
+                (Vec::new(), &fields[..], &[][..])
+            }
+            Value::Map(entries) => {
+                (Vec::new(), &[][..], &entries[..])
+            }
+            _ => (Vec::new(), &[][..], &[][..]),
+        };
+
+        object
+            .into_iter()
+            .map(|(key, value)| (&**key, value))
+            .chain(
+                fields
+                    .iter()
+                    .map(|(field, value)| (&**field, value)),
+            )
+            .chain(map.iter().filter_map(|(key, value)| {
+                Some((key.as_str()?, value))
+            }))
+    }
+
+    /// Returns the elements of a list, tuple, set or tuple
+    /// struct, or none for anything else.
+    fn elements(&self) -> &[Value<'a>] {
+        match self {
+            Value::List(elements)
+            | Value::Tuple(elements)
+            | Value::Set(elements)
+            | Value::TupleStruct {
+                fields: elements, ..
+            } => elements,
+            _ => &[],
+        }
+    }
+}
+
+/// Goes through the elements of a list, tuple, set or tuple
+/// struct, and through nothing for any other value, rather than
+/// the value itself once, so that a value that isn't a list
+/// can't be mistaken for one of a single element.
+impl<'a> IntoIterator for Value<'a> {
+    type Item = Value<'a>;
+    type IntoIter = vec::IntoIter<Value<'a>>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        match self {
+            Value::List(ref mut elements)
+            | Value::Tuple(ref mut elements)
+            | Value::Set(ref mut elements)
+            | Value::TupleStruct {
+                fields: ref mut elements,
+                ..
+            } => mem::take(elements).into_iter(),
+            _ => Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<'v, 'a> IntoIterator for &'v Value<'a> {
+    type Item = &'v Value<'a>;
+    type IntoIter = slice::Iter<'v, Value<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'v, 'a> IntoIterator for &'v mut Value<'a> {
+    type Item = &'v mut Value<'a>;
+    type IntoIter = slice::IterMut<'v, Value<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+
+    use crate::{from_str, Value};
+
+    #[test]
+    fn iterates_over_elements() {
+        let value = from_str("[1, 2, 3]").unwrap();
+        let mut sum = 0;
+        for element in &value {
+            sum += element.as_i64().unwrap();
+        }
+        assert_eq!(sum, 6);
+
+        let nested =
+            from_str("[[1, 2], [], (3,), {4}, 5]").unwrap();
+        let flattened: Vec<_> = nested
+            .iter()
+            .flat_map(Value::iter)
+            .filter_map(Value::as_i64)
+            .collect();
+        assert_eq!(flattened, [1, 2, 3, 4]);
+
+        // Anything else has no elements, rather than being one
+        for scalar in ["5", "\"abc\"", "Some([1])", "{\"a\": 1}"]
+        {
+            let scalar = from_str(scalar).unwrap();
+            assert_eq!(scalar.iter().count(), 0);
+            assert_eq!(scalar.into_iter().count(), 0);
+        }
+    }
+
+    #[test]
+    fn iterates_mutably_and_by_value() {
+        let mut value = from_str("Point(1, 2)").unwrap();
+        for element in &mut value {
+            if let Value::Integer(integer) = element {
+                *integer *= 10;
+            }
+        }
+        assert_eq!(value, from_str("Point(10, 20)").unwrap());
+
+        let mut scalar = Value::Integer(1);
+        assert_eq!(scalar.iter_mut().count(), 0);
+
+        let owned: Vec<Value<'_>> = value.into_iter().collect();
+        assert_eq!(
+            owned,
+            [Value::Integer(10), Value::Integer(20)]
+        );
+    }
+
+    #[test]
+    fn iterates_over_entries() {
+        let entries = |text| {
+            let value = from_str(text).unwrap();
+            value
+                .entries()
+                .map(|(key, value)| (key.into(), value.clone()))
+                .collect::<Vec<(String, _)>>()
+        };
+
+        assert_eq!(
+            entries("{\"b\": 1, \"a\": 2}"),
+            [
+                ("a".into(), Value::Integer(2)),
+                ("b".into(), Value::Integer(1))
+            ]
+        );
+        assert_eq!(
+            entries("Foo { y: 1, x: 2 }"),
+            [
+                ("y".into(), Value::Integer(1)),
+                ("x".into(), Value::Integer(2))
+            ]
+        );
+        assert_eq!(
+            entries("{1: 'a', \"z\": 'b'}"),
+            [("z".into(), Value::Char('b'))]
+        );
+        assert_eq!(entries("[1, 2]"), []);
+    }
+}
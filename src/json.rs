@@ -0,0 +1,420 @@
+use std::{fmt, io};
+
+use crate::Value;
+
+/// An error from [`Value::to_json_string`] or
+/// [`Value::write_json_to`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum JsonError {
+    /// JSON has no way of writing `NaN`, `inf` or `-inf`.
+    NonFiniteFloat(f64),
+    /// JSON objects only have string keys, but a `Value::Map`
+    /// had this key, written as `Display` writes it.
+    NonStringKey(String),
+    /// Writing to the writer failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::NonFiniteFloat(float) => {
+                write!(f, "{float:?} can't be written as JSON")
+            }
+            JsonError::NonStringKey(key) => {
+                write!(f, "JSON object keys must be strings, not {key}")
+            }
+            JsonError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {
+    fn source(
+        &self,
+    ) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for JsonError {
+    fn from(err: io::Error) -> Self {
+        JsonError::Io(err)
+    }
+}
+
+impl Value<'_> {
+    /// Writes this value as JSON, on a single line.
+    ///
+    /// Values JSON has no syntax for follow the conventions of
+    /// `serde_json`, so the output reads the way serializing the
+    /// original Rust value with `serde_json` would:
+    ///
+    /// - Lists, tuples, sets and byte strings (as numbers) are
+    ///   arrays, and chars and identifiers are strings.
+    /// - `None` is `null` and `Some(value)` is just the value,
+    ///   so `Some(None)` is `null` too. `Ok(value)` and
+    ///   `Err(value)` are `{"Ok": value}` and `{"Err": value}`.
+    /// - Structs are objects of their fields, and tuple structs
+    ///   are arrays of their fields, or just the field when
+    ///   there is only one. Their names are left out.
+    /// - Ranges are objects with a `"start"` and an `"end"`,
+    ///   each left out when the range has no such bound, and
+    ///   durations are objects with `"secs"` and `"nanos"`.
+    ///
+    /// Maps become objects, which is an error unless every key
+    /// is a string or a char, as is a float that is `NaN` or
+    /// infinite.
+    pub fn to_json_string(&self) -> Result<String, JsonError> {
+        let mut out = Vec::new();
+        self.write_json_to(&mut out)?;
+
+        Ok(
+            String::from_utf8(out)
+                .expect("JSON output is UTF-8"),
+        )
+    }
+
+    /// Writes this value to `writer` as JSON, as
+    /// [`Value::to_json_string`] does.
+    ///
+    /// On an error, whatever came before the value that couldn't
+    /// be written has already been written.
+    pub fn write_json_to<W: io::Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<(), JsonError> {
+        write_json(writer, self)
+    }
+}
+
+fn write_json<W: io::Write>(
+    w: &mut W,
+    value: &Value<'_>,
+) -> Result<(), JsonError> {
+    match value {
+        Value::Integer(integer) => write!(w, "{integer}")?,
+        Value::UInt(integer) => write!(w, "{integer}")?,
+        Value::BigInt(integer) => write!(w, "{integer}")?,
+        Value::BigUInt(integer) => write!(w, "{integer}")?,
+        Value::Float(float) if !float.is_finite() => {
+            return Err(JsonError::NonFiniteFloat(*float))
+        }
+        // Rust's shortest form is valid JSON, as in `0.1`,
+        // `-0.0` or `1e300`
+        Value::Float(float) => write!(w, "{float:?}")?,
+        #[cfg(feature = "arbitrary-precision")]
+        Value::RawNumber(number) => {
+            w.write_all(json_number(number).as_bytes())?
+        }
+        Value::Boolean(boolean) => write!(w, "{boolean}")?,
+        Value::String(string) => write_json_string(w, string)?,
+        Value::Char(chr) => {
+            write_json_string(w, chr.encode_utf8(&mut [0; 4]))?
+        }
+        Value::Ident(ident) => write_json_string(w, ident)?,
+        Value::Bytes(bytes) => {
+            w.write_all(b"[")?;
+            for (idx, byte) in bytes.iter().enumerate() {
+                if idx > 0 {
+                    w.write_all(b",")?;
+                }
+                write!(w, "{byte}")?;
+            }
+            w.write_all(b"]")?;
+        }
+        Value::List(elements)
+        | Value::Tuple(elements)
+        | Value::Set(elements) => write_json_array(w, elements)?,
+        Value::TupleStruct { fields, .. } => match &fields[..] {
+            // A newtype is written as what it wraps
+            [field] => write_json(w, field)?,
+            fields => write_json_array(w, fields)?,
+        },
+        Value::Optional(None) => w.write_all(b"null")?,
+        Value::Optional(Some(value)) => write_json(w, value)?,
+        Value::Result(Ok(value)) => {
+            write_json_object(w, [("Ok", &**value)])?
+        }
+        Value::Result(Err(value)) => {
+            write_json_object(w, [("Err", &**value)])?
+        }
+        Value::Object(object) => write_json_object(
+            w,
+            object.iter().map(|(key, value)| (&**key, value)),
+        )?,
+        Value::Struct { fields, .. } => write_json_object(
+            w,
+            fields
+                .iter()
+                .map(|(field, value)| (&**field, value)),
+        )?,
+        Value::Map(entries) => {
+            w.write_all(b"{")?;
+            for (idx, (key, value)) in entries.iter().enumerate()
+            {
+                if idx > 0 {
+                    w.write_all(b",")?;
+                }
+                match key {
+                    Value::String(_) | Value::Char(_) => {
+                        write_json(w, key)?
+                    }
+                    key => {
+                        return Err(JsonError::NonStringKey(
+                            key.to_string(),
+                        ))
+                    }
+                }
+                w.write_all(b":")?;
+                write_json(w, value)?;
+            }
+            w.write_all(b"}")?;
+        }
+        Value::Range { start, end, .. } => write_json_object(
+            w,
+            [("start", start), ("end", end)]
+                .into_iter()
+                .filter_map(|(name, bound)| {
+                    Some((name, &**bound.as_ref()?))
+                }),
+        )?,
+        #[cfg(feature = "duration")]
+        Value::Duration(duration) => write!(
+            w,
+            r#"{{"secs":{},"nanos":{}}}"#,
+            duration.as_secs(),
+            duration.subsec_nanos()
+        )?,
+        #[cfg(feature = "datetime")]
+        Value::DateTime(datetime) => {
+            write!(w, "\"{datetime}\"")?
+        }
+        #[cfg(feature = "uuid")]
+        Value::Uuid(uuid) => write!(w, "\"{uuid}\"")?,
+        #[cfg(feature = "net")]
+        Value::IpAddr(addr) => write!(w, "\"{addr}\"")?,
+        #[cfg(feature = "net")]
+        Value::SocketAddr(addr) => write!(w, "\"{addr}\"")?,
+    }
+
+    Ok(())
+}
+
+fn write_json_array<W: io::Write>(
+    w: &mut W,
+    elements: &[Value<'_>],
+) -> Result<(), JsonError> {
+    w.write_all(b"[")?;
+    for (idx, element) in elements.iter().enumerate() {
+        if idx > 0 {
+            w.write_all(b",")?;
+        }
+        write_json(w, element)?;
+    }
+    w.write_all(b"]")?;
+
+    Ok(())
+}
+
+fn write_json_object<'v, 'a: 'v, W: io::Write>(
+    w: &mut W,
+    entries: impl IntoIterator<Item = (&'v str, &'v Value<'a>)>,
+) -> Result<(), JsonError> {
+    w.write_all(b"{")?;
+    for (idx, (key, value)) in entries.into_iter().enumerate() {
+        if idx > 0 {
+            w.write_all(b",")?;
+        }
+        write_json_string(w, key)?;
+        w.write_all(b":")?;
+        write_json(w, value)?;
+    }
+    w.write_all(b"}")?;
+
+    Ok(())
+}
+
+/// Writes `string` as a JSON string, escaping quotes,
+/// backslashes and the control characters JSON requires to be
+/// escaped. Everything else, including characters outside of the
+/// Basic Multilingual Plane, is written as UTF-8.
+fn write_json_string<W: io::Write>(
+    w: &mut W,
+    string: &str,
+) -> io::Result<()> {
+    w.write_all(b"\"")?;
+
+    let mut chunk_start = 0;
+    for (idx, chr) in string.char_indices() {
+        let escape = match chr {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\u{8}' => "\\b",
+            '\u{c}' => "\\f",
+            '\0'..='\u{1f}' => "",
+            _ => continue,
+        };
+
+        w.write_all(&string.as_bytes()[chunk_start..idx])?;
+        match escape {
+            "" => write!(w, "\\u{:04x}", u32::from(chr))?,
+            escape => w.write_all(escape.as_bytes())?,
+        }
+        chunk_start = idx + chr.len_utf8();
+    }
+
+    w.write_all(&string.as_bytes()[chunk_start..])?;
+    w.write_all(b"\"")
+}
+
+/// Rewrites the text of a number, such as `+1_000.`, in JSON's
+/// stricter syntax, as in `1000`, without changing its value.
+#[cfg(feature = "arbitrary-precision")]
+fn json_number(number: &str) -> String {
+    let number = number.replace('_', "");
+    let (sign, unsigned) = match number.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", number.trim_start_matches('+')),
+    };
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(idx) => unsigned.split_at(idx),
+        None => (unsigned, ""),
+    };
+    let (integer, fraction) =
+        mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let integer = integer.trim_start_matches('0');
+    let integer = if integer.is_empty() { "0" } else { integer };
+    let fraction = match fraction {
+        "" => String::new(),
+        fraction => format!(".{fraction}"),
+    };
+    let exponent = exponent.replacen('+', "", 1);
+
+    format!("{sign}{integer}{fraction}{exponent}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use crate::{
+        parse_json_value, parse_value, JsonError, Value,
+    };
+
+    fn json(text: &str) -> String {
+        let (rest, value) = parse_value(text).unwrap();
+        assert_eq!(rest, "", "{text}");
+
+        value.to_json_string().unwrap()
+    }
+
+    #[test]
+    fn writes_json() {
+        let cases = [
+            ("[1, -2.5, 1e300, -0.0]", "[1,-2.5,1e300,-0.0]"),
+            ("(true, 'x', Red)", r#"[true,"x","Red"]"#),
+            (r#"{"a": None}"#, r#"{"a":null}"#),
+            ("Some(Some(1))", "1"),
+            (
+                "[Ok(1), Err(\"no\")]",
+                r#"[{"Ok":1},{"Err":"no"}]"#,
+            ),
+            ("Point { x: 1, y: 2, .. }", r#"{"x":1,"y":2}"#),
+            ("Meters(5)", "5"),
+            ("Pair(1, 2)", "[1,2]"),
+            ("{'a': 1}", r#"{"a":1}"#),
+            ("{1, 2}", "[1,2]"),
+            (r#"b"hi""#, "[104,105]"),
+            ("1..=5", r#"{"start":1,"end":5}"#),
+            ("..5", r#"{"end":5}"#),
+            (
+                "340282366920938463463374607431768211455",
+                "340282366920938463463374607431768211455",
+            ),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(json(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn escapes_json_strings() {
+        let string =
+            "\"\\/\n\r\t\u{8}\u{c}\0\u{1f}\u{7f}é\u{2028}😀";
+        let text = Value::String(string.into())
+            .to_json_string()
+            .unwrap();
+        assert_eq!(
+            text,
+            "\"\\\"\\\\/\\n\\r\\t\\b\\f\\u0000\\u001f\u{7f}é\u{2028}😀\""
+        );
+        assert_eq!(
+            parse_json_value(&text),
+            Ok(("", Value::String(Cow::Owned(string.into()))))
+        );
+
+        assert_eq!(
+            Value::Char('😀').to_json_string().unwrap(),
+            "\"😀\""
+        );
+        assert_eq!(
+            parse_json_value(r#""😀""#),
+            Ok(("", Value::String("😀".into())))
+        );
+    }
+
+    #[test]
+    fn reads_back_json() {
+        let text = r#"{"list": [1, 2.5, "x\u0001"], "none": null, "nested": {"a": [true, false]}}"#;
+        let (_, value) = parse_json_value(text).unwrap();
+        let json = value.to_json_string().unwrap();
+        assert_eq!(parse_json_value(&json), Ok(("", value)));
+    }
+
+    #[test]
+    fn rejects_values_json_cant_hold() {
+        assert!(matches!(
+            Value::List(vec![Value::Float(f64::NAN)]).to_json_string(),
+            Err(JsonError::NonFiniteFloat(float)) if float.is_nan()
+        ));
+        assert!(matches!(
+            Value::Float(f64::NEG_INFINITY).to_json_string(),
+            Err(JsonError::NonFiniteFloat(f64::NEG_INFINITY))
+        ));
+        assert!(matches!(
+            Value::Map(vec![(Value::Integer(1), Value::Integer(2))])
+                .to_json_string(),
+            Err(JsonError::NonStringKey(key)) if key == "1"
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary-precision")]
+    fn writes_raw_numbers_as_json() {
+        let cases = [
+            ("1_000", "1000"),
+            ("+1.", "1"),
+            (".5e+3", "0.5e3"),
+            ("-007.25", "-7.25"),
+            ("1.5f32", "1.5"),
+            (
+                "123456789012345678901234567890.000000000000000000001",
+                "123456789012345678901234567890.000000000000000000001",
+            ),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(json(text), expected, "{text}");
+        }
+    }
+}
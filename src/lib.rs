@@ -1,246 +1,7061 @@
-use std::collections::HashMap;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::{
+    borrow::Cow, boxed::Box, format, string::String, vec,
+    vec::Vec,
+};
+use core::{
+    cell::{Cell, RefCell},
+    mem,
+};
 
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take, take_while},
-    character::complete::char,
-    combinator::value,
-    multi::separated_list0,
-    number::complete::double,
-    sequence::{
-        delimited, preceded, separated_pair, terminated,
+    bytes::complete::{take_while, take_while1},
+    character::complete::{digit1, one_of, satisfy},
+    combinator::{
+        cut, not, opt, peek, recognize, value, verify,
     },
+    error::ErrorKind as NomErrorKind,
+    multi::many0_count,
+    sequence::{pair, preceded, terminated, tuple},
     IResult, Parser,
 };
 
-#[derive(PartialEq, Debug, Clone)]
-pub enum Value<'a> {
-    Float(f64),
-    Boolean(bool),
-    String(&'a str),
-    Char(char),
-    List(Vec<Value<'a>>),
-    Optional(Option<Box<Value<'a>>>),
-    Object(HashMap<&'a str, Value<'a>>),
-}
+mod approx;
+mod canonical;
+mod convert;
+#[cfg(feature = "std")]
+mod csv;
+#[cfg(feature = "datetime")]
+mod datetime;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+mod diff;
+mod display;
+mod document;
+mod edit;
+mod eq;
+mod error;
+mod events;
+mod formatter;
+mod homogeneous;
+mod iter;
+#[cfg(feature = "std")]
+mod json;
+mod list_iter;
+mod macros;
+mod merge;
+#[cfg(feature = "net")]
+mod net;
+mod path;
+mod pretty;
+mod quick;
+#[cfg(feature = "std")]
+mod reader;
+mod recovery;
+mod search;
+#[cfg(feature = "std")]
+mod serializer;
+mod stream;
+mod streaming;
+mod transform;
+#[cfg(feature = "uuid")]
+mod uuid;
+mod walk;
+
+pub use approx::Tolerance;
+#[doc(hidden)]
+pub use convert::__private;
+pub use convert::{ConvertError, FromTson, ToTson};
+#[cfg(feature = "std")]
+pub use csv::CsvError;
+#[cfg(feature = "datetime")]
+pub use datetime::{DateTime, InvalidDateTime};
+pub use diff::{Change, DiffOptions, Difference};
+pub use display::{EscapeMode, FloatFormat};
+pub use document::{
+    parse_document, parse_document_with, Document,
+    DocumentOptions,
+};
+pub use edit::SetError;
+use error::Located;
+pub use error::{Error, ErrorKind, Expected};
+pub use events::{Event, EventReader};
+pub use formatter::{fmt, FmtOptions};
+pub use homogeneous::Homogeneous;
+#[cfg(feature = "std")]
+pub use json::JsonError;
+pub use list_iter::{parse_list_iter, ListIter};
+pub use merge::{ListMerge, MergeStrategy, ScalarMerge};
+pub use path::{Path, PathSegment};
+pub use pretty::PrettyConfig;
+pub use quick::{from_str_quick, validate_quick, QuickError};
+#[cfg(feature = "std")]
+pub use reader::{document_from_reader, from_reader};
+pub use recovery::parse_lenient;
+use recovery::Recovery;
+#[cfg(feature = "std")]
+pub use serializer::ListSerializer;
+pub use stream::ValueStream;
+pub use streaming::{
+    from_slice_streaming, from_str_streaming, Streamed,
+};
+#[cfg(feature = "uuid")]
+pub use uuid::{InvalidUuid, Uuid};
+pub use walk::Walk;
+
+/// The map of a `Value::Object`: a `HashMap` with the `std`
+/// feature, and a `BTreeMap`, which only needs `alloc`, without
+/// it.
+#[cfg(feature = "std")]
+pub type ObjectMap<'a> =
+    std::collections::HashMap<Cow<'a, str>, Value<'a>>;
+#[cfg(not(feature = "std"))]
+pub type ObjectMap<'a> =
+    alloc::collections::BTreeMap<Cow<'a, str>, Value<'a>>;
+
+#[derive(Debug, Clone)]
+pub enum Value<'a> {
+    Integer(i64),
+    UInt(u64),
+    BigInt(i128),
+    BigUInt(u128),
+    /// A float, which may also be `NaN`, `inf` or `-inf`.
+    ///
+    /// Floats compare like `f64` does, except that every `NaN`
+    /// is equal to every other, so that `Value` can be `Eq` and
+    /// `Hash`. Use [`Value::is_nan`] to check for `NaN`.
+    Float(f64),
+    /// A number kept exactly as it was written, such as
+    /// `0.1` or `1e400`, including any sign and `_` separators
+    /// but not its type suffix.
+    ///
+    /// With the `arbitrary-precision` feature, this replaces
+    /// `Value::Float` for every base 10 float, and is used for
+    /// integers too large for a `u128`. Raw numbers are
+    /// compared by their text, so `1.0` is not equal to
+    /// `1.00`. Use [`Value::as_f64`] and [`Value::as_i64`]
+    /// to convert them.
+    #[cfg(feature = "arbitrary-precision")]
+    RawNumber(Cow<'a, str>),
+    Boolean(bool),
+    /// A string, borrowed from the input unless it contains
+    /// escapes.
+    String(Cow<'a, str>),
+    Char(char),
+    /// A byte string literal such as `b"\x00\xffabc"`, borrowed
+    /// from the input unless it contains escapes.
+    Bytes(Cow<'a, [u8]>),
+    List(Vec<Value<'a>>),
+    /// A tuple such as `(1, 'a')`. The unit value `()` is the
+    /// empty tuple.
+    Tuple(Vec<Value<'a>>),
+    Optional(Option<Box<Value<'a>>>),
+    /// `Ok(..)` or `Err(..)`.
+    Result(Result<Box<Value<'a>>, Box<Value<'a>>>),
+    /// A map whose keys are all strings, such as `{"a": 1}`.
+    ///
+    /// A key written more than once keeps the last value it is
+    /// given, as inserting each entry in turn would, so
+    /// `{"a": 1, "a": 2}` is `{"a": 2}`. The keys are in the
+    /// order of the [`ObjectMap`], not the one they were
+    /// written in.
+    Object(ObjectMap<'a>),
+    /// A struct with named fields, such as `Foo { x: 1 }`,
+    /// keeping the fields in the order they were written. The
+    /// name is kept as written, including any path such as
+    /// `shape::Shape::Circle`.
+    ///
+    /// `non_exhaustive` is set when the fields end in `..`, as
+    /// printed by `finish_non_exhaustive`. It takes part in
+    /// equality like any other field, so `Foo { x: 1, .. }` is
+    /// not equal to `Foo { x: 1 }`.
+    Struct {
+        name: Cow<'a, str>,
+        fields: Vec<(Cow<'a, str>, Value<'a>)>,
+        non_exhaustive: bool,
+    },
+    /// A tuple struct, newtype wrapper or tuple enum variant,
+    /// such as `Point(1, 2)` or `Shape::Move(1, 2)`, whose name
+    /// is kept as written.
+    TupleStruct {
+        name: Cow<'a, str>,
+        fields: Vec<Value<'a>>,
+    },
+    /// A map whose keys aren't all strings, such as `{1: 'x'}`.
+    /// Maps with only string keys are parsed as an `Object`.
+    ///
+    /// The entries are kept in the order they were written in,
+    /// along with any whose key was written before.
+    Map(Vec<(Value<'a>, Value<'a>)>),
+    /// A set such as `{1, 2, 3}`, in the order it was written.
+    /// Use [`Value::set_eq`] to compare sets regardless of
+    /// order.
+    Set(Vec<Value<'a>>),
+    /// A range such as `1..5`, `0..=255`, `..10`, `3..` or the
+    /// full range `..`.
+    ///
+    /// An inclusive range always has an end, so `..=5` is valid
+    /// but `1..=` is an error.
+    Range {
+        start: Option<Box<Value<'a>>>,
+        end: Option<Box<Value<'a>>>,
+        inclusive: bool,
+    },
+    /// A duration such as `150ms` or `2.000000001s`, written
+    /// the way `Duration`'s `Debug` output writes it.
+    #[cfg(feature = "duration")]
+    Duration(core::time::Duration),
+    /// A string holding an RFC 3339 datetime, such as
+    /// `"2024-01-02T03:04:05Z"`, parsed with
+    /// [`ParserOptions::datetimes`]. It is written back as that
+    /// string.
+    #[cfg(feature = "datetime")]
+    DateTime(DateTime),
+    /// A hyphenated UUID such as
+    /// `67e55044-10b1-426f-9247-bb680e5fe0c8`, bare or in a
+    /// string, parsed with [`ParserOptions::uuids`]. It is
+    /// written back as a string, in lowercase.
+    #[cfg(feature = "uuid")]
+    Uuid(Uuid),
+    /// An IP address such as `10.0.0.1` or `::1`, parsed with
+    /// [`ParserOptions::addresses`].
+    #[cfg(feature = "net")]
+    IpAddr(core::net::IpAddr),
+    /// A socket address such as `127.0.0.1:8080` or
+    /// `[::1]:443`, parsed with [`ParserOptions::addresses`].
+    #[cfg(feature = "net")]
+    SocketAddr(core::net::SocketAddr),
+    /// A bare identifier, such as the unit enum variant `Red`,
+    /// or a `::`-separated path like `Ordering::Less`.
+    Ident(Cow<'a, str>),
+}
+
+/// Which variant of [`Value`] a value is, as returned by
+/// [`Value::kind`], without what it holds.
+///
+/// Kinds are ordered as they are listed, which is the order
+/// values of different kinds are in.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+#[non_exhaustive]
+pub enum ValueKind {
+    Integer,
+    UInt,
+    BigInt,
+    BigUInt,
+    Float,
+    #[cfg(feature = "arbitrary-precision")]
+    RawNumber,
+    Boolean,
+    String,
+    Char,
+    Bytes,
+    List,
+    Tuple,
+    Optional,
+    Result,
+    Object,
+    Struct,
+    TupleStruct,
+    Map,
+    Set,
+    Range,
+    #[cfg(feature = "duration")]
+    Duration,
+    #[cfg(feature = "datetime")]
+    DateTime,
+    #[cfg(feature = "uuid")]
+    Uuid,
+    #[cfg(feature = "net")]
+    IpAddr,
+    #[cfg(feature = "net")]
+    SocketAddr,
+    Ident,
+}
+
+impl<'a> Value<'a> {
+    /// Returns which variant this is.
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            Value::Integer(_) => ValueKind::Integer,
+            Value::UInt(_) => ValueKind::UInt,
+            Value::BigInt(_) => ValueKind::BigInt,
+            Value::BigUInt(_) => ValueKind::BigUInt,
+            Value::Float(_) => ValueKind::Float,
+            #[cfg(feature = "arbitrary-precision")]
+            Value::RawNumber(_) => ValueKind::RawNumber,
+            Value::Boolean(_) => ValueKind::Boolean,
+            Value::String(_) => ValueKind::String,
+            Value::Char(_) => ValueKind::Char,
+            Value::Bytes(_) => ValueKind::Bytes,
+            Value::List(_) => ValueKind::List,
+            Value::Tuple(_) => ValueKind::Tuple,
+            Value::Optional(_) => ValueKind::Optional,
+            Value::Result(_) => ValueKind::Result,
+            Value::Object(_) => ValueKind::Object,
+            Value::Struct { .. } => ValueKind::Struct,
+            Value::TupleStruct { .. } => ValueKind::TupleStruct,
+            Value::Map(_) => ValueKind::Map,
+            Value::Set(_) => ValueKind::Set,
+            Value::Range { .. } => ValueKind::Range,
+            #[cfg(feature = "duration")]
+            Value::Duration(_) => ValueKind::Duration,
+            #[cfg(feature = "datetime")]
+            Value::DateTime(_) => ValueKind::DateTime,
+            #[cfg(feature = "uuid")]
+            Value::Uuid(_) => ValueKind::Uuid,
+            #[cfg(feature = "net")]
+            Value::IpAddr(_) => ValueKind::IpAddr,
+            #[cfg(feature = "net")]
+            Value::SocketAddr(_) => ValueKind::SocketAddr,
+            Value::Ident(_) => ValueKind::Ident,
+        }
+    }
+
+    /// Describes what kind of value this is, as in `a string`,
+    /// for error messages. The integer variants are all `an
+    /// integer`, and raw numbers are `a float`.
+    pub fn type_name(&self) -> &'static str {
+        match self.kind() {
+            ValueKind::Integer
+            | ValueKind::UInt
+            | ValueKind::BigInt
+            | ValueKind::BigUInt => "an integer",
+            ValueKind::Float => "a float",
+            #[cfg(feature = "arbitrary-precision")]
+            ValueKind::RawNumber => "a float",
+            ValueKind::Boolean => "a boolean",
+            ValueKind::String => "a string",
+            ValueKind::Char => "a char",
+            ValueKind::Bytes => "a byte string",
+            ValueKind::List => "a list",
+            ValueKind::Tuple => "a tuple",
+            ValueKind::Optional => "an option",
+            ValueKind::Result => "a result",
+            ValueKind::Object => "an object",
+            ValueKind::Struct => "a struct",
+            ValueKind::TupleStruct => "a tuple struct",
+            ValueKind::Map => "a map",
+            ValueKind::Set => "a set",
+            ValueKind::Range => "a range",
+            #[cfg(feature = "duration")]
+            ValueKind::Duration => "a duration",
+            #[cfg(feature = "datetime")]
+            ValueKind::DateTime => "a datetime",
+            #[cfg(feature = "uuid")]
+            ValueKind::Uuid => "a UUID",
+            #[cfg(feature = "net")]
+            ValueKind::IpAddr => "an IP address",
+            #[cfg(feature = "net")]
+            ValueKind::SocketAddr => "a socket address",
+            ValueKind::Ident => "an identifier",
+        }
+    }
+
+    /// Returns `true` if this is a number of any kind.
+    pub fn is_number(&self) -> bool {
+        match self.kind() {
+            ValueKind::Integer
+            | ValueKind::UInt
+            | ValueKind::BigInt
+            | ValueKind::BigUInt
+            | ValueKind::Float => true,
+            #[cfg(feature = "arbitrary-precision")]
+            ValueKind::RawNumber => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if this is a `Value::String`.
+    pub fn is_string(&self) -> bool {
+        matches!(self, Value::String(_))
+    }
+
+    /// Returns `true` if this is a `Value::List`.
+    pub fn is_list(&self) -> bool {
+        matches!(self, Value::List(_))
+    }
+
+    /// Returns `true` if this is `None`.
+    pub fn is_none(&self) -> bool {
+        matches!(self, Value::Optional(None))
+    }
+
+    /// Returns `true` if this is `Some(..)`.
+    pub fn is_some(&self) -> bool {
+        matches!(self, Value::Optional(Some(_)))
+    }
+
+    /// Returns the boolean this is, if it is one.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(boolean) => Some(*boolean),
+            _ => None,
+        }
+    }
+
+    /// Returns the text of a `Value::String`.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    /// Returns the char this is, if it is one.
+    pub fn as_char(&self) -> Option<char> {
+        match self {
+            Value::Char(chr) => Some(*chr),
+            _ => None,
+        }
+    }
+
+    /// Returns the identifier this is, path and all, as in
+    /// `Ordering::Less`.
+    pub fn as_ident(&self) -> Option<&str> {
+        match self {
+            Value::Ident(ident) => Some(ident),
+            _ => None,
+        }
+    }
+
+    /// Returns this number as a `u64`, if it is a whole number
+    /// that fits in one, converting it like [`Value::as_i64`]
+    /// does.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Integer(integer) => {
+                u64::try_from(*integer).ok()
+            }
+            Value::UInt(integer) => Some(*integer),
+            Value::BigInt(integer) => {
+                u64::try_from(*integer).ok()
+            }
+            Value::BigUInt(integer) => {
+                u64::try_from(*integer).ok()
+            }
+            #[cfg(feature = "arbitrary-precision")]
+            Value::RawNumber(number) => {
+                number.replace('_', "").parse().ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of a `Value::List`.
+    pub fn as_list(&self) -> Option<&[Value<'a>]> {
+        match self {
+            Value::List(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of a `Value::Tuple`, which for `()`
+    /// are none.
+    pub fn as_tuple(&self) -> Option<&[Value<'a>]> {
+        match self {
+            Value::Tuple(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of a `Value::Set`, in the order
+    /// they were written.
+    pub fn as_set(&self) -> Option<&[Value<'a>]> {
+        match self {
+            Value::Set(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// Returns the entries of a `Value::Map`.
+    pub fn as_map(&self) -> Option<&[(Value<'a>, Value<'a>)]> {
+        match self {
+            Value::Map(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Returns the entries of a `Value::Object`.
+    pub fn as_object(&self) -> Option<&ObjectMap<'a>> {
+        match self {
+            Value::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// Returns `Some` with the value inside of an option, or
+    /// with `None` for `None` itself, if this is an option.
+    pub fn as_option(&self) -> Option<Option<&Value<'a>>> {
+        match self {
+            Value::Optional(value) => Some(value.as_deref()),
+            _ => None,
+        }
+    }
+
+    /// Returns the element at `idx` of a list, tuple, set or
+    /// tuple struct, or `None` if there is no such element or
+    /// this is any other kind of value.
+    pub fn get(&self, idx: usize) -> Option<&Value<'a>> {
+        match self {
+            Value::List(elements)
+            | Value::Tuple(elements)
+            | Value::Set(elements)
+            | Value::TupleStruct {
+                fields: elements, ..
+            } => elements.get(idx),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the field named `key` of a struct,
+    /// the entry of an object, or the entry of a map whose key
+    /// is the string `key`, or `None` if there is none or this
+    /// is any other kind of value. A struct or map with `key`
+    /// more than once gives the first.
+    pub fn get_key(&self, key: &str) -> Option<&Value<'a>> {
+        match self {
+            Value::Object(object) => object.get(key),
+            Value::Struct { fields, .. } => fields
+                .iter()
+                .find(|(field, _)| field == key)
+                .map(|(_, value)| value),
+            Value::Map(entries) => entries
+                .iter()
+                .find(|(entry, _)| entry.as_str() == Some(key))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Returns the element at `idx` like [`Value::get`] does,
+    /// but so that it can be changed.
+    pub fn get_mut(
+        &mut self,
+        idx: usize,
+    ) -> Option<&mut Value<'a>> {
+        match self {
+            Value::List(elements)
+            | Value::Tuple(elements)
+            | Value::Set(elements)
+            | Value::TupleStruct {
+                fields: elements, ..
+            } => elements.get_mut(idx),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of `key` like [`Value::get_key`] does,
+    /// but so that it can be changed.
+    pub fn get_key_mut(
+        &mut self,
+        key: &str,
+    ) -> Option<&mut Value<'a>> {
+        match self {
+            Value::Object(object) => object.get_mut(key),
+            Value::Struct { fields, .. } => fields
+                .iter_mut()
+                .find(|(field, _)| field == key)
+                .map(|(_, value)| value),
+            Value::Map(entries) => entries
+                .iter_mut()
+                .find(|(entry, _)| entry.as_str() == Some(key))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this is a `Value::Float` holding `NaN`.
+    pub fn is_nan(&self) -> bool {
+        matches!(self, Value::Float(float) if float.is_nan())
+    }
+
+    /// Returns this number as an `f64`, which may round it.
+    ///
+    /// Returns `None` for anything that isn't a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(integer) => Some(*integer as f64),
+            Value::UInt(integer) => Some(*integer as f64),
+            Value::BigInt(integer) => Some(*integer as f64),
+            Value::BigUInt(integer) => Some(*integer as f64),
+            Value::Float(float) => Some(*float),
+            #[cfg(feature = "arbitrary-precision")]
+            Value::RawNumber(number) => {
+                number.replace('_', "").parse().ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns this number as an `i64`, if it is a whole number
+    /// that fits in one.
+    ///
+    /// A `Value::Float` is never converted, since it may have
+    /// been rounded when parsed.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(integer) => Some(*integer),
+            Value::UInt(integer) => i64::try_from(*integer).ok(),
+            Value::BigInt(integer) => {
+                i64::try_from(*integer).ok()
+            }
+            Value::BigUInt(integer) => {
+                i64::try_from(*integer).ok()
+            }
+            #[cfg(feature = "arbitrary-precision")]
+            Value::RawNumber(number) => {
+                number.replace('_', "").parse().ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this is an `Ok(..)` result.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Value::Result(Ok(_)))
+    }
+
+    /// Returns `true` if this is an `Err(..)` result.
+    pub fn is_err(&self) -> bool {
+        matches!(self, Value::Result(Err(_)))
+    }
+
+    /// Returns the value inside of `Ok(..)`, if this is one.
+    pub fn as_ok(&self) -> Option<&Self> {
+        match self {
+            Value::Result(Ok(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value inside of `Err(..)`, if this is one.
+    pub fn as_err(&self) -> Option<&Self> {
+        match self {
+            Value::Result(Err(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes of a `Value::Bytes`, or of a
+    /// `Value::List` made up only of integers from 0 to 255,
+    /// such as the `Debug` output of a `Vec<u8>`.
+    pub fn as_bytes(&self) -> Option<Cow<'_, [u8]>> {
+        match self {
+            Value::Bytes(bytes) => Some(Cow::Borrowed(bytes)),
+            Value::List(elements) => elements
+                .iter()
+                .map(|element| match element {
+                    Value::Integer(integer) => {
+                        u8::try_from(*integer).ok()
+                    }
+                    _ => None,
+                })
+                .collect::<Option<Vec<u8>>>()
+                .map(Cow::Owned),
+            _ => None,
+        }
+    }
+
+    /// Compares two values like `==`, except that two
+    /// `Value::Set`s are equal if they hold the same elements
+    /// (counting repeats) in any order.
+    ///
+    /// Only the outermost set is compared this way, its elements
+    /// are still compared with `==`.
+    pub fn set_eq(&self, other: &Value<'_>) -> bool {
+        let (Value::Set(left), Value::Set(right)) =
+            (self, other)
+        else {
+            return self == other;
+        };
+
+        if left.len() != right.len() {
+            return false;
+        }
+
+        let mut matched = vec![false; right.len()];
+        left.iter().all(|element| {
+            let found = right.iter().zip(&matched).position(
+                |(candidate, &taken)| {
+                    !taken && candidate == element
+                },
+            );
+
+            found.map(|idx| matched[idx] = true).is_some()
+        })
+    }
+
+    /// Returns this value with everything it borrows from the
+    /// input copied, so that it can outlive the input, such as
+    /// to be returned from where the input was read or kept in
+    /// a cache.
+    ///
+    /// Values that are already owned are moved rather than
+    /// copied, so this only allocates for the strings, names and
+    /// byte strings still borrowed from the input.
+    pub fn into_owned(mut self) -> OwnedValue {
+        fn owned(text: &mut Cow<'_, str>) -> Cow<'static, str> {
+            Cow::Owned(mem::take(text).into_owned())
+        }
+        let boxed = |value: &mut Box<Value<'_>>| {
+            Box::new(take(value).into_owned())
+        };
+        fn all(values: &mut Vec<Value<'_>>) -> Vec<OwnedValue> {
+            mem::take(values)
+                .into_iter()
+                .map(Value::into_owned)
+                .collect()
+        }
+
+        match self {
+            Value::Integer(integer) => Value::Integer(integer),
+            Value::UInt(integer) => Value::UInt(integer),
+            Value::BigInt(integer) => Value::BigInt(integer),
+            Value::BigUInt(integer) => Value::BigUInt(integer),
+            Value::Float(float) => Value::Float(float),
+            #[cfg(feature = "arbitrary-precision")]
+            Value::RawNumber(ref mut number) => {
+                Value::RawNumber(owned(number))
+            }
+            Value::Boolean(boolean) => Value::Boolean(boolean),
+            Value::String(ref mut string) => {
+                Value::String(owned(string))
+            }
+            Value::Char(chr) => Value::Char(chr),
+            Value::Bytes(ref mut bytes) => Value::Bytes(
+                Cow::Owned(mem::take(bytes).into_owned()),
+            ),
+            Value::List(ref mut elements) => {
+                Value::List(all(elements))
+            }
+            Value::Tuple(ref mut elements) => {
+                Value::Tuple(all(elements))
+            }
+            Value::Optional(ref mut value) => {
+                Value::Optional(value.as_mut().map(boxed))
+            }
+            Value::Result(ref mut result) => Value::Result(
+                result.as_mut().map(boxed).map_err(boxed),
+            ),
+            Value::Object(ref mut object) => Value::Object(
+                mem::take(object)
+                    .into_iter()
+                    .map(|(mut key, value)| {
+                        (owned(&mut key), value.into_owned())
+                    })
+                    .collect(),
+            ),
+            Value::Struct {
+                ref mut name,
+                ref mut fields,
+                non_exhaustive,
+            } => Value::Struct {
+                name: owned(name),
+                fields: mem::take(fields)
+                    .into_iter()
+                    .map(|(mut field, value)| {
+                        (owned(&mut field), value.into_owned())
+                    })
+                    .collect(),
+                non_exhaustive,
+            },
+            Value::TupleStruct {
+                ref mut name,
+                ref mut fields,
+            } => Value::TupleStruct {
+                name: owned(name),
+                fields: all(fields),
+            },
+            Value::Map(ref mut entries) => Value::Map(
+                mem::take(entries)
+                    .into_iter()
+                    .map(|(key, value)| {
+                        (key.into_owned(), value.into_owned())
+                    })
+                    .collect(),
+            ),
+            Value::Set(ref mut elements) => {
+                Value::Set(all(elements))
+            }
+            Value::Range {
+                ref mut start,
+                ref mut end,
+                inclusive,
+            } => Value::Range {
+                start: start.as_mut().map(boxed),
+                end: end.as_mut().map(boxed),
+                inclusive,
+            },
+            #[cfg(feature = "duration")]
+            Value::Duration(duration) => {
+                Value::Duration(duration)
+            }
+            #[cfg(feature = "datetime")]
+            Value::DateTime(datetime) => {
+                Value::DateTime(datetime)
+            }
+            #[cfg(feature = "uuid")]
+            Value::Uuid(uuid) => Value::Uuid(uuid),
+            #[cfg(feature = "net")]
+            Value::IpAddr(addr) => Value::IpAddr(addr),
+            #[cfg(feature = "net")]
+            Value::SocketAddr(addr) => Value::SocketAddr(addr),
+            Value::Ident(ref mut ident) => {
+                Value::Ident(owned(ident))
+            }
+        }
+    }
+}
+
+/// Values are dropped without recursing into the values they
+/// hold, which are moved onto a stack on the heap instead, so
+/// that a value nested however deep can be dropped.
+impl Drop for Value<'_> {
+    fn drop(&mut self) {
+        let mut values = Vec::new();
+        move_children(self, &mut values);
+        while let Some(mut value) = values.pop() {
+            move_children(&mut value, &mut values);
+        }
+    }
+}
+
+/// Moves the values held in `value` onto `values`, leaving it
+/// holding none.
+fn move_children<'a>(
+    value: &mut Value<'a>,
+    values: &mut Vec<Value<'a>>,
+) {
+    match value {
+        Value::List(elements)
+        | Value::Tuple(elements)
+        | Value::Set(elements)
+        | Value::TupleStruct {
+            fields: elements, ..
+        } => values.append(elements),
+        Value::Object(object) => {
+            values.extend(mem::take(object).into_values())
+        }
+        Value::Struct { fields, .. } => values.extend(
+            mem::take(fields)
+                .into_iter()
+                .map(|(_, value)| value),
+        ),
+        Value::Map(entries) => values.extend(
+            mem::take(entries)
+                .into_iter()
+                .flat_map(|(key, value)| [key, value]),
+        ),
+        Value::Optional(inner) => {
+            values.extend(inner.take().map(|inner| *inner))
+        }
+        Value::Result(Ok(inner) | Err(inner)) => {
+            values.push(take(inner))
+        }
+        Value::Range { start, end, .. } => values.extend(
+            [start.take(), end.take()]
+                .into_iter()
+                .flatten()
+                .map(|bound| *bound),
+        ),
+        _ => {}
+    }
+}
+
+/// Moves `value` out of where it is, leaving a value that holds
+/// nothing, since a `Value` can't be moved out of by matching
+/// on it now that it has a `Drop` impl.
+fn take<'a>(value: &mut Value<'a>) -> Value<'a> {
+    mem::replace(value, Value::Optional(None))
+}
+
+/// What indexing a [`Value`] gives when there is nothing there.
+static MISSING: Value<'static> = Value::Optional(None);
+
+/// `value[idx]` returns what [`Value::get`] does, or `None`
+/// (`Value::Optional(None)`) when that is `None`, rather than
+/// panicking, even if `value` isn't a list or anything else with
+/// elements. A chain such as `value[0][1]` can then be written
+/// without checking each step, but a missing element can't be
+/// told apart from a `None` that is there, which
+/// [`Value::get`] can.
+impl<'a> core::ops::Index<usize> for Value<'a> {
+    type Output = Value<'a>;
+
+    fn index(&self, idx: usize) -> &Value<'a> {
+        self.get(idx).unwrap_or(&MISSING)
+    }
+}
+
+/// `value["key"]` returns what [`Value::get_key`] does, or
+/// `None` (`Value::Optional(None)`) when that is `None`, as
+/// `value[idx]` does, so that
+/// `value["config"]["retries"].as_f64()` is `None` whether
+/// `config`, `retries` or a number is what is missing.
+impl<'a> core::ops::Index<&str> for Value<'a> {
+    type Output = Value<'a>;
+
+    fn index(&self, key: &str) -> &Value<'a> {
+        self.get_key(key).unwrap_or(&MISSING)
+    }
+}
+
+/// A [`Value`] that borrows nothing, as returned by
+/// [`Value::into_owned`].
+pub type OwnedValue = Value<'static>;
+
+/// Parses a whole document like [`from_str`] does, so that
+/// `text.parse::<OwnedValue>()` works where a `FromStr` type is
+/// wanted, such as for command line arguments. The value is
+/// owned, since `FromStr` can't borrow from the text.
+impl core::str::FromStr for OwnedValue {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Error> {
+        from_str(input).map(Value::into_owned)
+    }
+}
+
+/// The named fields of a struct, in the order they were written.
+type Fields<'a> = Vec<(Cow<'a, str>, Value<'a>)>;
+
+/// Options for [`parse_value_with`], which all default to
+/// parsing plain tson.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParserOptions {
+    /// Also accept JSON, i.e. `null` as `Optional(None)` and
+    /// the `\/`, `\b`, `\f` and `\uNNNN` string escapes.
+    pub json_compat: bool,
+    /// Only accept numbers the way Rust writes them, so a
+    /// leading `+` (as in `+1`) and a fraction without an
+    /// integer part (as in `.5`) are errors.
+    pub strict_numbers: bool,
+    /// Parse strings holding an RFC 3339 datetime, such as
+    /// `"2024-01-02T03:04:05Z"`, as `Value::DateTime`. Other
+    /// strings, including ones that look like datetimes but
+    /// aren't valid, are still parsed as `Value::String`.
+    #[cfg(feature = "datetime")]
+    pub datetimes: bool,
+    /// Parse hyphenated UUIDs, such as
+    /// `67e55044-10b1-426f-9247-bb680e5fe0c8`, as
+    /// `Value::Uuid`, whether they are bare, as
+    /// `uuid::Uuid`'s `Debug` output writes them, or in a
+    /// string. Their hex digits may be of either case. The
+    /// braced and URN forms aren't recognized,
+    /// so `"{67e55044-..}"` is still a string, but
+    /// [`Value::as_uuid`] reads them.
+    #[cfg(feature = "uuid")]
+    pub uuids: bool,
+    /// Parse bare IP and socket addresses, as `IpAddr` and
+    /// `SocketAddr`'s `Debug` output writes them (`10.0.0.1`,
+    /// `::1`, `127.0.0.1:8080`, `[::1]:443`), as
+    /// `Value::IpAddr` and `Value::SocketAddr`. Numbers, ranges
+    /// such as `1..5` and lists such as `[::1]` are still read
+    /// as before, but an identifier path made only of hex
+    /// digits, such as `cafe::beef`, is read as an IPv6
+    /// address.
+    #[cfg(feature = "net")]
+    pub addresses: bool,
+    /// How many values may be nested in each other, counting
+    /// the outermost one, so that `[[1]]` is 3 deep. A value
+    /// nested any deeper is a [`ErrorKind::DepthLimitExceeded`]
+    /// failure. Neither the parser nor dropping a value
+    /// recurses, so `usize::MAX` is safe to parse with, but
+    /// cloning or comparing a value does recurse into the
+    /// values it holds. Defaults to 128.
+    pub max_depth: usize,
+    /// How many values may be parsed in all, counting every
+    /// value nested in others, as well as keys of maps. A value
+    /// past the limit is a [`ErrorKind::NodeLimitExceeded`]
+    /// failure. Unlimited by default.
+    pub max_nodes: Option<usize>,
+    /// How many bytes long a string or byte string may be, once
+    /// its escapes are undone. A longer one is a
+    /// [`ErrorKind::StringLimitExceeded`] failure pointing at
+    /// its opening quote, found before the string is copied
+    /// anywhere. Unlimited by default.
+    pub max_string_len: Option<usize>,
+    /// How many elements a list, set, tuple, map, object or
+    /// struct may have. An element past the limit is a
+    /// [`ErrorKind::ElementLimitExceeded`] failure, found
+    /// before the elements after it are parsed. Unlimited
+    /// by default.
+    pub max_elements: Option<usize>,
+}
+
+impl ParserOptions {
+    const DEFAULT: Self = ParserOptions {
+        json_compat: false,
+        strict_numbers: false,
+        #[cfg(feature = "datetime")]
+        datetimes: false,
+        #[cfg(feature = "uuid")]
+        uuids: false,
+        #[cfg(feature = "net")]
+        addresses: false,
+        max_depth: 128,
+        max_nodes: None,
+        max_string_len: None,
+        max_elements: None,
+    };
+
+    pub fn json_compat(mut self, json_compat: bool) -> Self {
+        self.json_compat = json_compat;
+        self
+    }
+
+    pub fn strict_numbers(
+        mut self,
+        strict_numbers: bool,
+    ) -> Self {
+        self.strict_numbers = strict_numbers;
+        self
+    }
+
+    #[cfg(feature = "datetime")]
+    pub fn datetimes(mut self, datetimes: bool) -> Self {
+        self.datetimes = datetimes;
+        self
+    }
+
+    #[cfg(feature = "uuid")]
+    pub fn uuids(mut self, uuids: bool) -> Self {
+        self.uuids = uuids;
+        self
+    }
+
+    #[cfg(feature = "net")]
+    pub fn addresses(mut self, addresses: bool) -> Self {
+        self.addresses = addresses;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    pub fn max_string_len(
+        mut self,
+        max_string_len: usize,
+    ) -> Self {
+        self.max_string_len = Some(max_string_len);
+        self
+    }
+
+    pub fn max_elements(mut self, max_elements: usize) -> Self {
+        self.max_elements = Some(max_elements);
+        self
+    }
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// How many bytes into the input the furthest failure of a parse
+/// was, and what the parsers that failed there expected.
+pub(crate) type Failure = (usize, Vec<Expected>);
+
+/// The furthest point a parse failed at, and what the parsers
+/// that failed there expected.
+#[derive(Clone, Debug)]
+struct Furthest {
+    /// How much input was left there.
+    remaining: usize,
+    expected: Vec<Expected>,
+    /// Whether to keep track of `expected` at all, which the
+    /// quick parses don't.
+    detailed: bool,
+}
+
+impl Furthest {
+    const NONE: Self = Furthest {
+        remaining: usize::MAX,
+        expected: Vec::new(),
+        detailed: true,
+    };
+
+    /// Notes that a parser expecting `expected` failed with
+    /// `remaining` bytes of input left.
+    fn note(&mut self, remaining: usize, expected: Expected) {
+        if remaining < self.remaining {
+            self.remaining = remaining;
+            self.expected.clear();
+        }
+        if self.detailed
+            && remaining == self.remaining
+            && !self.expected.contains(&expected)
+        {
+            self.expected.push(expected);
+        }
+    }
+}
+
+/// What a parse has counted towards the limits in
+/// [`ParserOptions`].
+#[derive(Clone, Copy, Debug)]
+struct Counts {
+    /// How many values the parsers are inside of.
+    depth: usize,
+    /// How many values have been parsed, not counting ones that
+    /// were backtracked from.
+    nodes: usize,
+}
+
+/// What a parse keeps track of as it goes: the options it was
+/// started with, what it has counted towards their limits and
+/// where it failed furthest.
+///
+/// Every parse makes its own and passes it to each parser that
+/// needs it, so that parses running at the same time, on other
+/// threads or nested in one another, never see each other's.
+struct State {
+    options: ParserOptions,
+    counts: Cell<Counts>,
+    furthest: RefCell<Furthest>,
+}
+
+impl State {
+    /// Makes the state of a parse with `options`, which notes
+    /// what was expected where it failed if `detailed` is set.
+    fn new(options: ParserOptions, detailed: bool) -> Self {
+        State {
+            options,
+            counts: Cell::new(Counts { depth: 0, nodes: 0 }),
+            furthest: RefCell::new(Furthest {
+                detailed,
+                ..Furthest::NONE
+            }),
+        }
+    }
+
+    fn with_counts<R>(
+        &self,
+        f: impl FnOnce(&mut Counts) -> R,
+    ) -> R {
+        let mut counts = self.counts.get();
+        let result = f(&mut counts);
+        self.counts.set(counts);
+        result
+    }
+
+    /// Notes that a parser expecting `expected` failed on
+    /// `input`, so that an error can point at the furthest point
+    /// the parse got to and say what could have been there, even
+    /// when the failure was backtracked from.
+    fn note_failure(&self, input: &str, expected: Expected) {
+        self.furthest.borrow_mut().note(input.len(), expected);
+    }
+
+    /// Returns how much input was left at the furthest failure
+    /// so far and how many parsers failed there, for
+    /// [`State::relabel`].
+    fn furthest_so_far(&self) -> (usize, usize) {
+        let furthest = self.furthest.borrow();
+        (furthest.remaining, furthest.expected.len())
+    }
+
+    /// Notes that `label` was expected at `input`, in place of
+    /// what the parsers that failed there since
+    /// [`State::furthest_so_far`] returned `before` expected.
+    fn relabel(
+        &self,
+        input: &str,
+        before: (usize, usize),
+        label: &'static str,
+    ) {
+        let mut furthest = self.furthest.borrow_mut();
+        if furthest.remaining == input.len() {
+            let kept = match before {
+                (remaining, len) if remaining == input.len() => {
+                    len
+                }
+                _ => 0,
+            };
+            furthest.expected.truncate(kept);
+        }
+        furthest.note(input.len(), Expected::Label(label));
+    }
+
+    /// Takes the furthest failure so far out of the state, as
+    /// how many bytes into `input` it was and what was expected
+    /// there, leaving it as if nothing had failed yet.
+    fn take_failure(&self, input: &str) -> Option<Failure> {
+        let mut furthest = self.furthest.borrow_mut();
+        let detailed = furthest.detailed;
+        let Furthest {
+            remaining,
+            expected,
+            ..
+        } = mem::replace(
+            &mut *furthest,
+            Furthest {
+                detailed,
+                ..Furthest::NONE
+            },
+        );
+
+        input
+            .len()
+            .checked_sub(remaining)
+            .map(|offset| (offset, expected))
+    }
+}
+
+// The codes of the failures for going over the limits in
+// `ParserOptions`. nom has no codes of its own for these, so
+// they borrow ones of combinators that none of the parsers use.
+pub(crate) const DEPTH_LIMIT_EXCEEDED: NomErrorKind =
+    NomErrorKind::Fix;
+pub(crate) const NODE_LIMIT_EXCEEDED: NomErrorKind =
+    NomErrorKind::Count;
+pub(crate) const STRING_LIMIT_EXCEEDED: NomErrorKind =
+    NomErrorKind::TakeWhileMN;
+pub(crate) const ELEMENT_LIMIT_EXCEEDED: NomErrorKind =
+    NomErrorKind::ManyMN;
+
+/// Returns a failure with `code` at `input`.
+fn limit_exceeded(
+    input: &str,
+    code: NomErrorKind,
+) -> nom::Err<nom::error::Error<&str>> {
+    nom::Err::Failure(nom::error::Error::new(input, code))
+}
+
+/// Fails on the string starting at `input` if `len`, how many
+/// bytes of it have been read, is over `max_len`.
+fn check_string_len(
+    input: &str,
+    len: usize,
+    max_len: Option<usize>,
+) -> Result<(), nom::Err<nom::error::Error<&str>>> {
+    match max_len {
+        Some(max_len) if len > max_len => {
+            Err(limit_exceeded(input, STRING_LIMIT_EXCEEDED))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Parses a value like [`parse_value`] does, but with `options`,
+/// also returning how many bytes into `input` the furthest
+/// failure of any parser was, which is where an error is best
+/// reported, along with what the parsers that failed there
+/// expected if `detailed` is set.
+fn parse_tracked<'a>(
+    input: &'a str,
+    options: ParserOptions,
+    mode: Mode<'_>,
+    detailed: bool,
+) -> (IResult<&'a str, Value<'a>>, Option<Failure>) {
+    let state = State::new(options, detailed);
+    let result = parse_nested(&state, input, mode);
+
+    (result, state.take_failure(input))
+}
+
+/// Returns `parser`, but when it fails without getting past the
+/// start of its input, whatever its own parsers expected there
+/// is noted as `label` instead, as in "expected a value".
+fn labelled<'a, 's, O>(
+    state: &'s State,
+    label: &'static str,
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, O> + 's,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O> + 's {
+    move |input| {
+        let before = state.furthest_so_far();
+        let result = parser(input);
+        if result.is_err() {
+            state.relabel(input, before, label);
+        }
+
+        result
+    }
+}
+
+/// nom's `char`, noting in `state` where it fails.
+fn char<'a, 's>(
+    state: &'s State,
+    expected: char,
+) -> impl Fn(&'a str) -> IResult<&'a str, char> + 's {
+    move |input| {
+        nom::character::complete::char(expected)(input)
+            .inspect_err(|_| {
+                state.note_failure(
+                    input,
+                    Expected::Char(expected),
+                )
+            })
+    }
+}
+
+/// nom's `tag`, noting in `state` where it fails.
+///
+/// The whitespace, comment and range operator parsers use nom's
+/// `tag` instead, since these may follow any value and would
+/// otherwise be expected after every one.
+fn tag<'a, 's>(
+    state: &'s State,
+    expected: &'static str,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> + 's {
+    move |input| {
+        nom::bytes::complete::tag(expected)(input).inspect_err(
+            |_| {
+                state
+                    .note_failure(input, Expected::Tag(expected))
+            },
+        )
+    }
+}
+
+/// Parses a value like [`parse_value`] does, but with `options`
+/// applied to it and to every value nested in it.
+pub fn parse_value_with(
+    input: &str,
+    options: ParserOptions,
+) -> IResult<&str, Value<'_>> {
+    parse_nested(&State::new(options, false), input, Mode::Build)
+}
+
+/// Parses a JSON document, or any tson value, as
+/// [`ParserOptions::json_compat`] describes.
+///
+/// A JSON object always has string keys, so it is always parsed
+/// as a `Value::Object`, never as a `Value::Map` or
+/// `Value::Set`.
+pub fn parse_json_value(
+    input: &str,
+) -> IResult<&str, Value<'_>> {
+    parse_value_with(
+        input,
+        ParserOptions::default().json_compat(true),
+    )
+}
+
+/// The UTF-8 byte order mark, which is skipped at the start of a
+/// byte slice.
+const BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Parses a document from bytes, such as the contents of a file,
+/// which must hold a single value and nothing but whitespace and
+/// comments after it.
+///
+/// The bytes may start with a UTF-8 byte order mark, and strings
+/// are borrowed from `input` just like [`parse_value`] borrows
+/// them from a `&str`. The offset of an [`Error`] counts the
+/// byte order mark, but its column doesn't.
+pub fn from_slice(input: &[u8]) -> Result<Value<'_>, Error> {
+    from_slice_with(input, ParserOptions::default())
+}
+
+/// Parses a document like [`from_slice`] does, but with
+/// `options` applied as [`parse_value_with`] applies them.
+pub fn from_slice_with(
+    input: &[u8],
+    options: ParserOptions,
+) -> Result<Value<'_>, Error> {
+    let (bom_len, input) = decode_slice(input)?;
+    from_str_with(input, options)
+        .map_err(|err| err.shifted(bom_len))
+}
+
+/// Parses a document, which must hold a single value and nothing
+/// but whitespace and comments around it, so that
+/// `"true garbage"` is an error rather than `true`.
+/// [`from_str_partial`] parses just the first value instead.
+///
+/// When something else follows the value, the error is a
+/// [`ErrorKind::TrailingInput`] pointing at it, unless a parser
+/// got further into the input before failing, as in
+/// `Foo { x: 1 y }`, where the struct fails at the `y` and
+/// leaves only `Foo` to be parsed.
+pub fn from_str(input: &str) -> Result<Value<'_>, Error> {
+    from_str_with(input, ParserOptions::default())
+}
+
+/// Parses a document like [`from_str`] does, but with `options`.
+fn from_str_with(
+    input: &str,
+    options: ParserOptions,
+) -> Result<Value<'_>, Error> {
+    let (rest, value, furthest) =
+        match parse_tracked(input, options, Mode::Build, true) {
+            (Ok((rest, value)), furthest) => {
+                (rest, value, furthest)
+            }
+            (Err(err), furthest) => {
+                return Err(Error::from_nom(
+                    input, err, furthest,
+                ))
+            }
+        };
+
+    check_trailing(input, rest, furthest)?;
+    Ok(value)
+}
+
+/// Checks that only whitespace and comments follow a value of
+/// `input` that ended before `rest`, as [`from_str`] requires,
+/// given the furthest failure of the parse.
+fn check_trailing(
+    input: &str,
+    rest: &str,
+    furthest: Option<Failure>,
+) -> Result<(), Error> {
+    trailing(input, rest, furthest)
+        .map_err(|err| err.into_error(input))
+}
+
+/// Does what [`check_trailing`] does, but leaves working out the
+/// line and column of an error to the caller.
+fn trailing(
+    input: &str,
+    rest: &str,
+    furthest: Option<Failure>,
+) -> Result<(), Located> {
+    let (rest, _) = parse_ws(rest)
+        .map_err(|err| Located::from_nom(input, err, None))?;
+    if rest.is_empty() {
+        return Ok(());
+    }
+
+    let offset = input.len() - rest.len();
+    Err(match furthest {
+        Some((furthest, expected)) if furthest > offset => {
+            Located::unexpected(input, furthest)
+                .expecting(expected)
+        }
+        Some((furthest, expected)) if furthest == offset => {
+            Located::new(ErrorKind::TrailingInput, offset)
+                .expecting(expected)
+        }
+        _ => Located::new(ErrorKind::TrailingInput, offset),
+    })
+}
+
+/// Parses the first value out of `input` like [`from_slice`]
+/// does, but also returns the bytes after it, so that
+/// concatenated documents can be parsed one at a time.
+pub fn from_slice_partial(
+    input: &[u8],
+) -> Result<(Value<'_>, &[u8]), Error> {
+    let (bom_len, input) = decode_slice(input)?;
+    let (value, rest) = from_str_partial(input)
+        .map_err(|err| err.shifted(bom_len))?;
+
+    Ok((value, rest.as_bytes()))
+}
+
+/// Skips any byte order mark and checks that `input` is UTF-8,
+/// returning the length of the byte order mark and the text
+/// after it.
+fn decode_slice(input: &[u8]) -> Result<(usize, &str), Error> {
+    let (bom_len, bytes) = match input.strip_prefix(BOM) {
+        Some(bytes) => (BOM.len(), bytes),
+        None => (0, input),
+    };
+
+    match core::str::from_utf8(bytes) {
+        Ok(input) => Ok((bom_len, input)),
+        Err(err) => {
+            let valid = &bytes[..err.valid_up_to()];
+            let valid =
+                core::str::from_utf8(valid).unwrap_or("");
+            let err = Error::new(
+                ErrorKind::InvalidUtf8,
+                valid,
+                valid.len(),
+            );
+
+            Err(err.shifted(bom_len))
+        }
+    }
+}
+
+/// Parses the first value out of `input` like [`parse_value`]
+/// does, skipping any whitespace and comments before it, and
+/// also returns the rest of the input after it.
+///
+/// Unlike [`parse_value`], which is built out of `nom` parsers
+/// and returns `nom`'s errors, this returns an [`Error`] saying
+/// what went wrong and where.
+pub fn from_str_partial(
+    input: &str,
+) -> Result<(Value<'_>, &str), Error> {
+    let options = ParserOptions::default();
+    match parse_tracked(input, options, Mode::Build, true) {
+        (Ok((rest, value)), _) => Ok((value, rest)),
+        (Err(err), furthest) => {
+            Err(Error::from_nom(input, err, furthest))
+        }
+    }
+}
+
+/// Parses a value, skipping any whitespace and comments before
+/// it.
+///
+/// Values nested in others are parsed with a stack kept on the
+/// heap rather than by recursing, so how deeply values may be
+/// nested is up to [`ParserOptions::max_depth`] and memory, not
+/// to the size of the call stack.
+pub fn parse_value(input: &str) -> IResult<&str, Value<'_>> {
+    parse_value_with(input, ParserOptions::default())
+}
+
+/// Checks that `input` holds a document that [`from_str`] would
+/// parse, returning the same error if it doesn't, but without
+/// building its value.
+///
+/// The input goes through the same parsers, with the same
+/// [`ParserOptions`] and limits, but the elements of a list,
+/// struct or any other value aren't kept, and no string or byte
+/// string is unescaped. The parser still keeps the values it is
+/// inside of on a stack, a `Vec` that grows with how deeply they
+/// are nested, and notes what was expected where parsers failed,
+/// so this only saves what building the value costs, which is
+/// little next to the parsing itself.
+pub fn validate(input: &str) -> Result<(), Error> {
+    validate_with(input, ParserOptions::default())
+}
+
+/// Checks `input` like [`validate`] does, but with `options`
+/// applied as [`parse_value_with`] applies them.
+pub fn validate_with(
+    input: &str,
+    options: ParserOptions,
+) -> Result<(), Error> {
+    let (rest, furthest) = match parse_tracked(
+        input,
+        options,
+        Mode::Validate,
+        true,
+    ) {
+        (Ok((rest, _)), furthest) => (rest, furthest),
+        (Err(err), furthest) => {
+            return Err(Error::from_nom(input, err, furthest))
+        }
+    };
+
+    check_trailing(input, rest, furthest)
+}
+
+/// How [`parse_nested`] parses.
+enum Mode<'r> {
+    /// Builds the value.
+    Build,
+    /// Builds the value, recovering from the errors that
+    /// [`parse_lenient`] found before.
+    Recover(&'r mut Recovery),
+    /// Only checks the input, for [`validate`], with
+    /// [`unbuilt`] values standing in for the ones parsed.
+    Validate,
+}
+
+/// What the frames of a parse share.
+struct Context<'r> {
+    state: &'r State,
+    mode: Mode<'r>,
+}
+
+impl Context<'_> {
+    /// Returns whether values are built rather than only
+    /// checked.
+    fn build(&self) -> bool {
+        !matches!(self.mode, Mode::Validate)
+    }
+
+    /// Returns how many errors were recovered from so far.
+    fn recovered(&self) -> usize {
+        match &self.mode {
+            Mode::Recover(recovery) => recovery.recovered(),
+            _ => 0,
+        }
+    }
+
+    /// Forgets the errors recovered from after the first `len`,
+    /// by values that were backtracked from.
+    fn forget_recovered(&mut self, len: usize) {
+        if let Mode::Recover(recovery) = &mut self.mode {
+            recovery.forget(len);
+        }
+    }
+}
+
+/// Stands in for a value that is only checked, which is cheaper
+/// to make than any value holding others.
+fn unbuilt<'a>() -> Value<'a> {
+    Value::Optional(None)
+}
+
+/// Parses a value as [`parse_value`] does, but as `mode` says,
+/// counting it and noting its failures in `state`.
+fn parse_nested<'a>(
+    state: &State,
+    input: &'a str,
+    mode: Mode<'_>,
+) -> IResult<&'a str, Value<'a>> {
+    let mut cx = Context { state, mode };
+    let mut stack = vec![Frame::value(input)];
+    let mut returned = None;
+
+    while let Some(frame) = stack.last_mut() {
+        match frame.resume(returned.take(), &mut cx) {
+            Step::Call(frame) => stack.push(frame),
+            Step::Return(result) => {
+                stack.pop();
+                returned = Some(result);
+            }
+        }
+    }
+
+    returned.expect("the outermost value returns a result")
+}
+
+/// A value being parsed by [`parse_value`], which keeps the ones
+/// it is nested in on a stack, each waiting on the value nested
+/// in it to be parsed.
+enum Frame<'a> {
+    Value(ValueFrame<'a>),
+    Elements(Elements<'a>),
+    Wrapped(Wrapped<'a>),
+}
+
+/// What a [`Frame`] does next.
+enum Step<'a> {
+    /// Parses another value, resuming the frame with the
+    /// result.
+    Call(Frame<'a>),
+    /// Finishes the frame, resuming the one before it with the
+    /// result.
+    Return(IResult<&'a str, Value<'a>>),
+}
+
+impl<'a> Frame<'a> {
+    fn value(input: &'a str) -> Self {
+        Frame::Value(ValueFrame {
+            single: Single::new(input),
+            range: None,
+        })
+    }
+
+    /// Resumes parsing with what the value it called returned,
+    /// which is `None` when the frame is first resumed.
+    fn resume(
+        &mut self,
+        returned: Option<IResult<&'a str, Value<'a>>>,
+        cx: &mut Context<'_>,
+    ) -> Step<'a> {
+        match self {
+            Frame::Value(frame) => frame.resume(returned, cx),
+            Frame::Elements(frame) => frame.resume(returned, cx),
+            Frame::Wrapped(frame) => frame.resume(returned, cx),
+        }
+    }
+}
+
+/// A value that may be a range, whose start and end are
+/// [`Single`] values.
+struct ValueFrame<'a> {
+    single: Single<'a>,
+    /// The start of the range and the input after its operator,
+    /// and whether it is inclusive, once the operator is
+    /// parsed.
+    range: Option<(Option<Box<Value<'a>>>, &'a str, bool)>,
+}
+
+impl<'a> ValueFrame<'a> {
+    fn resume(
+        &mut self,
+        returned: Option<IResult<&'a str, Value<'a>>>,
+        cx: &mut Context<'_>,
+    ) -> Step<'a> {
+        if returned.is_none() {
+            match parse_ws(self.single.input) {
+                Ok((input, _)) => self.single.input = input,
+                Err(err) => return Step::Return(Err(err)),
+            }
+        }
+
+        match self.single.resume(returned, cx) {
+            Step::Return(result) => self.parsed(result, cx),
+            call => call,
+        }
+    }
+
+    /// Goes on from a parsed start or end of a range.
+    fn parsed(
+        &mut self,
+        result: IResult<&'a str, Value<'a>>,
+        cx: &mut Context<'_>,
+    ) -> Step<'a> {
+        let Some((start, rest, inclusive)) = self.range.take()
+        else {
+            return match result {
+                Ok((rest, start)) => {
+                    match opt(skip_ws(parse_range_operator))(
+                        rest,
+                    ) {
+                        Ok((rest, Some(inclusive))) => {
+                            let start =
+                                cx.build().then_some(start);
+                            self.parse_end(
+                                rest, start, inclusive, cx,
+                            )
+                        }
+                        Ok((rest, None)) => {
+                            Step::Return(Ok((rest, start)))
+                        }
+                        Err(err) => Step::Return(Err(err)),
+                    }
+                }
+                Err(nom::Err::Error(_)) => {
+                    match parse_range_operator(self.single.input)
+                    {
+                        Ok((rest, inclusive)) => self.parse_end(
+                            rest, None, inclusive, cx,
+                        ),
+                        Err(err) => Step::Return(Err(err)),
+                    }
+                }
+                Err(err) => Step::Return(Err(err)),
+            };
+        };
+
+        // The end of an inclusive range is required
+        let (rest, end) = match result {
+            Ok((rest, end)) => {
+                (rest, cx.build().then(|| Box::new(end)))
+            }
+            Err(nom::Err::Error(err)) if inclusive => {
+                return Step::Return(Err(nom::Err::Failure(err)))
+            }
+            Err(nom::Err::Error(_)) => (rest, None),
+            Err(err) => return Step::Return(Err(err)),
+        };
+
+        Step::Return(Ok((
+            rest,
+            Value::Range {
+                start,
+                end,
+                inclusive,
+            },
+        )))
+    }
+
+    /// Parses the optional end of a range whose start and
+    /// operator were parsed, ending before `input`.
+    fn parse_end(
+        &mut self,
+        input: &'a str,
+        start: Option<Value<'a>>,
+        inclusive: bool,
+        cx: &mut Context<'_>,
+    ) -> Step<'a> {
+        let (end, _) = match parse_ws(input) {
+            Ok(parsed) => parsed,
+            Err(err) => return Step::Return(Err(err)),
+        };
+
+        self.range =
+            Some((start.map(Box::new), input, inclusive));
+        self.single = Single::new(end);
+        match self.single.resume(None, cx) {
+            Step::Return(result) => self.parsed(result, cx),
+            call => call,
+        }
+    }
+}
+
+/// A value other than a range, which is what the start and end
+/// of a range may be, found by trying each [`Kind`] of value in
+/// turn as nom's `alt` would.
+///
+/// It fails if it is nested deeper in other values than
+/// [`ParserOptions::max_depth`] allows or is one more than
+/// [`ParserOptions::max_nodes`] allows. When nothing here
+/// parses, the error is that a value was expected, rather than
+/// any of the brackets, quotes and keywords one could start
+/// with.
+struct Single<'a> {
+    input: &'a str,
+    /// How many values were parsed, counting this one.
+    nodes: usize,
+    /// The furthest failure before this value, see
+    /// [`furthest_so_far`].
+    before: (usize, usize),
+    /// Which of [`KINDS`] is being tried.
+    kind: usize,
+    /// How many values were parsed before the kind being tried
+    /// was, to forget the ones it parsed if it fails.
+    kind_nodes: usize,
+    /// How many errors were recovered from before the kind
+    /// being tried was, likewise.
+    kind_recovered: usize,
+}
+
+impl<'a> Single<'a> {
+    fn new(input: &'a str) -> Self {
+        Single {
+            input,
+            nodes: 0,
+            before: (0, 0),
+            kind: 0,
+            kind_nodes: 0,
+            kind_recovered: 0,
+        }
+    }
+
+    /// Resumes parsing with what the value it called returned,
+    /// returning its result rather than returning from a frame,
+    /// since it is part of a [`ValueFrame`].
+    fn resume(
+        &mut self,
+        returned: Option<IResult<&'a str, Value<'a>>>,
+        cx: &mut Context<'_>,
+    ) -> Step<'a> {
+        let mut result =
+            match returned {
+                Some(result) => result,
+                None => {
+                    let Counts { depth, nodes } =
+                        cx.state.with_counts(|counts| {
+                            counts.depth += 1;
+                            counts.nodes += 1;
+                            *counts
+                        });
+                    self.nodes = nodes;
+                    if depth > cx.state.options.max_depth {
+                        return Step::Return(self.leave(
+                            Err(limit_exceeded(
+                                self.input,
+                                DEPTH_LIMIT_EXCEEDED,
+                            )),
+                            false,
+                            cx,
+                        ));
+                    }
+                    if cx.state.options.max_nodes.is_some_and(
+                        |max_nodes| nodes > max_nodes,
+                    ) {
+                        return Step::Return(self.leave(
+                            Err(limit_exceeded(
+                                self.input,
+                                NODE_LIMIT_EXCEEDED,
+                            )),
+                            false,
+                            cx,
+                        ));
+                    }
+
+                    self.before = cx.state.furthest_so_far();
+                    if let Some(result) = parse_special_value(
+                        self.input,
+                        &cx.state.options,
+                    ) {
+                        return Step::Return(
+                            self.leave(result, true, cx),
+                        );
+                    }
+
+                    self.kind = 0;
+                    match self.try_kind(cx) {
+                        Step::Return(result) => result,
+                        call => return call,
+                    }
+                }
+            };
+
+        // The kinds that may fail after parsing values, leaving
+        // the input to the ones after them, forget those values
+        loop {
+            if let Err(nom::Err::Error(_)) = result {
+                cx.forget_recovered(self.kind_recovered);
+            }
+            match result {
+                Err(nom::Err::Error(_))
+                    if self.kind + 1 < KINDS.len() =>
+                {
+                    if KINDS[self.kind].backtracks() {
+                        let nodes = self.kind_nodes;
+                        cx.state.with_counts(|counts| {
+                            counts.nodes = nodes
+                        });
+                    }
+                    self.kind += 1;
+                    result = match self.try_kind(cx) {
+                        Step::Return(result) => result,
+                        call => return call,
+                    };
+                }
+                result => {
+                    return Step::Return(
+                        self.leave(result, true, cx),
+                    )
+                }
+            }
+        }
+    }
+
+    fn try_kind(&mut self, cx: &Context<'_>) -> Step<'a> {
+        let kind = KINDS[self.kind];
+        if kind.backtracks() {
+            self.kind_nodes =
+                cx.state.with_counts(|counts| counts.nodes);
+        }
+        self.kind_recovered = cx.recovered();
+
+        kind.parse(cx.state, self.input, cx.build())
+    }
+
+    /// Finishes the value with `result`, noting that a value was
+    /// expected if it failed and `labelled` is set.
+    fn leave(
+        &self,
+        result: IResult<&'a str, Value<'a>>,
+        labelled: bool,
+        cx: &Context<'_>,
+    ) -> IResult<&'a str, Value<'a>> {
+        if labelled && result.is_err() {
+            cx.state.relabel(self.input, self.before, "a value");
+        }
+        let nodes = self.nodes;
+        cx.state.with_counts(|counts| {
+            if let Err(nom::Err::Error(_)) = result {
+                counts.nodes = nodes - 1;
+            }
+            counts.depth -= 1;
+        });
+
+        result
+    }
+}
+
+/// Parses the values that are tried before any [`Kind`], since
+/// those would take the digits or letters they start with,
+/// returning `None` when none of them is here.
+fn parse_special_value<'a>(
+    input: &'a str,
+    options: &ParserOptions,
+) -> Option<IResult<&'a str, Value<'a>>> {
+    if options.json_compat {
+        if let Ok((rest, _)) = parse_keyword("null")(input) {
+            return Some(Ok((rest, Value::Optional(None))));
+        }
+    }
+
+    if options.strict_numbers {
+        if let Err(err) = check_strict_number(input) {
+            return Some(Err(err));
+        }
+    }
+
+    #[cfg(feature = "uuid")]
+    if options.uuids {
+        if let Ok((rest, uuid)) = uuid::parse_uuid(input) {
+            return Some(Ok((rest, Value::Uuid(uuid))));
+        }
+    }
+
+    #[cfg(feature = "net")]
+    if options.addresses {
+        if let Ok(result) = net::parse_address(input) {
+            return Some(Ok(result));
+        }
+    }
+
+    // Tried before any number, which would otherwise fail on the
+    // unit glued to it
+    #[cfg(feature = "duration")]
+    match parse_duration(input) {
+        Err(nom::Err::Error(_)) => {}
+        result => {
+            return Some(result.map(|(rest, duration)| {
+                (rest, Value::Duration(duration))
+            }))
+        }
+    }
+
+    None
+}
+
+/// The kinds of values a [`Single`] value may be.
+#[derive(Clone, Copy)]
+enum Kind {
+    Object,
+    /// A map with arbitrary keys, e.g. `{1: 'x', 2: 'y'}`.
+    ///
+    /// This is tried after `Object`, so it only ends up
+    /// producing maps that have at least one non-string key,
+    /// and `{}` is always an empty `Object`.
+    Map,
+    /// A set, e.g. `{1, 2, 3}`.
+    ///
+    /// This is tried after `Object` and `Map`, so braces hold a
+    /// set only when the first element isn't followed by a `:`.
+    /// The empty `{}` is an `Object`, never a set.
+    Set,
+    List,
+    /// A tuple, including the unit value `()` and the
+    /// single-element form `(1,)`.
+    Tuple,
+    None,
+    Some,
+    Ok,
+    Err,
+    Integer,
+    Float,
+    Char,
+    String,
+    Bytes,
+    Boolean,
+    /// A tuple struct, e.g. `Point(1, 2)`, `Marker()` or
+    /// `Shape::Move(1, 2)`.
+    ///
+    /// The parenthesis must immediately follow the name, and
+    /// none of `Some`, `Ok` or `Err` is accepted as a name
+    /// since these are always parsed as `Value::Optional`
+    /// and `Value::Result`. A path ending in one of them,
+    /// like `Option::Some(1)`, is a tuple struct.
+    TupleStruct,
+    /// A struct with named fields, e.g. `Foo { x: 1, y: 2 }`,
+    /// whose name may be a path such as `shape::Shape::Circle`.
+    ///
+    /// A struct with no fields is written as `Foo {}`, since a
+    /// bare `Foo` is parsed as a `Value::Ident`.
+    Struct,
+    Ident,
+}
+
+/// The kinds of values, in the order they are tried in.
+const KINDS: [Kind; 18] = [
+    Kind::Object,
+    Kind::Map,
+    Kind::Set,
+    Kind::List,
+    Kind::Tuple,
+    Kind::None,
+    Kind::Some,
+    Kind::Ok,
+    Kind::Err,
+    Kind::Integer,
+    Kind::Float,
+    Kind::Char,
+    Kind::String,
+    Kind::Bytes,
+    Kind::Boolean,
+    Kind::TupleStruct,
+    Kind::Struct,
+    Kind::Ident,
+];
+
+impl Kind {
+    /// Returns whether values of this kind may fail after
+    /// parsing values, which should then be forgotten.
+    fn backtracks(self) -> bool {
+        matches!(
+            self,
+            Kind::Object
+                | Kind::Map
+                | Kind::Some
+                | Kind::Ok
+                | Kind::Err
+                | Kind::TupleStruct
+                | Kind::Struct
+        )
+    }
+
+    /// Parses a value of this kind, or the start of one, calling
+    /// the frame that parses the rest of it, and only checking
+    /// the value rather than building it unless `build` is set.
+    fn parse<'a>(
+        self,
+        state: &State,
+        input: &'a str,
+        build: bool,
+    ) -> Step<'a> {
+        let open =
+            |opening, items| match char(state, opening)(input) {
+                Ok((rest, _)) => Step::Call(Frame::Elements(
+                    Elements::new(rest, items, build),
+                )),
+                Err(err) => Step::Return(Err(err)),
+            };
+        let wrap =
+            |keyword, wrap: fn(Box<Value<'a>>) -> Value<'a>| {
+                match pair(
+                    tag(state, keyword),
+                    skip_ws(char(state, '(')),
+                )(input)
+                {
+                    Ok((rest, _)) => {
+                        Step::Call(Frame::Wrapped(Wrapped {
+                            rest,
+                            wrap,
+                        }))
+                    }
+                    Err(err) => Step::Return(Err(err)),
+                }
+            };
+
+        match self {
+            Kind::Object => open(
+                '{',
+                Items::Object(Vec::new(), Cow::Borrowed("")),
+            ),
+            Kind::Map => open('{', Items::Map(Vec::new(), None)),
+            Kind::Set => open('{', Items::Set(Vec::new())),
+            Kind::List => open('[', Items::List(Vec::new())),
+            Kind::Tuple => open('(', Items::Tuple(Vec::new())),
+            Kind::None => Step::Return(value(
+                Value::Optional(None),
+                parse_keyword("None"),
+            )(input)),
+            Kind::Some => wrap("Some", |value| {
+                Value::Optional(Some(value))
+            }),
+            Kind::Ok => {
+                wrap("Ok", |value| Value::Result(Ok(value)))
+            }
+            Kind::Err => {
+                wrap("Err", |value| Value::Result(Err(value)))
+            }
+            Kind::Integer => {
+                Step::Return(parse_integer(state, input))
+            }
+            Kind::Float => Step::Return(parse_float(input)),
+            Kind::Char => Step::Return(
+                parse_char(state, input)
+                    .map(|(rest, chr)| (rest, Value::Char(chr))),
+            ),
+            Kind::String if build => {
+                Step::Return(parse_string(state, input).map(
+                    |(rest, string)| {
+                        (
+                            rest,
+                            string_value(&state.options, string),
+                        )
+                    },
+                ))
+            }
+            Kind::String => Step::Return(
+                string(state, false)
+                    .map(|_| unbuilt())
+                    .parse(input),
+            ),
+            Kind::Bytes if build => Step::Return(
+                parse_byte_string(state, input).map(
+                    |(rest, bytes)| (rest, Value::Bytes(bytes)),
+                ),
+            ),
+            Kind::Bytes => Step::Return(
+                byte_string(state, input, false)
+                    .map(|(rest, _)| (rest, unbuilt())),
+            ),
+            Kind::Boolean => Step::Return(
+                parse_boolean.map(Value::Boolean).parse(input),
+            ),
+            Kind::TupleStruct => {
+                let name = verify(
+                    |input| parse_ident(state, input),
+                    |name: &str| {
+                        !WRAPPER_KEYWORDS.contains(&name)
+                    },
+                );
+                match pair(name, skip_ws(char(state, '(')))(
+                    input,
+                ) {
+                    Ok((rest, (name, _))) => Step::Call(
+                        Frame::Elements(Elements::new(
+                            rest,
+                            Items::TupleStruct(name, Vec::new()),
+                            build,
+                        )),
+                    ),
+                    Err(err) => Step::Return(Err(err)),
+                }
+            }
+            Kind::Struct => {
+                match pair(
+                    |input| parse_ident(state, input),
+                    skip_ws(char(state, '{')),
+                )(input)
+                {
+                    Ok((rest, (name, _))) => Step::Call(
+                        Frame::Elements(Elements::new(
+                            rest,
+                            Items::Struct(name, Vec::new(), ""),
+                            build,
+                        )),
+                    ),
+                    Err(err) => Step::Return(Err(err)),
+                }
+            }
+            Kind::Ident => {
+                Step::Return(parse_ident(state, input).map(
+                    |(rest, ident)| {
+                        (
+                            rest,
+                            Value::Ident(Cow::Borrowed(ident)),
+                        )
+                    },
+                ))
+            }
+        }
+    }
+}
+
+/// The elements parsed so far of a value in brackets, along with
+/// the key or field name of the one being parsed, once there is
+/// one.
+enum Items<'a> {
+    Object(Vec<(Cow<'a, str>, Value<'a>)>, Cow<'a, str>),
+    Map(
+        Vec<(Value<'a>, Value<'a>)>,
+        Option<Box<Value<'a>>>,
+    ),
+    Set(Vec<Value<'a>>),
+    List(Vec<Value<'a>>),
+    Tuple(Vec<Value<'a>>),
+    TupleStruct(&'a str, Vec<Value<'a>>),
+    Struct(&'a str, Fields<'a>, &'a str),
+}
+
+impl<'a> Items<'a> {
+    fn push(&mut self, value: Value<'a>) {
+        match self {
+            Items::Object(entries, key) => {
+                entries.push((core::mem::take(key), value));
+            }
+            Items::Map(entries, key) => {
+                let key = key.take().expect("the key is parsed");
+                entries.push((*key, value));
+            }
+            Items::Set(elements)
+            | Items::List(elements)
+            | Items::Tuple(elements)
+            | Items::TupleStruct(_, elements) => {
+                elements.push(value);
+            }
+            Items::Struct(_, fields, name) => {
+                fields.push((Cow::Borrowed(name), value));
+            }
+        }
+    }
+
+    fn closing(&self) -> char {
+        match self {
+            Items::List(_) => ']',
+            Items::Tuple(_) | Items::TupleStruct(..) => ')',
+            _ => '}',
+        }
+    }
+
+    fn into_value(self, non_exhaustive: bool) -> Value<'a> {
+        match self {
+            Items::Object(entries, _) => {
+                Value::Object(entries.into_iter().collect())
+            }
+            Items::Map(entries, _) => Value::Map(entries),
+            Items::Set(elements) => Value::Set(elements),
+            Items::List(elements) => Value::List(elements),
+            Items::Tuple(elements) => Value::Tuple(elements),
+            Items::TupleStruct(name, fields) => {
+                Value::TupleStruct {
+                    name: Cow::Borrowed(name),
+                    fields,
+                }
+            }
+            Items::Struct(name, fields, _) => Value::Struct {
+                name: Cow::Borrowed(name),
+                fields,
+                non_exhaustive,
+            },
+        }
+    }
+}
+
+/// Zero or more comma-separated elements in brackets, with a
+/// single trailing comma allowed after the last one.
+///
+/// A comma is only accepted after an element, so a lone `,` or
+/// a doubled `,,` is left for the closing bracket to reject, and
+/// so is an element that fails to parse. An element past
+/// [`ParserOptions::max_elements`] is a failure pointing at it.
+struct Elements<'a> {
+    items: Items<'a>,
+    /// The input after the last element, or after the opening
+    /// bracket until there is one.
+    rest: &'a str,
+    /// How many elements were parsed.
+    count: usize,
+    /// Where the element being parsed starts.
+    element: &'a str,
+    /// Whether the element is a map entry whose key was parsed.
+    keyed: bool,
+    /// How much input was left at the furthest failure before
+    /// the element, when recovering from errors.
+    before: usize,
+    /// Whether an element was skipped when recovering from an
+    /// error, after which the one element left in a tuple needs
+    /// no comma.
+    skipped: bool,
+    /// Whether the elements are kept in `items`, rather than
+    /// only checked.
+    build: bool,
+}
+
+impl<'a> Elements<'a> {
+    fn new(
+        rest: &'a str,
+        items: Items<'a>,
+        build: bool,
+    ) -> Self {
+        Elements {
+            items,
+            rest,
+            count: 0,
+            element: rest,
+            keyed: false,
+            before: usize::MAX,
+            skipped: false,
+            build,
+        }
+    }
+
+    fn resume(
+        &mut self,
+        returned: Option<IResult<&'a str, Value<'a>>>,
+        cx: &mut Context<'_>,
+    ) -> Step<'a> {
+        let (rest, value) = match returned {
+            None => return self.element(self.rest, cx),
+            Some(Ok(parsed)) => parsed,
+            Some(Err(err)) => {
+                return self.failed(self.element, err, false, cx)
+            }
+        };
+
+        if let (Items::Map(_, key), false) =
+            (&mut self.items, self.keyed)
+        {
+            return match skip_ws(char(cx.state, ':'))(rest) {
+                Ok((rest, _)) => {
+                    if self.build {
+                        *key = Some(Box::new(value));
+                    }
+                    self.keyed = true;
+                    Step::Call(Frame::value(rest))
+                }
+                Err(err) => {
+                    self.failed(self.element, err, true, cx)
+                }
+            };
+        }
+
+        if self.build {
+            self.items.push(value);
+        }
+        self.count += 1;
+        if cx
+            .state
+            .options
+            .max_elements
+            .is_some_and(|max| self.count > max)
+        {
+            return Step::Return(Err(limit_exceeded(
+                parse_ws(self.element)
+                    .map_or(self.element, |(rest, _)| rest),
+                ELEMENT_LIMIT_EXCEEDED,
+            )));
+        }
+
+        self.rest = rest;
+        match skip_ws(char(cx.state, ','))(rest) {
+            Ok((next, _)) => self.element(next, cx),
+            Err(err) => self.failed(rest, err, true, cx),
+        }
+    }
+
+    /// Parses the element starting at `input`, up to its value.
+    fn element(
+        &mut self,
+        input: &'a str,
+        cx: &mut Context<'_>,
+    ) -> Step<'a> {
+        self.element = input;
+        self.keyed = false;
+        if let Mode::Recover(_) = cx.mode {
+            self.before = cx.state.furthest_so_far().0;
+        }
+        let parsed = match &mut self.items {
+            Items::Object(_, key) => pair(
+                skip_ws(labelled(
+                    cx.state,
+                    "a string",
+                    string(cx.state, self.build),
+                )),
+                skip_ws(char(cx.state, ':')),
+            )(input)
+            .map(|(rest, (parsed, _))| {
+                *key = parsed;
+                rest
+            }),
+            Items::Struct(_, _, name) => pair(
+                skip_ws(labelled(
+                    cx.state,
+                    "a field name",
+                    parse_identifier,
+                )),
+                skip_ws(char(cx.state, ':')),
+            )(input)
+            .map(|(rest, (parsed, _))| {
+                *name = parsed;
+                rest
+            }),
+            _ => Ok(input),
+        };
+
+        match parsed {
+            Ok(rest) => Step::Call(Frame::value(rest)),
+            Err(err) => self.failed(input, err, false, cx),
+        }
+    }
+
+    /// Goes on after what was parsed from `from` failed with
+    /// `err`, which ends the elements, unless [`parse_lenient`]
+    /// is recovering from an error there.
+    ///
+    /// The failure is taken to be at the furthest one since the
+    /// element started, as long as that is further than any
+    /// before it, or where `err` is if that is further and
+    /// `exact` is set.
+    fn failed(
+        &mut self,
+        from: &'a str,
+        err: nom::Err<nom::error::Error<&'a str>>,
+        exact: bool,
+        cx: &mut Context<'_>,
+    ) -> Step<'a> {
+        let Mode::Recover(recovery) = &mut cx.mode else {
+            return match err {
+                nom::Err::Error(_) => self.close(cx.state),
+                err => Step::Return(Err(err)),
+            };
+        };
+
+        let (remaining, _) = cx.state.furthest_so_far();
+        let furthest = if remaining < self.before {
+            remaining
+        } else {
+            usize::MAX
+        };
+        let reached = match &err {
+            nom::Err::Error(_) if !exact => furthest,
+            nom::Err::Error(err) | nom::Err::Failure(err) => {
+                furthest.min(err.input.len())
+            }
+            nom::Err::Incomplete(_) => furthest,
+        };
+        let err = match err {
+            nom::Err::Error(_) => match self.close(cx.state) {
+                Step::Return(Err(err @ nom::Err::Error(_))) => {
+                    err
+                }
+                step => return step,
+            },
+            err => err,
+        };
+
+        let stop = recovery::skip_element(from);
+        if !recovery.recover(from.len(), stop.len(), reached) {
+            return Step::Return(Err(err));
+        }
+        self.skipped = true;
+
+        if let Some(next) = stop.strip_prefix(',') {
+            // A comma after no elements is skipped with the one
+            // before it, since the closing bracket rejects it
+            self.rest =
+                if self.count == 0 { next } else { stop };
+            return self.element(next, cx);
+        }
+
+        self.rest = stop;
+        if stop.starts_with(self.items.closing()) {
+            return self.close(cx.state);
+        }
+
+        // The end of the input or the bracket of a value these
+        // are in is taken to close them as well
+        Step::Return(Ok((stop, self.take(false))))
+    }
+
+    /// Parses the optional trailing comma and the closing
+    /// bracket after the elements.
+    ///
+    /// The comma is required after the one element of a tuple,
+    /// since `(1)` isn't the `Debug` output of a tuple. The
+    /// fields of a struct may end in `..`, as in
+    /// `Foo { x: 1, .. }`, which makes it non-exhaustive.
+    fn close(&mut self, state: &State) -> Step<'a> {
+        let mut rest = self.rest;
+        if let (Items::Tuple(_), 1, false) =
+            (&self.items, self.count, self.skipped)
+        {
+            match skip_ws(char(state, ','))(rest) {
+                Ok((after, _)) => rest = after,
+                Err(err) => return Step::Return(Err(err)),
+            }
+        } else if self.count > 0 {
+            match opt(skip_ws(char(state, ',')))(rest) {
+                Ok((after, _)) => rest = after,
+                Err(err) => return Step::Return(Err(err)),
+            }
+        } else if let Items::Set(_) = self.items {
+            return Step::Return(Err(nom::Err::Error(
+                nom::error::Error::new(
+                    rest,
+                    NomErrorKind::Verify,
+                ),
+            )));
+        }
+
+        let mut non_exhaustive = false;
+        if let Items::Struct(..) = self.items {
+            let mut marker = opt(terminated(
+                skip_ws(tag(state, "..")),
+                opt(skip_ws(char(state, ','))),
+            ));
+            match marker(rest) {
+                Ok((after, marker)) => {
+                    rest = after;
+                    non_exhaustive = marker.is_some();
+                }
+                Err(err) => return Step::Return(Err(err)),
+            }
+        }
+
+        let closing = self.items.closing();
+        Step::Return(
+            skip_ws(char(state, closing))(rest).map(
+                |(rest, _)| (rest, self.take(non_exhaustive)),
+            ),
+        )
+    }
+
+    /// Takes the value out of the elements parsed.
+    fn take(&mut self, non_exhaustive: bool) -> Value<'a> {
+        if !self.build {
+            return unbuilt();
+        }
+
+        let items = core::mem::replace(
+            &mut self.items,
+            Items::Set(Vec::new()),
+        );
+        items.into_value(non_exhaustive)
+    }
+}
+
+/// The value in `Some(..)`, `Ok(..)` or `Err(..)`, allowing
+/// whitespace inside of the parenthesis and a trailing comma, as
+/// in `{:#?}`'s `Some(\n    1,\n)`.
+struct Wrapped<'a> {
+    /// The input after the opening parenthesis.
+    rest: &'a str,
+    /// Makes the value it holds a `Value::Optional` or a
+    /// `Value::Result`.
+    wrap: fn(Box<Value<'a>>) -> Value<'a>,
+}
+
+impl<'a> Wrapped<'a> {
+    fn resume(
+        &mut self,
+        returned: Option<IResult<&'a str, Value<'a>>>,
+        cx: &Context<'_>,
+    ) -> Step<'a> {
+        let (rest, value) = match returned {
+            None => return Step::Call(Frame::value(self.rest)),
+            Some(Ok(parsed)) => parsed,
+            Some(Err(err)) => return Step::Return(Err(err)),
+        };
+
+        let mut closing = preceded(
+            opt(skip_ws(char(cx.state, ','))),
+            skip_ws(char(cx.state, ')')),
+        );
+        Step::Return(closing(rest).map(|(rest, _)| {
+            match cx.build() {
+                true => (rest, (self.wrap)(Box::new(value))),
+                false => (rest, unbuilt()),
+            }
+        }))
+    }
+}
+
+/// Makes a parsed string a `Value::String`, or a
+/// `Value::DateTime` or `Value::Uuid` if it holds one and
+/// [`ParserOptions::datetimes`] or [`ParserOptions::uuids`] is
+/// set in `options`.
+#[cfg_attr(
+    not(any(feature = "datetime", feature = "uuid")),
+    allow(unused_variables)
+)]
+fn string_value<'a>(
+    options: &ParserOptions,
+    string: Cow<'a, str>,
+) -> Value<'a> {
+    #[cfg(feature = "datetime")]
+    if options.datetimes {
+        if let Ok(datetime) = string.parse() {
+            return Value::DateTime(datetime);
+        }
+    }
+
+    #[cfg(feature = "uuid")]
+    if options.uuids {
+        if let Ok(("", uuid)) = uuid::parse_uuid(&string) {
+            return Value::Uuid(uuid);
+        }
+    }
+
+    Value::String(string)
+}
+
+/// Parses `..` or `..=`, returning whether the range is
+/// inclusive.
+fn parse_range_operator(input: &str) -> IResult<&str, bool> {
+    use nom::bytes::complete::tag;
+
+    alt((value(true, tag("..=")), value(false, tag(".."))))(
+        input,
+    )
+}
+
+/// Parses an identifier or a `::`-separated path of identifiers,
+/// e.g. `Red` or `std::cmp::Ordering::Less`.
+fn parse_ident<'a>(
+    state: &State,
+    input: &'a str,
+) -> IResult<&'a str, &'a str> {
+    recognize(pair(
+        parse_identifier,
+        many0_count(preceded(
+            tag(state, "::"),
+            parse_identifier,
+        )),
+    ))(input)
+}
+
+/// Parses a Rust-style identifier, i.e.
+/// `[A-Za-z_][A-Za-z0-9_]*`.
+fn parse_identifier(input: &str) -> IResult<&str, &str> {
+    let is_start =
+        |ch: char| ch.is_ascii_alphabetic() || ch == '_';
+
+    recognize(pair(
+        satisfy(is_start),
+        take_while(is_identifier_char),
+    ))(input)
+}
+
+fn is_identifier_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
+}
+
+/// The keywords that wrap a single value in parenthesis.
+const WRAPPER_KEYWORDS: [&str; 3] = ["Some", "Ok", "Err"];
+
+/// Skips whitespace along with any `//` line comments and `/*
+/// */` block comments in it.
+///
+/// Whitespace is anything [`char::is_whitespace`] accepts, so a
+/// non-breaking space (`\u{a0}`) is skipped like any other.
+fn parse_ws(input: &str) -> IResult<&str, &str> {
+    recognize(many0_count(alt((
+        take_while1(char::is_whitespace),
+        parse_line_comment,
+        parse_block_comment,
+    ))))(input)
+}
+
+/// Parses a `//` comment, up to but excluding the end of the
+/// line.
+/// Returns `parser` preceded by [`parse_ws`], which is how every
+/// parser skips the whitespace and comments around delimiters.
+fn skip_ws<'a, O>(
+    parser: impl Parser<&'a str, O, nom::error::Error<&'a str>>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O> {
+    preceded(parse_ws, parser)
+}
+
+fn parse_line_comment(input: &str) -> IResult<&str, &str> {
+    use nom::bytes::complete::tag;
+
+    recognize(pair(tag("//"), take_while(|ch| ch != '\n')))(
+        input,
+    )
+}
+
+/// Parses a `/* */` comment, which may nest like Rust's do, so
+/// `/* a /* b */ c */` is a single comment.
+///
+/// An unterminated comment is a failure pointing at the `/*`
+/// that was never closed.
+fn parse_block_comment(input: &str) -> IResult<&str, &str> {
+    nom::bytes::complete::tag("/*")(input)?;
+
+    let (rest, depth) = scan_block_comment(input, |_, _| {});
+    if depth == 0 {
+        let comment = &input[..input.len() - rest.len()];
+        return Ok((rest, comment));
+    }
+
+    // The innermost comment left open is the last one opened
+    // that deep
+    let mut opening = input;
+    scan_block_comment(input, |at, nested| {
+        if nested == depth {
+            opening = at;
+        }
+    });
+    Err(nom::Err::Failure(nom::error::Error::new(
+        opening,
+        NomErrorKind::TakeUntil,
+    )))
+}
+
+/// Scans the block comment starting at `input` up to where it
+/// ends, calling `opened` with the input at each `/*` and how
+/// deeply nested it is, counting from 1, and returns the input
+/// after the comment along with how many comments are left open.
+fn scan_block_comment<'a>(
+    input: &'a str,
+    mut opened: impl FnMut(&'a str, usize),
+) -> (&'a str, usize) {
+    let (mut rest, mut depth) = (input, 0);
+    loop {
+        if let Some(after) = rest.strip_prefix("*/") {
+            depth -= 1;
+            rest = after;
+        } else if rest.starts_with("/*") {
+            depth += 1;
+            opened(rest, depth);
+            rest = &rest[2..];
+        } else if let Some(ch) = rest.chars().next() {
+            rest = &rest[ch.len_utf8()..];
+        } else {
+            return (rest, depth);
+        }
+
+        if depth == 0 {
+            return (rest, depth);
+        }
+    }
+}
+
+/// Parses a char literal holding exactly one `char`, written
+/// as itself or as an escape.
+///
+/// Anything else after the opening quote is a failure pointing
+/// at it: `''` is an empty char, and a quote with no other quote
+/// after it on its line is unterminated. `'ab'` is an unexpected
+/// character at the `b`.
+fn parse_char<'a>(
+    state: &State,
+    input: &'a str,
+) -> IResult<&'a str, char> {
+    let (body, _) = char(state, '\'')(input)?;
+    let failure = |at, code| {
+        Err(nom::Err::Failure(nom::error::Error::new(
+            at, code,
+        )))
+    };
+
+    let (rest, chr) = match body.chars().next() {
+        None => return failure(input, NomErrorKind::TakeUntil),
+        Some('\'') => {
+            return failure(input, NomErrorKind::NonEmpty)
+        }
+        Some('\\') => parse_escaped_char(state, body)?,
+        Some(chr) => (&body[chr.len_utf8()..], chr),
+    };
+
+    if let Some(rest) = rest.strip_prefix('\'') {
+        return Ok((rest, chr));
+    }
+
+    let line =
+        rest.split(['\n', '\r']).next().unwrap_or_default();
+    if !line.contains('\'') {
+        return failure(input, NomErrorKind::TakeUntil);
+    }
+    cut(char(state, '\''))(rest).map(|(rest, _)| (rest, chr))
+}
+
+/// Parses a backslash escape standing for a single `char`, such
+/// as `\n`, `\x7f` or `\u{1f600}`. An invalid escape is a
+/// failure pointing at its backslash.
+fn parse_escaped_char<'a>(
+    state: &State,
+    input: &'a str,
+) -> IResult<&'a str, char> {
+    char(state, '\\')(input)?;
+
+    match unescape_char(input) {
+        Some((len, chr)) => Ok((&input[len..], chr)),
+        None => Err(nom::Err::Failure(nom::error::Error::new(
+            input,
+            NomErrorKind::Escaped,
+        ))),
+    }
+}
+
+/// Undoes the escape at the start of `escape` (which begins with
+/// a backslash), returning how long the escape was along with
+/// the `char` it stands for.
+///
+/// The supported escapes are `\n`, `\r`, `\t`, `\\`, `\'`, `\"`,
+/// `\0`, `\xNN` up to `\x7f`, and `\u{NNNN}` with 1 to 6 hex
+/// digits naming a Unicode scalar value (so not a surrogate, and
+/// at most `\u{10ffff}`).
+fn unescape_char(escape: &str) -> Option<(usize, char)> {
+    let chr = match escape.as_bytes().get(1)? {
+        b'u' => {
+            let digits = escape.get(2..)?.strip_prefix('{')?;
+            let len = digits.find('}')?;
+            let digits = &digits[..len];
+
+            if !(1..=6).contains(&len)
+                || !digits
+                    .bytes()
+                    .all(|byte| byte.is_ascii_hexdigit())
+            {
+                return None;
+            }
+
+            let code_point =
+                u32::from_str_radix(digits, 16).ok()?;
+            let chr = char::from_u32(code_point)?;
+
+            // The backslash, the `u`, the braces and the digits
+            return Some((len + 4, chr));
+        }
+        b'x' => {
+            let digits = escape.get(2..4)?;
+            if !digits
+                .bytes()
+                .all(|byte| byte.is_ascii_hexdigit())
+            {
+                return None;
+            }
+
+            let byte = u8::from_str_radix(digits, 16).ok()?;
+            return byte
+                .is_ascii()
+                .then_some((4, char::from(byte)));
+        }
+        b'n' => '\n',
+        b'r' => '\r',
+        b't' => '\t',
+        b'\\' => '\\',
+        b'\'' => '\'',
+        b'"' => '"',
+        b'0' => '\0',
+        _ => return None,
+    };
+
+    Some((2, chr))
+}
+
+fn parse_string<'a>(
+    state: &State,
+    input: &'a str,
+) -> IResult<&'a str, Cow<'a, str>> {
+    string(state, true)(input)
+}
+
+/// Returns a parser for raw and quoted strings, which only
+/// checks the escapes of a quoted string unless `build` is set,
+/// see [`parse_quoted_string`].
+fn string<'a, 's>(
+    state: &'s State,
+    build: bool,
+) -> impl Fn(&'a str) -> IResult<&'a str, Cow<'a, str>> + 's {
+    move |input| {
+        alt((
+            (|input| parse_raw_string(state, input))
+                .map(Cow::Borrowed),
+            |input| parse_quoted_string(state, input, build),
+        ))(input)
+    }
+}
+
+/// Parses a double-quoted string, undoing the escapes understood
+/// by `unescape_char` if `build` is set, and otherwise checking
+/// them and borrowing the string as it is written.
+///
+/// The string is borrowed from the input when there are no
+/// escapes to undo. An invalid escape is a failure pointing at
+/// its backslash, and a string that never ends is a failure
+/// pointing at its opening quote.
+fn parse_quoted_string<'a>(
+    state: &State,
+    input: &'a str,
+    build: bool,
+) -> IResult<&'a str, Cow<'a, str>> {
+    let (body, _) = char(state, '"')(input)?;
+    let max_len = state.options.max_string_len;
+
+    let mut unescaped: Option<String> = None;
+    // How long the string is unescaped, up to `chunk_start`
+    let mut len = 0;
+    let mut chunk_start = 0;
+    let mut idx = 0;
+
+    loop {
+        let Some(offset) = body[idx..].find(['"', '\\']) else {
+            return Err(nom::Err::Failure(
+                nom::error::Error::new(
+                    input,
+                    NomErrorKind::TakeUntil,
+                ),
+            ));
+        };
+        idx += offset;
+
+        check_string_len(
+            input,
+            len + idx - chunk_start,
+            max_len,
+        )?;
+
+        if body[idx..].starts_with('"') {
+            break;
+        }
+
+        let escape = &body[idx..];
+        let (escape_len, chr) = unescape_char(escape)
+            .or_else(|| {
+                state
+                    .options
+                    .json_compat
+                    .then(|| unescape_json_char(escape))
+                    .flatten()
+            })
+            .ok_or_else(|| {
+                nom::Err::Failure(nom::error::Error::new(
+                    escape,
+                    NomErrorKind::Escaped,
+                ))
+            })?;
+
+        len += idx - chunk_start + chr.len_utf8();
+        if build {
+            let string =
+                unescaped.get_or_insert_with(String::new);
+            string.push_str(&body[chunk_start..idx]);
+            string.push(chr);
+        }
+
+        idx += escape_len;
+        chunk_start = idx;
+    }
+
+    let string = match unescaped {
+        Some(mut string) => {
+            string.push_str(&body[chunk_start..idx]);
+            Cow::Owned(string)
+        }
+        None => Cow::Borrowed(&body[..idx]),
+    };
+
+    // Skip the closing quote
+    Ok((&body[idx + 1..], string))
+}
+
+/// Undoes the JSON escapes that `unescape_char` doesn't know,
+/// namely `\/`, `\b`, `\f` and `\uNNNN`, where a high surrogate
+/// must be followed by an escaped low surrogate, as in
+/// `\ud83d\ude00`.
+fn unescape_json_char(escape: &str) -> Option<(usize, char)> {
+    fn parse_hex4(digits: &str) -> Option<u32> {
+        let digits = digits.get(..4)?;
+        digits
+            .bytes()
+            .all(|byte| byte.is_ascii_hexdigit())
+            .then(|| u32::from_str_radix(digits, 16).ok())?
+    }
+
+    let chr = match escape.as_bytes().get(1)? {
+        b'u' => {
+            let high = parse_hex4(escape.get(2..)?)?;
+            if !(0xd800..=0xdbff).contains(&high) {
+                return Some((6, char::from_u32(high)?));
+            }
+
+            let low = parse_hex4(
+                escape.get(6..)?.strip_prefix("\\u")?,
+            )?;
+            if !(0xdc00..=0xdfff).contains(&low) {
+                return None;
+            }
+
+            let code_point = 0x10000
+                + ((high - 0xd800) << 10)
+                + (low - 0xdc00);
+            return Some((12, char::from_u32(code_point)?));
+        }
+        b'/' => '/',
+        b'b' => '\u{8}',
+        b'f' => '\u{c}',
+        _ => return None,
+    };
+
+    Some((2, chr))
+}
+
+/// Parses a raw string literal, e.g. `r"C:\temp"` or
+/// `r#"say "hi""#`, whose contents are taken verbatim.
+///
+/// The string ends at the first quote followed by as many `#` as
+/// the opening delimiter had. A raw string that never ends is a
+/// failure pointing at its opening `r`.
+fn parse_raw_string<'a>(
+    state: &State,
+    input: &'a str,
+) -> IResult<&'a str, &'a str> {
+    let (rest, hashes) = preceded(
+        char(state, 'r'),
+        take_while(|ch| ch == '#'),
+    )(input)?;
+    let (body, _) = char(state, '"')(rest)?;
+
+    let end = body.match_indices('"').find_map(|(idx, _)| {
+        body[idx + 1..].starts_with(hashes).then_some(idx)
+    });
+
+    match end {
+        Some(end) => {
+            check_string_len(
+                input,
+                end,
+                state.options.max_string_len,
+            )?;
+            let rest = &body[end + 1 + hashes.len()..];
+            Ok((rest, &body[..end]))
+        }
+        None => Err(nom::Err::Failure(nom::error::Error::new(
+            input,
+            NomErrorKind::TakeUntil,
+        ))),
+    }
+}
+
+/// Parses a byte string literal, e.g. `b"\x00\xffabc"`.
+///
+/// The supported escapes are `\xNN`, `\\`, `\"`, `\n`, `\t`,
+/// `\r` and `\0`. An invalid escape is a failure pointing at its
+/// backslash, and a byte string that never ends is a failure
+/// pointing at its opening `b`. As in Rust, everything else in
+/// it has to be ASCII, so a high byte is written as `\xNN`, and
+/// any other char is a failure pointing at it. The bytes are
+/// borrowed from the input when there are no escapes to undo.
+fn parse_byte_string<'a>(
+    state: &State,
+    input: &'a str,
+) -> IResult<&'a str, Cow<'a, [u8]>> {
+    byte_string(state, input, true)
+}
+
+/// Parses a byte string as [`parse_byte_string`] does if `build`
+/// is set, and otherwise checks its escapes and borrows it as it
+/// is written.
+fn byte_string<'a>(
+    state: &State,
+    input: &'a str,
+    build: bool,
+) -> IResult<&'a str, Cow<'a, [u8]>> {
+    let (body, _) = tag(state, "b\"")(input)?;
+    let max_len = state.options.max_string_len;
+
+    let raw = body.as_bytes();
+    let mut unescaped: Option<Vec<u8>> = None;
+    // How many bytes there are unescaped, up to `chunk_start`
+    let mut len = 0;
+    let mut chunk_start = 0;
+    let mut idx = 0;
+
+    loop {
+        let byte = raw.get(idx);
+        if matches!(byte, Some(b'"' | b'\\')) {
+            check_string_len(
+                input,
+                len + idx - chunk_start,
+                max_len,
+            )?;
+        }
+
+        match byte {
+            Some(b'"') => break,
+            Some(b'\\') => {
+                let escape = &body[idx..];
+                let invalid_escape = || {
+                    nom::Err::Failure(nom::error::Error::new(
+                        escape,
+                        NomErrorKind::Escaped,
+                    ))
+                };
+                let (escape_len, byte) =
+                    unescape_byte(escape)
+                        .ok_or_else(invalid_escape)?;
+
+                len += idx - chunk_start + 1;
+                if build {
+                    let bytes =
+                        unescaped.get_or_insert_with(Vec::new);
+                    bytes.extend_from_slice(
+                        &raw[chunk_start..idx],
+                    );
+                    bytes.push(byte);
+                }
+
+                idx += escape_len;
+                chunk_start = idx;
+            }
+            Some(byte) if !byte.is_ascii() => {
+                // Everything before it is ASCII, so this is the
+                // start of a char
+                return Err(nom::Err::Failure(
+                    nom::error::Error::new(
+                        &body[idx..],
+                        NomErrorKind::Char,
+                    ),
+                ));
+            }
+            Some(_) => idx += 1,
+            None => {
+                return Err(nom::Err::Failure(
+                    nom::error::Error::new(
+                        input,
+                        NomErrorKind::TakeUntil,
+                    ),
+                ))
+            }
+        }
+    }
+
+    let bytes = match unescaped {
+        Some(mut bytes) => {
+            bytes.extend_from_slice(&raw[chunk_start..idx]);
+            Cow::Owned(bytes)
+        }
+        None => Cow::Borrowed(&raw[..idx]),
+    };
+
+    // Skip the closing quote
+    Ok((&body[idx + 1..], bytes))
+}
+
+/// Undoes the byte escape at the start of `escape` (which begins
+/// with a backslash), returning how long the escape was along
+/// with the byte it stands for.
+fn unescape_byte(escape: &str) -> Option<(usize, u8)> {
+    let byte = match escape.as_bytes().get(1)? {
+        b'x' => {
+            let digits = escape.get(2..4)?;
+            if !digits
+                .bytes()
+                .all(|byte| byte.is_ascii_hexdigit())
+            {
+                return None;
+            }
+
+            return u8::from_str_radix(digits, 16)
+                .ok()
+                .map(|byte| (4, byte));
+        }
+        b'\\' => b'\\',
+        b'"' => b'"',
+        b'n' => b'\n',
+        b't' => b'\t',
+        b'r' => b'\r',
+        b'0' => b'\0',
+        _ => return None,
+    };
+
+    Some((2, byte))
+}
+
+fn parse_boolean(input: &str) -> IResult<&str, bool> {
+    let (rest, boolean) = alt((
+        parse_keyword("true"),
+        parse_keyword("false"),
+    ))(input)?;
+
+    let is_true = boolean == "true";
+
+    Ok((rest, is_true))
+}
+
+/// Parses a whole number with no fraction or exponent, either in
+/// base 10 or with a `0x`, `0o` or `0b` prefix.
+///
+/// The number is stored in the smallest of `i64`, `u64`, `i128`
+/// and `u128` that holds it, and anything beyond that range is
+/// an error rather than a silently truncated float.
+fn parse_integer<'a>(
+    state: &State,
+    input: &'a str,
+) -> IResult<&'a str, Value<'a>> {
+    alt((
+        |input| parse_radix_integer(state, input),
+        parse_decimal_integer,
+    ))(input)
+}
+
+/// Fails (so that `parse_double` gets a chance at the input) if
+/// the digits are followed by a decimal point or an exponent.
+/// The `..` of a range such as `1..5` isn't a decimal point.
+fn parse_decimal_integer(
+    input: &str,
+) -> IResult<&str, Value<'_>> {
+    let (after_digits, (sign, digits)) = terminated(
+        pair(
+            recognize(opt(one_of("+-"))),
+            preceded(peek(digit1), parse_decimal_digits),
+        ),
+        not(alt((
+            recognize(one_of("eE")),
+            parse_decimal_point,
+        ))),
+    )(input)?;
+    let (rest, suffix) = parse_number_suffix(
+        after_digits,
+        NomErrorKind::AlphaNumeric,
+    )?;
+    let number = &input[..input.len() - after_digits.len()];
+    // The sign is only copied in front of the digits when they
+    // had separators to strip
+    let digits = match digits {
+        Cow::Borrowed(_) => Cow::Borrowed(number),
+        Cow::Owned(digits) => {
+            Cow::Owned(format!("{sign}{digits}"))
+        }
+    };
+
+    let integer = match suffix {
+        // `1f32` is a float literal, whose digits are always
+        // valid as a float.
+        #[cfg(feature = "arbitrary-precision")]
+        Some(Suffix::Float) => {
+            Some(Value::RawNumber(Cow::Borrowed(number)))
+        }
+        #[cfg(not(feature = "arbitrary-precision"))]
+        Some(Suffix::Float) => {
+            Some(Value::Float(digits.parse().unwrap()))
+        }
+        Some(Suffix::Int { signed, bits }) => {
+            integer_from_digits(&digits, 10)
+                .filter(|integer| fits_in(integer, signed, bits))
+        }
+        None => integer_from_digits(&digits, 10),
+    };
+    #[cfg(feature = "arbitrary-precision")]
+    let integer = match suffix {
+        None => integer
+            .or(Some(Value::RawNumber(Cow::Borrowed(number)))),
+        Some(_) => integer,
+    };
+
+    integer
+        .map(|integer| (rest, integer))
+        .ok_or_else(|| too_large(input))
+}
+
+/// The type suffix of a numeric literal, such as the `u8` in
+/// `255u8`.
+#[derive(Clone, Copy)]
+enum Suffix {
+    Int { signed: bool, bits: u32 },
+    Float,
+}
+
+/// Parses the optional type suffix right after a number, e.g.
+/// `u8`, `isize` or `f32`, which may follow a single `_` as in
+/// `1_u8`.
+///
+/// Any other identifier characters glued to the number are a
+/// failure of `error_kind`, so that `3q32` is rejected rather
+/// than split into `3` and `q32`.
+fn parse_number_suffix(
+    input: &str,
+    error_kind: NomErrorKind,
+) -> IResult<&str, Option<Suffix>> {
+    let suffix = preceded(
+        opt(nom::character::complete::char('_')),
+        parse_suffix,
+    );
+
+    match opt(suffix)(input)? {
+        (rest, Some(suffix)) => Ok((rest, Some(suffix))),
+        (rest, None) if rest.starts_with(is_identifier_char) => {
+            Err(nom::Err::Failure(nom::error::Error::new(
+                rest, error_kind,
+            )))
+        }
+        (rest, None) => Ok((rest, None)),
+    }
+}
+
+/// Parses a numeric type suffix such as `u8` or `f32`.
+fn parse_suffix(input: &str) -> IResult<&str, Suffix> {
+    let int = |signed, bits| Suffix::Int { signed, bits };
+    alt((
+        value(int(true, 8), parse_keyword("i8")),
+        value(int(true, 16), parse_keyword("i16")),
+        value(int(true, 32), parse_keyword("i32")),
+        value(int(true, 64), parse_keyword("i64")),
+        value(int(true, 128), parse_keyword("i128")),
+        value(int(true, isize::BITS), parse_keyword("isize")),
+        value(int(false, 8), parse_keyword("u8")),
+        value(int(false, 16), parse_keyword("u16")),
+        value(int(false, 32), parse_keyword("u32")),
+        value(int(false, 64), parse_keyword("u64")),
+        value(int(false, 128), parse_keyword("u128")),
+        value(int(false, usize::BITS), parse_keyword("usize")),
+        value(Suffix::Float, parse_keyword("f32")),
+        value(Suffix::Float, parse_keyword("f64")),
+    ))(input)
+}
+
+/// Gives the `_` at the end of `digits`, a run of digits and
+/// separators at the start of `input`, to a suffix right after
+/// it, so that `1_u8` isn't taken for a misplaced separator.
+fn before_suffix<'a>(
+    input: &'a str,
+    digits: &'a str,
+) -> &'a str {
+    match digits.strip_suffix('_') {
+        Some(before)
+            if parse_suffix(&input[digits.len()..]).is_ok() =>
+        {
+            before
+        }
+        _ => digits,
+    }
+}
+
+/// Returns `true` if `integer` is in range for the integer type
+/// with the given signedness and width.
+fn fits_in(
+    integer: &Value<'_>,
+    signed: bool,
+    bits: u32,
+) -> bool {
+    let (is_negative, magnitude) = match *integer {
+        Value::Integer(integer) => {
+            (integer < 0, integer.unsigned_abs().into())
+        }
+        Value::UInt(integer) => (false, integer.into()),
+        Value::BigInt(integer) => {
+            (integer < 0, integer.unsigned_abs())
+        }
+        Value::BigUInt(integer) => (false, integer),
+        _ => return false,
+    };
+
+    let max = match signed {
+        true => u128::MAX >> (129 - bits),
+        false => u128::MAX >> (128 - bits),
+    };
+
+    match (is_negative, signed) {
+        (true, true) => magnitude <= max + 1,
+        (true, false) => false,
+        (false, _) => magnitude <= max,
+    }
+}
+
+/// Parses a run of decimal digits that may be separated by
+/// underscores, as in `1_000_000`, returning the digits without
+/// the separators.
+///
+/// A misplaced underscore (see `strip_digit_separators`) is a
+/// failure pointing at it.
+fn parse_decimal_digits(
+    input: &str,
+) -> IResult<&str, Cow<'_, str>> {
+    let is_digit_or_separator =
+        |ch: char| ch.is_ascii_digit() || ch == '_';
+    let (_, digits) = take_while1(is_digit_or_separator)(input)?;
+    let digits = before_suffix(input, digits);
+    let rest = &input[digits.len()..];
+
+    let digits =
+        strip_digit_separators(digits, |ch| ch.is_ascii_digit())
+            .map_err(|offset| {
+                nom::Err::Failure(nom::error::Error::new(
+                    &input[offset..],
+                    NomErrorKind::Digit,
+                ))
+            })?;
+
+    Ok((rest, digits))
+}
+
+/// Parses a hexadecimal (`0xff`), octal (`0o755`) or binary
+/// (`0b1010`) integer, where the letter after the `0` may be in
+/// either case and the digits may be separated by underscores.
+///
+/// Once the prefix is seen, a missing digit, a digit out of
+/// range for the radix or a misplaced underscore is a failure
+/// pointing at the offending spot.
+fn parse_radix_integer<'a>(
+    state: &State,
+    input: &'a str,
+) -> IResult<&'a str, Value<'a>> {
+    let radix = alt((
+        value((16, NomErrorKind::HexDigit), one_of("xX")),
+        value((8, NomErrorKind::OctDigit), one_of("oO")),
+        value((2, NomErrorKind::Digit), one_of("bB")),
+    ));
+    let (rest, (sign, (radix, error_kind))) = pair(
+        recognize(opt(one_of("+-"))),
+        labelled(
+            state,
+            "a number",
+            preceded(char(state, '0'), radix),
+        ),
+    )(input)?;
+
+    // Octal and binary digits are taken like decimal ones, so
+    // that an out of range digit can be pointed at.
+    let is_digit_or_separator = |ch: char| {
+        let is_digit = match radix {
+            16 => ch.is_ascii_hexdigit(),
+            _ => ch.is_ascii_digit(),
+        };
+
+        is_digit || ch == '_'
+    };
+    let (_, digits) = take_while(is_digit_or_separator)(rest)?;
+    let digits = before_suffix(rest, digits);
+    let after_digits = &rest[digits.len()..];
+
+    let digits =
+        strip_digit_separators(digits, |ch| ch.is_digit(radix))
+            .map_err(|offset| {
+                nom::Err::Failure(nom::error::Error::new(
+                    &rest[offset..],
+                    error_kind,
+                ))
+            })?;
+
+    let (rest, suffix) =
+        parse_number_suffix(after_digits, error_kind)?;
+    let integer = match sign {
+        "" => integer_from_digits(&digits, radix),
+        sign => integer_from_digits(
+            &format!("{sign}{digits}"),
+            radix,
+        ),
+    };
+
+    let integer = match suffix {
+        Some(Suffix::Int { signed, bits }) => integer
+            .filter(|integer| fits_in(integer, signed, bits)),
+        Some(Suffix::Float) => {
+            return Err(nom::Err::Failure(
+                nom::error::Error::new(after_digits, error_kind),
+            ))
+        }
+        None => integer,
+    };
+
+    integer
+        .map(|integer| (rest, integer))
+        .ok_or_else(|| too_large(input))
+}
+
+/// Removes the `_` separators from `digits`, which must be
+/// non-empty and made only of digits accepted by `is_digit`.
+///
+/// An underscore must sit between two digits, so `_1`, `1_` and
+/// `1__0` are rejected. On error, returns the offset of the
+/// offending character (or `0` if there were no digits at all).
+fn strip_digit_separators(
+    digits: &str,
+    is_digit: impl Fn(char) -> bool,
+) -> Result<Cow<'_, str>, usize> {
+    if digits.is_empty() {
+        return Err(0);
+    }
+
+    let mut previous_is_digit = false;
+    for (idx, ch) in digits.char_indices() {
+        let next_is_digit = digits[idx + ch.len_utf8()..]
+            .chars()
+            .next()
+            .is_some_and(&is_digit);
+
+        let is_valid = if ch == '_' {
+            previous_is_digit && next_is_digit
+        } else {
+            is_digit(ch)
+        };
+
+        if !is_valid {
+            return Err(idx);
+        }
+
+        previous_is_digit = ch != '_';
+    }
+
+    if digits.contains('_') {
+        Ok(Cow::Owned(digits.replace('_', "")))
+    } else {
+        Ok(Cow::Borrowed(digits))
+    }
+}
+
+fn too_large(input: &str) -> nom::Err<nom::error::Error<&str>> {
+    nom::Err::Failure(nom::error::Error::new(
+        input,
+        NomErrorKind::TooLarge,
+    ))
+}
+
+fn integer_from_digits(
+    digits: &str,
+    radix: u32,
+) -> Option<Value<'static>> {
+    if let Ok(integer) = i64::from_str_radix(digits, radix) {
+        Some(Value::Integer(integer))
+    } else if let Ok(integer) =
+        u64::from_str_radix(digits, radix)
+    {
+        Some(Value::UInt(integer))
+    } else if let Ok(integer) =
+        i128::from_str_radix(digits, radix)
+    {
+        Some(Value::BigInt(integer))
+    } else if let Ok(integer) =
+        u128::from_str_radix(digits, radix)
+    {
+        Some(Value::BigUInt(integer))
+    } else {
+        None
+    }
+}
+
+/// Fails on the start of a number that Rust wouldn't write, for
+/// [`ParserOptions::strict_numbers`], pointing at the leading
+/// `+` of `+1` or at the `.` of `.5` and `-.5`.
+fn check_strict_number(
+    input: &str,
+) -> Result<(), nom::Err<nom::error::Error<&str>>> {
+    if input.starts_with('+') {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            input,
+            NomErrorKind::Char,
+        )));
+    }
+
+    let unsigned = input.strip_prefix('-').unwrap_or(input);
+    let mut chars = unsigned.chars();
+    if let (Some('.'), Some('0'..='9')) =
+        (chars.next(), chars.next())
+    {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            unsigned,
+            NomErrorKind::Digit,
+        )));
+    }
+
+    Ok(())
+}
+
+/// Parses a float into a `Value::Float`, or into a
+/// `Value::RawNumber` with the `arbitrary-precision` feature
+/// (except for `NaN`, `inf` and `-inf`, which are exact).
+fn parse_float(input: &str) -> IResult<&str, Value<'_>> {
+    #[cfg(feature = "arbitrary-precision")]
+    return alt((
+        recognize_decimal_float.map(|number| {
+            Value::RawNumber(Cow::Borrowed(number))
+        }),
+        parse_special_float.map(Value::Float),
+    ))(input);
+    #[cfg(not(feature = "arbitrary-precision"))]
+    parse_double.map(Value::Float).parse(input)
+}
+
+#[cfg_attr(feature = "arbitrary-precision", allow(dead_code))]
+fn parse_double(input: &str) -> IResult<&str, f64> {
+    alt((parse_decimal_float, parse_special_float))(input)
+}
+
+/// Parses `NaN`, `inf` and `-inf`, spelled as `f64`'s `Debug`
+/// output spells them.
+fn parse_special_float(input: &str) -> IResult<&str, f64> {
+    alt((
+        value(f64::NAN, parse_keyword("NaN")),
+        value(f64::INFINITY, parse_keyword("inf")),
+        value(f64::NEG_INFINITY, parse_keyword("-inf")),
+    ))(input)
+}
+
+/// Returns a parser for `keyword` that refuses to match the
+/// start of a longer identifier, so that `inf` doesn't match
+/// `inferred`.
+fn parse_keyword<'a>(
+    keyword: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(
+        nom::bytes::complete::tag(keyword),
+        not(satisfy(is_identifier_char)),
+    )
+}
+
+/// Parses a `.` that isn't the start of a range's `..`, so that
+/// `1..5` isn't read as `1.` followed by `.5`.
+fn parse_decimal_point(input: &str) -> IResult<&str, &str> {
+    terminated(
+        nom::bytes::complete::tag("."),
+        not(nom::character::complete::char('.')),
+    )(input)
+}
+
+/// Parses a base 10 float such as `3.141_592` or `1_000e1_0`,
+/// whose integer part, fraction and exponent may all have `_`
+/// between their digits.
+#[cfg_attr(feature = "arbitrary-precision", allow(dead_code))]
+fn parse_decimal_float(input: &str) -> IResult<&str, f64> {
+    let (rest, float) = recognize_decimal_float(input)?;
+
+    let float = match float.contains('_') {
+        true => Cow::Owned(float.replace('_', "")),
+        false => Cow::Borrowed(float),
+    };
+
+    match float.parse() {
+        Ok(float) => Ok((rest, float)),
+        Err(_) => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            NomErrorKind::Float,
+        ))),
+    }
+}
+
+/// Recognizes the text of a base 10 float for
+/// `parse_decimal_float`, checking and then skipping its type
+/// suffix, which isn't part of the returned text.
+fn recognize_decimal_float(input: &str) -> IResult<&str, &str> {
+    let integer_part =
+        preceded(peek(digit1), parse_decimal_digits);
+    let mantissa = alt((
+        recognize(pair(
+            integer_part,
+            opt(pair(
+                parse_decimal_point,
+                opt(parse_decimal_digits),
+            )),
+        )),
+        recognize(pair(
+            nom::character::complete::char('.'),
+            parse_decimal_digits,
+        )),
+    ));
+    let exponent = tuple((
+        one_of("eE"),
+        opt(one_of("+-")),
+        cut(parse_decimal_digits),
+    ));
+
+    let (rest, float) = recognize(tuple((
+        opt(one_of("+-")),
+        mantissa,
+        opt(exponent),
+    )))(input)?;
+
+    // Only a float suffix makes sense after a fraction or
+    // exponent
+    let (rest, suffix) =
+        parse_number_suffix(rest, NomErrorKind::AlphaNumeric)?;
+    if let Some(Suffix::Int { .. }) = suffix {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            &input[float.len()..],
+            NomErrorKind::AlphaNumeric,
+        )));
+    }
+
+    Ok((rest, float))
+}
+
+/// Parses a duration as `Duration`'s `Debug` output writes it,
+/// i.e. a number with an optional fraction followed by one of
+/// the units `s`, `ms`, `µs` (or `us`) and `ns`.
+///
+/// The fraction may not be more precise than a nanosecond, so
+/// `1.5ns` is rejected, and durations beyond `Duration::MAX` are
+/// an error.
+#[cfg(feature = "duration")]
+fn parse_duration(
+    input: &str,
+) -> IResult<&str, core::time::Duration> {
+    const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+    let unit = alt((
+        value(NANOS_PER_SEC, parse_keyword("s")),
+        value(1_000_000, parse_keyword("ms")),
+        value(1_000, parse_keyword("µs")),
+        value(1_000, parse_keyword("us")),
+        value(1, parse_keyword("ns")),
+    ));
+    let (rest, (whole, fraction, unit)) = tuple((
+        digit1,
+        opt(preceded(
+            nom::character::complete::char('.'),
+            digit1,
+        )),
+        unit,
+    ))(input)?;
+
+    let fraction = fraction.unwrap_or_default();
+    if fraction.len() > unit.ilog10() as usize {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            NomErrorKind::Float,
+        )));
+    }
+
+    let fraction_nanos = match fraction {
+        "" => 0,
+        fraction => {
+            let scale = unit / 10u128.pow(fraction.len() as u32);
+            fraction.parse::<u128>().unwrap() * scale
+        }
+    };
+    let nanos = whole
+        .parse::<u128>()
+        .ok()
+        .and_then(|whole| whole.checked_mul(unit))
+        .and_then(|nanos| nanos.checked_add(fraction_nanos))
+        .ok_or_else(|| too_large(input))?;
+    let secs = u64::try_from(nanos / NANOS_PER_SEC)
+        .map_err(|_| too_large(input))?;
+
+    Ok((
+        rest,
+        core::time::Duration::new(
+            secs,
+            (nanos % NANOS_PER_SEC) as u32,
+        ),
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{borrow::Cow, collections::HashMap, mem};
+
+    use nom::error::ErrorKind;
+
+    use crate::{
+        display::tests::Generator, fits_in, from_slice,
+        from_slice_partial, from_slice_with, from_str,
+        from_str_partial, parse_boolean, parse_decimal_digits,
+        parse_double, parse_float, parse_identifier,
+        parse_json_value, parse_special_float, parse_value,
+        parse_value_with, parse_ws, strip_digit_separators,
+        take, Fields, IResult, ObjectMap, OwnedValue,
+        ParserOptions, State, Value, ValueKind,
+    };
+
+    // The parsers that take the state of a parse, run as parses
+    // of their own with the default options
+
+    fn state() -> State {
+        State::new(ParserOptions::default(), true)
+    }
+
+    fn parse_byte_string(
+        input: &str,
+    ) -> IResult<&str, Cow<'_, [u8]>> {
+        super::parse_byte_string(&state(), input)
+    }
+
+    fn parse_char(input: &str) -> IResult<&str, char> {
+        super::parse_char(&state(), input)
+    }
+
+    fn parse_ident(input: &str) -> IResult<&str, &str> {
+        super::parse_ident(&state(), input)
+    }
+
+    fn parse_integer(input: &str) -> IResult<&str, Value<'_>> {
+        super::parse_integer(&state(), input)
+    }
+
+    fn parse_raw_string(input: &str) -> IResult<&str, &str> {
+        super::parse_raw_string(&state(), input)
+    }
+
+    fn parse_string(input: &str) -> IResult<&str, Cow<'_, str>> {
+        super::parse_string(&state(), input)
+    }
+
+    /// Parses a value, failing unless `held` returns what it
+    /// holds, so that each kind of value can be tested on its
+    /// own.
+    fn parse_held<'a, T>(
+        input: &'a str,
+        held: impl FnOnce(&mut Value<'a>) -> Option<T>,
+    ) -> IResult<&'a str, T> {
+        let (rest, mut value) = parse_value(input)?;
+        let error =
+            nom::error::Error::new(input, ErrorKind::Verify);
+
+        held(&mut value)
+            .map(|held| (rest, held))
+            .ok_or(nom::Err::Error(error))
+    }
+
+    /// Returns the float that `text` parses as, which with the
+    /// `arbitrary-precision` feature is kept as written.
+    pub(crate) fn float(text: &str) -> Value<'_> {
+        #[cfg(feature = "arbitrary-precision")]
+        return Value::RawNumber(Cow::Borrowed(text));
+        #[cfg(not(feature = "arbitrary-precision"))]
+        Value::Float(text.replace('_', "").parse().unwrap())
+    }
+
+    /// Returns `value` with the numbers that the
+    /// `arbitrary-precision` feature keeps as written read as
+    /// `f64`s, as they are without it, so that floats can be
+    /// compared after a round trip.
+    #[cfg(feature = "arbitrary-precision")]
+    pub(crate) fn with_f64_floats(
+        mut value: Value<'_>,
+    ) -> Value<'_> {
+        fn read(value: &mut Value<'_>) {
+            match value {
+                Value::RawNumber(number) => {
+                    *value = Value::Float(
+                        number.replace('_', "").parse().unwrap(),
+                    )
+                }
+                Value::List(elements)
+                | Value::Tuple(elements)
+                | Value::Set(elements)
+                | Value::TupleStruct {
+                    fields: elements, ..
+                } => elements.iter_mut().for_each(read),
+                Value::Object(object) => {
+                    object.values_mut().for_each(read)
+                }
+                Value::Struct { fields, .. } => fields
+                    .iter_mut()
+                    .for_each(|(_, value)| read(value)),
+                Value::Map(entries) => {
+                    for (key, value) in entries {
+                        read(key);
+                        read(value);
+                    }
+                }
+                Value::Optional(Some(inner))
+                | Value::Result(Ok(inner) | Err(inner)) => {
+                    read(inner)
+                }
+                Value::Range { start, end, .. } => {
+                    start
+                        .iter_mut()
+                        .for_each(|start| read(start));
+                    end.iter_mut().for_each(|end| read(end));
+                }
+                _ => {}
+            }
+        }
+
+        read(&mut value);
+        value
+    }
+
+    #[cfg(not(feature = "arbitrary-precision"))]
+    pub(crate) fn with_f64_floats(
+        value: Value<'_>,
+    ) -> Value<'_> {
+        value
+    }
+
+    fn parse_list(input: &str) -> IResult<&str, Vec<Value<'_>>> {
+        parse_held(input, |value| match value {
+            Value::List(elements) => Some(mem::take(elements)),
+            _ => None,
+        })
+    }
+
+    fn parse_tuple(
+        input: &str,
+    ) -> IResult<&str, Vec<Value<'_>>> {
+        parse_held(input, |value| match value {
+            Value::Tuple(elements) => Some(mem::take(elements)),
+            _ => None,
+        })
+    }
+
+    fn parse_set(input: &str) -> IResult<&str, Vec<Value<'_>>> {
+        parse_held(input, |value| match value {
+            Value::Set(elements) => Some(mem::take(elements)),
+            _ => None,
+        })
+    }
+
+    fn parse_object(
+        input: &str,
+    ) -> IResult<&str, ObjectMap<'_>> {
+        parse_held(input, |value| match value {
+            Value::Object(object) => Some(mem::take(object)),
+            _ => None,
+        })
+    }
+
+    fn parse_map(
+        input: &str,
+    ) -> IResult<&str, Vec<(Value<'_>, Value<'_>)>> {
+        parse_held(input, |value| match value {
+            Value::Map(entries) => Some(mem::take(entries)),
+            _ => None,
+        })
+    }
+
+    fn parse_struct(
+        input: &str,
+    ) -> IResult<&str, (Cow<'_, str>, Fields<'_>, bool)> {
+        parse_held(input, |value| match value {
+            Value::Struct {
+                name,
+                fields,
+                non_exhaustive,
+            } => Some((
+                mem::take(name),
+                mem::take(fields),
+                *non_exhaustive,
+            )),
+            _ => None,
+        })
+    }
+
+    fn parse_tuple_struct(
+        input: &str,
+    ) -> IResult<&str, (Cow<'_, str>, Vec<Value<'_>>)> {
+        parse_held(input, |value| match value {
+            Value::TupleStruct { name, fields } => {
+                Some((mem::take(name), mem::take(fields)))
+            }
+            _ => None,
+        })
+    }
+
+    fn parse_optional(
+        input: &str,
+    ) -> IResult<&str, Option<Box<Value<'_>>>> {
+        parse_held(input, |value| match value {
+            Value::Optional(optional) => Some(optional.take()),
+            _ => None,
+        })
+    }
+
+    fn parse_result(
+        input: &str,
+    ) -> IResult<&str, Result<Box<Value<'_>>, Box<Value<'_>>>>
+    {
+        parse_held(input, |value| match value {
+            Value::Result(result) => Some(
+                result
+                    .as_mut()
+                    .map(|value| Box::new(take(value)))
+                    .map_err(|value| Box::new(take(value))),
+            ),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn parses_doubles() {
+        assert_eq!(parse_double("2.2"), Ok(("", 2.2)));
+        assert_eq!(parse_double("5."), Ok(("", 5.)));
+        assert_eq!(parse_double(".5"), Ok(("", 0.5)));
+        assert_eq!(parse_double("-1.5e3]"), Ok(("]", -1500.)));
+        assert_eq!(parse_double("2E-2"), Ok(("", 0.02)));
+        assert!(parse_double("1e").is_err());
+        assert!(parse_double(".").is_err());
+    }
+
+    #[test]
+    fn parses_integers() {
+        assert_eq!(
+            parse_integer("3"),
+            Ok(("", Value::Integer(3)))
+        );
+        assert_eq!(
+            parse_integer("-7"),
+            Ok(("", Value::Integer(-7)))
+        );
+        assert_eq!(
+            parse_integer("+12]"),
+            Ok(("]", Value::Integer(12)))
+        );
+        assert_eq!(
+            parse_integer("9007199254740993"),
+            Ok(("", Value::Integer(9007199254740993)))
+        );
+
+        assert!(parse_integer("3.0").is_err());
+        assert!(parse_integer("3.").is_err());
+        assert!(parse_integer("1e5").is_err());
+        assert!(parse_integer("-").is_err());
+    }
+
+    #[test]
+    fn parses_wide_integers() {
+        assert_eq!(
+            parse_value("9223372036854775808"),
+            Ok(("", Value::UInt(9223372036854775808)))
+        );
+        assert_eq!(
+            parse_value("18446744073709551615"),
+            Ok(("", Value::UInt(u64::MAX)))
+        );
+        assert_eq!(
+            parse_value("-9223372036854775809"),
+            Ok(("", Value::BigInt(-9223372036854775809)))
+        );
+        assert_eq!(
+            parse_value(&i128::MIN.to_string()),
+            Ok(("", Value::BigInt(i128::MIN)))
+        );
+        assert_eq!(
+            parse_value(&u128::MAX.to_string()),
+            Ok(("", Value::BigUInt(u128::MAX)))
+        );
+
+        // Kept as written with `arbitrary-precision`, see
+        // `keeps_raw_numbers`
+        if !cfg!(feature = "arbitrary-precision") {
+            let too_large =
+                "340282366920938463463374607431768211456";
+            assert_eq!(
+                parse_value(too_large),
+                Err(nom::Err::Failure(nom::error::Error::new(
+                    too_large,
+                    ErrorKind::TooLarge
+                )))
+            );
+        }
+    }
+
+    #[test]
+    fn parses_booleans() {
+        assert_eq!(parse_boolean("true"), Ok(("", true)));
+        assert_eq!(parse_boolean("false"), Ok(("", false)));
+        assert_eq!(
+            parse_boolean("false false"),
+            Ok((" false", false))
+        );
+
+        assert!(parse_boolean("False").is_err());
+        assert!(parse_boolean("True").is_err());
+        assert!(parse_boolean("1").is_err());
+
+        // Identifiers that start with a keyword aren't cut short
+        assert!(parse_boolean("trueX").is_err());
+        assert!(parse_boolean("false_").is_err());
+        assert!(parse_boolean("falsey").is_err());
+        assert_eq!(
+            parse_value("trueX"),
+            Ok(("", Value::Ident("trueX".into())))
+        );
+        assert_eq!(
+            parse_value("[true, false]"),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::Boolean(true),
+                    Value::Boolean(false)
+                ])
+            ))
+        );
+        assert_eq!(
+            parse_value("{true: false}"),
+            Ok((
+                "",
+                Value::Map(vec![(
+                    Value::Boolean(true),
+                    Value::Boolean(false)
+                )])
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_chars() {
+        assert_eq!(parse_char("'a'"), Ok(("", 'a')));
+        assert_eq!(parse_char("'ã'"), Ok(("", 'ã')));
+        assert_eq!(parse_char("'é'"), Ok(("", 'é')));
+        assert_eq!(parse_char("'日', 1"), Ok((", 1", '日')));
+
+        // A char is one char, not some text between quotes
+        let fail = |at, code| {
+            Err(nom::Err::Failure(nom::error::Error::new(
+                at, code,
+            )))
+        };
+        assert_eq!(
+            parse_char("''"),
+            fail("''", ErrorKind::NonEmpty)
+        );
+        assert_eq!(
+            parse_char("'ab'"),
+            fail("b'", ErrorKind::Char)
+        );
+        assert_eq!(
+            parse_char("'é日'"),
+            fail("日'", ErrorKind::Char)
+        );
+        assert_eq!(
+            parse_char("'a"),
+            fail("'a", ErrorKind::TakeUntil)
+        );
+        assert_eq!(
+            parse_char("'a\n'"),
+            fail("'a\n'", ErrorKind::TakeUntil)
+        );
+        assert_eq!(
+            parse_char("'"),
+            fail("'", ErrorKind::TakeUntil)
+        );
+        assert!(parse_char("a").is_err());
+    }
+
+    #[test]
+    fn parses_escaped_chars() {
+        assert_eq!(parse_char(r"'\n'"), Ok(("", '\n')));
+        assert_eq!(parse_char(r"'\r'"), Ok(("", '\r')));
+        assert_eq!(parse_char(r"'\t'"), Ok(("", '\t')));
+        assert_eq!(parse_char(r"'\\'"), Ok(("", '\\')));
+        assert_eq!(parse_char(r"'\''"), Ok(("", '\'')));
+        assert_eq!(parse_char(r#"'\"'"#), Ok(("", '"')));
+        assert_eq!(parse_char(r"'\0'"), Ok(("", '\0')));
+        assert_eq!(parse_char(r"'\x41'"), Ok(("", 'A')));
+        assert_eq!(parse_char(r"'\x7F'"), Ok(("", '\x7f')));
+        assert_eq!(parse_char("'\"'"), Ok(("", '"')));
+
+        assert_eq!(
+            parse_value(r"['\'', '\\']"),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::Char('\''),
+                    Value::Char('\\')
+                ])
+            ))
+        );
+
+        for (char_literal, escape) in [
+            (r"'\q'", r"\q'"),
+            (r"'\x80'", r"\x80'"),
+            (r"'\x4'", r"\x4'"),
+            (r"'\xg1'", r"\xg1'"),
+            (r"'\", r"\"),
+        ] {
+            assert_eq!(
+                parse_char(char_literal),
+                Err(nom::Err::Failure(nom::error::Error::new(
+                    escape,
+                    ErrorKind::Escaped
+                )))
+            );
+        }
+
+        assert!(parse_char(r"'\n").is_err());
+        assert!(parse_char("'''").is_err());
+    }
+
+    #[test]
+    fn parses_unicode_escapes() {
+        assert_eq!(parse_char(r"'\u{1f600}'"), Ok(("", '😀')));
+        assert_eq!(parse_char(r"'\u{41}'"), Ok(("", 'A')));
+        assert_eq!(parse_char(r"'\u{0}'"), Ok(("", '\0')));
+        assert_eq!(parse_char(r"'\u{00E9}'"), Ok(("", 'é')));
+        assert_eq!(
+            parse_char(r"'\u{10FFFF}'"),
+            Ok(("", '\u{10ffff}'))
+        );
+
+        for (char_literal, escape) in [
+            (r"'\u{110000}'", r"\u{110000}'"),
+            (r"'\u{d800}'", r"\u{d800}'"),
+            (r"'\u{dfff}'", r"\u{dfff}'"),
+            (r"'\u{}'", r"\u{}'"),
+            (r"'\u{1234567}'", r"\u{1234567}'"),
+            (r"'\u{12g}'", r"\u{12g}'"),
+            (r"'\u{41'", r"\u{41'"),
+            (r"'\u41'", r"\u41'"),
+        ] {
+            assert_eq!(
+                parse_char(char_literal),
+                Err(nom::Err::Failure(nom::error::Error::new(
+                    escape,
+                    ErrorKind::Escaped
+                )))
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_char_debug_output() {
+        for chr in [
+            'a',
+            '\'',
+            '"',
+            '\\',
+            '\n',
+            '\r',
+            '\t',
+            '\0',
+            '\x07',
+            '\x7f',
+            '\u{200b}',
+            '\u{feff}',
+            '\u{e000}',
+            'é',
+            '😀',
+            '\u{10ffff}',
+        ] {
+            let debug = format!("{chr:?}");
+            assert_eq!(
+                parse_char(&debug),
+                Ok(("", chr)),
+                "{debug}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_option() {
+        assert_eq!(
+            parse_optional("Some(2)"),
+            Ok(("", Some(Box::new(Value::Integer(2)))))
+        );
+
+        assert_eq!(
+            parse_optional("Some('a')"),
+            Ok(("", Some(Box::new(Value::Char('a')))))
+        );
+
+        assert_eq!(
+            parse_optional("Some(\"hey\")"),
+            Ok((
+                "",
+                Some(Box::new(Value::String("hey".into())))
+            ))
+        );
+
+        assert_eq!(
+            parse_optional("Some (2)"),
+            parse_optional("Some(2)")
+        );
+
+        assert_eq!(parse_optional("None"), Ok(("", None)));
+        assert_eq!(parse_optional("None)"), Ok((")", None)));
+
+        assert!(parse_optional("None1").is_err());
+        assert!(parse_optional("Nonetheless").is_err());
+        assert!(parse_optional("Something(1)").is_err());
+        assert_eq!(
+            parse_value("None1"),
+            Ok(("", Value::Ident("None1".into())))
+        );
+        assert_eq!(
+            parse_value("Something(1)"),
+            Ok((
+                "",
+                Value::TupleStruct {
+                    name: "Something".into(),
+                    fields: vec![Value::Integer(1)]
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_strings() {
+        assert_eq!(
+            parse_string("\"hey\""),
+            Ok(("", "hey".into()))
+        );
+        assert_eq!(
+            parse_string("\"2 * 2\""),
+            Ok(("", "2 * 2".into()))
+        );
+
+        assert_eq!(
+            parse_value("  \"ignores leading whitespace\""),
+            Ok((
+                "",
+                Value::String(
+                    "ignores leading whitespace".into()
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_escaped_strings() {
+        assert_eq!(
+            parse_string(r#""she said \"hi\"""#),
+            Ok(("", r#"she said "hi""#.into()))
+        );
+        assert_eq!(
+            parse_string(r#""C:\\temp\\""#),
+            Ok(("", r"C:\temp\".into()))
+        );
+        assert_eq!(
+            parse_string(r#""a\nb\rc\td\0e\'f""#),
+            Ok(("", "a\nb\rc\td\0e'f".into()))
+        );
+        assert_eq!(
+            parse_string(r#""\x41\u{1f600}\u{e9}""#),
+            Ok(("", "A😀é".into()))
+        );
+        assert_eq!(
+            parse_string(r#""\"", 1"#),
+            Ok((", 1", "\"".into()))
+        );
+
+        assert_eq!(
+            parse_value(r#"["\"", "\\"]"#),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::String("\"".into()),
+                    Value::String("\\".into()),
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn borrows_strings_without_escapes() {
+        let input = r#"{"key": ["plain", r"raw\n", "esc\n"]}"#;
+        let Ok(("", Value::Object(ref object))) =
+            parse_value(input)
+        else {
+            panic!("expected an object");
+        };
+
+        let borrows_from_input = |string: &str| {
+            input
+                .as_bytes()
+                .as_ptr_range()
+                .contains(&string.as_ptr())
+        };
+
+        let (key, value) = object.iter().next().unwrap();
+        assert!(
+            matches!(key, Cow::Borrowed(key) if borrows_from_input(key))
+        );
+
+        let Value::List(strings) = value else {
+            panic!("expected a list");
+        };
+        let [Value::String(plain), Value::String(raw), Value::String(escaped)] =
+            strings.as_slice()
+        else {
+            panic!("expected three strings");
+        };
+
+        assert!(
+            matches!(plain, Cow::Borrowed(plain) if borrows_from_input(plain))
+        );
+        assert!(
+            matches!(raw, Cow::Borrowed(raw) if borrows_from_input(raw))
+        );
+        assert!(matches!(escaped, Cow::Owned(_)));
+        assert_eq!(escaped, "esc\n");
+    }
+
+    #[test]
+    fn rejects_invalid_strings() {
+        let failure = |input, kind| {
+            Err(nom::Err::Failure(nom::error::Error::new(
+                input, kind,
+            )))
+        };
+
+        assert_eq!(
+            parse_string(r#""bad \q escape""#),
+            failure(r#"\q escape""#, ErrorKind::Escaped)
+        );
+        assert_eq!(
+            parse_string(r#""\u{d800}""#),
+            failure(r#"\u{d800}""#, ErrorKind::Escaped)
+        );
+        assert_eq!(
+            parse_string(r#""trailing \"#),
+            failure(r"\", ErrorKind::Escaped)
+        );
+        assert_eq!(
+            parse_string(r#""never closed"#),
+            failure(r#""never closed"#, ErrorKind::TakeUntil)
+        );
+        assert_eq!(
+            parse_string(r#""escaped end\""#),
+            failure(r#""escaped end\""#, ErrorKind::TakeUntil)
+        );
+    }
+
+    #[test]
+    fn round_trips_string_debug_output() {
+        let strings = [
+            "",
+            "plain",
+            "quote \" and backslash \\",
+            "new\nline, tab\t, return\r, nul\0",
+            "bell \x07 and delete \x7f",
+            "zero\u{200b}width and bom \u{feff}",
+            "emoji 😀🎉 and accents éàü",
+            "combining e\u{301}",
+        ];
+
+        for string in strings {
+            let debug = format!("{string:?}");
+            assert_eq!(
+                parse_string(&debug),
+                Ok(("", string.into())),
+                "{debug}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_values() {
+        assert_eq!(
+            parse_value("'B'"),
+            Ok(("", Value::Char('B')))
+        );
+        assert_eq!(
+            parse_value("\"this is a test\""),
+            Ok(("", Value::String("this is a test".into())))
+        );
+    }
+
+    #[test]
+    fn parses_lists() {
+        assert_eq!(parse_list("[]"), Ok(("", vec![])));
+
+        assert_eq!(
+            parse_list("['A']"),
+            Ok(("", vec![Value::Char('A')]))
+        );
+
+        assert_eq!(
+            parse_list("['z', 5]"),
+            Ok(("", vec![Value::Char('z'), Value::Integer(5)]))
+        );
+
+        assert_eq!(
+            parse_list("['f', 2.2, \"a string\"]"),
+            Ok((
+                "",
+                vec![
+                    Value::Char('f'),
+                    float("2.2"),
+                    Value::String("a string".into()),
+                ]
+            ))
+        );
+
+        assert_eq!(
+            parse_list("[1, 2.0, -7]"),
+            Ok((
+                "",
+                vec![
+                    Value::Integer(1),
+                    float("2.0"),
+                    Value::Integer(-7),
+                ]
+            ))
+        );
+
+        assert_eq!(
+            parse_list("[[]]"),
+            Ok(("", vec![Value::List(vec![])]))
+        );
+    }
+
+    #[test]
+    fn parses_tuples() {
+        assert_eq!(parse_tuple("()"), Ok(("", vec![])));
+        assert_eq!(parse_tuple("( )"), Ok(("", vec![])));
+
+        assert_eq!(
+            parse_tuple("(1,)"),
+            Ok(("", vec![Value::Integer(1)]))
+        );
+
+        assert_eq!(
+            parse_tuple("(1, 'a', \"x\")"),
+            Ok((
+                "",
+                vec![
+                    Value::Integer(1),
+                    Value::Char('a'),
+                    Value::String("x".into()),
+                ]
+            ))
+        );
+
+        assert_eq!(
+            parse_tuple("((1, 2), ())"),
+            Ok((
+                "",
+                vec![
+                    Value::Tuple(vec![
+                        Value::Integer(1),
+                        Value::Integer(2),
+                    ]),
+                    Value::Tuple(vec![]),
+                ]
+            ))
+        );
+
+        assert!(parse_tuple("(,)").is_err());
+        assert!(parse_tuple("(1)").is_err());
+        assert!(parse_tuple("( 1 )").is_err());
+        assert!(parse_tuple("(1,,)").is_err());
+        assert!(parse_tuple("(1, 2").is_err());
+    }
+
+    #[test]
+    fn parses_tuples_inside_other_values() {
+        assert_eq!(
+            parse_value("Some((\"a\", 2))"),
+            Ok((
+                "",
+                Value::Optional(Some(Box::new(Value::Tuple(
+                    vec![
+                        Value::String("a".into()),
+                        Value::Integer(2),
+                    ]
+                ))))
+            ))
+        );
+
+        assert_eq!(
+            parse_value("[(1, 2) , (3, 4)]"),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::Tuple(vec![
+                        Value::Integer(1),
+                        Value::Integer(2),
+                    ]),
+                    Value::Tuple(vec![
+                        Value::Integer(3),
+                        Value::Integer(4),
+                    ]),
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_identifiers() {
+        assert_eq!(
+            parse_identifier("Point {"),
+            Ok((" {", "Point"))
+        );
+        assert_eq!(parse_identifier("_x1"), Ok(("", "_x1")));
+        assert!(parse_identifier("1x").is_err());
+    }
+
+    #[test]
+    fn parses_structs() {
+        assert_eq!(
+            parse_struct("Point { x: 1.5, y: 2 }"),
+            Ok((
+                "",
+                (
+                    "Point".into(),
+                    vec![
+                        ("x".into(), float("1.5")),
+                        ("y".into(), Value::Integer(2)),
+                    ],
+                    false
+                )
+            ))
+        );
+
+        // Field order is kept as written
+        assert_eq!(
+            parse_struct("P { y: 'b', x: 'a' }"),
+            Ok((
+                "",
+                (
+                    "P".into(),
+                    vec![
+                        ("y".into(), Value::Char('b')),
+                        ("x".into(), Value::Char('a')),
+                    ],
+                    false
+                )
+            ))
+        );
+
+        assert_eq!(
+            parse_struct("Foo {}"),
+            Ok(("", ("Foo".into(), vec![], false)))
+        );
+
+        assert!(parse_struct("Foo").is_err());
+        assert!(parse_struct("Foo { x }").is_err());
+        assert!(parse_struct("Foo { x: 1").is_err());
+        assert!(parse_struct("Foo { x: 1,, y: 2 }").is_err());
+    }
+
+    #[test]
+    fn parses_non_exhaustive_structs() {
+        assert_eq!(
+            parse_struct("Foo { a: 1, .. }"),
+            Ok((
+                "",
+                (
+                    "Foo".into(),
+                    vec![("a".into(), Value::Integer(1))],
+                    true
+                )
+            ))
+        );
+        assert_eq!(
+            parse_struct("Foo {\n    a: 1,\n    ..\n}"),
+            Ok((
+                "",
+                (
+                    "Foo".into(),
+                    vec![("a".into(), Value::Integer(1))],
+                    true
+                )
+            ))
+        );
+        assert_eq!(
+            parse_struct("Foo { .., }"),
+            Ok(("", ("Foo".into(), vec![], true)))
+        );
+
+        struct Wrapper;
+
+        impl std::fmt::Debug for Wrapper {
+            fn fmt(
+                &self,
+                f: &mut std::fmt::Formatter,
+            ) -> std::fmt::Result {
+                f.debug_struct("Wrapper")
+                    .field("name", &"x")
+                    .finish_non_exhaustive()
+            }
+        }
+
+        assert_eq!(
+            parse_value(&format!("{:?}", Wrapper)),
+            Ok((
+                "",
+                Value::Struct {
+                    name: "Wrapper".into(),
+                    fields: vec![(
+                        "name".into(),
+                        Value::String("x".into())
+                    )],
+                    non_exhaustive: true,
+                }
+            ))
+        );
+        assert_eq!(
+            parse_value(&format!("{:#?}", Wrapper)),
+            parse_value(&format!("{:?}", Wrapper)),
+        );
+
+        assert!(parse_struct("Foo { .., a: 1 }").is_err());
+        assert!(parse_struct("Foo { a: 1, .., b: 2 }").is_err());
+        assert!(parse_struct("Foo { a: 1, .., .. }").is_err());
+        assert!(parse_struct("Foo { ... }").is_err());
+    }
+
+    #[test]
+    fn parses_nested_structs() {
+        let line = |start, end| Value::Struct {
+            name: "Line".into(),
+            fields: vec![
+                ("start".into(), start),
+                ("end".into(), end),
+            ],
+            non_exhaustive: false,
+        };
+        let point = |x, y| Value::Struct {
+            name: "Point".into(),
+            fields: vec![
+                ("x".into(), Value::Integer(x)),
+                ("y".into(), Value::Integer(y)),
+            ],
+            non_exhaustive: false,
+        };
+
+        assert_eq!(
+            parse_value(
+                "Line { start: Point { x: 0, y: 0 }, end: Point { x: \
+                 1, y: 2 } }"
+            ),
+            Ok(("", line(point(0, 0), point(1, 2))))
+        );
+
+        assert_eq!(
+            parse_value(
+                "[Point { x: 0, y: 0 }, Point { x: 3, y: 4 }]"
+            ),
+            Ok((
+                "",
+                Value::List(vec![point(0, 0), point(3, 4)])
+            ))
+        );
+
+        assert_eq!(
+            parse_value("Some(Point { x: 5, y: 6 })"),
+            Ok((
+                "",
+                Value::Optional(Some(Box::new(point(5, 6))))
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_tuple_structs() {
+        assert_eq!(
+            parse_tuple_struct("Point(1, 2)"),
+            Ok((
+                "",
+                (
+                    "Point".into(),
+                    vec![Value::Integer(1), Value::Integer(2)]
+                )
+            ))
+        );
+        assert_eq!(
+            parse_tuple_struct("Wrapper(\"x\")"),
+            Ok((
+                "",
+                (
+                    "Wrapper".into(),
+                    vec![Value::String("x".into())]
+                )
+            ))
+        );
+        assert_eq!(
+            parse_tuple_struct("Marker()"),
+            Ok(("", ("Marker".into(), vec![])))
+        );
+
+        // Whitespace before the `(` is skipped, as it is before
+        // a struct's `{`
+        assert_eq!(
+            parse_tuple_struct("Point /* x, y */ (1, 2)"),
+            parse_tuple_struct("Point(1, 2)")
+        );
+        assert!(parse_tuple_struct("Point(1, 2").is_err());
+        assert!(parse_tuple_struct("Some(1, 2)").is_err());
+    }
+
+    #[test]
+    fn parses_enum_variants() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        enum Shape {
+            Empty,
+            Move(i32, i32),
+            Circle { radius: i32 },
+        }
+
+        let shapes = [
+            Shape::Empty,
+            Shape::Move(1, 2),
+            Shape::Circle { radius: 3 },
+        ];
+        assert_eq!(
+            parse_value(&format!("{shapes:?}")),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::Ident("Empty".into()),
+                    Value::TupleStruct {
+                        name: "Move".into(),
+                        fields: vec![
+                            Value::Integer(1),
+                            Value::Integer(2)
+                        ],
+                    },
+                    Value::Struct {
+                        name: "Circle".into(),
+                        fields: vec![(
+                            "radius".into(),
+                            Value::Integer(3)
+                        )],
+                        non_exhaustive: false,
+                    },
+                ])
+            ))
+        );
+
+        assert_eq!(
+            parse_value(
+                "shape::Shape::Circle { radius: 1, .. }"
+            ),
+            Ok((
+                "",
+                Value::Struct {
+                    name: "shape::Shape::Circle".into(),
+                    fields: vec![(
+                        "radius".into(),
+                        Value::Integer(1)
+                    )],
+                    non_exhaustive: true,
+                }
+            ))
+        );
+        assert_eq!(
+            parse_value("Shape::Move(1, 2)"),
+            Ok((
+                "",
+                Value::TupleStruct {
+                    name: "Shape::Move".into(),
+                    fields: vec![
+                        Value::Integer(1),
+                        Value::Integer(2)
+                    ],
+                }
+            ))
+        );
+        assert_eq!(
+            parse_value("Shape::Empty"),
+            Ok(("", Value::Ident("Shape::Empty".into())))
+        );
+
+        // Only the bare keywords are special
+        assert_eq!(
+            parse_value("Some(Ok(1))"),
+            Ok((
+                "",
+                Value::Optional(Some(Box::new(Value::Result(
+                    Ok(Box::new(Value::Integer(1)))
+                ))))
+            ))
+        );
+        assert_eq!(
+            parse_value("Option::Some(1)"),
+            Ok((
+                "",
+                Value::TupleStruct {
+                    name: "Option::Some".into(),
+                    fields: vec![Value::Integer(1)],
+                }
+            ))
+        );
+
+        assert!(parse_struct("Shape:: { radius: 1 }").is_err());
+        assert!(parse_tuple_struct("Shape::(1)").is_err());
+    }
+
+    #[test]
+    fn parses_nested_tuple_structs() {
+        assert_eq!(
+            parse_value("Outer(Inner(1), [2, 3])"),
+            Ok((
+                "",
+                Value::TupleStruct {
+                    name: "Outer".into(),
+                    fields: vec![
+                        Value::TupleStruct {
+                            name: "Inner".into(),
+                            fields: vec![Value::Integer(1)],
+                        },
+                        Value::List(vec![
+                            Value::Integer(2),
+                            Value::Integer(3),
+                        ]),
+                    ],
+                }
+            ))
+        );
+
+        // `Some` keeps meaning `Optional`
+        assert_eq!(
+            parse_value("Some(NonZeroU32(5))"),
+            Ok((
+                "",
+                Value::Optional(Some(Box::new(
+                    Value::TupleStruct {
+                        name: "NonZeroU32".into(),
+                        fields: vec![Value::Integer(5)],
+                    }
+                )))
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_idents() {
+        assert_eq!(parse_ident("Red"), Ok(("", "Red")));
+        assert_eq!(
+            parse_ident("Ordering::Less]"),
+            Ok(("]", "Ordering::Less"))
+        );
+        assert_eq!(
+            parse_ident("std::cmp::Ordering::Less"),
+            Ok(("", "std::cmp::Ordering::Less"))
+        );
+        assert_eq!(parse_ident("Foo::"), Ok(("::", "Foo")));
+        assert!(parse_ident("::Foo").is_err());
+        assert!(parse_ident("1st").is_err());
+    }
+
+    #[test]
+    fn parses_idents_inside_other_values() {
+        assert_eq!(
+            parse_value("[Red, Green]"),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::Ident("Red".into()),
+                    Value::Ident("Green".into()),
+                ])
+            ))
+        );
+        assert_eq!(
+            parse_value("Some(Blue)"),
+            Ok((
+                "",
+                Value::Optional(Some(Box::new(Value::Ident(
+                    "Blue".into()
+                ))))
+            ))
+        );
+        assert_eq!(
+            parse_value("Foo"),
+            Ok(("", Value::Ident("Foo".into())))
+        );
+
+        // Keywords keep their meaning
+        assert_eq!(
+            parse_value("true"),
+            Ok(("", Value::Boolean(true)))
+        );
+        assert_eq!(
+            parse_value("None"),
+            Ok(("", Value::Optional(None)))
+        );
+    }
+
+    #[test]
+    fn parses_objects() {
+        assert_eq!(
+            parse_object("{}"),
+            Ok(("", ObjectMap::new()))
+        );
+
+        assert_eq!(
+            parse_object("{\"a\": 1, \"b\": 'x' }"),
+            Ok((
+                "",
+                ObjectMap::from([
+                    ("a".into(), Value::Integer(1)),
+                    ("b".into(), Value::Char('x')),
+                ])
+            ))
+        );
+
+        assert!(parse_object("{1: 2}").is_err());
+
+        // The last value of a key wins
+        assert_eq!(
+            parse_object("{\"a\": 1, \"b\": 2, \"a\": 3}"),
+            Ok((
+                "",
+                ObjectMap::from([
+                    ("a".into(), Value::Integer(3)),
+                    ("b".into(), Value::Integer(2)),
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_maps() {
+        assert_eq!(
+            parse_map("{1: 'x', 2: 'y'}"),
+            Ok((
+                "",
+                vec![
+                    (Value::Integer(1), Value::Char('x')),
+                    (Value::Integer(2), Value::Char('y')),
+                ]
+            ))
+        );
+
+        assert_eq!(
+            parse_value("{\"a\": 1, 2: 3}"),
+            Ok((
+                "",
+                Value::Map(vec![
+                    (
+                        Value::String("a".into()),
+                        Value::Integer(1)
+                    ),
+                    (Value::Integer(2), Value::Integer(3)),
+                ])
+            ))
+        );
+
+        // Only string keys means an object, and so does `{}`
+        assert!(matches!(
+            parse_value("{\"a\": 1}"),
+            Ok(("", Value::Object(_)))
+        ));
+        assert_eq!(
+            parse_value("{}"),
+            Ok(("", Value::Object(ObjectMap::new())))
+        );
+
+        // Every entry is kept, in order
+        assert_eq!(
+            parse_map("{2: 'x', 1: 'y', 2: 'z'}"),
+            Ok((
+                "",
+                vec![
+                    (Value::Integer(2), Value::Char('x')),
+                    (Value::Integer(1), Value::Char('y')),
+                    (Value::Integer(2), Value::Char('z')),
+                ]
+            ))
+        );
+
+        assert!(parse_map("{1: 2,,}").is_err());
+        assert!(parse_map("{1 2}").is_err());
+        assert!(parse_map("{1: 2").is_err());
+    }
+
+    #[test]
+    fn parses_nested_maps() {
+        let map = |key, value| Value::Map(vec![(key, value)]);
+
+        assert_eq!(
+            parse_value("[{1: 2}, Some({(0, 0): {'a': None}})]"),
+            Ok((
+                "",
+                Value::List(vec![
+                    map(Value::Integer(1), Value::Integer(2)),
+                    Value::Optional(Some(Box::new(map(
+                        Value::Tuple(vec![
+                            Value::Integer(0),
+                            Value::Integer(0),
+                        ]),
+                        map(
+                            Value::Char('a'),
+                            Value::Optional(None)
+                        ),
+                    )))),
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_sets() {
+        assert_eq!(
+            parse_set("{1, 2, 3}"),
+            Ok((
+                "",
+                vec![
+                    Value::Integer(1),
+                    Value::Integer(2),
+                    Value::Integer(3),
+                ]
+            ))
+        );
+
+        assert_eq!(
+            parse_value("{\"b\", \"a\" \n}"),
+            Ok((
+                "",
+                Value::Set(vec![
+                    Value::String("b".into()),
+                    Value::String("a".into())
+                ])
+            ))
+        );
+
+        assert_eq!(
+            parse_value("{{1}, {}}"),
+            Ok((
+                "",
+                Value::Set(vec![
+                    Value::Set(vec![Value::Integer(1)]),
+                    Value::Object(ObjectMap::new()),
+                ])
+            ))
+        );
+
+        assert!(parse_set("{}").is_err());
+        assert!(parse_set("{,}").is_err());
+        assert!(parse_value("{1: 2, 3}").is_err());
+        assert!(parse_value("{1, 2: 3}").is_err());
+    }
+
+    #[test]
+    fn compares_sets_regardless_of_order() {
+        let set = |input| parse_value(input).unwrap().1;
+
+        assert!(set("{1, 2, 3}").set_eq(&set("{3, 1, 2}")));
+        assert!(set("{1, 1, 2}").set_eq(&set("{1, 2, 1}")));
+        assert!(!set("{1, 1, 2}").set_eq(&set("{1, 2, 2}")));
+        assert!(!set("{1, 2}").set_eq(&set("{1, 2, 3}")));
+
+        // Anything else compares as usual
+        assert!(set("[1, 2]").set_eq(&set("[1, 2]")));
+        assert!(!set("[1, 2]").set_eq(&set("[2, 1]")));
+    }
+
+    #[test]
+    fn accesses_values_of_each_kind() {
+        let texts = [
+            "1",
+            "18446744073709551615",
+            "-170141183460469231731687303715884105728",
+            "340282366920938463463374607431768211455",
+            "1.5",
+            "true",
+            "\"a\"",
+            "'c'",
+            "b\"x\"",
+            "[1]",
+            "[-1]",
+            "(1, 2)",
+            "Some(1)",
+            "None",
+            "Ok(1)",
+            "Err(1)",
+            "{\"a\": 1}",
+            "P { x: 1 }",
+            "P(1)",
+            "{1: 2}",
+            "{1}",
+            "1..2",
+            "Red",
+        ];
+
+        for text in texts {
+            let value = from_str(text).unwrap();
+            let accessors = [
+                ("as_f64", value.as_f64().is_some()),
+                ("as_i64", value.as_i64().is_some()),
+                ("as_u64", value.as_u64().is_some()),
+                ("as_bool", value.as_bool().is_some()),
+                ("as_str", value.as_str().is_some()),
+                ("as_char", value.as_char().is_some()),
+                ("as_ident", value.as_ident().is_some()),
+                ("as_bytes", value.as_bytes().is_some()),
+                ("as_list", value.as_list().is_some()),
+                ("as_tuple", value.as_tuple().is_some()),
+                ("as_set", value.as_set().is_some()),
+                ("as_map", value.as_map().is_some()),
+                ("as_object", value.as_object().is_some()),
+                ("as_option", value.as_option().is_some()),
+                ("as_ok", value.as_ok().is_some()),
+                ("as_err", value.as_err().is_some()),
+                ("is_number", value.is_number()),
+                ("is_string", value.is_string()),
+                ("is_list", value.is_list()),
+                ("is_none", value.is_none()),
+                ("is_some", value.is_some()),
+                ("is_ok", value.is_ok()),
+                ("is_err", value.is_err()),
+            ];
+            let found: Vec<_> = accessors
+                .into_iter()
+                .filter(|&(_, some)| some)
+                .map(|(name, _)| name)
+                .collect();
+
+            // Every kind is listed, so that a new one can't be
+            // added without deciding which of these it answers
+            let expected: &[&str] = match value.kind() {
+                ValueKind::Integer => {
+                    &["as_f64", "as_i64", "as_u64", "is_number"]
+                }
+                ValueKind::UInt => {
+                    &["as_f64", "as_u64", "is_number"]
+                }
+                ValueKind::BigInt | ValueKind::BigUInt => {
+                    &["as_f64", "is_number"]
+                }
+                ValueKind::Float => &["as_f64", "is_number"],
+                #[cfg(feature = "arbitrary-precision")]
+                ValueKind::RawNumber => &["as_f64", "is_number"],
+                ValueKind::Boolean => &["as_bool"],
+                ValueKind::String => &["as_str", "is_string"],
+                ValueKind::Char => &["as_char"],
+                ValueKind::Bytes => &["as_bytes"],
+                ValueKind::List if text == "[1]" => {
+                    &["as_bytes", "as_list", "is_list"]
+                }
+                ValueKind::List => &["as_list", "is_list"],
+                ValueKind::Tuple => &["as_tuple"],
+                ValueKind::Optional if value.is_none() => {
+                    &["as_option", "is_none"]
+                }
+                ValueKind::Optional => &["as_option", "is_some"],
+                ValueKind::Result if value.is_ok() => {
+                    &["as_ok", "is_ok"]
+                }
+                ValueKind::Result => &["as_err", "is_err"],
+                ValueKind::Object => &["as_object"],
+                ValueKind::Struct
+                | ValueKind::TupleStruct
+                | ValueKind::Range => &[],
+                ValueKind::Map => &["as_map"],
+                ValueKind::Set => &["as_set"],
+                #[cfg(feature = "duration")]
+                ValueKind::Duration => &[],
+                #[cfg(feature = "datetime")]
+                ValueKind::DateTime => &[],
+                #[cfg(feature = "uuid")]
+                ValueKind::Uuid => &[],
+                #[cfg(feature = "net")]
+                ValueKind::IpAddr | ValueKind::SocketAddr => &[],
+                ValueKind::Ident => &["as_ident"],
+            };
+            assert_eq!(found, expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn returns_what_values_hold() {
+        let value = |text| from_str(text).unwrap();
+
+        assert_eq!(value("false").as_bool(), Some(false));
+        assert_eq!(value("\"a\\tb\"").as_str(), Some("a\tb"));
+        assert_eq!(value("'\\n'").as_char(), Some('\n'));
+        assert_eq!(value("a::B").as_ident(), Some("a::B"));
+        assert_eq!(value("-1").as_u64(), None);
+        assert_eq!(value("7").as_u64(), Some(7));
+        assert_eq!(
+            value("[1, 2]").as_list(),
+            Some(&[Value::Integer(1), Value::Integer(2)][..])
+        );
+        assert_eq!(value("()").as_tuple(), Some(&[][..]));
+        assert_eq!(
+            value("Some(1)").as_option(),
+            Some(Some(&Value::Integer(1)))
+        );
+        assert_eq!(value("None").as_option(), Some(None));
+
+        // Near misses are still other kinds
+        assert_eq!(value("\"true\"").as_bool(), None);
+        assert_eq!(value("'a'").as_str(), None);
+        assert_eq!(value("\"a\"").as_char(), None);
+        assert_eq!(value("(1,)").as_list(), None);
+        assert_eq!(value("[1]").as_tuple(), None);
+        assert_eq!(value("{}").as_set(), None);
+        assert_eq!(value("{}").as_map(), None);
+        assert_eq!(value("null").as_option(), None);
+        assert!(!value("null").is_none());
+        assert_eq!(value("\"Red\"").as_ident(), None);
+    }
+
+    #[test]
+    fn names_kinds_of_values() {
+        let name = |text| from_str(text).unwrap().type_name();
+
+        assert_eq!(name("1"), "an integer");
+        assert_eq!(name("18446744073709551615"), "an integer");
+        assert_eq!(name("1.5"), "a float");
+        assert_eq!(name("\"a\""), "a string");
+        assert_eq!(name("P(1)"), "a tuple struct");
+        assert_eq!(name("Red"), "an identifier");
+        assert_eq!(
+            from_str("0..1").unwrap().kind(),
+            ValueKind::Range
+        );
+    }
+
+    #[test]
+    fn indexes_into_values() {
+        let value = from_str(
+            "Config { retries: 3, hosts: [\"a\", \"b\"], \
+             limits: {\"cpu\": 1.5}, ports: {\"http\": 80, 1: 2}, \
+             pair: (1, Point(2, 3)), tags: {x, y}, \
+             retries: 4 }",
+        )
+        .unwrap();
+
+        assert_eq!(value["retries"].as_i64(), Some(3));
+        assert_eq!(value["hosts"][1].as_str(), Some("b"));
+        assert_eq!(value["limits"]["cpu"].as_f64(), Some(1.5));
+        assert_eq!(value["ports"]["http"].as_i64(), Some(80));
+        assert_eq!(value["pair"][1][0].as_i64(), Some(2));
+        assert_eq!(value["tags"][1].as_ident(), Some("y"));
+
+        assert_eq!(
+            value.get_key("hosts"),
+            Some(&value["hosts"])
+        );
+        assert_eq!(
+            value["hosts"].get(0),
+            Some(&value["hosts"][0])
+        );
+    }
+
+    #[test]
+    fn indexes_missing_values_as_none() {
+        let value = from_str(
+            "{\"list\": [1], \"none\": None, \"number\": 1}",
+        )
+        .unwrap();
+        let missing = Value::Optional(None);
+
+        assert_eq!(value["nope"], missing);
+        assert_eq!(value["list"][1], missing);
+        assert_eq!(value["list"][usize::MAX], missing);
+        assert_eq!(value["nope"]["deeper"][0], missing);
+        assert_eq!(value["nope"]["retries"].as_f64(), None);
+        // Scalars have nothing in them
+        assert_eq!(value["number"][0], missing);
+        assert_eq!(value["number"]["key"], missing);
+        // Lists have no keys and objects no positions
+        assert_eq!(value["list"]["0"], missing);
+        assert_eq!(value[0], missing);
+
+        // A missing value looks like a `None` that is there,
+        // which only `get` and `get_key` tell apart
+        assert_eq!(value["none"], value["nope"]);
+        assert_eq!(value.get_key("none"), Some(&missing));
+        assert_eq!(value.get_key("nope"), None);
+        assert_eq!(value["list"].get(1), None);
+        assert_eq!(value["number"].get(0), None);
+        assert_eq!(
+            from_str("{1: 2}").unwrap().get_key("1"),
+            None
+        );
+    }
+
+    #[test]
+    fn owns_values_that_outlive_the_input() {
+        fn parse(text: String) -> OwnedValue {
+            from_str(&text).unwrap().into_owned()
+        }
+
+        let text = "Config { name: \"tson\", raw: b\"\\x00\", \
+                    tags: {\"a\": [Some(Red), Err(x::Y)]}, \
+                    span: ..=1.5, pair: Point(1, 'c'), \
+                    keys: {(): {b}} }";
+        let owned = parse(text.to_owned());
+        assert_eq!(owned, from_str(text).unwrap());
+        assert_eq!(from_str(text).unwrap(), owned);
+
+        let mut generator = Generator(0x0e0e);
+        for _ in 0..500 {
+            let written = generator.value(4).to_string();
+            let owned = parse(written.clone());
+            assert_eq!(
+                owned,
+                from_str(&written).unwrap(),
+                "{written}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_owned_values_with_from_str() {
+        let text =
+            String::from("  Some([\"a\", 'b'])  // done\n");
+        let value: OwnedValue = text.parse().unwrap();
+        drop(text);
+        assert_eq!(
+            value,
+            from_str("Some([\"a\", 'b'])").unwrap()
+        );
+
+        for text in ["true garbage", "[1] [2]", "1,", ""] {
+            assert_eq!(
+                text.parse::<OwnedValue>(),
+                Err(from_str(text).unwrap_err()),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            "true garbage"
+                .parse::<OwnedValue>()
+                .unwrap_err()
+                .kind(),
+            crate::ErrorKind::TrailingInput
+        );
+    }
+
+    #[test]
+    fn parses_results() {
+        assert_eq!(
+            parse_result("Ok(5)"),
+            Ok(("", Ok(Box::new(Value::Integer(5)))))
+        );
+        assert_eq!(
+            parse_result("Err(\"boom\")"),
+            Ok((
+                "",
+                Err(Box::new(Value::String("boom".into())))
+            ))
+        );
+        assert_eq!(
+            parse_result("Ok( () )"),
+            Ok(("", Ok(Box::new(Value::Tuple(vec![])))))
+        );
+
+        assert_eq!(
+            parse_value("Ok(Some([1, 2]))"),
+            Ok((
+                "",
+                Value::Result(Ok(Box::new(Value::Optional(
+                    Some(Box::new(Value::List(vec![
+                        Value::Integer(1),
+                        Value::Integer(2),
+                    ])))
+                ))))
+            ))
+        );
+
+        assert!(parse_result("Ok()").is_err());
+        assert!(parse_result("Ok(1").is_err());
+        assert!(parse_tuple_struct("Err(1, 2)").is_err());
+    }
+
+    #[test]
+    fn accesses_results() {
+        let ok = parse_value("Ok('a')").unwrap().1;
+        let err = parse_value("Err('b')").unwrap().1;
+
+        assert!(ok.is_ok());
+        assert!(!ok.is_err());
+        assert_eq!(ok.as_ok(), Some(&Value::Char('a')));
+        assert_eq!(ok.as_err(), None);
+
+        assert!(err.is_err());
+        assert_eq!(err.as_err(), Some(&Value::Char('b')));
+        assert_eq!(err.as_ok(), None);
+
+        assert!(!Value::Char('a').is_ok());
+        assert_eq!(Value::Char('a').as_ok(), None);
+    }
+
+    #[test]
+    fn parses_byte_strings() {
+        assert_eq!(
+            parse_byte_string("b\"abc\""),
+            Ok(("", Cow::Borrowed(&b"abc"[..])))
+        );
+        assert_eq!(
+            parse_byte_string("b\"\""),
+            Ok(("", Cow::Borrowed(&b""[..])))
+        );
+        assert_eq!(
+            parse_byte_string(r#"b"\x00\xffabc\xAb""#),
+            Ok(("", Cow::Owned(b"\x00\xffabc\xab".to_vec())))
+        );
+        assert_eq!(
+            parse_byte_string(r#"b"\\ \" \n \t \r \0""#),
+            Ok(("", Cow::Owned(b"\\ \" \n \t \r \0".to_vec())))
+        );
+
+        assert_eq!(
+            parse_value(r#"[b"hi", b"\x01"]"#),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::Bytes(Cow::Borrowed(b"hi")),
+                    Value::Bytes(Cow::Owned(vec![1])),
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_byte_escapes() {
+        let invalid_escape = |escape| {
+            Err(nom::Err::Failure(nom::error::Error::new(
+                escape,
+                ErrorKind::Escaped,
+            )))
+        };
+
+        assert_eq!(
+            parse_byte_string(r#"b"ab\xg1""#),
+            invalid_escape(r#"\xg1""#)
+        );
+        assert_eq!(
+            parse_byte_string(r#"b"\x1""#),
+            invalid_escape(r#"\x1""#)
+        );
+        assert_eq!(
+            parse_byte_string(r#"b"\q""#),
+            invalid_escape(r#"\q""#)
+        );
+        assert_eq!(
+            parse_byte_string(r#"b"\"#),
+            invalid_escape(r#"\"#)
+        );
+
+        assert!(parse_byte_string(r#"b"abc"#).is_err());
+    }
+
+    #[test]
+    fn rejects_non_ascii_in_byte_strings() {
+        assert_eq!(
+            parse_byte_string("b\"caf\u{e9}\""),
+            Err(nom::Err::Failure(nom::error::Error::new(
+                "\u{e9}\"",
+                ErrorKind::Char,
+            )))
+        );
+        assert!(
+            parse_byte_string("b\"\\x41\u{1F600}\"").is_err()
+        );
+        assert_eq!(
+            parse_byte_string(r#"b"caf\xC3\xA9""#),
+            Ok(("", Cow::Owned(b"caf\xc3\xa9".to_vec())))
+        );
+    }
+
+    #[test]
+    fn coerces_lists_into_bytes() {
+        let value = |input| parse_value(input).unwrap().1;
+
+        assert_eq!(
+            value("[104, 105]").as_bytes(),
+            Some(Cow::Owned(b"hi".to_vec()))
+        );
+        assert_eq!(
+            value(r#"b"hi""#).as_bytes(),
+            Some(Cow::Borrowed(&b"hi"[..]))
+        );
+        assert_eq!(
+            value("[]").as_bytes(),
+            Some(Cow::Owned(vec![]))
+        );
+
+        assert_eq!(value("[104, 256]").as_bytes(), None);
+        assert_eq!(value("[-1]").as_bytes(), None);
+        assert_eq!(value("['h']").as_bytes(), None);
+        assert_eq!(value("\"hi\"").as_bytes(), None);
+    }
+
+    #[test]
+    fn parses_raw_strings() {
+        assert_eq!(
+            parse_raw_string(r#"r"C:\temp\n""#),
+            Ok(("", r"C:\temp\n"))
+        );
+        assert_eq!(parse_raw_string(r#"r"""#), Ok(("", "")));
+        assert_eq!(
+            parse_raw_string(r###"r#"C:\temp\"quoted""#"###),
+            Ok(("", r#"C:\temp\"quoted""#))
+        );
+        assert_eq!(
+            parse_raw_string(r###"r##"a "# b"##, 1"###),
+            Ok((", 1", r##"a "# b"##))
+        );
+
+        assert_eq!(
+            parse_value(r##"[r"\d+", r#"""#]"##),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::String(r"\d+".into()),
+                    Value::String("\"".into()),
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_unterminated_raw_strings() {
+        let input = r###"r##"never "# closed"###;
+        assert_eq!(
+            parse_raw_string(input),
+            Err(nom::Err::Failure(nom::error::Error::new(
+                input,
+                ErrorKind::TakeUntil
+            )))
+        );
+
+        let input = r#"[1, r"abc]"#;
+        assert_eq!(
+            parse_value(input),
+            Err(nom::Err::Failure(nom::error::Error::new(
+                &input[4..],
+                ErrorKind::TakeUntil
+            )))
+        );
+
+        // Not a raw string at all
+        assert!(parse_raw_string("r#abc").is_err());
+        assert!(parse_raw_string("\"abc\"").is_err());
+    }
+
+    #[test]
+    fn parses_radix_integers() {
+        assert_eq!(
+            parse_integer("0xDEADBEEF"),
+            Ok(("", Value::Integer(0xDEAD_BEEF)))
+        );
+        assert_eq!(
+            parse_integer("0Xff"),
+            Ok(("", Value::Integer(255)))
+        );
+        assert_eq!(
+            parse_integer("0o755"),
+            Ok(("", Value::Integer(0o755)))
+        );
+        assert_eq!(
+            parse_integer("0O17"),
+            Ok(("", Value::Integer(0o17)))
+        );
+        assert_eq!(
+            parse_integer("0b1010]"),
+            Ok(("]", Value::Integer(10)))
+        );
+        assert_eq!(
+            parse_integer("0B1111_0000"),
+            Ok(("", Value::Integer(0xf0)))
+        );
+        assert_eq!(
+            parse_integer("-0x10"),
+            Ok(("", Value::Integer(-16)))
+        );
+        assert_eq!(
+            parse_integer("0xffff_ffff_ffff_ffff"),
+            Ok(("", Value::UInt(u64::MAX)))
+        );
+        assert_eq!(
+            parse_value("[0x1, 0o2, 0b11]"),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::Integer(1),
+                    Value::Integer(2),
+                    Value::Integer(3),
+                ])
+            ))
+        );
+
+        // The base 10 path is unaffected
+        assert_eq!(
+            parse_integer("0"),
+            Ok(("", Value::Integer(0)))
+        );
+        assert_eq!(
+            parse_integer("012"),
+            Ok(("", Value::Integer(12)))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_radix_integers() {
+        let failure = |input, kind| {
+            Err(nom::Err::Failure(nom::error::Error::new(
+                input, kind,
+            )))
+        };
+
+        assert_eq!(
+            parse_integer("0x"),
+            failure("", ErrorKind::HexDigit)
+        );
+        assert_eq!(
+            parse_integer("0x]"),
+            failure("]", ErrorKind::HexDigit)
+        );
+        assert_eq!(
+            parse_integer("0b2"),
+            failure("2", ErrorKind::Digit)
+        );
+        assert_eq!(
+            parse_integer("0b12"),
+            failure("2", ErrorKind::Digit)
+        );
+        assert_eq!(
+            parse_integer("0o78"),
+            failure("8", ErrorKind::OctDigit)
+        );
+        assert_eq!(
+            parse_integer("0xfg"),
+            failure("g", ErrorKind::HexDigit)
+        );
+        assert_eq!(
+            parse_integer("0x_1"),
+            failure("_1", ErrorKind::HexDigit)
+        );
+        assert_eq!(
+            parse_integer("0x1__2"),
+            failure("__2", ErrorKind::HexDigit)
+        );
+        assert_eq!(
+            parse_integer("0x1_"),
+            failure("_", ErrorKind::HexDigit)
+        );
+
+        let too_large =
+            "0x1_0000_0000_0000_0000_0000_0000_0000_0000";
+        assert_eq!(
+            parse_integer(too_large),
+            failure(too_large, ErrorKind::TooLarge)
+        );
+    }
+
+    #[test]
+    fn strips_digit_separators() {
+        let is_digit = |ch: char| ch.is_ascii_digit();
+
+        assert_eq!(
+            strip_digit_separators("1_000", is_digit),
+            Ok(Cow::Owned("1000".to_owned()))
+        );
+        assert_eq!(
+            strip_digit_separators("1000", is_digit),
+            Ok(Cow::Borrowed("1000"))
+        );
+        assert_eq!(strip_digit_separators("", is_digit), Err(0));
+        assert_eq!(
+            strip_digit_separators("_1", is_digit),
+            Err(0)
+        );
+        assert_eq!(
+            strip_digit_separators("1_", is_digit),
+            Err(1)
+        );
+        assert_eq!(
+            strip_digit_separators("1__0", is_digit),
+            Err(1)
+        );
+        assert_eq!(
+            strip_digit_separators("1a", is_digit),
+            Err(1)
+        );
+    }
+
+    #[test]
+    fn parses_digit_separators() {
+        assert_eq!(
+            parse_value("1_000_000"),
+            Ok(("", Value::Integer(1_000_000)))
+        );
+        assert_eq!(
+            parse_value("-1_0"),
+            Ok(("", Value::Integer(-10)))
+        );
+        assert_eq!(
+            parse_value("1.234_567"),
+            Ok(("", float("1.234_567")))
+        );
+        assert_eq!(
+            parse_value("1_000.000_1"),
+            Ok(("", float("1_000.000_1")))
+        );
+        assert_eq!(
+            parse_value("1_0e1_0"),
+            Ok(("", float("1_0e1_0")))
+        );
+        assert_eq!(
+            parse_value("[1_000, 2_0.5]"),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::Integer(1000),
+                    float("2_0.5"),
+                ])
+            ))
+        );
+
+        // `_1` is an identifier rather than a number
+        assert_eq!(
+            parse_value("_1"),
+            Ok(("", Value::Ident("_1".into())))
+        );
+    }
+
+    #[test]
+    fn rejects_misplaced_digit_separators() {
+        let misplaced = |input| {
+            Err(nom::Err::Failure(nom::error::Error::new(
+                input,
+                ErrorKind::Digit,
+            )))
+        };
+
+        assert_eq!(parse_value("1_"), misplaced("_"));
+        assert_eq!(parse_value("1__0"), misplaced("__0"));
+        assert_eq!(parse_value("[1_]"), misplaced("_]"));
+        assert_eq!(parse_value("1_.0"), misplaced("_.0"));
+        assert_eq!(parse_value("1._0"), misplaced("_0"));
+        assert_eq!(parse_value("1.0_"), misplaced("_"));
+        assert_eq!(parse_value("1e_5"), misplaced("_5"));
+        assert_eq!(parse_value("1e5_"), misplaced("_"));
+
+        assert_eq!(
+            parse_decimal_digits("12"),
+            Ok(("", "12".into()))
+        );
+        assert_eq!(
+            parse_decimal_digits("1_2"),
+            Ok(("", Cow::Owned("12".to_owned())))
+        );
+    }
+
+    #[test]
+    fn parses_special_floats() {
+        assert!(parse_double("NaN").unwrap().1.is_nan());
+        assert_eq!(parse_double("inf"), Ok(("", f64::INFINITY)));
+        assert_eq!(
+            parse_double("-inf"),
+            Ok(("", f64::NEG_INFINITY))
+        );
+        assert_eq!(
+            parse_special_float("inf]"),
+            Ok(("]", f64::INFINITY))
+        );
+
+        // Word boundaries are respected
+        assert!(parse_special_float("inferred").is_err());
+        assert!(parse_special_float("infinity").is_err());
+        assert!(parse_special_float("NaNa").is_err());
+        assert_eq!(
+            parse_value("inferred"),
+            Ok(("", Value::Ident("inferred".into())))
+        );
+
+        // Only the spelling used by `Debug` is accepted
+        assert_eq!(
+            parse_value("nan"),
+            Ok(("", Value::Ident("nan".into())))
+        );
+        assert_eq!(
+            parse_value("Inf"),
+            Ok(("", Value::Ident("Inf".into())))
+        );
+
+        let list = parse_value("[NaN, inf, -inf]").unwrap().1;
+        let Value::List(ref elements) = list else {
+            panic!("expected a list");
+        };
+        assert!(elements[0].is_nan());
+        assert_eq!(elements[1], Value::Float(f64::INFINITY));
+        assert_eq!(elements[2], Value::Float(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn nan_is_equal_to_itself() {
+        let nan = Value::Float(f64::NAN);
+
+        assert_eq!(nan, nan.clone());
+        assert!(nan.is_nan());
+        assert!(!Value::Float(1.).is_nan());
+        assert!(!Value::Ident("NaN".into()).is_nan());
+    }
+
+    #[test]
+    fn parses_number_suffixes() {
+        assert_eq!(
+            parse_value("255u8"),
+            Ok(("", Value::Integer(255)))
+        );
+        assert_eq!(
+            parse_value("-128i8"),
+            Ok(("", Value::Integer(-128)))
+        );
+        assert_eq!(
+            parse_value("10usize"),
+            Ok(("", Value::Integer(10)))
+        );
+        assert_eq!(
+            parse_value("1_000i64"),
+            Ok(("", Value::Integer(1000)))
+        );
+        assert_eq!(
+            parse_value("18446744073709551615u64"),
+            Ok(("", Value::UInt(u64::MAX)))
+        );
+        assert_eq!(
+            parse_value("0xffu8"),
+            Ok(("", Value::Integer(255)))
+        );
+        assert_eq!(
+            parse_value("0b1i32"),
+            Ok(("", Value::Integer(1)))
+        );
+
+        assert_eq!(
+            parse_value("1.5f32"),
+            Ok(("", float("1.5")))
+        );
+        assert_eq!(
+            parse_value("2e3f64"),
+            Ok(("", float("2e3")))
+        );
+        assert_eq!(parse_value("1f32"), Ok(("", float("1"))));
+
+        // `f32` is made of hex digits
+        assert_eq!(
+            parse_value("0x1f32"),
+            Ok(("", Value::Integer(0x1f32)))
+        );
+
+        assert_eq!(
+            parse_value("[1u8, 2.5f32]"),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::Integer(1),
+                    float("2.5")
+                ])
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_number_suffixes_after_a_separator() {
+        assert_eq!(
+            parse_value("1_u8"),
+            Ok(("", Value::Integer(1)))
+        );
+        assert_eq!(
+            parse_value("-1_000_i64"),
+            Ok(("", Value::Integer(-1000)))
+        );
+        assert_eq!(
+            parse_value("0b1_u8"),
+            Ok(("", Value::Integer(1)))
+        );
+        for (separated, glued) in [
+            ("1_f32", "1f32"),
+            ("1.5_f32", "1.5f32"),
+            ("2e3_f64", "2e3f64"),
+        ] {
+            assert_eq!(
+                parse_value(separated),
+                parse_value(glued)
+            );
+        }
+
+        // Only a single `_` may come before the suffix
+        let failure = |input| {
+            Err(nom::Err::Failure(nom::error::Error::new(
+                input,
+                ErrorKind::Digit,
+            )))
+        };
+        assert_eq!(parse_value("1__u8"), failure("__u8"));
+        assert_eq!(parse_value("1_q8"), failure("_q8"));
+    }
+
+    #[test]
+    fn rejects_invalid_number_suffixes() {
+        let failure = |input, kind| {
+            Err(nom::Err::Failure(nom::error::Error::new(
+                input, kind,
+            )))
+        };
+
+        assert_eq!(
+            parse_value("3q32"),
+            failure("q32", ErrorKind::AlphaNumeric)
+        );
+        assert_eq!(
+            parse_value("3u7"),
+            failure("u7", ErrorKind::AlphaNumeric)
+        );
+        assert_eq!(
+            parse_value("3u8x"),
+            failure("u8x", ErrorKind::AlphaNumeric)
+        );
+        assert_eq!(
+            parse_value("1.5u8"),
+            failure("u8", ErrorKind::AlphaNumeric)
+        );
+        assert_eq!(
+            parse_value("0b1f32"),
+            failure("f32", ErrorKind::Digit)
+        );
+
+        // Out of range for the suffix
+        assert_eq!(
+            parse_value("256u8"),
+            failure("256u8", ErrorKind::TooLarge)
+        );
+        assert_eq!(
+            parse_value("-129i8"),
+            failure("-129i8", ErrorKind::TooLarge)
+        );
+        assert_eq!(
+            parse_value("-1u32"),
+            failure("-1u32", ErrorKind::TooLarge)
+        );
+        assert_eq!(
+            parse_value("0x100u8"),
+            failure("0x100u8", ErrorKind::TooLarge)
+        );
+    }
+
+    #[test]
+    fn checks_integer_ranges() {
+        assert!(fits_in(&Value::Integer(127), true, 8));
+        assert!(!fits_in(&Value::Integer(128), true, 8));
+        assert!(fits_in(&Value::Integer(-128), true, 8));
+        assert!(!fits_in(&Value::Integer(-129), true, 8));
+        assert!(fits_in(&Value::Integer(255), false, 8));
+        assert!(!fits_in(&Value::Integer(-1), false, 8));
+        assert!(fits_in(&Value::BigUInt(u128::MAX), false, 128));
+        assert!(!fits_in(&Value::BigUInt(u128::MAX), true, 128));
+        assert!(fits_in(&Value::BigInt(i128::MIN), true, 128));
+        assert!(!fits_in(&Value::Float(1.), true, 128));
+    }
+
+    #[test]
+    fn allows_trailing_commas() {
+        let ints = |ints: &[i64]| {
+            ints.iter().copied().map(Value::Integer).collect()
+        };
+
+        assert_eq!(
+            parse_value("[1, 2, 3,]"),
+            Ok(("", Value::List(ints(&[1, 2, 3]))))
+        );
+        assert_eq!(
+            parse_value("[\n    1,\n    2 ,\n]"),
+            Ok(("", Value::List(ints(&[1, 2]))))
+        );
+        assert_eq!(
+            parse_value("(1, 2,)"),
+            Ok(("", Value::Tuple(ints(&[1, 2]))))
+        );
+        assert_eq!(
+            parse_value("{1, 2,}"),
+            Ok(("", Value::Set(ints(&[1, 2]))))
+        );
+        assert_eq!(
+            parse_value("{1: 2,}"),
+            Ok((
+                "",
+                Value::Map(vec![(
+                    Value::Integer(1),
+                    Value::Integer(2)
+                )])
+            ))
+        );
+        assert_eq!(
+            parse_value("{\"a\": 1, }"),
+            Ok((
+                "",
+                Value::Object(ObjectMap::from([(
+                    "a".into(),
+                    Value::Integer(1)
+                )]))
+            ))
+        );
+        assert_eq!(
+            parse_value("Foo { x: 1, }"),
+            Ok((
+                "",
+                Value::Struct {
+                    name: "Foo".into(),
+                    fields: vec![(
+                        "x".into(),
+                        Value::Integer(1)
+                    )],
+                    non_exhaustive: false,
+                }
+            ))
+        );
+        assert_eq!(
+            parse_value("Point(1, 2,)"),
+            Ok((
+                "",
+                Value::TupleStruct {
+                    name: "Point".into(),
+                    fields: ints(&[1, 2]),
+                }
+            ))
+        );
+
+        assert!(parse_list("[,]").is_err());
+        assert!(parse_list("[1,,2]").is_err());
+        assert!(parse_list("[1,,]").is_err());
+        assert!(parse_value("{,}").is_err());
+        assert!(parse_struct("Foo { , }").is_err());
+    }
+
+    #[test]
+    fn skips_comments() {
+        let ints = |ints: &[i64]| {
+            ints.iter().copied().map(Value::Integer).collect()
+        };
+
+        assert_eq!(
+            parse_value("[1, // count\n 2]"),
+            Ok(("", Value::List(ints(&[1, 2]))))
+        );
+        assert_eq!(
+            parse_value("[ /* a */ 1 /* b */, 2, /* c */ ]"),
+            Ok(("", Value::List(ints(&[1, 2]))))
+        );
+        assert_eq!(
+            parse_value("{\"a\" /* key */: // value\n 1}"),
+            Ok((
+                "",
+                Value::Object(ObjectMap::from([(
+                    "a".into(),
+                    Value::Integer(1)
+                )]))
+            ))
+        );
+        assert_eq!(
+            parse_value("Some(/* nested /* comments */ */ 1)"),
+            Ok((
+                "",
+                Value::Optional(Some(Box::new(Value::Integer(
+                    1
+                ))))
+            ))
+        );
+        assert_eq!(
+            parse_value("\"// not a comment\""),
+            Ok(("", Value::String("// not a comment".into())))
+        );
+        assert_eq!(
+            parse_ws("/**/ // done"),
+            Ok(("", "/**/ // done"))
+        );
+
+        let input = "[1, /* a /* b */ 2]";
+        assert_eq!(
+            parse_value(input),
+            Err(nom::Err::Failure(nom::error::Error::new(
+                &input[4..],
+                ErrorKind::TakeUntil
+            )))
+        );
+    }
 
-pub fn parse_value(input: &str) -> IResult<&str, Value> {
-    alt((
-        parse_object.map(Value::Object),
-        parse_list.map(Value::List),
-        parse_optional.map(Value::Optional),
-        parse_double.map(Value::Float),
-        parse_char.map(Value::Char),
-        parse_string.map(Value::String),
-        parse_boolean.map(Value::Boolean),
-    ))(input)
-}
+    #[test]
+    fn skips_whitespace_uniformly() {
+        assert_eq!(
+            parse_value("  true"),
+            Ok(("", Value::Boolean(true)))
+        );
+        assert_eq!(parse_value("  1.0"), Ok(("", float("1.0"))));
+        assert_eq!(
+            parse_value("[ 1 ,\n 'a' ,\t \"x\" ]"),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::Integer(1),
+                    Value::Char('a'),
+                    Value::String("x".into()),
+                ])
+            ))
+        );
+        assert_eq!(
+            parse_value("Some(\n  true\n)"),
+            Ok((
+                "",
+                Value::Optional(Some(Box::new(Value::Boolean(
+                    true
+                ))))
+            ))
+        );
+        assert_eq!(
+            parse_value("Some( 'a' )"),
+            Ok((
+                "",
+                Value::Optional(Some(Box::new(Value::Char(
+                    'a'
+                ))))
+            ))
+        );
+        assert_eq!(
+            parse_value("\u{a0}[\u{a0}1\u{a0}]"),
+            Ok(("", Value::List(vec![Value::Integer(1)])))
+        );
+        assert_eq!(
+            parse_value(" Foo {\n\tx : [ ] ,\n}"),
+            Ok((
+                "",
+                Value::Struct {
+                    name: "Foo".into(),
+                    fields: vec![(
+                        "x".into(),
+                        Value::List(vec![])
+                    )],
+                    non_exhaustive: false,
+                }
+            ))
+        );
+        assert_eq!(
+            parse_value("( 1 .. 5 , )"),
+            Ok((
+                "",
+                Value::Tuple(vec![Value::Range {
+                    start: Some(Box::new(Value::Integer(1))),
+                    end: Some(Box::new(Value::Integer(5))),
+                    inclusive: false,
+                }])
+            ))
+        );
+    }
 
-fn parse_object(
-    input: &str,
-) -> IResult<&str, HashMap<&str, Value>> {
-    fn parse_key_value(
-        input: &str,
-    ) -> IResult<&str, (&str, Value)> {
-        separated_pair(
-            preceded(parse_ws, parse_string),
-            preceded(parse_ws, char(':')),
-            parse_value,
-        )(input)
-    }
-
-    preceded(
-        char('{'),
-        terminated(
-            separated_list0(
-                preceded(parse_ws, char(',')),
-                parse_key_value,
-            )
-            .map(|tuple_vec| tuple_vec.into_iter().collect()),
-            preceded(parse_ws, char('}')),
-        ),
-    )(input)
-}
+    #[test]
+    fn parses_pretty_debug_output() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Inner {
+            name: String,
+            scores: Vec<f64>,
+        }
 
-fn parse_optional(
-    input: &str,
-) -> IResult<&str, Option<Box<Value>>> {
-    let parse_none = value(None, tag("None"));
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Point(i32, i32);
 
-    let parse_some = preceded(
-        tag("Some("),
-        terminated(parse_value, preceded(parse_ws, char(')'))),
-    )
-    .map(Box::new)
-    .map(Some);
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        enum Shape {
+            Empty,
+            Circle { radius: f64 },
+            Line(Point, Point),
+        }
 
-    alt((parse_none, parse_some))(input)
-}
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Outer {
+            inner: Inner,
+            maybe: Option<Option<Inner>>,
+            result: Result<Vec<u8>, String>,
+            shapes: Vec<Shape>,
+            map: HashMap<String, (i32, char)>,
+            set: std::collections::BTreeSet<u8>,
+            range: std::ops::Range<i32>,
+            unit: (),
+        }
 
-fn parse_ws(input: &str) -> IResult<&str, &str> {
-    // transform is_ascii_whitespace from &self to self
-    let is_ascii_whitespace =
-        |ch: char| ch.is_ascii_whitespace();
+        fn assert_pretty_eq(value: impl std::fmt::Debug) {
+            let compact = format!("{value:?}");
+            let pretty = format!("{value:#?}");
 
-    take_while(is_ascii_whitespace)(input)
-}
+            let parsed = parse_value(&compact);
+            assert!(matches!(parsed, Ok(("", _))), "{compact}");
+            assert_eq!(parse_value(&pretty), parsed, "{pretty}");
+        }
+
+        let inner = || Inner {
+            name: "tab\there".into(),
+            scores: vec![1.5, -2.],
+        };
+
+        assert_pretty_eq(Some(2.0));
+        assert_pretty_eq(vec![1, 2]);
+        assert_pretty_eq(Vec::<i32>::new());
+        assert_pretty_eq(Some(vec![Some(1), None]));
+        assert_pretty_eq(Ok::<_, ()>(Point(1, -1)));
+        assert_pretty_eq(inner());
+        assert_pretty_eq(Outer {
+            inner: inner(),
+            maybe: Some(Some(inner())),
+            result: Ok(vec![0, 255]),
+            shapes: vec![
+                Shape::Empty,
+                Shape::Circle { radius: 1. },
+                Shape::Line(Point(0, 0), Point(1, 1)),
+            ],
+            map: HashMap::from([("a".into(), (1, 'x'))]),
+            set: [3, 1, 2].into(),
+            range: 0..10,
+            unit: (),
+        });
+    }
 
-fn parse_list(input: &str) -> IResult<&str, Vec<Value>> {
-    preceded(
-        char('['),
-        terminated(
-            separated_list0(
-                preceded(parse_ws, char(',')),
-                parse_value,
+    #[cfg(feature = "arbitrary-precision")]
+    #[test]
+    fn keeps_raw_numbers() {
+        assert_eq!(
+            parse_value("0.1"),
+            Ok(("", Value::RawNumber("0.1".into())))
+        );
+        assert_eq!(
+            parse_value("[1e400, -1_000.000_1f64]"),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::RawNumber("1e400".into()),
+                    Value::RawNumber("-1_000.000_1".into()),
+                ])
+            ))
+        );
+        assert_eq!(
+            parse_value("123456789012345678901234567890.123"),
+            Ok((
+                "",
+                Value::RawNumber(
+                    "123456789012345678901234567890.123".into()
+                )
+            ))
+        );
+        assert_eq!(
+            parse_value("1f32"),
+            Ok(("", Value::RawNumber("1".into())))
+        );
+        assert_eq!(
+            parse_value(
+                "340282366920938463463374607431768211456"
             ),
-            preceded(parse_ws, char(']')),
-        ),
-    )(input)
-}
+            Ok((
+                "",
+                Value::RawNumber(
+                    "340282366920938463463374607431768211456"
+                        .into()
+                )
+            ))
+        );
 
-fn parse_char(input: &str) -> IResult<&str, char> {
-    let (rest, chr) =
-        delimited(char('\''), take(1_usize), char('\''))(input)?;
+        // Integers that fit, and special floats, are unchanged
+        assert_eq!(
+            parse_value("7"),
+            Ok(("", Value::Integer(7)))
+        );
+        assert!(parse_value("NaN").unwrap().1.is_nan());
 
-    // Safety: safe unwrap since we know that there's at least
-    // one element
-    let chr = chr.chars().next().unwrap();
+        assert_eq!(
+            Value::RawNumber("1_0.5".into()).as_f64(),
+            Some(10.5)
+        );
+        assert_eq!(
+            Value::RawNumber("-12".into()).as_i64(),
+            Some(-12)
+        );
+        assert_eq!(
+            Value::RawNumber("1.5".into()).as_i64(),
+            None
+        );
+        assert_eq!(
+            Value::RawNumber("-1_000.000_1".into()).to_string(),
+            "-1_000.000_1"
+        );
+        assert_ne!(
+            Value::RawNumber("1.0".into()),
+            Value::RawNumber("1.00".into())
+        );
+    }
 
-    Ok((rest, chr))
-}
+    #[test]
+    fn parses_strict_numbers() {
+        let strict =
+            ParserOptions::default().strict_numbers(true);
 
-fn parse_string(input: &str) -> IResult<&str, &str> {
-    let input = input.trim_start();
-    let (rest, string) = delimited(
-        char('"'),
-        take_while(|ch| ch != '"'),
-        char('"'),
-    )(input)?;
+        // The input, what it parses to by default, and the
+        // offset of the error in strict mode if there is one
+        let corpus = [
+            ("1", Value::Integer(1), None),
+            ("-1", Value::Integer(-1), None),
+            ("+1", Value::Integer(1), Some(0)),
+            ("+0x1f", Value::Integer(31), Some(0)),
+            ("1.5", float("1.5"), None),
+            ("-1.5e3", float("-1.5e3"), None),
+            ("5.", float("5."), None),
+            (".5", float(".5"), Some(0)),
+            ("-.5", float("-.5"), Some(1)),
+            ("+1.5", float("+1.5"), Some(0)),
+            ("1e5", float("1e5"), None),
+        ];
 
-    Ok((rest, string))
-}
+        for (input, value, strict_error) in corpus {
+            assert_eq!(
+                parse_value(input),
+                Ok(("", value.clone()))
+            );
 
-fn parse_boolean(input: &str) -> IResult<&str, bool> {
-    let (rest, boolean) =
-        alt((tag("true"), tag("false")))(input)?;
+            let expected = match strict_error {
+                None => Ok(("", value)),
+                Some(offset) => Err(nom::Err::Failure(
+                    nom::error::Error::new(
+                        &input[offset..],
+                        match input[offset..].starts_with('+') {
+                            true => ErrorKind::Char,
+                            false => ErrorKind::Digit,
+                        },
+                    ),
+                )),
+            };
+            assert_eq!(
+                parse_value_with(input, strict),
+                expected
+            );
+        }
 
-    let is_true = boolean == "true";
+        // Also inside of other values, and ranges are unaffected
+        let input = "[1, .5]";
+        assert!(matches!(
+            parse_value_with(input, strict),
+            Err(nom::Err::Failure(error)) if error.input == ".5]"
+        ));
+        assert_eq!(
+            parse_value_with("..5", strict),
+            parse_value("..5")
+        );
+        assert!(parse_value("1e").is_err());
+        assert!(parse_value_with("1e", strict).is_err());
+    }
 
-    Ok((rest, is_true))
-}
+    #[test]
+    fn parses_slices() {
+        let input = b"\xef\xbb\xbf[\"a\", 1] // done\n";
+        let value = from_slice(input).unwrap();
+        assert_eq!(
+            value,
+            Value::List(vec![
+                Value::String("a".into()),
+                Value::Integer(1)
+            ])
+        );
 
-fn parse_double(input: &str) -> IResult<&str, f64> {
-    let input = input.trim_start();
-    double(input)
-}
+        // Strings are borrowed from the slice
+        let Value::List(elements) = &value else {
+            unreachable!()
+        };
+        let Value::String(Cow::Borrowed(string)) = &elements[0]
+        else {
+            panic!("expected a borrowed string");
+        };
+        assert!(input.as_ptr_range().contains(&string.as_ptr()));
 
-#[cfg(test)]
-mod tests {
-    use crate::{
-        parse_boolean, parse_char, parse_double, parse_list,
-        parse_optional, parse_string, parse_value, Value,
-    };
+        let error = |input| {
+            from_slice(input)
+                .map_err(|err| (err.kind(), err.offset()))
+        };
+        assert_eq!(
+            error(b"[1, \"\xff\"]"),
+            Err((crate::ErrorKind::InvalidUtf8, 5))
+        );
+        assert_eq!(
+            error(b"\xef\xbb\xbf\xc3"),
+            Err((crate::ErrorKind::InvalidUtf8, 3))
+        );
+        assert_eq!(
+            error(b"1 2"),
+            Err((crate::ErrorKind::TrailingInput, 2))
+        );
+        assert!(from_slice(b"[1").is_err());
 
-    #[test]
-    fn parses_doubles() {
-        assert_eq!(parse_double(" 2.2"), Ok(("", 2.2)));
-        assert_eq!(parse_double("5."), Ok(("", 5.)));
+        // Concatenated documents
+        let (first, rest) =
+            from_slice_partial(b"1 {2} ").unwrap();
+        assert_eq!(first, Value::Integer(1));
+        assert_eq!(rest, b" {2} ");
+        assert_eq!(
+            from_slice(rest),
+            Ok(Value::Set(vec![Value::Integer(2)]))
+        );
     }
 
     #[test]
-    fn parses_booleans() {
-        assert_eq!(parse_boolean("true"), Ok(("", true)));
-        assert_eq!(parse_boolean("false"), Ok(("", false)));
+    fn limits_nesting() {
+        let nested = |depth| {
+            format!(
+                "{}1{}",
+                "[".repeat(depth),
+                "]".repeat(depth)
+            )
+        };
+
+        let text = "[".repeat(10_000);
+        let err = from_str(&text).unwrap_err();
         assert_eq!(
-            parse_boolean("false false"),
-            Ok((" false", false))
+            (err.kind(), err.offset()),
+            (crate::ErrorKind::DepthLimitExceeded, 128)
         );
+        assert_eq!(
+            parse_value(&text),
+            Err(nom::Err::Failure(nom::error::Error::new(
+                &text[128..],
+                ErrorKind::Fix
+            )))
+        );
+        assert!(from_str(&nested(127)).is_ok());
+        assert!(from_str(&nested(128)).is_err());
 
-        assert!(parse_boolean("False").is_err());
-        assert!(parse_boolean("True").is_err());
-        assert!(parse_boolean("1").is_err());
+        let options = ParserOptions::default().max_depth(2);
+        for text in [
+            "[[1]]",
+            "Some(Some(1))",
+            "{1: {2: 3}}",
+            "{\"a\": [1]}",
+            "((1,),)",
+            "P(P(1))",
+            "S { a: S { a: 1 } }",
+            "1..[[2]]",
+        ] {
+            assert!(
+                matches!(
+                    parse_value_with(text, options),
+                    Err(nom::Err::Failure(err))
+                        if err.code == ErrorKind::Fix
+                ),
+                "{text}"
+            );
+        }
+        for text in
+            ["[1, 2]", "Some(1)", "{1: 2}", "S { a: 1 }", "1..2"]
+        {
+            assert!(
+                parse_value_with(text, options).is_ok(),
+                "{text}"
+            );
+        }
+        assert!(parse_value_with(
+            &nested(3),
+            ParserOptions::default().max_depth(4)
+        )
+        .is_ok());
+        assert!(parse_value_with(
+            &nested(3),
+            ParserOptions::default().max_depth(3)
+        )
+        .is_err());
     }
 
     #[test]
-    fn parses_chars() {
-        assert_eq!(parse_char("'a'"), Ok(("", 'a')));
-        assert_eq!(parse_char("'ã'"), Ok(("", 'ã')));
-        assert!(parse_boolean("'aa'").is_err());
-        assert!(parse_boolean("''").is_err());
+    fn parses_with_other_options_at_the_same_time() {
+        let text = "[".repeat(8) + &"]".repeat(8);
+        let shallow = ParserOptions::default().max_depth(4);
+        std::thread::scope(|scope| {
+            let limited = scope.spawn(|| {
+                (0..1000).all(|_| {
+                    from_slice_with(text.as_bytes(), shallow)
+                        .is_err()
+                })
+            });
+            let unlimited = scope.spawn(|| {
+                (0..1000).all(|_| from_str(&text).is_ok())
+            });
+            assert!(limited.join().unwrap());
+            assert!(unlimited.join().unwrap());
+        });
     }
 
     #[test]
-    fn parses_option() {
+    fn parses_deeply_nested_values() {
+        let options =
+            ParserOptions::default().max_depth(usize::MAX);
+
+        let depth = 1_000_000;
+        let text = "[".repeat(depth) + &"]".repeat(depth);
+        let (rest, value) =
+            parse_value_with(&text, options).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(value.to_string(), text);
+        // Dropped as it is thrown away on the error
+        assert!(from_slice_with(
+            (text.clone() + " x").as_bytes(),
+            options
+        )
+        .is_err());
+
+        let levels = 10_000;
+        let text = format!(
+            "{}1{}",
+            r#"Some([{"a": (P(S { a: {1: "#.repeat(levels),
+            "} }),)}])".repeat(levels)
+        );
+        let (rest, value) =
+            parse_value_with(&text, options).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(value.to_string(), text);
+
+        let text = "[".repeat(depth / 10);
+        assert!(parse_value_with(&text, options).is_err());
         assert_eq!(
-            parse_optional("Some(2)"),
-            Ok(("", Some(Box::new(Value::Float(2.0)))))
+            from_str(&text).unwrap_err().kind(),
+            crate::ErrorKind::DepthLimitExceeded
         );
+    }
+
+    #[test]
+    fn enforces_limits() {
+        // The offset and code of the failure parsing `text`
+        let failure =
+            |text: &str, options| match parse_value_with(
+                text, options,
+            ) {
+                Err(nom::Err::Failure(err)) => Some((
+                    text.len() - err.input.len(),
+                    err.code,
+                )),
+                _ => None,
+            };
 
+        let nodes = crate::NODE_LIMIT_EXCEEDED;
+        let options = ParserOptions::default().max_nodes(8);
+        assert!(parse_value_with(
+            "[1, [2, 3], {4: 5}]",
+            options
+        )
+        .is_ok());
         assert_eq!(
-            parse_optional("Some('a')"),
-            Ok(("", Some(Box::new(Value::Char('a')))))
+            failure("[1, [2, 3], {4: 5}, 6]", options),
+            Some((20, nodes))
+        );
+        // Neither the map nor the tuple struct that the set and
+        // the identifier were first taken for count
+        let options = ParserOptions::default().max_nodes(6);
+        assert!(
+            parse_value_with("{[1, 2], [3]}", options).is_ok()
         );
+        assert!(parse_value_with("[Foo, [1, 2, 3]]", options)
+            .is_ok());
+        assert_eq!(
+            failure("{[1, 2], [3, 4]}", options),
+            Some((13, nodes))
+        );
+
+        let strings = crate::STRING_LIMIT_EXCEEDED;
+        let options = ParserOptions::default().max_string_len(5);
+        for text in [
+            "\"hello\"",
+            "\"\\u{1f600}a\"",
+            "r#\"a\"bc\"#",
+            "b\"\\x00\\x01abc\"",
+            "{\"key\": \"value\"}",
+        ] {
+            assert!(
+                parse_value_with(text, options).is_ok(),
+                "{text}"
+            );
+        }
+        for (text, offset) in [
+            ("\"hello!\"", 0),
+            ("[1, \"a\\nlong\"]", 4),
+            ("\"\\u{1f600}ab\"", 0),
+            ("r#\"abcdef\"#", 0),
+            ("b\"\\x00\\x01\\x02abc\"", 0),
+            ("{\"long key\": 1}", 1),
+        ] {
+            assert_eq!(
+                failure(text, options),
+                Some((offset, strings)),
+                "{text}"
+            );
+        }
+
+        let elements = crate::ELEMENT_LIMIT_EXCEEDED;
+        let options = ParserOptions::default().max_elements(2);
+        for text in [
+            "[1, 2]",
+            "[1, 2,]",
+            "{1: [3, 4], 2: 5}",
+            "S { a: 1, b: 2 }",
+        ] {
+            assert!(
+                parse_value_with(text, options).is_ok(),
+                "{text}"
+            );
+        }
+        for (text, offset) in [
+            ("[1, 2, 3]", 7),
+            ("(1, 2,\n 3)", 8),
+            ("{1, 2, 3}", 7),
+            ("{1: 2, 3: 4, 5: 6}", 13),
+            ("{\"a\": 1, \"b\": 2, \"c\": 3}", 17),
+            ("S { a: 1, b: 2, c: 3 }", 16),
+            ("P(1, 2, 3)", 8),
+        ] {
+            assert_eq!(
+                failure(text, options),
+                Some((offset, elements)),
+                "{text}"
+            );
+        }
 
+        let text = r#"Config { name: "tson", ports: [80, 443], tags: {"a": 'x'}, raw: b"\x00" }"#;
+        let generous = ParserOptions::default()
+            .max_nodes(100)
+            .max_string_len(100)
+            .max_elements(100);
         assert_eq!(
-            parse_optional("Some(\"hey\")"),
-            Ok(("", Some(Box::new(Value::String("hey")))))
+            parse_value_with(text, generous),
+            parse_value(text)
         );
 
-        assert_eq!(parse_optional("None"), Ok(("", None)));
+        let text = "[1, 2, 3]";
+        let err = parse_value_with(text, options).unwrap_err();
+        assert_eq!(
+            crate::Error::from_nom(text, err, None).kind(),
+            crate::ErrorKind::ElementLimitExceeded
+        );
     }
 
     #[test]
-    fn parses_strings() {
-        assert_eq!(parse_string("\"hey\""), Ok(("", "hey")));
-        assert_eq!(parse_string("\"2 * 2\""), Ok(("", "2 * 2")));
+    fn parses_whole_strings() {
+        assert_eq!(
+            from_str(" // the answer\n 42 /* done */\n"),
+            Ok(Value::Integer(42))
+        );
+        assert_eq!(
+            from_str("Some(1)"),
+            Ok(Value::Optional(Some(Box::new(Value::Integer(
+                1
+            )))))
+        );
 
+        let err = from_str("true garbage").unwrap_err();
+        assert_eq!(
+            (err.kind(), err.offset()),
+            (crate::ErrorKind::TrailingInput, 5)
+        );
+        let err = from_str("[1, 2] // comment\n]").unwrap_err();
+        assert_eq!(
+            (err.kind(), err.line(), err.column()),
+            (crate::ErrorKind::TrailingInput, 2, 1)
+        );
         assert_eq!(
-            parse_string("  \"ignores leading whitespace\""),
-            Ok(("", "ignores leading whitespace"))
+            from_str_partial("true garbage"),
+            Ok((Value::Boolean(true), " garbage"))
         );
     }
 
     #[test]
-    fn parses_values() {
+    fn validates_what_from_str_parses() {
+        for text in [
+            "Config { name: \"a\\tb\", ports: [80, 443], tags: {\"k\": 'x'} }",
+            "{1: Some(b\"\\x00\"), 2: None} // done",
+            "[0..10, ..=5, 1.., Ok((1, -2.5)), Err(Unit)]",
+            "Foo { a: {1, 2}, .. }",
+            "/* a /* nested */ comment */ r#\"raw\"#",
+            "[1, 2",
+            "{1: 2, 3}",
+            "Foo { a: 1 b: 2 }",
+            "\"a\\qb\"",
+            "b\"\\xZZ\"",
+            "[1, 2] 3",
+            "Some(1,,)",
+            "1 /* /* */",
+            "{}",
+            "{,}",
+        ] {
+            assert_eq!(
+                crate::validate(text),
+                from_str(text).map(drop),
+                "{text}"
+            );
+        }
+
+        let options = ParserOptions::default()
+            .max_depth(2)
+            .max_string_len(3)
+            .max_elements(2);
+        for text in [
+            "[[1]]",
+            "[[[1]]]",
+            "\"abcd\"",
+            "\"a\\nbc\"",
+            "{1: 2, 3: 4, 5: 6}",
+        ] {
+            let expected = match parse_value_with(text, options)
+            {
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    Err(crate::Error::from_nom(text, err, None)
+                        .kind())
+                }
+            };
+            let validated = crate::validate_with(text, options)
+                .map_err(|err| err.kind());
+            assert_eq!(validated, expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn converts_numbers() {
+        assert_eq!(Value::Integer(-3).as_f64(), Some(-3.));
+        assert_eq!(Value::UInt(u64::MAX).as_i64(), None);
+        assert_eq!(Value::BigInt(-5).as_i64(), Some(-5));
+        assert_eq!(Value::Float(2.5).as_f64(), Some(2.5));
+        assert_eq!(Value::Float(2.).as_i64(), None);
+        assert_eq!(Value::Boolean(true).as_f64(), None);
+    }
+
+    #[cfg(feature = "duration")]
+    #[test]
+    fn parses_durations() {
+        use std::time::Duration;
+
+        use crate::parse_duration;
+
+        for duration in [
+            Duration::from_millis(150),
+            Duration::from_millis(2500),
+            Duration::from_micros(3),
+            Duration::from_nanos(40),
+            Duration::from_nanos(1_500),
+            Duration::new(2, 1),
+            Duration::ZERO,
+            Duration::MAX,
+        ] {
+            assert_eq!(
+                parse_value(&format!("{duration:?}")),
+                Ok(("", Value::Duration(duration)))
+            );
+            assert_eq!(
+                Value::Duration(duration).to_string(),
+                format!("{duration:?}")
+            );
+        }
+
         assert_eq!(
-            parse_value("'B'"),
-            Ok(("", Value::Char('B')))
+            parse_duration("3us]"),
+            Ok(("]", Duration::from_micros(3)))
         );
         assert_eq!(
-            parse_value("\"this is a test\""),
-            Ok(("", Value::String("this is a test")))
+            parse_value("[1s, 2ms]"),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::Duration(Duration::from_secs(1)),
+                    Value::Duration(Duration::from_millis(2)),
+                ])
+            ))
+        );
+
+        // Plain numbers are unaffected
+        assert_eq!(
+            parse_value("150"),
+            Ok(("", Value::Integer(150)))
         );
+        assert_eq!(parse_value("2.5"), parse_float("2.5"));
+
+        assert!(parse_duration("1.5ns").is_err());
+        assert!(parse_duration("1.0000001ms").is_err());
+        assert!(parse_duration("1sec").is_err());
+        assert!(parse_value("18446744073709551616s").is_err());
     }
 
     #[test]
-    fn parses_lists() {
-        assert_eq!(parse_list("[]"), Ok(("", vec![])));
+    fn parses_json() {
+        let object =
+            |entries: Vec<(&'static str, Value<'static>)>| {
+                Value::Object(
+                    entries
+                        .into_iter()
+                        .map(|(key, value)| (key.into(), value))
+                        .collect(),
+                )
+            };
 
+        let document = r#"{
+            "id": 7,
+            "name": "café 😀",
+            "path": "a\/b",
+            "tags": ["x", "y"],
+            "score": -1.5e2,
+            "owner": null,
+            "nested": {"ok": true, "items": [{}, []]}
+        }"#;
         assert_eq!(
-            parse_list("['A']"),
-            Ok(("", vec![Value::Char('A')]))
+            parse_json_value(document),
+            Ok((
+                "",
+                object(vec![
+                    ("id", Value::Integer(7)),
+                    ("name", Value::String("café 😀".into())),
+                    ("path", Value::String("a/b".into())),
+                    (
+                        "tags",
+                        Value::List(vec![
+                            Value::String("x".into()),
+                            Value::String("y".into()),
+                        ])
+                    ),
+                    ("score", parse_float("-1.5e2").unwrap().1),
+                    ("owner", Value::Optional(None)),
+                    (
+                        "nested",
+                        object(vec![
+                            ("ok", Value::Boolean(true)),
+                            (
+                                "items",
+                                Value::List(vec![
+                                    object(vec![]),
+                                    Value::List(vec![]),
+                                ])
+                            ),
+                        ])
+                    ),
+                ])
+            ))
+        );
+        assert_eq!(
+            parse_json_value(r#"[null, "\b\f\n", 0]"#),
+            Ok((
+                "",
+                Value::List(vec![
+                    Value::Optional(None),
+                    Value::String("\u{8}\u{c}\n".into()),
+                    Value::Integer(0),
+                ])
+            ))
         );
 
+        // tson is still accepted
         assert_eq!(
-            parse_list("['z', 5]"),
-            Ok(("", vec![Value::Char('z'), Value::Float(5.)]))
+            parse_json_value("Some({1, 2})"),
+            parse_value("Some({1, 2})")
+        );
+        assert_eq!(
+            parse_json_value("nullable"),
+            Ok(("", Value::Ident("nullable".into())))
         );
 
+        // Lone or reversed surrogates aren't chars
+        assert!(parse_json_value(r#""\ud83d""#).is_err());
+        assert!(parse_json_value(r#""\ude00\ud83d""#).is_err());
+        assert!(parse_json_value(r#""\u00e""#).is_err());
+
+        // None of this leaks into plain tson
         assert_eq!(
-            parse_list("['f', 2.2, \"a string\"]"),
+            parse_value("null"),
+            Ok(("", Value::Ident("null".into())))
+        );
+        assert!(parse_value(r#""\u00e9""#).is_err());
+        assert!(parse_value(r#""a\/b""#).is_err());
+    }
+
+    #[test]
+    fn parses_ranges() {
+        let range =
+            |start: Option<Value<'static>>,
+             end: Option<Value<'static>>,
+             inclusive| Value::Range {
+                start: start.map(Box::new),
+                end: end.map(Box::new),
+                inclusive,
+            };
+        let int = |int| Some(Value::Integer(int));
+
+        assert_eq!(
+            parse_value("1..5"),
+            Ok(("", range(int(1), int(5), false)))
+        );
+        assert_eq!(
+            parse_value("0..=255"),
+            Ok(("", range(int(0), int(255), true)))
+        );
+        assert_eq!(
+            parse_value("..10"),
+            Ok(("", range(None, int(10), false)))
+        );
+        assert_eq!(
+            parse_value("..=5"),
+            Ok(("", range(None, int(5), true)))
+        );
+        assert_eq!(
+            parse_value("3.."),
+            Ok(("", range(int(3), None, false)))
+        );
+        assert_eq!(
+            parse_value(".."),
+            Ok(("", range(None, None, false)))
+        );
+        assert_eq!(
+            parse_value("-1.5..2.5"),
             Ok((
                 "",
-                vec![
-                    Value::Char('f'),
-                    Value::Float(2.2),
-                    Value::String("a string"),
-                ]
+                range(
+                    Some(float("-1.5")),
+                    Some(float("2.5")),
+                    false
+                )
+            ))
+        );
+        assert_eq!(
+            parse_value("'a'..='z'"),
+            Ok((
+                "",
+                range(
+                    Some(Value::Char('a')),
+                    Some(Value::Char('z')),
+                    true
+                )
             ))
         );
 
         assert_eq!(
-            parse_list("[[]]"),
-            Ok(("", vec![Value::List(vec![])]))
+            parse_value("[1..5, 3.., ..]"),
+            Ok((
+                "",
+                Value::List(vec![
+                    range(int(1), int(5), false),
+                    range(int(3), None, false),
+                    range(None, None, false),
+                ])
+            ))
+        );
+        assert_eq!(
+            parse_value("Some(..=5)"),
+            Ok((
+                "",
+                Value::Optional(Some(Box::new(range(
+                    None,
+                    int(5),
+                    true
+                ))))
+            ))
         );
+
+        assert!(matches!(
+            parse_value("1..="),
+            Err(nom::Err::Failure(_))
+        ));
+        assert!(matches!(
+            parse_value("[1..=]"),
+            Err(nom::Err::Failure(_))
+        ));
+        assert!(parse_value("..=").is_err());
     }
 }
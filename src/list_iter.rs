@@ -0,0 +1,159 @@
+use alloc::vec;
+use core::iter::FusedIterator;
+
+use crate::{
+    parse_ws, Error, Event, EventReader, Expected, Located,
+    Value,
+};
+
+/// Starts reading the list `input` starts with one element at a
+/// time, so that a list too long to hold as a `Vec` of values
+/// can be gone through without ever building it.
+///
+/// Only the opening `[` is read before returning, so an error in
+/// an element is returned by the [`ListIter`] when it gets
+/// there, and anything else than a list is an error expecting
+/// `[`.
+pub fn parse_list_iter(
+    input: &str,
+) -> Result<ListIter<'_>, Error> {
+    let (rest, _) = parse_ws(input)
+        .map_err(|err| Error::from_nom(input, err, None))?;
+    if !rest.starts_with('[') {
+        let offset = input.len() - rest.len();
+        return Err(Located::unexpected(input, offset)
+            .expecting(vec![Expected::Char('[')])
+            .into_error(input));
+    }
+
+    let mut reader = EventReader::new(input);
+    reader.next_event()?;
+    Ok(ListIter {
+        input,
+        reader,
+        done: false,
+    })
+}
+
+/// An iterator over the elements of a list, each parsed whole
+/// as it is reached, as made by [`parse_list_iter`].
+///
+/// The commas between elements and the closing `]` are read
+/// along the way, and whatever follows the list is left to
+/// [`ListIter::remaining_input`], as with
+/// [`from_str_partial`](crate::from_str_partial). The first
+/// error, in an element or in the list itself, is the last item:
+/// the iterator ends after it rather than guessing where the
+/// next element starts.
+#[derive(Clone, Debug)]
+pub struct ListIter<'a> {
+    input: &'a str,
+    reader: EventReader<'a>,
+    /// Whether the list ended or failed.
+    done: bool,
+}
+
+impl<'a> ListIter<'a> {
+    /// Returns the input after what has been read so far, which
+    /// is what follows the list's `]` once the iterator has
+    /// ended, and from where the next element is looked for
+    /// until then.
+    pub fn remaining_input(&self) -> &'a str {
+        &self.input[self.reader.offset()..]
+    }
+}
+
+impl<'a> Iterator for ListIter<'a> {
+    type Item = Result<Value<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = match self.reader.read_value() {
+            Ok(Some(value)) => return Some(Ok(value)),
+            // There is no element here, so only the `]` can be
+            Ok(None) => match self.reader.next_event() {
+                Ok(event) => {
+                    debug_assert_eq!(
+                        event,
+                        Some(Event::ListEnd)
+                    );
+                    None
+                }
+                Err(err) => Some(Err(err)),
+            },
+            Err(err) => Some(Err(err)),
+        };
+        self.done = true;
+        result
+    }
+}
+
+impl FusedIterator for ListIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec::Vec};
+
+    use crate::{from_str, parse_list_iter, ErrorKind, Value};
+
+    #[test]
+    fn parses_elements_as_they_are_reached() {
+        let text = "// points\n[1, Some([2, 3]), P { x: 4 }, 5..6,] rest";
+        let mut elements = parse_list_iter(text).unwrap();
+        assert_eq!(elements.next(), Some(Ok(Value::Integer(1))));
+        assert_eq!(
+            elements.remaining_input(),
+            ", Some([2, 3]), P { x: 4 }, 5..6,] rest"
+        );
+
+        let rest: Vec<_> =
+            elements.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            rest,
+            ["Some([2, 3])", "P { x: 4 }", "5..6"]
+                .map(|text| from_str(text).unwrap())
+        );
+        assert_eq!(elements.remaining_input(), " rest");
+        assert_eq!(elements.next(), None);
+
+        let mut empty = parse_list_iter("[ ]").unwrap();
+        assert_eq!(empty.next(), None);
+        assert_eq!(empty.remaining_input(), "");
+    }
+
+    #[test]
+    fn ends_at_the_first_error() {
+        let mut elements =
+            parse_list_iter("[1, [2 3], 4]").unwrap();
+        assert_eq!(elements.next(), Some(Ok(Value::Integer(1))));
+        let err = elements.next().unwrap().unwrap_err();
+        assert_eq!(err, from_str("[1, [2 3], 4]").unwrap_err());
+        assert_eq!(elements.next(), None);
+
+        for (text, kind) in [
+            ("[1 2]", ErrorKind::UnexpectedChar('2')),
+            ("[1, 2", ErrorKind::UnexpectedEnd),
+            ("[\"\\q\"]", ErrorKind::InvalidEscape),
+        ] {
+            let results: Vec<_> = parse_list_iter(text)
+                .unwrap()
+                .map(|result| result.map_err(|err| err.kind()))
+                .collect();
+            assert_eq!(
+                results.last(),
+                Some(&Err(kind)),
+                "{text}"
+            );
+        }
+
+        let err = parse_list_iter(" {1: 2}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unexpected character '{' at line 1, column 2, \
+             expected '['"
+        );
+    }
+}
@@ -0,0 +1,284 @@
+/// Builds a [`Value`](crate::Value) written like the values
+/// `parse_value` reads, with Rust expressions spliced in.
+///
+/// - `[..]` is a list, `(..)` a tuple, and `()` the unit value.
+///   Parentheses always make a tuple, so `(x)` is a tuple of
+///   one, the way `Debug` writes it as `(x,)`.
+/// - `{"key": ..}` is an object. A key in parentheses, as in
+///   `{(name): ..}`, is an expression giving a `&str`, `String`
+///   or `Cow<str>`.
+/// - `Name { field: .. }` is a struct with named fields, in the
+///   order they are written.
+/// - `Some(..)`, `None`, `Ok(..)` and `Err(..)` are written out.
+/// - Anything else is an expression converted with
+///   `Value::from`, such as a literal, a local variable or a
+///   function call. An expression with a `,` outside of brackets
+///   has to be bound to a variable first.
+///
+/// ```
+/// use tson::{tson, Value};
+///
+/// let retries = 3;
+/// let host = String::from("localhost");
+/// let value = tson!({
+///     "servers": [Server { host: host, port: 8080u16 }],
+///     "retries": Some(retries),
+///     "pair": ('a', -1.5),
+///     "fallback": None,
+/// });
+///
+/// assert_eq!(value.get_path("servers.0.port"), Some(&Value::Integer(8080)));
+/// assert_eq!(
+///     value["servers"].to_string(),
+///     r#"[Server { host: "localhost", port: 8080 }]"#,
+/// );
+/// ```
+#[macro_export]
+macro_rules! tson {
+    // The elements of a list or tuple, each converted and
+    // collected before the ones after it
+    (@elements [$($done:expr,)*]) => {
+        $crate::__private::vec![$($done),*]
+    };
+    (@elements [$($done:expr,)*] , $($rest:tt)*) => {
+        ::core::compile_error!("expected a value before `,` in tson!")
+    };
+    (@elements [$($done:expr,)*] $value:tt $(, $($rest:tt)*)?) => {
+        $crate::tson!(
+            @elements [$($done,)* $crate::tson!($value),] $($($rest)*)?
+        )
+    };
+    (@elements [$($done:expr,)*] $first:tt $second:tt $(, $($rest:tt)*)?) => {
+        $crate::tson!(
+            @elements [$($done,)* $crate::tson!($first $second),] $($($rest)*)?
+        )
+    };
+    (@elements [$($done:expr,)*] $value:expr $(, $($rest:tt)*)?) => {
+        $crate::tson!(
+            @elements [$($done,)* $crate::Value::from($value),] $($($rest)*)?
+        )
+    };
+
+    // The entries of an object or struct, as pairs of a key and
+    // a value
+    (@entries [$($done:expr,)*]) => {
+        $crate::__private::vec![$($done),*]
+    };
+    (@entries [$($done:expr,)*] $key:literal : $($rest:tt)*) => {
+        $crate::tson!(
+            @entry [$($done,)*] ($crate::__private::Cow::Borrowed($key)) [$key] $($rest)*
+        )
+    };
+    (@entries [$($done:expr,)*] $key:ident : $($rest:tt)*) => {
+        $crate::tson!(
+            @entry [$($done,)*]
+                ($crate::__private::Cow::Borrowed(::core::stringify!($key))) [$key]
+                $($rest)*
+        )
+    };
+    (@entries [$($done:expr,)*] ($key:expr) : $($rest:tt)*) => {
+        $crate::tson!(
+            @entry [$($done,)*] ($crate::__private::Cow::from($key)) [($key)] $($rest)*
+        )
+    };
+    (@entries [$($done:expr,)*] $key:tt $($rest:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "expected a key followed by `:` in tson!, found `",
+            ::core::stringify!($key),
+            "`",
+        ))
+    };
+    (@entry [$($done:expr,)*] ($key:expr) [$($written:tt)*] $(, $($rest:tt)*)?) => {
+        ::core::compile_error!(::core::concat!(
+            "expected a value after `",
+            ::core::stringify!($($written)*),
+            ":` in tson!",
+        ))
+    };
+    (@entry [$($done:expr,)*] ($key:expr) [$($written:tt)*] $value:tt $(, $($rest:tt)*)?) => {
+        $crate::tson!(
+            @entries [$($done,)* ($key, $crate::tson!($value)),] $($($rest)*)?
+        )
+    };
+    (@entry [$($done:expr,)*] ($key:expr) [$($written:tt)*] $first:tt $second:tt $(, $($rest:tt)*)?) => {
+        $crate::tson!(
+            @entries [$($done,)* ($key, $crate::tson!($first $second)),] $($($rest)*)?
+        )
+    };
+    (@entry [$($done:expr,)*] ($key:expr) [$($written:tt)*] $value:expr $(, $($rest:tt)*)?) => {
+        $crate::tson!(
+            @entries [$($done,)* ($key, $crate::Value::from($value)),] $($($rest)*)?
+        )
+    };
+
+    () => {
+        ::core::compile_error!("expected a value in tson!")
+    };
+    (None) => {
+        $crate::Value::Optional(::core::option::Option::None)
+    };
+    (Some($($value:tt)+)) => {
+        $crate::Value::Optional(::core::option::Option::Some(
+            $crate::__private::Box::new($crate::tson!($($value)+)),
+        ))
+    };
+    (Ok($($value:tt)+)) => {
+        $crate::Value::Result(::core::result::Result::Ok(
+            $crate::__private::Box::new($crate::tson!($($value)+)),
+        ))
+    };
+    (Err($($value:tt)+)) => {
+        $crate::Value::Result(::core::result::Result::Err(
+            $crate::__private::Box::new($crate::tson!($($value)+)),
+        ))
+    };
+    ([$($elements:tt)*]) => {
+        $crate::Value::List($crate::tson!(@elements [] $($elements)*))
+    };
+    (($($elements:tt)*)) => {
+        $crate::Value::Tuple($crate::tson!(@elements [] $($elements)*))
+    };
+    ({$($entries:tt)*}) => {
+        $crate::Value::Object(
+            $crate::tson!(@entries [] $($entries)*).into_iter().collect(),
+        )
+    };
+    ($name:ident {$($fields:tt)*}) => {
+        $crate::Value::Struct {
+            name: $crate::__private::Cow::Borrowed(::core::stringify!($name)),
+            fields: $crate::tson!(@entries [] $($fields)*),
+            non_exhaustive: false,
+        }
+    };
+    ($value:expr) => {
+        $crate::Value::from($value)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        borrow::Cow,
+        string::{String, ToString},
+        vec,
+    };
+
+    use crate::{
+        from_str, tests::with_f64_floats, ObjectMap, Value,
+    };
+
+    #[test]
+    fn builds_what_parses() {
+        let cases = [
+            (tson!(1), "1"),
+            (tson!(-1), "-1"),
+            (tson!(1.5), "1.5"),
+            (tson!(-1.5), "-1.5"),
+            (tson!(true), "true"),
+            (tson!('x'), "'x'"),
+            (tson!("a"), "\"a\""),
+            (tson!(()), "()"),
+            (tson!([]), "[]"),
+            (tson!(["a", 1.5, Some(true), [1, 2]]), r#"["a", 1.5, Some(true), [1, 2]]"#),
+            (tson!([1, 2,]), "[1, 2]"),
+            (tson!([-1, -2.5, 1 + 1]), "[-1, -2.5, 2]"),
+            (tson!((1,)), "(1,)"),
+            (tson!((1)), "(1,)"),
+            (tson!(('a', (), [()])), "('a', (), [()])"),
+            (tson!(None), "None"),
+            (tson!(Some(None)), "Some(None)"),
+            (tson!(Some([Some(1), None])), "Some([Some(1), None])"),
+            (tson!([Ok(1), Err("no")]), r#"[Ok(1), Err("no")]"#),
+            (tson!({}), "{}"),
+            (tson!({"a": 1}), r#"{"a": 1}"#),
+            (tson!({"a": {"b": [None]},}), r#"{"a": {"b": [None]}}"#),
+            (tson!(Point { x: 1, y: -2 }), "Point { x: 1, y: -2 }"),
+            (tson!(Unit {}), "Unit {}"),
+            (
+                tson!([Line { from: Point { x: 0 }, to: Some(Point { x: 1 }) }]),
+                "[Line { from: Point { x: 0 }, to: Some(Point { x: 1 }) }]",
+            ),
+        ];
+
+        for (value, text) in cases {
+            assert_eq!(
+                value,
+                with_f64_floats(from_str(text).unwrap()),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn splices_expressions() {
+        let count = 3u8;
+        let name = String::from("tson");
+        let list = vec![1.5, 2.5];
+        let inner = tson!([count]);
+        let key = "dynamic";
+        let add = |a: i64, b: i64| a + b;
+
+        let value = tson!({
+            "spliced": [count, name, list, inner],
+            "called": add(1, 2),
+            "method": "a-b".len(),
+            "computed": Some(count as f64 * 0.5),
+            (key): (key.to_uppercase()),
+            (String::from("owned")): None,
+        });
+
+        assert_eq!(
+            value,
+            with_f64_floats(
+                from_str(
+                    r#"{
+                    "spliced": [3, "tson", [1.5, 2.5], [3]],
+                    "called": 3,
+                    "method": 3,
+                    "computed": Some(1.5),
+                    "dynamic": ("DYNAMIC",),
+                    "owned": None,
+                }"#
+                )
+                .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn keeps_fields_in_order_and_borrows() {
+        let text = String::from("borrowed");
+        let value =
+            tson!(Config { z: 1, "a": [&text[..]], m: None });
+        let Value::Struct { name, fields, .. } = &value else {
+            panic!("not a struct: {value:?}");
+        };
+
+        assert_eq!(name, "Config");
+        let keys: vec::Vec<_> =
+            fields.iter().map(|(key, _)| &key[..]).collect();
+        assert_eq!(keys, ["z", "a", "m"]);
+        assert!(matches!(
+            value.get_path("a.0"),
+            Some(Value::String(Cow::Borrowed("borrowed")))
+        ));
+        assert_eq!(
+            value.to_string(),
+            r#"Config { z: 1, a: ["borrowed"], m: None }"#
+        );
+    }
+
+    #[test]
+    fn builds_objects_from_entries() {
+        let mut object = ObjectMap::new();
+        object.insert("a".into(), Value::Integer(1));
+        object.insert("b".into(), Value::Optional(None));
+
+        assert_eq!(
+            tson!({"a": 1, "b": None}),
+            Value::Object(object)
+        );
+        // A later duplicate key wins, as when parsing
+        assert_eq!(tson!({"a": 1, "a": 2}), tson!({"a": 2}));
+    }
+}
@@ -0,0 +1,347 @@
+use core::mem;
+
+use crate::{take, Value};
+
+/// How [`Value::merge`] combines two values, which by default
+/// lets the value merged in win, replaces lists and ignores
+/// `None`, as suits overriding the defaults of a config.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MergeStrategy {
+    /// What happens where neither value is merged into the
+    /// other: numbers, strings and the like, tuples, and values
+    /// of different kinds, such as an object merged onto a
+    /// float.
+    pub scalars: ScalarMerge,
+    /// What happens where a list is merged onto a list, or a
+    /// set onto a set.
+    pub lists: ListMerge,
+    /// Let a `None` merged in replace whatever it is merged
+    /// onto. Otherwise a `None` changes nothing, so that it can
+    /// stand for a setting that was left out.
+    pub clear_with_none: bool,
+}
+
+impl MergeStrategy {
+    pub fn scalars(mut self, scalars: ScalarMerge) -> Self {
+        self.scalars = scalars;
+        self
+    }
+
+    pub fn lists(mut self, lists: ListMerge) -> Self {
+        self.lists = lists;
+        self
+    }
+
+    pub fn clear_with_none(
+        mut self,
+        clear_with_none: bool,
+    ) -> Self {
+        self.clear_with_none = clear_with_none;
+        self
+    }
+}
+
+/// How [`MergeStrategy::scalars`] are merged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ScalarMerge {
+    /// The value merged in replaces the one it is merged onto.
+    #[default]
+    Replace,
+    /// The value merged onto is kept.
+    KeepExisting,
+}
+
+/// How [`MergeStrategy::lists`] are merged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ListMerge {
+    /// The list merged in replaces the one it is merged onto.
+    #[default]
+    Replace,
+    /// The list merged onto is kept.
+    KeepExisting,
+    /// The elements of the list merged in are added to the end
+    /// of the one it is merged onto. For sets, only the ones
+    /// not already in it are.
+    Concat,
+}
+
+impl<'a> Value<'a> {
+    /// Merges `other` into this value, as when laying overrides
+    /// over a config's defaults, following `strategy` wherever
+    /// the two values conflict.
+    ///
+    /// Objects are merged entry by entry, maps by their keys,
+    /// and structs of the same name field by field, each
+    /// merging the values under a key both have and adding
+    /// the ones only `other` has, in the order they are in
+    /// `other`. A `Some` merged onto a `Some` merges what
+    /// they hold. Anything merged onto a `None` replaces it,
+    /// since it stands for something not set, and a `None`
+    /// merged in changes nothing unless
+    /// [`MergeStrategy::clear_with_none`] is set. Lists and sets
+    /// follow [`MergeStrategy::lists`], and anything else,
+    /// including structs of different names and values of
+    /// different kinds, [`MergeStrategy::scalars`].
+    pub fn merge(
+        &mut self,
+        mut other: Value<'a>,
+        strategy: MergeStrategy,
+    ) {
+        match (self, &mut other) {
+            (existing, Value::Optional(None)) => {
+                if strategy.clear_with_none {
+                    *existing = Value::Optional(None);
+                }
+            }
+            (existing @ Value::Optional(None), other) => {
+                *existing = take(other);
+            }
+            (
+                Value::Optional(Some(existing)),
+                Value::Optional(Some(other)),
+            ) => existing.merge(take(other), strategy),
+            (Value::Object(existing), Value::Object(other)) => {
+                for (key, value) in mem::take(other) {
+                    match existing.get_mut(&key) {
+                        Some(existing) => {
+                            existing.merge(value, strategy)
+                        }
+                        None => {
+                            existing.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (
+                Value::Struct {
+                    name,
+                    fields: existing,
+                    ..
+                },
+                Value::Struct {
+                    name: other_name,
+                    fields: other,
+                    ..
+                },
+            ) if name == other_name => {
+                for (field, value) in mem::take(other) {
+                    match existing
+                        .iter_mut()
+                        .find(|(existing, _)| *existing == field)
+                    {
+                        Some((_, existing)) => {
+                            existing.merge(value, strategy)
+                        }
+                        None => existing.push((field, value)),
+                    }
+                }
+            }
+            (Value::Map(existing), Value::Map(other)) => {
+                for (key, value) in mem::take(other) {
+                    match existing
+                        .iter_mut()
+                        .find(|(existing, _)| *existing == key)
+                    {
+                        Some((_, existing)) => {
+                            existing.merge(value, strategy)
+                        }
+                        None => existing.push((key, value)),
+                    }
+                }
+            }
+            (
+                existing @ (Value::List(_) | Value::Set(_)),
+                other @ (Value::List(_) | Value::Set(_)),
+            ) if existing.kind() == other.kind() => {
+                match (strategy.lists, existing, other) {
+                    (ListMerge::Replace, existing, other) => {
+                        *existing = take(other)
+                    }
+                    (ListMerge::KeepExisting, _, _) => {}
+                    (
+                        ListMerge::Concat,
+                        Value::Set(existing),
+                        Value::Set(other),
+                    ) => {
+                        for element in mem::take(other) {
+                            if !existing.contains(&element) {
+                                existing.push(element);
+                            }
+                        }
+                    }
+                    (
+                        ListMerge::Concat,
+                        Value::List(existing),
+                        Value::List(other),
+                    ) => existing.append(other),
+                    _ => unreachable!(
+                        "only lists and sets get here"
+                    ),
+                }
+            }
+            (existing, other) => match strategy.scalars {
+                ScalarMerge::Replace => *existing = take(other),
+                ScalarMerge::KeepExisting => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        from_str, tson, ListMerge, MergeStrategy, ScalarMerge,
+        Value,
+    };
+
+    const DEFAULTS: &str = r#"Config {
+        name: "service",
+        retries: 3,
+        servers: [Server { host: "a", port: 80 }],
+        tls: Some(Tls { cert: "default.pem", verify: true }),
+        proxy: None,
+        labels: {"team": "core", "tier": "2"},
+        ports: {80, 443},
+        limits: {1: "low", 2: "high"},
+        timeout: 3.5,
+    }"#;
+
+    const SITE: &str = r#"Config {
+        retries: 5,
+        servers: [Server { host: "b", port: 81 }],
+        tls: Some(Tls { cert: "site.pem" }),
+        proxy: Some("proxy:3128"),
+        labels: {"tier": "1", "region": "eu"},
+        ports: {443, 8443},
+        limits: {2: "higher"},
+        timeout: None,
+    }"#;
+
+    const LOCAL: &str = r#"Config {
+        name: Name("local"),
+        tls: Tls { cert: "local.pem" },
+        labels: {"region": None, "debug": true},
+        proxy: None,
+        extra: [1],
+    }"#;
+
+    fn merged(strategy: MergeStrategy) -> Value<'static> {
+        let mut config = from_str(DEFAULTS).unwrap();
+        for layer in [SITE, LOCAL] {
+            config.merge(from_str(layer).unwrap(), strategy);
+        }
+        config
+    }
+
+    #[test]
+    fn lets_later_layers_win() {
+        assert_eq!(
+            merged(MergeStrategy::default()),
+            from_str(
+                r#"Config {
+                    name: Name("local"),
+                    retries: 5,
+                    servers: [Server { host: "b", port: 81 }],
+                    tls: Tls { cert: "local.pem" },
+                    proxy: Some("proxy:3128"),
+                    labels: {"team": "core", "tier": "1", "region": "eu", "debug": true},
+                    ports: {443, 8443},
+                    limits: {1: "low", 2: "higher"},
+                    timeout: 3.5,
+                    extra: [1],
+                }"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn keeps_and_concatenates_on_request() {
+        let strategy = MergeStrategy::default()
+            .scalars(ScalarMerge::KeepExisting)
+            .lists(ListMerge::Concat);
+        assert_eq!(
+            merged(strategy),
+            from_str(
+                r#"Config {
+                    name: "service",
+                    retries: 3,
+                    servers: [Server { host: "a", port: 80 }, Server { host: "b", port: 81 }],
+                    tls: Some(Tls { cert: "default.pem", verify: true }),
+                    proxy: Some("proxy:3128"),
+                    labels: {"team": "core", "tier": "2", "region": "eu", "debug": true},
+                    ports: {80, 443, 8443},
+                    limits: {1: "low", 2: "high"},
+                    timeout: 3.5,
+                    extra: [1],
+                }"#
+            )
+            .unwrap()
+        );
+
+        let mut list = tson!([1]);
+        list.merge(
+            tson!([2]),
+            MergeStrategy::default()
+                .lists(ListMerge::KeepExisting),
+        );
+        assert_eq!(list, tson!([1]));
+        // A set merged onto a list is of another kind
+        list.merge(from_str("{2}").unwrap(), strategy);
+        assert_eq!(list, tson!([1]));
+    }
+
+    #[test]
+    fn clears_with_none_on_request() {
+        let strategy =
+            MergeStrategy::default().clear_with_none(true);
+        let config = merged(strategy);
+
+        assert_eq!(config["proxy"], Value::Optional(None));
+        assert_eq!(config["timeout"], Value::Optional(None));
+        assert_eq!(
+            config["labels"].get_key("region"),
+            Some(&Value::Optional(None))
+        );
+        assert_eq!(config["retries"], Value::Integer(5));
+    }
+
+    #[test]
+    fn merges_through_options() {
+        let mut value = tson!(Some(Tls {
+            cert: "a",
+            verify: true
+        }));
+        value.merge(
+            tson!(Some(Tls { cert: "b" })),
+            MergeStrategy::default(),
+        );
+        assert_eq!(
+            value,
+            tson!(Some(Tls {
+                cert: "b",
+                verify: true
+            }))
+        );
+
+        // Other structs, and anything but a `Some`, are scalars
+        value.merge(
+            tson!(Some(Other { cert: "c" })),
+            MergeStrategy::default(),
+        );
+        assert_eq!(value, tson!(Some(Other { cert: "c" })));
+        value.merge(tson!("plain"), MergeStrategy::default());
+        assert_eq!(value, tson!("plain"));
+
+        let mut none = tson!(None);
+        none.merge(
+            tson!([1]),
+            MergeStrategy::default()
+                .scalars(ScalarMerge::KeepExisting),
+        );
+        assert_eq!(none, tson!([1]));
+    }
+}
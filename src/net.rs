@@ -0,0 +1,229 @@
+use core::net::{IpAddr, SocketAddr};
+
+use nom::{error::ErrorKind, IResult};
+
+use crate::Value;
+
+/// Parses a bare IP or socket address, as `IpAddr` and
+/// `SocketAddr` print them: `10.0.0.1`, `::1`, `127.0.0.1:8080`
+/// or `[::1]:443`.
+///
+/// An address ends at the first `..`, so it may start a range,
+/// and a trailing `:` is left alone when the address wouldn't
+/// parse with it, so it may be the key of a map. A bracketed
+/// IPv6 address is only read when a port follows it, leaving
+/// `[::1]` to be parsed as a list.
+pub(crate) fn parse_address(
+    input: &str,
+) -> IResult<&str, Value<'_>> {
+    let error = || {
+        nom::Err::Error(nom::error::Error::new(
+            input,
+            ErrorKind::Verify,
+        ))
+    };
+
+    let token = if input.starts_with('[') {
+        let end = input.find(']').ok_or_else(error)?;
+        let port = input[end + 1..].strip_prefix(':').map_or(
+            0,
+            |port| {
+                port.bytes()
+                    .take_while(u8::is_ascii_digit)
+                    .count()
+            },
+        );
+        if port == 0 {
+            return Err(error());
+        }
+
+        &input[..end + 2 + port]
+    } else {
+        let len = input
+            .find(|chr: char| {
+                !(chr.is_ascii_hexdigit()
+                    || matches!(chr, '.' | ':'))
+            })
+            .unwrap_or(input.len());
+        let token = &input[..len];
+
+        token.find("..").map_or(token, |end| &token[..end])
+    };
+
+    for text in [Some(token), token.strip_suffix(':')]
+        .into_iter()
+        .flatten()
+    {
+        let rest = &input[text.len()..];
+        if rest.starts_with(|chr: char| {
+            chr.is_alphanumeric() || matches!(chr, '_' | '-')
+        }) {
+            return Err(error());
+        }
+
+        if let Ok(addr) = text.parse() {
+            return Ok((rest, Value::SocketAddr(addr)));
+        }
+        if let Ok(addr) = text.parse() {
+            return Ok((rest, Value::IpAddr(addr)));
+        }
+    }
+
+    Err(error())
+}
+
+impl Value<'_> {
+    /// Returns the IP address this value holds, whether it was
+    /// parsed as a `Value::IpAddr` or is a `Value::String`
+    /// holding one.
+    pub fn as_ip_addr(&self) -> Option<IpAddr> {
+        match self {
+            Value::IpAddr(addr) => Some(*addr),
+            Value::String(string) => string.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the socket address this value holds, whether it
+    /// was parsed as a `Value::SocketAddr` or is a
+    /// `Value::String` holding one.
+    pub fn as_socket_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Value::SocketAddr(addr) => Some(*addr),
+            Value::String(string) => string.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl From<IpAddr> for Value<'_> {
+    fn from(addr: IpAddr) -> Self {
+        Value::IpAddr(addr)
+    }
+}
+
+impl From<SocketAddr> for Value<'_> {
+    fn from(addr: SocketAddr) -> Self {
+        Value::SocketAddr(addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+    use core::net::{IpAddr, SocketAddr};
+
+    use crate::{
+        parse_value, parse_value_with, tests::float,
+        ParserOptions, Value,
+    };
+
+    fn ip(text: &str) -> Value<'static> {
+        Value::IpAddr(text.parse::<IpAddr>().unwrap())
+    }
+
+    fn socket(text: &str) -> Value<'static> {
+        Value::SocketAddr(text.parse::<SocketAddr>().unwrap())
+    }
+
+    #[test]
+    fn parses_addresses_when_asked() {
+        let options = ParserOptions::default().addresses(true);
+        let cases = [
+            (
+                "[127.0.0.1:8080, 10.0.0.1, 255.255.255.255]",
+                Value::List(vec![
+                    socket("127.0.0.1:8080"),
+                    ip("10.0.0.1"),
+                    ip("255.255.255.255"),
+                ]),
+            ),
+            (
+                "[[::1]:443, [fe80::1]:8080]",
+                Value::List(vec![
+                    socket("[::1]:443"),
+                    socket("[fe80::1]:8080"),
+                ]),
+            ),
+            (
+                "[::1, fe80::1, 2001:DB8::8a2e:370:7334, ::ffff:10.0.0.1]",
+                Value::List(vec![
+                    ip("::1"),
+                    ip("fe80::1"),
+                    ip("2001:db8::8a2e:370:7334"),
+                    ip("::ffff:10.0.0.1"),
+                ]),
+            ),
+            ("[::1]", Value::List(vec![ip("::1")])),
+            (
+                r#"{10.0.0.1: "a", ::1: "b"}"#,
+                Value::Map(vec![
+                    (ip("10.0.0.1"), Value::String("a".into())),
+                    (ip("::1"), Value::String("b".into())),
+                ]),
+            ),
+            (
+                "10.0.0.1..10.0.0.9",
+                Value::Range {
+                    start: Some(Box::new(ip("10.0.0.1"))),
+                    end: Some(Box::new(ip("10.0.0.9"))),
+                    inclusive: false,
+                },
+            ),
+            (
+                "Peer { addr: [::1]:443, local: 127.0.0.1 }",
+                Value::Struct {
+                    name: "Peer".into(),
+                    fields: vec![
+                        ("addr".into(), socket("[::1]:443")),
+                        ("local".into(), ip("127.0.0.1")),
+                    ],
+                    non_exhaustive: false,
+                },
+            ),
+        ];
+
+        for (text, expected) in cases {
+            let (rest, value) =
+                parse_value_with(text, options).unwrap();
+            assert_eq!(rest, "", "{text}");
+            assert_eq!(value, expected, "{text}");
+            assert_eq!(
+                parse_value_with(&value.to_string(), options),
+                Ok(("", value))
+            );
+        }
+    }
+
+    #[test]
+    fn leaves_numbers_alone() {
+        let options = ParserOptions::default().addresses(true);
+        for text in [
+            "[127.0, 1..5, 1.5..2, 1e5, 8080, 0xff, -1, abc]",
+            "{1: 2, 1.5: 3, a: 4}",
+            "Face(1)",
+            "10.0.0.1x",
+            "::1_",
+        ] {
+            assert_eq!(
+                parse_value_with(text, options),
+                parse_value(text),
+                "{text}"
+            );
+        }
+
+        assert_eq!(
+            parse_value("10.0.0.1"),
+            Ok((".0.1", float("10.0")))
+        );
+        assert!(parse_value("[127.0.0.1:8080]").is_err());
+        assert_eq!(
+            parse_value(r#""[::1]:443""#)
+                .unwrap()
+                .1
+                .as_socket_addr(),
+            socket("[::1]:443").as_socket_addr()
+        );
+        assert_eq!(socket("[::1]:443").to_string(), "[::1]:443");
+    }
+}
@@ -0,0 +1,389 @@
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::fmt;
+
+use crate::Value;
+
+/// A step of a path into a value, for [`Value::get_at`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathSegment<'k> {
+    /// A field of a struct, or an entry of an object or of a
+    /// map keyed by this string, as [`Value::get_key`]
+    /// finds them.
+    Key(&'k str),
+    /// An element of a list, tuple, set or tuple struct, as
+    /// [`Value::get`] finds them.
+    Index(usize),
+}
+
+/// The path to a value found by [`Value::find_all`] or
+/// [`Value::find_key`], borrowing its keys from the value
+/// searched.
+///
+/// It is written the way [`Value::get_path`] reads paths, as in
+/// `items.3.name`, and its segments can be given to
+/// [`Value::get_at`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Path<'k> {
+    segments: Vec<PathSegment<'k>>,
+}
+
+impl<'k> Path<'k> {
+    pub fn segments(&self) -> &[PathSegment<'k>] {
+        &self.segments
+    }
+
+    /// Makes this the path to `segment` in what it led to.
+    pub(crate) fn push(&mut self, segment: PathSegment<'k>) {
+        self.segments.push(segment);
+    }
+
+    /// Makes this the path it was when it had `len` segments.
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.segments.truncate(len);
+    }
+
+    /// Returns the path to `segment` in what this leads to.
+    pub(crate) fn join(&self, segment: PathSegment<'k>) -> Self {
+        let mut segments =
+            Vec::with_capacity(self.segments.len() + 1);
+        segments.extend_from_slice(&self.segments);
+        segments.push(segment);
+        Path { segments }
+    }
+}
+
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, segment) in self.segments.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(".")?;
+            }
+            match segment {
+                PathSegment::Index(idx) => write!(f, "{idx}")?,
+                PathSegment::Key(key) => {
+                    for chr in key.chars() {
+                        if matches!(chr, '.' | '\\') {
+                            f.write_str("\\")?;
+                        }
+                        write!(f, "{chr}")?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Returns the value at the end of `path`, a list of keys
+    /// and positions separated by `.`, as in `items.3.name`,
+    /// or `None` if anything along the way is missing.
+    ///
+    /// A segment made up only of digits picks an element from a
+    /// list, tuple, set or tuple struct, and like any other
+    /// segment picks a field or entry from anything keyed, so
+    /// `{"3": 1}` has `1` at `3`. A `.` or `\` in a key is
+    /// written `\.` or `\\`, and a `\` before anything else is
+    /// kept as it is. An empty path is the value itself.
+    ///
+    /// Any `Some(..)` found along the way is looked inside of,
+    /// so `config.retries` finds `3` in
+    /// `{"config": Some(Config { retries: 3 })}`, but a `Some`
+    /// at the end of the path is returned as it is.
+    pub fn get_path(&self, path: &str) -> Option<&Value<'a>> {
+        segments(path).try_fold(self, |value, segment| {
+            let value = value.unwrapped();
+            match index(value, &segment) {
+                Some(idx) => value.get(idx),
+                None => value.get_key(&segment),
+            }
+        })
+    }
+
+    /// Returns the value at the end of `path` like
+    /// [`Value::get_path`] does, but so that it can be changed.
+    pub fn get_path_mut(
+        &mut self,
+        path: &str,
+    ) -> Option<&mut Value<'a>> {
+        segments(path).try_fold(self, |value, segment| {
+            let value = value.unwrapped_mut();
+            match index(value, &segment) {
+                Some(idx) => value.get_mut(idx),
+                None => value.get_key_mut(&segment),
+            }
+        })
+    }
+
+    /// Returns the value at the end of `path`, like
+    /// [`Value::get_path`] does but with its segments already
+    /// split, so that keys need no escaping and a key made up of
+    /// digits is never taken to be a position.
+    pub fn get_at(
+        &self,
+        path: &[PathSegment<'_>],
+    ) -> Option<&Value<'a>> {
+        path.iter().try_fold(self, |value, segment| {
+            let value = value.unwrapped();
+            match *segment {
+                PathSegment::Key(key) => value.get_key(key),
+                PathSegment::Index(idx) => value.get(idx),
+            }
+        })
+    }
+
+    /// Returns the value at the end of `path` like
+    /// [`Value::get_at`] does, but so that it can be changed.
+    pub fn get_at_mut(
+        &mut self,
+        path: &[PathSegment<'_>],
+    ) -> Option<&mut Value<'a>> {
+        path.iter().try_fold(self, |value, segment| {
+            let value = value.unwrapped_mut();
+            match *segment {
+                PathSegment::Key(key) => value.get_key_mut(key),
+                PathSegment::Index(idx) => value.get_mut(idx),
+            }
+        })
+    }
+
+    /// Returns the value inside of any `Some(..)` this is.
+    pub(crate) fn unwrapped(&self) -> &Value<'a> {
+        let mut value = self;
+        while let Value::Optional(Some(inner)) = value {
+            value = inner;
+        }
+        value
+    }
+
+    pub(crate) fn unwrapped_mut(&mut self) -> &mut Value<'a> {
+        match self {
+            Value::Optional(Some(inner)) => {
+                inner.unwrapped_mut()
+            }
+            value => value,
+        }
+    }
+}
+
+/// Returns the position `segment` names if `value` has
+/// elements and `segment` is made up of digits.
+pub(crate) fn index(
+    value: &Value<'_>,
+    segment: &str,
+) -> Option<usize> {
+    let positional = matches!(
+        value,
+        Value::List(_)
+            | Value::Tuple(_)
+            | Value::Set(_)
+            | Value::TupleStruct { .. }
+    );
+    let digits = !segment.is_empty()
+        && segment.bytes().all(|b| b.is_ascii_digit());
+
+    // Too many digits for a `usize` is a position past the end
+    (positional && digits)
+        .then(|| segment.parse().unwrap_or(usize::MAX))
+}
+
+/// Splits `path` at each `.` not escaped with `\`, undoing the
+/// escapes.
+pub(crate) fn segments(
+    path: &str,
+) -> impl Iterator<Item = Cow<'_, str>> {
+    let mut rest = (!path.is_empty()).then_some(path);
+    core::iter::from_fn(move || {
+        let path = rest?;
+        let mut escaped = false;
+        let mut end = path.len();
+        for (idx, b) in path.bytes().enumerate() {
+            match b {
+                b'.' if !escaped => {
+                    end = idx;
+                    break;
+                }
+                b'\\' => escaped = !escaped,
+                _ => escaped = false,
+            }
+        }
+
+        let segment = &path[..end];
+        rest = path.get(end + 1..);
+        Some(unescape(segment))
+    })
+}
+
+fn unescape(segment: &str) -> Cow<'_, str> {
+    if !segment.contains('\\') {
+        return Cow::Borrowed(segment);
+    }
+
+    let mut unescaped = String::with_capacity(segment.len());
+    let mut chars = segment.chars();
+    while let Some(chr) = chars.next() {
+        match (chr, chars.clone().next()) {
+            ('\\', Some(next @ ('.' | '\\'))) => {
+                unescaped.push(next);
+                chars.next();
+            }
+            _ => unescaped.push(chr),
+        }
+    }
+
+    Cow::Owned(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{segments, PathSegment};
+    use crate::{from_str, Value};
+
+    const DOCUMENT: &str = r#"Order {
+        items: [
+            Item { name: "a", tags: {x, y} },
+            Item { name: "b", tags: {} },
+            Item { name: "c", tags: {} },
+            Item { name: "d", tags: {z}, size: Some(Size(3, 4)) },
+        ],
+        counts: {"3": "three", "a.b": "dotted", "a\\b": "slashed"},
+        pair: (1, Some(Some([2]))),
+        note: None,
+    }"#;
+
+    #[test]
+    fn finds_values_along_paths() {
+        let value = from_str(DOCUMENT).unwrap();
+        let at = |path| {
+            value
+                .get_path(path)
+                .unwrap_or_else(|| panic!("{path}"))
+        };
+
+        assert_eq!(
+            at("items.3.name"),
+            &Value::String("d".into())
+        );
+        assert_eq!(
+            at("items.0.tags.1"),
+            &Value::Ident("y".into())
+        );
+        assert_eq!(at("pair.1.0"), &Value::Integer(2));
+        assert_eq!(at("items.3.size.1"), &Value::Integer(4));
+        assert_eq!(at(""), &value);
+        // A `Some` is only looked inside of on the way
+        assert!(at("items.3.size").is_some());
+        assert_eq!(at("note"), &Value::Optional(None));
+    }
+
+    #[test]
+    fn finds_keys_made_of_digits_and_escapes() {
+        let value = from_str(DOCUMENT).unwrap();
+        let string =
+            |text: &'static str| Value::String(text.into());
+
+        assert_eq!(
+            value.get_path("counts.3"),
+            Some(&string("three"))
+        );
+        assert_eq!(
+            value.get_path(r"counts.a\.b"),
+            Some(&string("dotted"))
+        );
+        assert_eq!(
+            value.get_path(r"counts.a\\b"),
+            Some(&string("slashed"))
+        );
+        assert_eq!(value.get_path("counts.a.b"), None);
+        assert_eq!(
+            value.get_at(&[
+                PathSegment::Key("counts"),
+                PathSegment::Key("3")
+            ]),
+            Some(&string("three"))
+        );
+        assert_eq!(
+            value.get_at(&[
+                PathSegment::Key("counts"),
+                PathSegment::Index(3)
+            ]),
+            None
+        );
+        assert_eq!(
+            value.get_at(&[
+                PathSegment::Key("items"),
+                PathSegment::Index(1),
+                PathSegment::Key("name"),
+            ]),
+            Some(&string("b"))
+        );
+    }
+
+    #[test]
+    fn finds_nothing_where_segments_are_missing() {
+        let value = from_str(DOCUMENT).unwrap();
+        for path in [
+            "orders",
+            "items.4",
+            "items.4.name",
+            "items.name",
+            "items.-1",
+            "items.+1",
+            "items.99999999999999999999999999",
+            "items.0.name.0",
+            "items.1.size.0",
+            "note.0",
+            "counts.4",
+            "items.",
+            ".items",
+        ] {
+            assert_eq!(value.get_path(path), None, "{path}");
+        }
+        assert_eq!(
+            value.get_at(&[
+                PathSegment::Key("items"),
+                PathSegment::Key("0")
+            ]),
+            None
+        );
+    }
+
+    #[test]
+    fn changes_values_along_paths() {
+        let mut value = from_str(DOCUMENT).unwrap();
+        *value.get_path_mut("items.3.size.0").unwrap() =
+            Value::Integer(5);
+        *value
+            .get_at_mut(&[
+                PathSegment::Key("counts"),
+                PathSegment::Key("3"),
+            ])
+            .unwrap() = Value::Boolean(true);
+
+        assert_eq!(
+            value.get_path("items.3.size.0"),
+            Some(&Value::Integer(5))
+        );
+        assert_eq!(
+            value.get_path("counts.3"),
+            Some(&Value::Boolean(true))
+        );
+        assert!(value.get_path_mut("items.9").is_none());
+    }
+
+    #[test]
+    fn splits_paths_at_unescaped_dots() {
+        let split = |path| segments(path).collect::<Vec<_>>();
+
+        assert_eq!(split(""), Vec::<&str>::new());
+        assert_eq!(split("a"), ["a"]);
+        assert_eq!(split("a.b.0"), ["a", "b", "0"]);
+        assert_eq!(split("a..b"), ["a", "", "b"]);
+        assert_eq!(split("a."), ["a", ""]);
+        assert_eq!(split(r"a\.b.c"), ["a.b", "c"]);
+        assert_eq!(split(r"a\\.b"), [r"a\", "b"]);
+        assert_eq!(split(r"a\\\.b"), [r"a\.b"]);
+        assert_eq!(split(r"a\b"), [r"a\b"]);
+    }
+}
@@ -0,0 +1,772 @@
+use alloc::{string::String, vec, vec::Vec};
+use core::fmt::{self, Write};
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::{
+    display::{sorted, write_string, Style},
+    EscapeMode, FloatFormat, Value,
+};
+
+/// How [`Value::to_string_pretty_with`] lays out a value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PrettyConfig {
+    /// What each level of nesting is indented with. An empty
+    /// string writes the whole value on a single line.
+    pub indent: String,
+    /// Whether a space follows the commas between elements on a
+    /// single line.
+    pub space_after_comma: bool,
+    /// Whether the last element of a multi-line value is
+    /// followed by a comma, as `{:#?}` does.
+    pub trailing_commas: bool,
+    /// The column that lines should stay within, if any.
+    ///
+    /// With a width, a value is only spread across lines when
+    /// writing it on a single line would go past that column,
+    /// and each of its elements is laid out the same way.
+    /// Without one, every non-empty value is spread across
+    /// lines.
+    pub max_width: Option<usize>,
+    /// How floats are written.
+    pub float_format: FloatFormat,
+    /// How strings and chars are escaped.
+    pub escape_mode: EscapeMode,
+}
+
+impl Default for PrettyConfig {
+    fn default() -> Self {
+        Self {
+            indent: "    ".into(),
+            space_after_comma: true,
+            trailing_commas: true,
+            max_width: None,
+            float_format: FloatFormat::Shortest,
+            escape_mode: EscapeMode::Utf8,
+        }
+    }
+}
+
+impl PrettyConfig {
+    pub fn indent(mut self, indent: impl Into<String>) -> Self {
+        self.indent = indent.into();
+        self
+    }
+
+    pub fn space_after_comma(
+        mut self,
+        space_after_comma: bool,
+    ) -> Self {
+        self.space_after_comma = space_after_comma;
+        self
+    }
+
+    pub fn trailing_commas(
+        mut self,
+        trailing_commas: bool,
+    ) -> Self {
+        self.trailing_commas = trailing_commas;
+        self
+    }
+
+    pub fn max_width(mut self, max_width: usize) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub fn float_format(
+        mut self,
+        float_format: FloatFormat,
+    ) -> Self {
+        self.float_format = float_format;
+        self
+    }
+
+    pub fn escape_mode(
+        mut self,
+        escape_mode: EscapeMode,
+    ) -> Self {
+        self.escape_mode = escape_mode;
+        self
+    }
+
+    fn style(&self) -> Style {
+        Style {
+            floats: self.float_format,
+            escapes: self.escape_mode,
+        }
+    }
+
+    /// The config writing a value on a single line, as values
+    /// that fit within `max_width` are.
+    fn flat(&self) -> Self {
+        Self {
+            indent: String::new(),
+            max_width: None,
+            ..self.clone()
+        }
+    }
+}
+
+impl Value<'_> {
+    /// Writes this value across several lines in the style of
+    /// `{:#?}`, which parses back to an equal value just like
+    /// the output of `Display` does.
+    pub fn to_string_pretty(&self) -> String {
+        self.to_string_pretty_with(&PrettyConfig::default())
+    }
+
+    /// Writes this value like [`Value::to_string_pretty`], laid
+    /// out as `config` says.
+    pub fn to_string_pretty_with(
+        &self,
+        config: &PrettyConfig,
+    ) -> String {
+        let mut out = Output::new(String::new());
+        write_pretty(&mut out, self, config, 0)
+            .expect("writing to a String can't fail");
+
+        out.inner
+    }
+
+    /// Writes this value to `writer` like
+    /// [`Value::to_string_pretty_with`] does, without building
+    /// the whole output in memory first.
+    ///
+    /// The value is written in many small pieces, so a
+    /// `BufWriter` is worth wrapping around unbuffered writers.
+    #[cfg(feature = "std")]
+    pub fn write_pretty_to<W: io::Write>(
+        &self,
+        writer: &mut W,
+        config: &PrettyConfig,
+    ) -> io::Result<()> {
+        write_pretty_io(writer, self, config, 0)
+    }
+}
+
+/// Writes `value` to `writer` as `config` says, as if it were
+/// already nested `depth` levels deep, with the cursor right
+/// after that level's indentation.
+#[cfg(feature = "std")]
+pub(crate) fn write_pretty_io<W: io::Write>(
+    writer: &mut W,
+    value: &Value<'_>,
+    config: &PrettyConfig,
+    depth: usize,
+) -> io::Result<()> {
+    let mut out = Output {
+        inner: IoWriter {
+            inner: writer,
+            error: None,
+        },
+        column: depth * config.indent.chars().count(),
+    };
+
+    match write_pretty(&mut out, value, config, depth) {
+        Ok(()) => Ok(()),
+        Err(fmt::Error) => {
+            Err(out.inner.error.take().unwrap_or_else(|| {
+                io::Error::other("formatting a value failed")
+            }))
+        }
+    }
+}
+
+/// Adapts an `io::Write` to `fmt::Write`, keeping the error that
+/// `fmt::Write` has no room for.
+#[cfg(feature = "std")]
+struct IoWriter<'w, W> {
+    inner: &'w mut W,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> Write for IoWriter<'_, W> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.inner.write_all(text.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Writes `value` on a single line in the given style, exactly
+/// as `Display` does, but without recursing.
+pub(crate) fn write_single_line<W: Write>(
+    f: &mut W,
+    value: &Value<'_>,
+    style: Style,
+) -> fmt::Result {
+    let config = PrettyConfig {
+        indent: String::new(),
+        float_format: style.floats,
+        escape_mode: style.escapes,
+        ..PrettyConfig::default()
+    };
+
+    write_pretty(&mut Output::new(f), value, &config, 0)
+}
+
+/// Writes `value`, nested `depth` levels deep, to `out` as
+/// `config` says.
+fn write_pretty<W: Write>(
+    out: &mut Output<W>,
+    value: &Value<'_>,
+    config: &PrettyConfig,
+    depth: usize,
+) -> fmt::Result {
+    let flat = config.max_width.map(|_| config.flat());
+    let multi_line = !config.indent.is_empty();
+
+    // Popped from the back, so each value pushes its parts in
+    // reverse. Keeping this stack on the heap means that deeply
+    // nested values can't overflow the call stack.
+    let mut steps = vec![Step::Value(value, depth)];
+
+    while let Some(step) = steps.pop() {
+        match step {
+            Step::Value(value, depth) => {
+                if let (Some(width), Some(flat)) =
+                    (config.max_width, &flat)
+                {
+                    // Leave room for the comma that follows an
+                    // element
+                    let reserved = usize::from(depth > 0);
+                    let budget = width
+                        .saturating_sub(out.column + reserved);
+
+                    if multi_line && fits(value, flat, budget) {
+                        write_pretty(out, value, flat, 0)?;
+                        continue;
+                    }
+                }
+
+                push_value(
+                    out,
+                    &mut steps,
+                    value,
+                    depth,
+                    config.style(),
+                )?
+            }
+            Step::Text(text) => out.write_str(text)?,
+            Step::Key(key) => {
+                write_string(out, key, config.escape_mode)?;
+                out.write_str(": ")?
+            }
+            Step::Line { depth, inline } => {
+                if multi_line {
+                    out.write_char('\n')?;
+                    for _ in 0..depth {
+                        out.write_str(&config.indent)?;
+                    }
+                } else {
+                    out.write_str(inline)?;
+                }
+            }
+            Step::Comma { last, required } => {
+                if !last
+                    || required
+                    || (multi_line && config.trailing_commas)
+                {
+                    out.write_char(',')?;
+                }
+                if !last
+                    && !multi_line
+                    && config.space_after_comma
+                {
+                    out.write_char(' ')?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns whether `value`, written with the single-line `flat`
+/// config, takes up at most `width` columns.
+///
+/// This stops writing as soon as the width runs out, so checking
+/// a large value costs no more than checking a small one.
+fn fits(
+    value: &Value<'_>,
+    flat: &PrettyConfig,
+    width: usize,
+) -> bool {
+    let mut out = Output::new(Budget(width));
+    write_pretty(&mut out, value, flat, 0).is_ok()
+}
+
+/// A writer that keeps track of the column it is at.
+struct Output<W> {
+    inner: W,
+    column: usize,
+}
+
+impl<W> Output<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, column: 0 }
+    }
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.column = match text.rfind('\n') {
+            Some(idx) => text[idx + 1..].chars().count(),
+            None => self.column + text.chars().count(),
+        };
+        self.inner.write_str(text)
+    }
+}
+
+/// A writer that fails once more than the given number of chars
+/// are written to it.
+struct Budget(usize);
+
+impl Write for Budget {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        self.0 = self
+            .0
+            .checked_sub(text.chars().count())
+            .ok_or(fmt::Error)?;
+        Ok(())
+    }
+}
+
+/// A piece of pretty-printed output still to be written.
+enum Step<'v, 'a> {
+    /// A value nested `depth` levels deep.
+    Value(&'v Value<'a>, usize),
+    Text(&'v str),
+    /// An object key, written as a string followed by `: `.
+    Key(&'v str),
+    /// A line break followed by `depth` levels of indentation,
+    /// or `inline` when writing everything on one line.
+    Line {
+        depth: usize,
+        inline: &'static str,
+    },
+    /// The comma after an element, where the `last` one only
+    /// gets a trailing comma, unless the comma is `required`.
+    Comma {
+        last: bool,
+        required: bool,
+    },
+}
+
+/// One element of a bracketed value: its parts, written in
+/// order.
+type Element<'v, 'a> = Vec<Step<'v, 'a>>;
+
+/// Writes a value that holds no other values, or pushes the
+/// steps writing one that does.
+fn push_value<'v, 'a, W: Write>(
+    out: &mut Output<W>,
+    steps: &mut Vec<Step<'v, 'a>>,
+    value: &'v Value<'a>,
+    depth: usize,
+    style: Style,
+) -> fmt::Result {
+    let inner = depth + 1;
+    let (open, elements, close, inline): (
+        &'v str,
+        Vec<Element<'v, 'a>>,
+        &'static str,
+        &'static str,
+    ) = match value {
+        Value::List(elements) => {
+            ("[", values(elements, inner), "]", "")
+        }
+        Value::Tuple(elements) => {
+            ("(", values(elements, inner), ")", "")
+        }
+        Value::Set(elements) => {
+            ("{", values(elements, inner), "}", "")
+        }
+        Value::Optional(Some(value)) => {
+            ("Some(", values([&**value], inner), ")", "")
+        }
+        Value::Result(Ok(value)) => {
+            ("Ok(", values([&**value], inner), ")", "")
+        }
+        Value::Result(Err(value)) => {
+            ("Err(", values([&**value], inner), ")", "")
+        }
+        Value::TupleStruct { name, fields } => {
+            out.write_str(name)?;
+            ("(", values(fields, inner), ")", "")
+        }
+        Value::Object(object) => {
+            let elements = sorted(object)
+                .into_iter()
+                .map(|(key, value)| {
+                    vec![
+                        Step::Key(key),
+                        Step::Value(value, inner),
+                    ]
+                })
+                .collect();
+            ("{", elements, "}", "")
+        }
+        Value::Map(entries) => {
+            let elements = entries
+                .iter()
+                .map(|(key, value)| {
+                    vec![
+                        Step::Value(key, inner),
+                        Step::Text(": "),
+                        Step::Value(value, inner),
+                    ]
+                })
+                .collect();
+            ("{", elements, "}", "")
+        }
+        Value::Struct {
+            name,
+            fields,
+            non_exhaustive,
+        } => {
+            out.write_str(name)?;
+            let mut elements: Vec<_> = fields
+                .iter()
+                .map(|(field, value)| {
+                    vec![
+                        Step::Text(field),
+                        Step::Text(": "),
+                        Step::Value(value, inner),
+                    ]
+                })
+                .collect();
+            if *non_exhaustive {
+                elements.push(vec![Step::Text("..")]);
+            }
+            (" {", elements, "}", " ")
+        }
+        Value::Range {
+            start,
+            end,
+            inclusive,
+        } => {
+            if let Some(end) = end {
+                steps.push(Step::Value(end, depth));
+            }
+            steps.push(Step::Text(if *inclusive {
+                "..="
+            } else {
+                ".."
+            }));
+            if let Some(start) = start {
+                steps.push(Step::Value(start, depth));
+            }
+            return Ok(());
+        }
+        value => {
+            return write!(out, "{}", value.formatted(style))
+        }
+    };
+
+    out.write_str(open)?;
+    if elements.is_empty() {
+        return out.write_str(close);
+    }
+
+    steps.push(Step::Text(close));
+    steps.push(Step::Line { depth, inline });
+
+    let len = elements.len();
+    // `(1)` would read as a parenthesized value
+    let one_tuple = matches!(value, Value::Tuple(elements) if len == 1 && elements.len() == 1);
+    for (idx, element) in elements.into_iter().enumerate().rev()
+    {
+        // `{:#?}` never puts a comma after the `..` of a
+        // non-exhaustive struct
+        let is_rest = matches!(element[..], [Step::Text("..")]);
+        if !is_rest {
+            steps.push(Step::Comma {
+                last: idx == len - 1,
+                required: one_tuple,
+            });
+        }
+        steps.extend(element.into_iter().rev());
+        steps.push(Step::Line {
+            depth: inner,
+            inline: if idx == 0 { inline } else { "" },
+        });
+    }
+
+    Ok(())
+}
+
+/// Turns each of `values` into an element of its own.
+fn values<'v, 'a>(
+    values: impl IntoIterator<Item = &'v Value<'a>>,
+    depth: usize,
+) -> Vec<Element<'v, 'a>>
+where
+    'a: 'v,
+{
+    values
+        .into_iter()
+        .map(|value| vec![Step::Value(value, depth)])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    use std::io;
+
+    use crate::{
+        display::tests::{parse_f64s, Generator},
+        parse_value, PrettyConfig, Value,
+    };
+
+    #[test]
+    fn writes_pretty_values() {
+        let (_, value) = parse_value(
+            r#"Foo { a: [1, []], b: Some((2.5, 'x')), c: Bar {}, d: Baz { e: 1, .. } }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            value.to_string_pretty(),
+            "Foo {
+    a: [
+        1,
+        [],
+    ],
+    b: Some(
+        (
+            2.5,
+            'x',
+        ),
+    ),
+    c: Bar {},
+    d: Baz {
+        e: 1,
+        ..
+    },
+}"
+        );
+        assert_eq!(
+            parse_value(&value.to_string_pretty()),
+            Ok(("", value.clone()))
+        );
+
+        let config = PrettyConfig::default()
+            .indent("\t")
+            .trailing_commas(false);
+        assert_eq!(
+            value.to_string_pretty_with(&config),
+            "Foo {
+\ta: [
+\t\t1,
+\t\t[]
+\t],
+\tb: Some(
+\t\t(
+\t\t\t2.5,
+\t\t\t'x'
+\t\t)
+\t),
+\tc: Bar {},
+\td: Baz {
+\t\te: 1,
+\t\t..
+\t}
+}"
+        );
+
+        let one_line = PrettyConfig::default().indent("");
+        assert_eq!(
+            value.to_string_pretty_with(&one_line),
+            value.to_string()
+        );
+        assert_eq!(
+            value.to_string_pretty_with(&one_line.space_after_comma(false)),
+            "Foo { a: [1,[]],b: Some((2.5,'x')),c: Bar {},d: Baz { e: 1,.. } }"
+        );
+    }
+
+    #[test]
+    fn round_trips_pretty_values() {
+        let configs = [
+            PrettyConfig::default(),
+            PrettyConfig::default().max_width(24),
+            PrettyConfig::default()
+                .indent("  ")
+                .trailing_commas(false),
+            PrettyConfig::default()
+                .indent("")
+                .space_after_comma(false),
+        ];
+
+        let mut generator = Generator(0x9e77);
+        for _ in 0..1000 {
+            let value = generator.value(4);
+            for config in &configs {
+                let text = value.to_string_pretty_with(config);
+                assert_eq!(
+                    parse_f64s(&text),
+                    Ok(("", value.clone())),
+                    "{text}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fits_values_within_a_width() {
+        let (_, value) = parse_value(
+            r#"Config { name: "demo", ports: [80, 443], limits: Limits { cpu: 2, memory: [512, 1024, 2048, 4096] }, tags: [] }"#,
+        )
+        .unwrap();
+        let config = PrettyConfig::default().max_width(40);
+
+        assert_eq!(
+            value.to_string_pretty_with(&config),
+            r#"Config {
+    name: "demo",
+    ports: [80, 443],
+    limits: Limits {
+        cpu: 2,
+        memory: [512, 1024, 2048, 4096],
+    },
+    tags: [],
+}"#
+        );
+
+        // A narrower width breaks the inner list too
+        assert_eq!(
+            value.to_string_pretty_with(
+                &config.clone().max_width(30)
+            ),
+            r#"Config {
+    name: "demo",
+    ports: [80, 443],
+    limits: Limits {
+        cpu: 2,
+        memory: [
+            512,
+            1024,
+            2048,
+            4096,
+        ],
+    },
+    tags: [],
+}"#
+        );
+
+        // Everything fits, so nothing is broken
+        assert_eq!(
+            value.to_string_pretty_with(
+                &config.clone().max_width(200)
+            ),
+            value.to_string()
+        );
+
+        // A width of zero breaks every non-empty value
+        assert_eq!(
+            value.to_string_pretty_with(
+                &config.clone().max_width(0)
+            ),
+            value.to_string_pretty()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn writes_large_values_to_writers() {
+        let row = |idx: i64| {
+            Value::List(vec![
+                Value::Integer(idx),
+                Value::String("some text to pad the row".into()),
+                Value::Optional(Some(Box::new(Value::Tuple(
+                    vec![
+                        Value::Boolean(idx % 2 == 0),
+                        Value::Char('x'),
+                    ],
+                )))),
+            ])
+        };
+        let value = Value::List((0..40_000).map(row).collect());
+
+        let mut compact = Vec::new();
+        value.write_to(&mut compact).unwrap();
+        assert!(compact.len() > 2_000_000);
+        assert_eq!(compact, value.to_string().into_bytes());
+        assert_eq!(
+            crate::from_slice(&compact),
+            Ok(value.clone())
+        );
+
+        let mut pretty = Vec::new();
+        let config = PrettyConfig::default().max_width(60);
+        value.write_pretty_to(&mut pretty, &config).unwrap();
+        assert!(pretty.len() > 2_000_000);
+        assert_eq!(
+            pretty,
+            value.to_string_pretty_with(&config).into_bytes()
+        );
+        assert_eq!(crate::from_slice(&pretty), Ok(value));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn propagates_writer_errors() {
+        /// Accepts a few bytes, then fails.
+        struct Full(usize);
+
+        impl io::Write for Full {
+            fn write(
+                &mut self,
+                buf: &[u8],
+            ) -> io::Result<usize> {
+                if self.0 < buf.len() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::StorageFull,
+                        "full",
+                    ));
+                }
+                self.0 -= buf.len();
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let value =
+            parse_value("[1, [2, 3], Foo { x: 4 }]").unwrap().1;
+        let config = PrettyConfig::default();
+
+        let err = value.write_to(&mut Full(5)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::StorageFull);
+        let err = value
+            .write_pretty_to(&mut Full(5), &config)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::StorageFull);
+
+        assert!(value
+            .write_pretty_to(&mut Full(1000), &config)
+            .is_ok());
+    }
+
+    #[test]
+    fn writes_deeply_nested_values() {
+        let depth = 100_000;
+        let mut value = Value::List(vec![]);
+        for _ in 0..depth {
+            value = Value::List(vec![value]);
+        }
+
+        let text = value.to_string_pretty_with(
+            &PrettyConfig::default().indent(""),
+        );
+        assert_eq!(text.len(), 2 * (depth + 1));
+    }
+}
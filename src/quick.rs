@@ -0,0 +1,207 @@
+use core::fmt;
+
+use crate::{
+    error::Located, parse_tracked, trailing, validate, Error,
+    ErrorKind, Mode, ParserOptions, Value,
+};
+
+/// An error from [`from_str_quick`] or [`validate_quick`],
+/// saying only what went wrong and how many bytes into the
+/// input, with [`QuickError::detail`] to find out the rest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuickError {
+    kind: ErrorKind,
+    offset: usize,
+}
+
+impl QuickError {
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Returns how many bytes into the input the error is.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the [`Error`] that [`from_str`](crate::from_str)
+    /// gives for `source`, the input this error came from, with
+    /// its line, column and what was expected there, by checking
+    /// it again.
+    ///
+    /// Given any other input that also fails, this returns that
+    /// input's error, and given one that doesn't, an error with
+    /// this one's kind at its offset, or at the end of `source`
+    /// if it is shorter.
+    pub fn detail(&self, source: &str) -> Error {
+        validate(source).err().unwrap_or_else(|| {
+            let offset = (0..=self.offset.min(source.len()))
+                .rev()
+                .find(|&idx| source.is_char_boundary(idx))
+                .unwrap_or(0);
+            Error::new(self.kind, source, offset)
+        })
+    }
+}
+
+impl From<Located> for QuickError {
+    fn from(err: Located) -> Self {
+        QuickError {
+            kind: err.kind,
+            offset: err.offset,
+        }
+    }
+}
+
+impl fmt::Display for QuickError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.kind, self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for QuickError {}
+
+/// Parses a document like [`from_str`](crate::from_str) does,
+/// but only says what went wrong and where if it fails, for when
+/// most of what is parsed isn't expected to be tson at all, such
+/// as when sniffing which lines of a log hold values.
+///
+/// Neither what was expected where the error is nor its line
+/// and column are worked out, which saves noting what every
+/// parser that failed expected, and scanning the input before
+/// the error for line breaks. The kind and offset of the error
+/// are the same as [`from_str`](crate::from_str)'s, so
+/// [`QuickError::detail`] can find the rest when it is wanted.
+pub fn from_str_quick(
+    input: &str,
+) -> Result<Value<'_>, QuickError> {
+    quickly(input, ParserOptions::default(), Mode::Build)
+}
+
+/// Checks a document like [`validate`] does, but with the errors
+/// of [`from_str_quick`].
+pub fn validate_quick(input: &str) -> Result<(), QuickError> {
+    quickly(input, ParserOptions::default(), Mode::Validate)
+        .map(drop)
+}
+
+fn quickly<'a>(
+    input: &'a str,
+    options: ParserOptions,
+    mode: Mode<'_>,
+) -> Result<Value<'a>, QuickError> {
+    match parse_tracked(input, options, mode, false) {
+        (Ok((rest, value)), furthest) => {
+            trailing(input, rest, furthest)?;
+            Ok(value)
+        }
+        (Err(err), furthest) => {
+            Err(Located::from_nom(input, err, furthest).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_str_quick, quickly, validate_quick};
+    use crate::{
+        from_str, validate_with, ErrorKind, Mode, ParserOptions,
+    };
+
+    const INVALID: &[&str] = &[
+        "",
+        "hello world",
+        "user@example.com",
+        "key=value",
+        "<html>",
+        "[1, 2",
+        "[1 2]",
+        "Foo { x: 1 y }",
+        "\"abc",
+        "\"\\q\"",
+        "/* never closed",
+        "0b2",
+        "''",
+        "1 2",
+        "true garbage",
+        "{\"a\": }",
+        "Some(1, 2",
+        "1..=",
+        "[\n  1,\n  é@,\n]",
+    ];
+
+    #[test]
+    fn fails_where_from_str_does() {
+        for text in INVALID {
+            let err = from_str(text).unwrap_err();
+            let quick = from_str_quick(text).unwrap_err();
+            assert_eq!(
+                (quick.kind(), quick.offset()),
+                (err.kind(), err.offset()),
+                "{text}"
+            );
+            assert_eq!(
+                validate_quick(text),
+                Err(quick),
+                "{text}"
+            );
+            assert_eq!(quick.detail(text), err, "{text}");
+        }
+    }
+
+    #[test]
+    fn fails_where_from_str_does_with_limits() {
+        let options = ParserOptions::default()
+            .max_depth(2)
+            .max_string_len(3);
+        for text in
+            ["[[[1]]]", "\"abcd\"", "[1, [2, \"abcde\"]]"]
+        {
+            let err = validate_with(text, options).unwrap_err();
+            let quick = quickly(text, options, Mode::Validate)
+                .unwrap_err();
+            assert_eq!(quick.kind(), err.kind(), "{text}");
+            assert_eq!(quick.offset(), err.offset(), "{text}");
+        }
+    }
+
+    #[test]
+    fn parses_what_from_str_does() {
+        for text in [
+            "[1, 2, 3]",
+            "Foo { x: 1, y: Some(\"a\") } // done",
+            "{1: (2, 3)}",
+            "  1..=5  ",
+        ] {
+            assert_eq!(
+                from_str_quick(text),
+                Ok(from_str(text).unwrap())
+            );
+            assert_eq!(validate_quick(text), Ok(()));
+        }
+    }
+
+    #[test]
+    fn details_other_inputs() {
+        let quick = from_str_quick("[1, @]").unwrap_err();
+        assert_eq!(quick.kind(), ErrorKind::UnexpectedChar('@'));
+        assert_eq!(
+            quick.to_string(),
+            "unexpected character '@' at byte 4"
+        );
+
+        assert_eq!(
+            quick.detail("[@]"),
+            from_str("[@]").unwrap_err()
+        );
+        // Valid input gets the error where it would be, moved
+        // back to the start of the char there
+        let err = quick.detail("\"éé\"");
+        assert_eq!(
+            (err.kind(), err.offset()),
+            (quick.kind(), 3)
+        );
+        assert_eq!(err.column(), 3);
+    }
+}
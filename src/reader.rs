@@ -0,0 +1,262 @@
+use alloc::vec::Vec;
+use std::io::Read;
+
+use crate::{
+    decode_slice, from_slice, from_slice_streaming,
+    parse_document, parse_ws, Document, Error, ErrorKind,
+    OwnedValue, Streamed, Value,
+};
+
+/// How many bytes are read at a time.
+const CHUNK: u64 = 8 * 1024;
+
+/// Parses a document read from `reader`, which must hold a
+/// single value like [`from_str`](crate::from_str) requires,
+/// without the caller reading it all into a string first.
+///
+/// The input is read a chunk at a time, so a reader that returns
+/// a few bytes per read, such as a pipe, is read until it ends,
+/// and the input is parsed again each time twice as much of it
+/// has been read, which takes as long over all as parsing it
+/// once more. Once the value has been read, reading goes on so
+/// as to check that only whitespace and comments follow it, but
+/// stops as soon as anything else does, with the error
+/// [`from_str`](crate::from_str) would give, so a stream isn't
+/// read to its end for nothing. Errors point into the input as
+/// it was read, counting any byte order mark.
+///
+/// An error reading from `reader` is an [`ErrorKind::Io`] at
+/// how far into the input it was read.
+pub fn from_reader<R: Read>(
+    mut reader: R,
+) -> Result<OwnedValue, Error> {
+    let mut buf = Vec::new();
+    let mut parsed_len = 0;
+    while read_chunk(&mut reader, &mut buf)? {
+        if buf.len() < parsed_len * 2 {
+            continue;
+        }
+        parsed_len = buf.len();
+
+        match from_slice_streaming(&buf)? {
+            Streamed::Complete(_, rest)
+                if is_trailing_input(rest) =>
+            {
+                break
+            }
+            _ => {}
+        }
+    }
+
+    from_slice(&buf).map(Value::into_owned)
+}
+
+/// Parses a document of `key = value` entries read from
+/// `reader`, like [`parse_document`] does, reading it to its end
+/// first.
+///
+/// An error reading from `reader` is an [`ErrorKind::Io`] at
+/// how far into the input it was read, as with [`from_reader`].
+pub fn document_from_reader<R: Read>(
+    mut reader: R,
+) -> Result<Document<'static>, Error> {
+    let mut buf = Vec::new();
+    while read_chunk(&mut reader, &mut buf)? {}
+
+    let (bom_len, input) = decode_slice(&buf)?;
+    parse_document(input)
+        .map(Document::into_owned)
+        .map_err(|err| err.shifted(bom_len))
+}
+
+/// Reads up to [`CHUNK`] more bytes of `reader` into `buf`,
+/// returning whether there were any.
+fn read_chunk<R: Read>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+) -> Result<bool, Error> {
+    match reader.by_ref().take(CHUNK).read_to_end(buf) {
+        Ok(read) => Ok(read > 0),
+        Err(err) => {
+            let valid = match core::str::from_utf8(buf) {
+                Ok(valid) => valid,
+                Err(utf8) => core::str::from_utf8(
+                    &buf[..utf8.valid_up_to()],
+                )
+                .unwrap_or(""),
+            };
+            let kind = ErrorKind::Io(err.kind());
+            Err(Error::new(kind, valid, valid.len())
+                .shifted(buf.len() - valid.len()))
+        }
+    }
+}
+
+/// Returns whether `rest`, the input after a value, starts with
+/// something other than whitespace and comments, which no more
+/// input can change.
+fn is_trailing_input(rest: &[u8]) -> bool {
+    let rest = match core::str::from_utf8(rest) {
+        Ok(rest) => rest,
+        Err(err) if err.error_len().is_some() => return true,
+        Err(err) => {
+            core::str::from_utf8(&rest[..err.valid_up_to()])
+                .unwrap_or("")
+        }
+    };
+
+    match parse_ws(rest) {
+        // The start of a comment may be cut off
+        Ok((rest, _)) => !rest.is_empty() && rest != "/",
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, string::ToString, vec::Vec};
+    use std::{
+        fs::File,
+        io::{self, Cursor, Read},
+    };
+
+    use super::{document_from_reader, from_reader};
+    use crate::{from_str, parse_document, ErrorKind, Value};
+
+    fn fixture(name: &str) -> File {
+        File::open(format!(
+            "{}/tests/fixtures/{name}",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap()
+    }
+
+    /// Returns a byte of its input at a time, failing with
+    /// `Interrupted` before each one and with `err` at the end,
+    /// if it is given.
+    struct Trickle<'a> {
+        input: &'a [u8],
+        interrupted: bool,
+        err: Option<io::ErrorKind>,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupted = !self.interrupted;
+            if self.interrupted {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            match (self.input.split_first(), self.err) {
+                (Some((&byte, rest)), _) => {
+                    buf[0] = byte;
+                    self.input = rest;
+                    Ok(1)
+                }
+                (None, Some(err)) => Err(err.into()),
+                (None, None) => Ok(0),
+            }
+        }
+    }
+
+    fn trickle(input: &str) -> Trickle<'_> {
+        Trickle {
+            input: input.as_bytes(),
+            interrupted: false,
+            err: None,
+        }
+    }
+
+    #[test]
+    fn reads_values_in_pieces() {
+        let elements: Vec<_> = (0..2_000)
+            .map(|idx| {
+                format!(
+                    "Point {{ x: {idx}, name: \"点{idx}\" }}"
+                )
+            })
+            .collect();
+        let text = format!(
+            "// points\n[\n{}\n] /* end */\n",
+            elements.join(",\n")
+        );
+        let expected = from_str(&text).unwrap();
+
+        assert_eq!(
+            from_reader(Cursor::new(&text)),
+            Ok(expected.clone())
+        );
+        assert_eq!(from_reader(trickle(&text)), Ok(expected));
+        assert_eq!(
+            from_reader(Cursor::new(b"\xef\xbb\xbf// a\n'x'")),
+            Ok(Value::Char('x'))
+        );
+    }
+
+    #[test]
+    fn reads_files() {
+        let text = include_str!("../tests/fixtures/broken.tson");
+        let err =
+            from_reader(fixture("broken.tson")).unwrap_err();
+        assert_eq!(err, from_str(text).unwrap_err());
+        assert_eq!((err.line(), err.column()), (37, 41));
+
+        let text =
+            include_str!("../tests/fixtures/settings.tson");
+        let document =
+            document_from_reader(fixture("settings.tson"))
+                .unwrap();
+        assert_eq!(document, parse_document(text).unwrap());
+        assert_eq!(document["replicas"], Value::Integer(3));
+        assert_eq!(
+            document_from_reader(Cursor::new("a = 1\na = 2"))
+                .unwrap_err()
+                .kind(),
+            ErrorKind::DuplicateKey
+        );
+    }
+
+    #[test]
+    fn stops_reading_at_trailing_input() {
+        // Reading the endless input after the value to its end
+        // would never finish
+        for text in
+            ["[1, 2] x", "Foo { x: 1 y }", "1 /* a */ \u{2603}"]
+        {
+            let endless =
+                Cursor::new(text).chain(io::repeat(b'z'));
+            assert_eq!(
+                from_reader(endless).unwrap_err(),
+                from_str(&format!("{text}z")).unwrap_err(),
+                "{text}"
+            );
+        }
+
+        let err =
+            from_reader(Cursor::new(b"[\"\xff\"]")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidUtf8);
+    }
+
+    #[test]
+    fn tells_io_errors_apart() {
+        let mut reader = trickle("[1, 2,\n 3");
+        reader.err = Some(io::ErrorKind::ConnectionReset);
+        let err = from_reader(reader).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            ErrorKind::Io(io::ErrorKind::ConnectionReset)
+        );
+        assert_eq!(
+            (err.offset(), err.line(), err.column()),
+            (9, 2, 3)
+        );
+        assert_eq!(
+            err.to_string(),
+            "failed to read the input: connection reset at line 2, column 3"
+        );
+
+        // The input is complete, so it is the parse that fails
+        let err =
+            from_reader(trickle("[1, 2,\n 3")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEnd);
+    }
+}
@@ -0,0 +1,419 @@
+use alloc::vec::Vec;
+
+use crate::{
+    check_trailing, parse_tracked, parse_ws, Error, Mode,
+    ParserOptions, Value,
+};
+
+/// Parses a document like [`from_str`](crate::from_str) does,
+/// but goes on past the elements that fail to parse in lists,
+/// structs and other values in brackets rather than stopping at
+/// the first error, returning what could be parsed along with
+/// every error found, for the likes of editors and linters.
+///
+/// A failed element is skipped up to the comma or closing
+/// bracket after it and left out, so `[1, @, 3]` is parsed as
+/// `[1, 3]` with an error at the `@`. A bracket that is never
+/// closed is taken to close where the input ends, or where the
+/// value it is in does. The value is `None` when an error isn't
+/// inside of any brackets, as in `1 +`, while something after
+/// the value, as in `1 2`, is an error but keeps the value.
+///
+/// Each error is found by parsing the input again, skipping the
+/// elements that failed before, so the errors are the ones
+/// [`from_str`](crate::from_str) would report with those left
+/// out, and a document with many errors takes as many parses.
+pub fn parse_lenient(
+    input: &str,
+) -> (Option<Value<'_>>, Vec<Error>) {
+    let mut recovery = Recovery::default();
+    let mut errors = Vec::new();
+    loop {
+        recovery.restart();
+        let (value, err, inside) = match parse_tracked(
+            input,
+            ParserOptions::default(),
+            Mode::Recover(&mut recovery),
+            true,
+        ) {
+            (Ok((rest, value)), furthest) => {
+                let end = input.len() - rest.len();
+                match check_trailing(input, rest, furthest) {
+                    Ok(()) => return (Some(value), errors),
+                    // A parser got further than the value,
+                    // so the error may be in brackets
+                    Err(err) => {
+                        let inside = err.offset() > end;
+                        (Some(value), err, inside)
+                    }
+                }
+            }
+            (Err(err), furthest) => (
+                None,
+                Error::from_nom(input, err, furthest),
+                true,
+            ),
+        };
+
+        // Parsing again only helps when this parse got past
+        // every error found before
+        let again =
+            inside && recovery.peak == recovery.targets.len();
+        if again || !errors.contains(&err) {
+            recovery.add(input.len() - err.offset());
+            errors.push(err);
+        }
+        if !again {
+            return (value, errors);
+        }
+    }
+}
+
+/// The errors found by the parses of [`parse_lenient`] before
+/// the current one, to be recovered from by it.
+#[derive(Default)]
+pub(crate) struct Recovery {
+    /// How much input was left where each error is, from the
+    /// least to the most.
+    targets: Vec<usize>,
+    /// Whether the current parse recovered from each of
+    /// `targets`, leaving out any in values it backtracked
+    /// from.
+    done: Vec<bool>,
+    /// Which of `targets` were recovered from, in order.
+    recovered: Vec<usize>,
+    /// The most errors the current parse had recovered from at
+    /// once.
+    peak: usize,
+}
+
+impl Recovery {
+    /// Adds an error with `remaining` bytes of input left there.
+    fn add(&mut self, remaining: usize) {
+        let idx = self
+            .targets
+            .partition_point(|&target| target <= remaining);
+        self.targets.insert(idx, remaining);
+        self.done.push(false);
+    }
+
+    /// Gets ready for another parse.
+    fn restart(&mut self) {
+        self.done.fill(false);
+        self.recovered.clear();
+        self.peak = 0;
+    }
+
+    /// Returns how many errors were recovered from so far.
+    pub(crate) fn recovered(&self) -> usize {
+        self.recovered.len()
+    }
+
+    /// Forgets the errors recovered from after the first `len`.
+    pub(crate) fn forget(&mut self, len: usize) {
+        for idx in self.recovered.drain(len..) {
+            self.done[idx] = false;
+        }
+    }
+
+    /// Recovers from an error in an element that failed, which
+    /// is then skipped from where `start` bytes of input are
+    /// left to where `end` are, returning whether there was
+    /// one.
+    ///
+    /// The error must be one the element got as far as, with
+    /// `reached` bytes left at its furthest failure. Any others
+    /// in the part skipped are recovered from along with it.
+    pub(crate) fn recover(
+        &mut self,
+        start: usize,
+        end: usize,
+        reached: usize,
+    ) -> bool {
+        let at = |least| {
+            self.targets
+                .partition_point(|&target| target < least)
+        };
+        let (first, last) =
+            (at(end.max(reached)), at(start + 1));
+        let Some(idx) =
+            (first..last).find(|&idx| !self.done[idx])
+        else {
+            return false;
+        };
+
+        self.done[idx] = true;
+        self.recovered.push(idx);
+        for idx in at(end + 1)..last {
+            if !self.done[idx] {
+                self.done[idx] = true;
+                self.recovered.push(idx);
+            }
+        }
+        self.peak = self.peak.max(self.recovered.len());
+
+        true
+    }
+}
+
+/// Returns the input at the comma or closing bracket that ends
+/// the element starting at `input`, or at the end of the input
+/// if nothing does, skipping over anything in brackets, quotes
+/// or comments.
+pub(crate) fn skip_element(mut input: &str) -> &str {
+    let mut depth = 0usize;
+    loop {
+        // A comment that is never closed runs to the end
+        input = parse_ws(input).map_or("", |(rest, _)| rest);
+        let mut chars = input.chars();
+        let Some(chr) = chars.next() else {
+            return input;
+        };
+
+        let rest = chars.as_str();
+        input = match chr {
+            ',' | ']' | ')' | '}' if depth == 0 => return input,
+            ']' | ')' | '}' => {
+                depth -= 1;
+                rest
+            }
+            '[' | '(' | '{' => {
+                depth += 1;
+                rest
+            }
+            '"' | '\'' => skip_quoted(rest, chr),
+            chr if is_word(chr) => {
+                let len = input.len()
+                    - input.trim_start_matches(is_word).len();
+                let (word, rest) = input.split_at(len);
+                match word {
+                    "r" | "br" => skip_raw(rest),
+                    _ => rest,
+                }
+            }
+            _ => rest,
+        };
+    }
+}
+
+fn is_word(chr: char) -> bool {
+    chr.is_alphanumeric() || chr == '_'
+}
+
+/// Returns the input after the string or char whose opening
+/// `quote` is just before `input`, or the end of the input if it
+/// is never closed.
+fn skip_quoted(input: &str, quote: char) -> &str {
+    let mut chars = input.chars();
+    while let Some(chr) = chars.next() {
+        if chr == '\\' {
+            chars.next();
+        } else if chr == quote {
+            return chars.as_str();
+        }
+    }
+
+    ""
+}
+
+/// Returns the input after the raw string whose `r` is just
+/// before `input`, as in `r#"..."#`, or `input` if it doesn't
+/// hold one.
+fn skip_raw(input: &str) -> &str {
+    let hashes =
+        input.len() - input.trim_start_matches('#').len();
+    let Some(mut body) = input[hashes..].strip_prefix('"')
+    else {
+        return input;
+    };
+
+    while let Some(idx) = body.find('"') {
+        body = &body[idx + 1..];
+        if body.get(..hashes).is_some_and(|closing| {
+            closing.bytes().all(|b| b == b'#')
+        }) {
+            return &body[hashes..];
+        }
+    }
+
+    ""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_lenient;
+    use crate::{from_str, ErrorKind, Value};
+
+    /// Returns the value and where each error is, as a line and
+    /// column.
+    fn lenient(
+        text: &str,
+    ) -> (Option<Value<'_>>, Vec<(usize, usize)>) {
+        let (value, errors) = parse_lenient(text);
+        let positions = errors
+            .iter()
+            .map(|err| (err.line(), err.column()))
+            .collect();
+        (value, positions)
+    }
+
+    fn list(elements: Vec<Value<'static>>) -> Value<'static> {
+        Value::List(elements)
+    }
+
+    #[test]
+    fn reports_every_error() {
+        let text = r#"[
+    1,
+    @,
+    Foo { a: 1 b: 2 },
+    "x\q",
+    [4 5],
+    6,
+]"#;
+        let (value, errors) = parse_lenient(text);
+        assert_eq!(
+            value,
+            Some(list(vec![
+                Value::Integer(1),
+                Value::Struct {
+                    name: "Foo".into(),
+                    fields: vec![(
+                        "a".into(),
+                        Value::Integer(1)
+                    )],
+                    non_exhaustive: false,
+                },
+                list(vec![Value::Integer(4)]),
+                Value::Integer(6),
+            ]))
+        );
+
+        let positions: Vec<_> = errors
+            .iter()
+            .map(|err| (err.line(), err.column(), err.kind()))
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                (3, 5, ErrorKind::UnexpectedChar('@')),
+                (4, 16, ErrorKind::UnexpectedChar('b')),
+                (5, 7, ErrorKind::InvalidEscape),
+                (6, 8, ErrorKind::UnexpectedChar('5')),
+            ]
+        );
+        assert_eq!(from_str(text), Err(errors[0].clone()));
+    }
+
+    #[test]
+    fn leaves_valid_documents_alone() {
+        for text in [
+            "[1, 2, 3]",
+            "Config { name: \"tson\", ports: {1: 2}, tags: {a, b} }",
+            "{\"a\": [1, (2, 3)], \"b\": Some(r#\"]\"#)}",
+            "1..=5",
+        ] {
+            assert_eq!(
+                parse_lenient(text),
+                (Some(from_str(text).unwrap()), Vec::new()),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn skips_to_the_comma_or_bracket_after_an_error() {
+        let cases = [
+            (
+                "[1, @, 3]",
+                list(vec![Value::Integer(1), Value::Integer(3)]),
+            ),
+            ("[@, 3]", list(vec![Value::Integer(3)])),
+            (
+                "[1 2, 3]",
+                list(vec![Value::Integer(1), Value::Integer(3)]),
+            ),
+            ("[1, @,]", list(vec![Value::Integer(1)])),
+            (
+                "[1, (2 \"),\"), 4]",
+                list(vec![
+                    Value::Integer(1),
+                    Value::Tuple(vec![Value::Integer(2)]),
+                    Value::Integer(4),
+                ]),
+            ),
+            (
+                "[1, x /* ] */ y, 2]",
+                list(vec![
+                    Value::Integer(1),
+                    Value::Ident("x".into()),
+                    Value::Integer(2),
+                ]),
+            ),
+        ];
+
+        for (text, expected) in cases {
+            let (value, errors) = lenient(text);
+            assert_eq!(value, Some(expected), "{text}");
+            assert_eq!(errors.len(), 1, "{text}");
+        }
+    }
+
+    #[test]
+    fn closes_brackets_that_are_never_closed() {
+        assert_eq!(
+            lenient("[1, [2, 3"),
+            (
+                Some(list(vec![
+                    Value::Integer(1),
+                    list(vec![
+                        Value::Integer(2),
+                        Value::Integer(3)
+                    ]),
+                ])),
+                vec![(1, 10), (1, 10)]
+            )
+        );
+        assert_eq!(
+            lenient("[1, (2, 3]"),
+            (
+                Some(list(vec![
+                    Value::Integer(1),
+                    Value::Tuple(vec![
+                        Value::Integer(2),
+                        Value::Integer(3)
+                    ]),
+                ])),
+                vec![(1, 10)]
+            )
+        );
+    }
+
+    #[test]
+    fn keeps_the_kind_of_value_the_error_is_in() {
+        let (value, errors) = lenient("{1: [2 3], 4: 5}");
+        assert_eq!(
+            value,
+            Some(Value::Map(vec![
+                (
+                    Value::Integer(1),
+                    list(vec![Value::Integer(2)])
+                ),
+                (Value::Integer(4), Value::Integer(5)),
+            ]))
+        );
+        assert_eq!(errors, [(1, 8)]);
+    }
+
+    #[test]
+    fn reports_errors_outside_of_brackets() {
+        let (value, errors) = parse_lenient("@");
+        assert_eq!(value, None);
+        assert_eq!(errors, [from_str("@").unwrap_err()]);
+
+        let (value, errors) = parse_lenient("1 2");
+        assert_eq!(value, Some(Value::Integer(1)));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind(), ErrorKind::TrailingInput);
+    }
+}
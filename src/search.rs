@@ -0,0 +1,189 @@
+use alloc::vec;
+
+use crate::{walk::children, Path, PathSegment, Value};
+
+impl<'a> Value<'a> {
+    /// Returns every value in this one that `predicate` holds
+    /// for, this one included, along with the path to it.
+    ///
+    /// Values are visited depth first, each before the ones in
+    /// it, in the order they were written. The entries of an
+    /// object, whose order isn't kept, are visited in the order
+    /// of their keys. Only values a [`Path`] can lead to are
+    /// searched, so the insides of `Ok(..)`, `Err(..)` and
+    /// ranges aren't, nor are maps' keys or the values of
+    /// entries whose keys aren't strings.
+    ///
+    /// The value inside of a `Some(..)` is visited after the
+    /// `Some` itself, at the same path, which
+    /// [`Value::get_path`] looks inside of on the way to
+    /// anything further but returns the `Some` for.
+    ///
+    /// The paths borrow from this value, so to change what was
+    /// found, write them out first with `to_string` and then
+    /// give them to [`Value::get_path_mut`].
+    pub fn find_all<'v, F>(
+        &'v self,
+        mut predicate: F,
+    ) -> impl Iterator<Item = (Path<'v>, &'v Value<'a>)>
+    where
+        F: FnMut(&Value<'a>) -> bool,
+    {
+        descendants(self)
+            .filter(move |(_, value)| predicate(value))
+    }
+
+    /// Returns every value of a struct field or of an object or
+    /// map entry named `key` in this value, at any depth, along
+    /// with the path to it, in the order [`Value::find_all`]
+    /// visits them.
+    pub fn find_key<'v>(
+        &'v self,
+        key: &'v str,
+    ) -> impl Iterator<Item = (Path<'v>, &'v Value<'a>)> {
+        descendants(self).filter(move |(path, _)| {
+            path.segments().last()
+                == Some(&PathSegment::Key(key))
+        })
+    }
+}
+
+/// Returns `value` and every value in it that a path leads to,
+/// in the order [`Value::find_all`] visits them.
+fn descendants<'v, 'a>(
+    value: &'v Value<'a>,
+) -> impl Iterator<Item = (Path<'v>, &'v Value<'a>)> {
+    let mut stack = vec![(Path::default(), value)];
+    core::iter::from_fn(move || {
+        let (path, value) = stack.pop()?;
+        let start = stack.len();
+        children(value, |segment, child| {
+            let path = match segment {
+                Some(segment) => path.join(segment),
+                None => path.clone(),
+            };
+            stack.push((path, child));
+        });
+        // The stack is popped from the end, so the first of them
+        // needs to be last
+        stack[start..].reverse();
+
+        Some((path, value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec::Vec};
+
+    use crate::{from_str, PathSegment, Value};
+
+    const DUMP: &str = r#"Dump {
+        status: Ok("fine"),
+        users: [
+            User { user_id: 1, name: "a", last: Some("error: timeout") },
+            User { user_id: 2, name: "error.log", last: None },
+        ],
+        meta: {"user_id": 3, "b.c": {"user_id": 4}},
+        counts: {1: "error", "x": [Session { user_id: 5 }]},
+        errors: ("error: disk", Err("error: hidden")),
+    }"#;
+
+    /// Returns the paths to the strings holding `error`.
+    fn errors(value: &Value<'_>) -> Vec<String> {
+        value
+            .find_all(|value| {
+                value
+                    .as_str()
+                    .is_some_and(|s| s.contains("error"))
+            })
+            .map(|(path, _)| path.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn finds_values_in_document_order() {
+        let value = from_str(DUMP).unwrap();
+        assert_eq!(
+            errors(&value),
+            ["users.0.last", r"users.1.name", "errors.0"]
+        );
+
+        let everything: Vec<_> = value
+            .find_all(|_| true)
+            .map(|(path, _)| path.to_string())
+            .collect();
+        assert_eq!(everything[0], "");
+        assert_eq!(everything[1], "status");
+        assert_eq!(everything[2], "users");
+        assert_eq!(everything[3], "users.0");
+        assert_eq!(everything[4], "users.0.user_id");
+    }
+
+    #[test]
+    fn finds_keys_at_any_depth() {
+        let value = from_str(DUMP).unwrap();
+        let found: Vec<_> = value
+            .find_key("user_id")
+            .map(|(path, value)| {
+                (path.to_string(), value.as_i64())
+            })
+            .collect();
+
+        assert_eq!(
+            found,
+            [
+                ("users.0.user_id".to_string(), Some(1)),
+                ("users.1.user_id".to_string(), Some(2)),
+                (r"meta.b\.c.user_id".to_string(), Some(4)),
+                ("meta.user_id".to_string(), Some(3)),
+                ("counts.x.0.user_id".to_string(), Some(5)),
+            ]
+        );
+        assert_eq!(value.find_key("nobody").count(), 0);
+    }
+
+    #[test]
+    fn finds_paths_that_lead_back() {
+        let value = from_str(DUMP).unwrap();
+        for (path, found) in value.find_all(|_| true) {
+            let written = path.to_string();
+            let at = value.get_path(&written).unwrap();
+            // A `Some` and what is in it share a path
+            assert!(
+                at == found
+                    || at.as_option() == Some(Some(found)),
+                "{written}"
+            );
+            assert_eq!(value.get_at(path.segments()), Some(at));
+        }
+
+        let (path, _) =
+            value.find_key("user_id").nth(2).unwrap();
+        assert_eq!(
+            path.segments(),
+            [
+                PathSegment::Key("meta"),
+                PathSegment::Key("b.c"),
+                PathSegment::Key("user_id"),
+            ]
+        );
+    }
+
+    #[test]
+    fn changes_what_was_found() {
+        let mut value = from_str(DUMP).unwrap();
+        let paths: Vec<String> = value
+            .find_key("user_id")
+            .map(|(path, _)| path.to_string())
+            .collect();
+        for path in paths {
+            *value.get_path_mut(&path).unwrap() =
+                Value::Integer(0);
+        }
+
+        assert!(value
+            .find_key("user_id")
+            .all(|(_, value)| value == &Value::Integer(0)));
+    }
+}
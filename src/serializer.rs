@@ -0,0 +1,205 @@
+use std::{borrow::Cow, io};
+
+use crate::{pretty::write_pretty_io, PrettyConfig, Value};
+
+/// Writes a list one element at a time, for lists too large to
+/// build as a `Value::List` first.
+///
+/// Each element is written exactly as `to_string` (or, with
+/// [`ListSerializer::pretty`], `to_string_pretty_with`) writes
+/// it, though a pretty list is always spread across lines,
+/// even when it would fit within `max_width`. Call
+/// [`ListSerializer::finish`] to write the closing `]`
+/// and get the writer back. A serializer dropped without
+/// finishing still writes the `]`, but any error doing so is
+/// lost.
+pub struct ListSerializer<W: io::Write> {
+    /// Only `None` once `finish` has taken it back.
+    writer: Option<W>,
+    /// Set when pretty printing.
+    config: Option<PrettyConfig>,
+    len: usize,
+}
+
+impl<W: io::Write> ListSerializer<W> {
+    /// Returns a serializer writing `[a, b, c]` on one line.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Some(writer),
+            config: None,
+            len: 0,
+        }
+    }
+
+    /// Returns a serializer writing each element on a line of
+    /// its own, laid out as `config` says.
+    pub fn pretty(writer: W, config: PrettyConfig) -> Self {
+        Self {
+            writer: Some(writer),
+            config: Some(config),
+            len: 0,
+        }
+    }
+
+    /// Writes `value` as the next element of the list.
+    pub fn push(&mut self, value: &Value<'_>) -> io::Result<()> {
+        self.write_separator()?;
+        self.len += 1;
+
+        let writer = self.writer.as_mut().unwrap();
+        match &self.config {
+            Some(config) => {
+                let depth =
+                    usize::from(!config.indent.is_empty());
+                write_pretty_io(writer, value, config, depth)
+            }
+            None => value.write_to(writer),
+        }
+    }
+
+    /// Writes `string` as the next element of the list, just
+    /// like pushing a `Value::String` would.
+    pub fn push_str(&mut self, string: &str) -> io::Result<()> {
+        self.push(&Value::String(Cow::Borrowed(string)))
+    }
+
+    /// Writes the end of the list and returns the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_end()?;
+        Ok(self.writer.take().unwrap())
+    }
+
+    /// Writes what comes before the next element: the opening
+    /// `[` before the first one, and a comma before the others.
+    fn write_separator(&mut self) -> io::Result<()> {
+        let writer = self.writer.as_mut().unwrap();
+        if self.len == 0 {
+            writer.write_all(b"[")?;
+        } else {
+            writer.write_all(b",")?;
+        }
+
+        match &self.config {
+            Some(config) if !config.indent.is_empty() => {
+                writer.write_all(b"\n")?;
+                writer.write_all(config.indent.as_bytes())
+            }
+            Some(config) if !config.space_after_comma => Ok(()),
+            _ if self.len == 0 => Ok(()),
+            _ => writer.write_all(b" "),
+        }
+    }
+
+    fn write_end(&mut self) -> io::Result<()> {
+        let writer = self.writer.as_mut().unwrap();
+        if self.len == 0 {
+            return writer.write_all(b"[]");
+        }
+
+        match &self.config {
+            Some(config) if !config.indent.is_empty() => {
+                if config.trailing_commas {
+                    writer.write_all(b",")?;
+                }
+                writer.write_all(b"\n]")
+            }
+            _ => writer.write_all(b"]"),
+        }
+    }
+}
+
+impl<W: io::Write> Drop for ListSerializer<W> {
+    fn drop(&mut self) {
+        if self.writer.is_some() {
+            // There is no way to report this error, which is why
+            // `finish` should be called instead
+            let _ = self.write_end();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse_value, ListSerializer, PrettyConfig, Value,
+    };
+
+    fn elements() -> Vec<Value<'static>> {
+        parse_value(
+            r#"[1, "two\n", Some([3, 4]), Foo { x: 'x' }]"#,
+        )
+        .map(|(_, value)| value.into_iter().collect())
+        .unwrap()
+    }
+
+    #[test]
+    fn writes_lists() {
+        let elements = elements();
+        let list = Value::List(elements.clone());
+
+        let mut serializer = ListSerializer::new(Vec::new());
+        for element in &elements[..3] {
+            serializer.push(element).unwrap();
+        }
+        serializer.push(&elements[3]).unwrap();
+        let out = serializer.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            list.to_string()
+        );
+
+        for config in [
+            PrettyConfig::default(),
+            PrettyConfig::default()
+                .indent("\t")
+                .trailing_commas(false),
+            PrettyConfig::default().max_width(20),
+            PrettyConfig::default().indent(""),
+            PrettyConfig::default()
+                .indent("")
+                .space_after_comma(false),
+        ] {
+            let mut serializer = ListSerializer::pretty(
+                Vec::new(),
+                config.clone(),
+            );
+            for element in &elements {
+                serializer.push(element).unwrap();
+            }
+            let out = serializer.finish().unwrap();
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                list.to_string_pretty_with(&config)
+            );
+        }
+    }
+
+    #[test]
+    fn writes_strings_and_empty_lists() {
+        let mut serializer = ListSerializer::new(Vec::new());
+        serializer.push_str("say \"hi\"").unwrap();
+        serializer.push(&Value::Integer(1)).unwrap();
+        assert_eq!(
+            serializer.finish().unwrap(),
+            br#"["say \"hi\"", 1]"#
+        );
+
+        let serializer = ListSerializer::new(Vec::new());
+        assert_eq!(serializer.finish().unwrap(), b"[]");
+        let serializer = ListSerializer::pretty(
+            Vec::new(),
+            PrettyConfig::default(),
+        );
+        assert_eq!(serializer.finish().unwrap(), b"[]");
+    }
+
+    #[test]
+    fn closes_the_list_when_dropped() {
+        let mut out = Vec::new();
+        {
+            let mut serializer = ListSerializer::new(&mut out);
+            serializer.push(&Value::Integer(1)).unwrap();
+        }
+        assert_eq!(out, b"[1]");
+    }
+}
@@ -0,0 +1,192 @@
+use core::iter::FusedIterator;
+
+use crate::{
+    parse_tracked, parse_ws, Error, Located, Mode,
+    ParserOptions, Value,
+};
+
+/// An iterator over the values of a string holding many of
+/// them one after the other, such as a capture file with a
+/// value on each line, as made by [`ValueStream::new`].
+///
+/// Values may be separated by any whitespace and comments, or by
+/// nothing at all where they can't run together, as in `[1][2]`.
+/// Each error's line and column are those in the whole string.
+///
+/// After an error, the stream goes on from the line after the
+/// one the error is on, so that one malformed line doesn't end
+/// it, and the values on that line after the error are skipped.
+/// A value spread over many lines can leave its later lines to
+/// fail again, as each is then read as values of its own. To
+/// stop at the first error instead, take values while they are
+/// `Ok`, or collect them into a `Result<Vec<_>, _>`.
+#[derive(Clone, Debug)]
+pub struct ValueStream<'a> {
+    input: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> ValueStream<'a> {
+    pub fn new(input: &'a str) -> Self {
+        ValueStream { input, rest: input }
+    }
+
+    /// Returns how many bytes into the input the stream is,
+    /// which is where the next value is looked for, or the end
+    /// of the input once there are none.
+    pub fn offset(&self) -> usize {
+        self.input.len() - self.rest.len()
+    }
+
+    /// Returns the input that is left to be read, starting at
+    /// [`ValueStream::offset`].
+    pub fn remaining(&self) -> &'a str {
+        self.rest
+    }
+
+    /// Parses the next value, returning it along with the input
+    /// after it, or `None` if only whitespace and comments are
+    /// left.
+    fn parse_next(
+        &self,
+    ) -> Result<Option<(Value<'a>, &'a str)>, Error> {
+        let input = self.input;
+        let (rest, _) = parse_ws(self.rest)
+            .map_err(|err| Error::from_nom(input, err, None))?;
+        if rest.is_empty() {
+            return Ok(None);
+        }
+
+        let start = input.len() - rest.len();
+        let located =
+            |err: Located| err.shifted(start).into_error(input);
+        let options = ParserOptions::default();
+        match parse_tracked(rest, options, Mode::Build, true) {
+            (Ok((after, value)), furthest) => {
+                // A parser that got past the start of the next
+                // value failed in a longer one, as in
+                // `Foo { x: 1 y }`, which is otherwise taken to
+                // be `Foo`
+                let next = parse_ws(after)
+                    .map_or(after, |(next, _)| next);
+                let next = rest.len() - next.len();
+                match furthest {
+                    Some((furthest, expected))
+                        if furthest > next =>
+                    {
+                        Err(located(
+                            Located::unexpected(rest, furthest)
+                                .expecting(expected),
+                        ))
+                    }
+                    _ => Ok(Some((value, after))),
+                }
+            }
+            (Err(err), furthest) => Err(located(
+                Located::from_nom(rest, err, furthest),
+            )),
+        }
+    }
+}
+
+impl<'a> Iterator for ValueStream<'a> {
+    type Item = Result<Value<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.parse_next() {
+            Ok(Some((value, rest))) => {
+                self.rest = rest;
+                Some(Ok(value))
+            }
+            Ok(None) => {
+                self.rest = &self.input[self.input.len()..];
+                None
+            }
+            Err(err) => {
+                let line = &self.input[err.offset()..];
+                self.rest = line
+                    .find('\n')
+                    .map_or("", |idx| &line[idx + 1..]);
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl FusedIterator for ValueStream<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec::Vec};
+
+    use crate::{from_str, ErrorKind, ValueStream};
+
+    #[test]
+    fn reads_values_one_after_another() {
+        let values: Vec<_> = ValueStream::new(
+            "1 [2, 3]\n\"four\" // five\nSix(7)/* */{8}[9]",
+        )
+        .collect::<Result<_, _>>()
+        .unwrap();
+        assert_eq!(
+            values,
+            ["1", "[2, 3]", "\"four\"", "Six(7)", "{8}", "[9]"]
+                .map(|text| from_str(text).unwrap())
+        );
+
+        for empty in ["", "  \n", "// nothing\n/* at all */"] {
+            let mut stream = ValueStream::new(empty);
+            assert_eq!(stream.next(), None);
+            assert_eq!(stream.next(), None);
+            assert_eq!(stream.offset(), empty.len());
+        }
+    }
+
+    #[test]
+    fn goes_on_after_errors() {
+        let capture =
+            include_str!("../tests/fixtures/capture.tson");
+        let results: Vec<_> = ValueStream::new(capture)
+            .map(|result| match result {
+                Ok(value) => Ok(value["id"].as_i64().unwrap()),
+                Err(err) => {
+                    Err((err.kind(), err.line(), err.column()))
+                }
+            })
+            .collect();
+
+        assert_eq!(
+            results,
+            [
+                Ok(1),
+                Ok(2),
+                Ok(3),
+                Err((ErrorKind::UnexpectedChar('e'), 5, 39)),
+                Err((ErrorKind::InvalidEscape, 6, 27)),
+                Ok(6),
+                Err((ErrorKind::UnexpectedChar('p'), 12, 17)),
+                Ok(9),
+                Err((ErrorKind::UnterminatedString, 14, 25)),
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_at_the_first_error_when_asked() {
+        let capture =
+            include_str!("../tests/fixtures/capture.tson");
+        let err = ValueStream::new(capture)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unexpected character 'e' at line 5, column 39, \
+             expected '(', '{', ',', '..' or '}'"
+        );
+
+        let mut stream = ValueStream::new("1 2");
+        stream.next();
+        assert_eq!(stream.offset(), 1);
+        assert_eq!(stream.remaining(), " 2");
+    }
+}
@@ -0,0 +1,451 @@
+use core::num::NonZeroUsize;
+
+use nom::Needed;
+
+use crate::{
+    check_trailing, decode_slice, is_identifier_char,
+    parse_tracked, parse_ws, Error, ErrorKind, Mode,
+    ParserOptions, Value,
+};
+
+/// What [`from_str_streaming`] and [`from_slice_streaming`]
+/// found at the start of their input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Streamed<'a, R: ?Sized = str> {
+    /// A whole value, along with the input after it.
+    Complete(Value<'a>, &'a R),
+    /// The input ends before the value does, so it should be
+    /// parsed again once more of it has arrived, with at least
+    /// as many more bytes as `Needed` says when it knows.
+    Incomplete(Needed),
+}
+
+/// Parses the first value out of `input` like
+/// [`from_str_partial`](crate::from_str_partial) does, but tells
+/// a value that was cut off by the end of the input apart from
+/// one that is malformed, for reading values as they arrive,
+/// such as off a socket.
+///
+/// The input is incomplete if it ends inside of brackets, a
+/// string, a comment or an escape, or before there is a value at
+/// all. A value ending in a word or number is incomplete too
+/// until something follows it, as more input could make it
+/// another one: `12` may become `12.5` and `tru` may become
+/// `true`, but `12\n` and `true ` are complete. A name needs
+/// something other than whitespace or comments after it, since
+/// `Foo ` may become `Foo { a: 1 }`, and so does a range with no
+/// end, since `1.. ` may become `1.. 5`. A value in brackets or
+/// quotes is complete once it is closed, even if only
+/// whitespace follows, so a range that is streamed should have
+/// no whitespace before its `..`.
+///
+/// Once the input has ended for good, the last value is best
+/// parsed with [`from_str`](crate::from_str), which knows there
+/// is nothing more to come.
+pub fn from_str_streaming(
+    input: &str,
+) -> Result<Streamed<'_>, Error> {
+    let options = ParserOptions::default();
+    let (result, furthest) =
+        parse_tracked(input, options, Mode::Build, true);
+    // A parser that failed at the end of the input, or at the
+    // start of a token there, may have parsed more with more of
+    // it, even if what the error is about is further back
+    let at_end = furthest.as_ref().is_some_and(|(offset, _)| {
+        let rest = &input[*offset..];
+        rest.is_empty() || is_token_start(rest)
+    });
+    let (value, rest, err) = match result {
+        Ok((rest, ref value))
+            if at_end
+                || may_continue(
+                    value,
+                    &input[..input.len() - rest.len()],
+                    rest,
+                ) =>
+        {
+            return Ok(Streamed::Incomplete(Needed::Unknown))
+        }
+        // A longer value may have been cut off, as when `Foo {`
+        // is taken to be `Foo` for now, so what the parsers that
+        // got further than the value found is what matters
+        Ok((rest, value)) => {
+            match check_trailing(input, rest, furthest) {
+                Err(err)
+                    if err.kind()
+                        != ErrorKind::TrailingInput =>
+                {
+                    (Some(value), rest, err)
+                }
+                _ => return Ok(Streamed::Complete(value, rest)),
+            }
+        }
+        Err(err) => {
+            (None, input, Error::from_nom(input, err, furthest))
+        }
+    };
+
+    match value {
+        _ if at_end || is_cut_off(input, &err) => {
+            Ok(Streamed::Incomplete(Needed::Unknown))
+        }
+        Some(value) => Ok(Streamed::Complete(value, rest)),
+        None => Err(err),
+    }
+}
+
+/// Parses the first value out of `input` like
+/// [`from_slice_partial`](crate::from_slice_partial) does, but
+/// tells a cut off value apart from a malformed one, as
+/// [`from_str_streaming`] does.
+///
+/// A UTF-8 sequence cut off by the end of the input is
+/// incomplete as well, needing the bytes it is missing.
+pub fn from_slice_streaming(
+    input: &[u8],
+) -> Result<Streamed<'_, [u8]>, Error> {
+    let (valid, missing) = match core::str::from_utf8(input) {
+        Err(err) if err.error_len().is_none() => {
+            let valid = &input[..err.valid_up_to()];
+            let missing = utf8_len(input[valid.len()])
+                - (input.len() - valid.len());
+            (valid, missing)
+        }
+        _ => (input, 0),
+    };
+
+    let (bom_len, text) = decode_slice(valid)?;
+    let streamed = from_str_streaming(text)
+        .map_err(|err| err.shifted(bom_len))?;
+    Ok(match streamed {
+        Streamed::Complete(value, rest) => Streamed::Complete(
+            value,
+            &input[valid.len() - rest.len()..],
+        ),
+        Streamed::Incomplete(needed) => Streamed::Incomplete(
+            NonZeroUsize::new(missing)
+                .map_or(needed, Needed::Size),
+        ),
+    })
+}
+
+/// Returns how long the UTF-8 sequence starting with `first` is.
+fn utf8_len(first: u8) -> usize {
+    match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        _ => 4,
+    }
+}
+
+/// Returns whether `value`, parsed out of `parsed` with `rest`
+/// after it, could be the start of another value, were there
+/// more input.
+fn may_continue(
+    value: &Value<'_>,
+    parsed: &str,
+    rest: &str,
+) -> bool {
+    let ends_in_word = parsed
+        .ends_with(|chr| is_identifier_char(chr) || chr == '.');
+    let after_ws = parse_ws(rest).map_or("", |(rest, _)| rest);
+    // A struct's name may be followed by whitespace before its
+    // `{`, and so may a range's operator before its end, but
+    // anything else ending in a word or number ends there
+    let spaced = matches!(
+        value,
+        Value::Ident(_) | Value::Range { end: None, .. }
+    );
+    let ended = match spaced {
+        true => !after_ws.is_empty(),
+        false => !rest.is_empty(),
+    };
+    if !ended && ends_in_word || is_token_start(after_ws) {
+        return true;
+    }
+
+    // The `r#` of a raw string is taken as an identifier until
+    // its quote comes
+    let word = parsed.trim_end_matches(is_identifier_char);
+    let word = &parsed[word.len()..];
+    matches!(word, "r" | "br")
+        && rest.bytes().all(|byte| byte == b'#')
+}
+
+/// Returns whether `err` is only there because `input` ends too
+/// soon.
+fn is_cut_off(input: &str, err: &Error) -> bool {
+    let (before, rest) = input.split_at(err.offset());
+    match err.kind() {
+        _ if rest.is_empty() => true,
+        // Everything after the opening delimiter is in it, up to
+        // the end of the line for a char
+        ErrorKind::UnterminatedString
+            if rest.starts_with('\'') =>
+        {
+            !rest.contains(['\n', '\r'])
+        }
+        ErrorKind::UnterminatedString
+        | ErrorKind::UnterminatedComment => true,
+        ErrorKind::InvalidEscape => is_escape_start(rest),
+        ErrorKind::InvalidNumber => is_number_end_start(rest),
+        ErrorKind::UnexpectedChar(_) => {
+            is_token_start(rest)
+                || before.ends_with('-')
+                    && "inf".starts_with(rest)
+        }
+        _ => false,
+    }
+}
+
+/// Returns whether `rest` is the start of a token longer than
+/// it, such as the `:` of a path's `::`, with nothing after it.
+fn is_token_start(rest: &str) -> bool {
+    !rest.is_empty()
+        && ["..=", "::", "/*"]
+            .iter()
+            .any(|token| token.starts_with(rest))
+}
+
+/// Returns whether `rest` is the start of an escape, such as
+/// `\u{1f` or `\x`, with nothing after it.
+fn is_escape_start(rest: &str) -> bool {
+    let Some(escape) = rest.strip_prefix('\\') else {
+        return false;
+    };
+    let is_hex = |digits: &str, max: usize| {
+        digits.len() <= max
+            && digits
+                .bytes()
+                .all(|byte| byte.is_ascii_hexdigit())
+    };
+
+    match escape.split_at(escape.len().min(1)) {
+        ("", _) => true,
+        ("x", digits) => is_hex(digits, 1),
+        ("u", digits) => match digits.strip_prefix('{') {
+            Some(digits) => is_hex(digits, 6),
+            // A JSON escape, which may be the first of a pair
+            None => match digits.split_at(digits.len().min(4)) {
+                (high, "") => is_hex(high, 4),
+                (high, low) => {
+                    is_hex(high, 4)
+                        && ("\\u".starts_with(low)
+                            || low
+                                .strip_prefix("\\u")
+                                .is_some_and(|low| {
+                                    is_hex(low, 3)
+                                }))
+                }
+            },
+        },
+        _ => false,
+    }
+}
+
+/// Returns whether `rest`, the end of a number that isn't valid,
+/// is the start of what could end one, such as the `_` of `1_0`,
+/// or the `u` of `1u8` or `1_u8`.
+fn is_number_end_start(rest: &str) -> bool {
+    const SUFFIXES: [&str; 14] = [
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16",
+        "u32", "u64", "u128", "usize", "f32", "f64",
+    ];
+
+    rest.bytes().all(|byte| byte == b'_')
+        || SUFFIXES.iter().any(|suffix| {
+            suffix.starts_with(
+                rest.strip_prefix('_').unwrap_or(rest),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::Needed;
+
+    use super::{
+        from_slice_streaming, from_str_streaming, Streamed,
+    };
+    use crate::{
+        from_slice, from_str, tests::float, ErrorKind, Value,
+    };
+
+    /// Documents covering every kind of value, each cut off
+    /// everywhere it can be.
+    const DOCUMENTS: [&str; 12] = [
+        r#"Config { name: "a\tb\u{1f600}", ports: [80, 443], tags: {"k": 'x', "l": '\n'}, limits: {1: (0..10, -1.5e3), 2: (..=20, 0x1f)} }"#,
+        r##"[Some(1_000u64), Some(1_u8), None, Ok(r#"raw "quoted""#), Err(b"\x00\xff"), true, false]"##,
+        "Foo(1, 2, Bar::Baz) /* a /* nested */ comment */",
+        "{\"a\": \"\\\\\\\"\", \"b\": b\"\\\\\"}",
+        "[1.5, -2., 3e-2, inf, -inf, NaN, 1f32, 255u8, 0b1010, 0o17]",
+        "Outer { inner: Inner { x: 1 }, .. }",
+        "'\\u{1f600}'",
+        "\"café ☕ 日本語\"",
+        "[1..5, 1.., ..5, 1..=5]",
+        "{1, 2, 3}",
+        "// a comment\nUnit",
+        "  12345  ",
+    ];
+
+    #[test]
+    fn finds_values_cut_off() {
+        for document in DOCUMENTS {
+            let bytes = document.as_bytes();
+            let expected = from_slice(bytes).unwrap();
+            for len in 0..bytes.len() {
+                // A value found early must be the whole one,
+                // with nothing but whitespace
+                // and comments after it
+                match from_slice_streaming(&bytes[..len]) {
+                    Ok(Streamed::Incomplete(_)) => {}
+                    Ok(Streamed::Complete(value, rest)) => {
+                        assert_eq!(
+                            value, expected,
+                            "{document} {len}"
+                        );
+                        let rest =
+                            [b"1", rest, &bytes[len..]].concat();
+                        assert!(
+                            from_slice(&rest).is_ok(),
+                            "{document} {len}"
+                        );
+                    }
+                    Err(err) => {
+                        panic!("{document} {len}: {err}")
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parses_values_fed_a_byte_at_a_time() {
+        let stream =
+            "1 [2, 3]\"four\"Five { x: 5 }\n'6'Seven 8.5";
+        let mut buffer = Vec::new();
+        let mut values = Vec::new();
+        for &byte in stream.as_bytes() {
+            buffer.push(byte);
+            loop {
+                let Ok(Streamed::Complete(value, rest)) =
+                    from_slice_streaming(&buffer)
+                else {
+                    break;
+                };
+                let parsed = buffer.len() - rest.len();
+                values.push(format!("{value}"));
+                drop(value);
+                buffer.drain(..parsed);
+            }
+        }
+        // Nothing more is coming, so the last value is complete
+        values.push(format!("{}", from_slice(&buffer).unwrap()));
+
+        assert_eq!(
+            values,
+            [
+                "1",
+                "[2, 3]",
+                "\"four\"",
+                "Five { x: 5 }",
+                "'6'",
+                "Seven",
+                "8.5"
+            ]
+        );
+    }
+
+    #[test]
+    fn tells_cut_off_values_from_malformed_ones() {
+        for text in [
+            "",
+            " // a comment",
+            "[\"abc",
+            "12",
+            "12.",
+            "-",
+            "tru",
+            "Some(",
+            "Foo ",
+            "Foo {",
+            "1.. ",
+            "Foo::",
+            "\"\\u{1f",
+            "b\"\\x",
+            "'a",
+            "1_",
+            "1u",
+            "/* a",
+            "[1, 2",
+            "{1: 2, 3",
+            "r#",
+            "1..",
+            "[-in",
+        ] {
+            assert_eq!(
+                from_str_streaming(text),
+                Ok(Streamed::Incomplete(Needed::Unknown)),
+                "{text:?}"
+            );
+        }
+
+        for (text, kind) in [
+            ("[1 2", ErrorKind::UnexpectedChar('2')),
+            ("\"\\q", ErrorKind::InvalidEscape),
+            ("'a\n", ErrorKind::UnterminatedString),
+            ("1q", ErrorKind::InvalidNumber),
+            ("]", ErrorKind::UnexpectedChar(']')),
+        ] {
+            let err = from_str_streaming(text).unwrap_err();
+            assert_eq!(
+                err,
+                from_str(text).unwrap_err(),
+                "{text:?}"
+            );
+            assert_eq!(err.kind(), kind, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn finds_values_followed_by_more() {
+        for (text, value, rest) in [
+            ("12,", Value::Integer(12), ","),
+            ("12\n", Value::Integer(12), "\n"),
+            ("true]", Value::Boolean(true), "]"),
+            ("true ", Value::Boolean(true), " "),
+            ("None\n", Value::Optional(None), "\n"),
+            ("-1.5e3 // done", float("-1.5e3"), " // done"),
+            ("\"a\" ", Value::String("a".into()), " "),
+            (
+                "[1] [",
+                Value::List(vec![Value::Integer(1)]),
+                " [",
+            ),
+        ] {
+            assert_eq!(
+                from_str_streaming(text),
+                Ok(Streamed::Complete(value, rest)),
+                "{text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn waits_for_the_rest_of_a_utf8_sequence() {
+        let text = "\"日本\"".as_bytes();
+        assert_eq!(
+            from_slice_streaming(&text[..2]),
+            Ok(Streamed::Incomplete(Needed::new(2)))
+        );
+        assert_eq!(
+            from_slice_streaming(
+                &[text, &"é".as_bytes()[..1]].concat()
+            ),
+            Ok(Streamed::Complete(
+                Value::String("日本".into()),
+                &b"\xc3"[..]
+            ))
+        );
+    }
+}
@@ -0,0 +1,390 @@
+use alloc::{borrow::Cow, vec::Vec};
+use core::mem;
+
+use crate::{path::segments, Path, PathSegment, Value, Walk};
+
+impl<'a> Value<'a> {
+    /// Removes every element of a list, tuple, set or tuple
+    /// struct, and every entry of an object, struct or map, in
+    /// this value at any depth, that `keep` returns `false` for,
+    /// given its path and the value.
+    ///
+    /// Values are looked at before the ones in them, so removing
+    /// a value removes everything in it without it being looked
+    /// at. An element's path has its position once the ones
+    /// before it that weren't kept are gone, as the value in a
+    /// `Some` does its `Some`'s path, and it is the `Some` that
+    /// is removed or kept. The entries of maps whose keys aren't
+    /// strings are always kept, as no path leads to them.
+    ///
+    /// Like [`Value::walk_mut`], this doesn't recurse, so values
+    /// nested however deep can be retained, though like
+    /// [`Value::find_all`] it gives each value a path of its
+    /// own.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&Path<'_>, &Value<'a>) -> bool,
+    {
+        self.walk_mut(|path, value| {
+            match value {
+                Value::List(elements)
+                | Value::Tuple(elements)
+                | Value::Set(elements)
+                | Value::TupleStruct {
+                    fields: elements, ..
+                } => {
+                    let mut idx = 0;
+                    elements.retain(|element| {
+                        let kept = keep(
+                            &path.join(PathSegment::Index(idx)),
+                            element,
+                        );
+                        idx += usize::from(kept);
+                        kept
+                    });
+                }
+                Value::Struct { fields, .. } => {
+                    fields.retain(|(field, value)| {
+                        keep(
+                            &path.join(PathSegment::Key(field)),
+                            value,
+                        )
+                    })
+                }
+                Value::Object(object) => {
+                    object.retain(|key, value| {
+                        keep(
+                            &path.join(PathSegment::Key(key)),
+                            value,
+                        )
+                    })
+                }
+                Value::Map(entries) => {
+                    entries.retain(|(key, value)| {
+                        match key.as_str() {
+                            Some(key) => keep(
+                                &path
+                                    .join(PathSegment::Key(key)),
+                                value,
+                            ),
+                            None => true,
+                        }
+                    })
+                }
+                _ => {}
+            }
+
+            Walk::Continue
+        });
+    }
+
+    /// Returns this value with each value in it that isn't a
+    /// list, tuple, set, struct, object, map or `Some` replaced
+    /// by what `f` returns for it, such as a number, a string, a
+    /// `None` or a `Result`, which is given to `f` whole.
+    ///
+    /// What `f` returns isn't looked into even if it has values
+    /// in it. Like [`Value::walk_mut`], this doesn't recurse.
+    pub fn map_values<F>(self, mut f: F) -> Value<'a>
+    where
+        F: FnMut(Value<'a>) -> Value<'a>,
+    {
+        let mut mapped = self;
+        mapped.walk_mut(|_, value| {
+            if !is_leaf(value) {
+                return Walk::Continue;
+            }
+
+            let leaf =
+                mem::replace(value, Value::Optional(None));
+            *value = f(leaf);
+            Walk::SkipChildren
+        });
+
+        mapped
+    }
+
+    /// Calls `f` with each string in this value, at any depth,
+    /// and the path to it, so that it can be changed in place.
+    /// Keys, names and identifiers aren't strings, so they are
+    /// left as they are.
+    pub fn map_strings_in_place<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Path<'_>, &mut Cow<'a, str>),
+    {
+        self.walk_mut(|path, value| {
+            if let Value::String(string) = value {
+                f(path, string);
+            }
+            Walk::Continue
+        });
+    }
+
+    /// Replaces each value at one of `patterns` with the string
+    /// `"***"`, as when scrubbing secrets before a value is
+    /// logged.
+    ///
+    /// A pattern of a single key, such as `password`, matches a
+    /// field or entry of that name at any depth, and any other
+    /// pattern is a whole path read like [`Value::get_path`]
+    /// reads it, such as `db.url` or `users.0.token`. Whatever
+    /// is at a pattern is replaced whole, whether it is a
+    /// string or not.
+    pub fn redact(&mut self, patterns: &[&str]) {
+        let patterns: Vec<Vec<Cow<'_, str>>> = patterns
+            .iter()
+            .map(|pattern| segments(pattern).collect())
+            .collect();
+
+        self.walk_mut(|path, value| {
+            let path = path.segments();
+            let matched = patterns.iter().any(|pattern| match &pattern[..] {
+                [key] => {
+                    matches!(path.last(), Some(PathSegment::Key(last)) if last == key)
+                }
+                pattern => {
+                    pattern.len() == path.len()
+                        && pattern.iter().zip(path).all(|(expected, segment)| {
+                            match segment {
+                                PathSegment::Key(key) => key == expected,
+                                PathSegment::Index(idx) => {
+                                    expected.parse() == Ok(*idx)
+                                }
+                            }
+                        })
+                }
+            });
+            if !matched {
+                return Walk::Continue;
+            }
+
+            *value = Value::String(Cow::Borrowed("***"));
+            Walk::SkipChildren
+        });
+    }
+}
+
+/// Returns `true` if no path leads further into `value`.
+fn is_leaf(value: &Value<'_>) -> bool {
+    !matches!(
+        value,
+        Value::List(_)
+            | Value::Tuple(_)
+            | Value::Set(_)
+            | Value::TupleStruct { .. }
+            | Value::Struct { .. }
+            | Value::Object(_)
+            | Value::Map(_)
+            | Value::Optional(Some(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::Cow, string::ToString, vec, vec::Vec};
+
+    use crate::{from_str, PathSegment, Value, Walk};
+
+    const CAPTURED: &str = r#"Request {
+        user: User { name: "ana", password: "hunter2", roles: ["admin"] },
+        headers: {"auth": "Bearer sk-123", "accept": "*/*"},
+        upstreams: [
+            Upstream { url: "db://a", password: "a", debug: Some({"trace": [1, 2]}) },
+            Upstream { url: "db://b", password: 12, debug: None },
+        ],
+        body: Some(["sk-456", "plain"]),
+        status: Ok("sk-hidden"),
+    }"#;
+
+    #[test]
+    fn scrubs_secrets_and_leaves_the_rest_alone() {
+        let original = from_str(CAPTURED).unwrap();
+        let mut value = original.clone();
+        value.map_strings_in_place(|path, string| {
+            let named_password = matches!(
+                path.segments().last(),
+                Some(PathSegment::Key("password"))
+            );
+            if named_password || string.contains("sk-") {
+                *string = Cow::Borrowed("***");
+            }
+        });
+
+        assert_eq!(
+            value,
+            from_str(
+                r#"Request {
+                    user: User { name: "ana", password: "***", roles: ["admin"] },
+                    headers: {"auth": "***", "accept": "*/*"},
+                    upstreams: [
+                        Upstream { url: "db://a", password: "***", debug: Some({"trace": [1, 2]}) },
+                        Upstream { url: "db://b", password: 12, debug: None },
+                    ],
+                    body: Some(["***", "plain"]),
+                    status: Ok("sk-hidden"),
+                }"#
+            )
+            .unwrap()
+        );
+        // What wasn't scrubbed is just as it was
+        assert_eq!(
+            value["user"]["roles"],
+            original["user"]["roles"]
+        );
+        assert_eq!(
+            value["upstreams"][0]["debug"],
+            original["upstreams"][0]["debug"]
+        );
+        assert_eq!(value["status"], original["status"]);
+    }
+
+    #[test]
+    fn drops_subtrees_and_elements() {
+        let mut value = from_str(CAPTURED).unwrap();
+        let mut looked_at = Vec::new();
+        value.retain(|path, value| {
+            looked_at.push(path.to_string());
+            !matches!(
+                path.segments().last(),
+                Some(PathSegment::Key("debug" | "headers"))
+            ) && value.as_str() != Some("sk-456")
+        });
+
+        assert_eq!(
+            value,
+            from_str(
+                r#"Request {
+                    user: User { name: "ana", password: "hunter2", roles: ["admin"] },
+                    upstreams: [
+                        Upstream { url: "db://a", password: "a" },
+                        Upstream { url: "db://b", password: 12 },
+                    ],
+                    body: Some(["plain"]),
+                    status: Ok("sk-hidden"),
+                }"#
+            )
+            .unwrap()
+        );
+        // Nothing in a removed value is looked at, and an
+        // element is at the position it ends up in
+        assert!(!looked_at
+            .iter()
+            .any(|path| path.contains("trace")));
+        assert!(looked_at.contains(&"body.0".to_string()));
+        assert_eq!(
+            looked_at
+                .iter()
+                .filter(|path| *path == "body.0")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn maps_every_leaf() {
+        let value = from_str(
+            "[1, Some(2), [], {\"a\": (3, \"x\")}, None, Ok(4)]",
+        )
+        .unwrap();
+        let mut leaves = 0;
+        let mapped = value.map_values(|value| {
+            leaves += 1;
+            match value {
+                Value::Integer(integer) => {
+                    Value::Integer(integer * 10)
+                }
+                // What is put in place isn't mapped again
+                Value::String(_) => {
+                    Value::List(vec![Value::Integer(5)])
+                }
+                value => value,
+            }
+        });
+
+        assert_eq!(leaves, 6);
+        assert_eq!(
+            mapped,
+            from_str(
+                "[10, Some(20), [], {\"a\": (30, [5])}, None, Ok(4)]"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn redacts_keys_and_paths() {
+        let mut value = from_str(CAPTURED).unwrap();
+        value.redact(&[
+            "password",
+            "upstreams.1.url",
+            "body",
+            "nowhere.x",
+        ]);
+
+        assert_eq!(
+            value,
+            from_str(
+                r#"Request {
+                    user: User { name: "ana", password: "***", roles: ["admin"] },
+                    headers: {"auth": "Bearer sk-123", "accept": "*/*"},
+                    upstreams: [
+                        Upstream { url: "db://a", password: "***", debug: Some({"trace": [1, 2]}) },
+                        Upstream { url: "***", password: "***", debug: None },
+                    ],
+                    body: "***",
+                    status: Ok("sk-hidden"),
+                }"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn transforms_deep_values_without_recursing() {
+        let deep = |depth| {
+            let mut value = Value::Integer(0);
+            for _ in 0..depth {
+                value = Value::List(vec![
+                    value,
+                    Value::Optional(None),
+                ]);
+            }
+            value
+        };
+        // How many elements each list has and what is at the
+        // bottom
+        let shape = |value: &Value<'_>| {
+            let mut lens = Vec::new();
+            let mut bottom = None;
+            value.walk(|_, value| {
+                match value {
+                    Value::List(elements) => {
+                        lens.push(elements.len())
+                    }
+                    Value::Integer(integer) => {
+                        bottom = Some(*integer)
+                    }
+                    _ => {}
+                }
+                Walk::Continue
+            });
+            lens.dedup();
+            (lens, bottom)
+        };
+
+        // Each value `retain` looks at gets a path of its own,
+        // so it is given a shallower value
+        let mut value = deep(10_000);
+        value.retain(|_, value| *value != Value::Optional(None));
+        assert_eq!(shape(&value), (vec![1], Some(0)));
+
+        let mut value = deep(100_000);
+        value.map_strings_in_place(|_, _| {});
+        value.redact(&["x"]);
+        let value = value.map_values(|value| match value {
+            Value::Integer(_) => Value::Integer(1),
+            value => value,
+        });
+        assert_eq!(shape(&value), (vec![2], Some(1)));
+    }
+}
@@ -0,0 +1,255 @@
+use core::{fmt, str::FromStr};
+
+use nom::{error::ErrorKind, IResult};
+
+use crate::Value;
+
+/// A UUID, such as `67e55044-10b1-426f-9247-bb680e5fe0c8`, as
+/// its 16 bytes in the order they are written in.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+pub struct Uuid(pub [u8; 16]);
+
+/// An error parsing a [`Uuid`] from text that isn't one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidUuid;
+
+impl fmt::Display for InvalidUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid UUID")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidUuid {}
+
+/// The length of a hyphenated UUID.
+const HYPHENATED_LEN: usize = 36;
+
+impl Uuid {
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    /// Parses the hyphenated form, `8-4-4-4-12` hex digits of
+    /// either case.
+    fn parse_hyphenated(text: &str) -> Option<Self> {
+        let bytes = text.as_bytes();
+        if bytes.len() != HYPHENATED_LEN
+            || [8, 13, 18, 23]
+                .iter()
+                .any(|&idx| bytes[idx] != b'-')
+        {
+            return None;
+        }
+
+        let digits = bytes.iter().filter(|&&byte| byte != b'-');
+        Self::from_hex_digits(digits.copied())
+    }
+
+    /// Reads exactly 32 hex digits.
+    fn from_hex_digits(
+        mut digits: impl Iterator<Item = u8>,
+    ) -> Option<Self> {
+        let mut bytes = [0; 16];
+        for byte in &mut bytes {
+            let high = hex_digit(digits.next()?)?;
+            let low = hex_digit(digits.next()?)?;
+            *byte = high << 4 | low;
+        }
+
+        digits.next().is_none().then_some(Uuid(bytes))
+    }
+}
+
+fn hex_digit(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
+
+impl FromStr for Uuid {
+    type Err = InvalidUuid;
+
+    /// Parses a UUID in any of the forms the `uuid` crate reads:
+    /// hyphenated (`67e55044-10b1-426f-9247-bb680e5fe0c8`),
+    /// simple (`67e5504410b1426f9247bb680e5fe0c8`), braced
+    /// (`{67e55044-..}`) or as a URN (`urn:uuid:67e55044-..`),
+    /// with hex digits of either case.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let hyphenated = text
+            .strip_prefix('{')
+            .and_then(|text| text.strip_suffix('}'))
+            .or_else(|| text.strip_prefix("urn:uuid:"))
+            .unwrap_or(text);
+
+        Uuid::parse_hyphenated(hyphenated)
+            .or_else(|| Uuid::from_hex_digits(text.bytes()))
+            .ok_or(InvalidUuid)
+    }
+}
+
+/// Writes the UUID hyphenated, in lowercase, as the `uuid`
+/// crate's `Display` and `Debug` do.
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, byte) in self.0.iter().enumerate() {
+            if matches!(idx, 4 | 6 | 8 | 10) {
+                f.write_str("-")?;
+            }
+            write!(f, "{byte:02x}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a bare hyphenated UUID, which mustn't be followed by
+/// anything that could continue a number or an identifier.
+pub(crate) fn parse_uuid(input: &str) -> IResult<&str, Uuid> {
+    let error = || {
+        nom::Err::Error(nom::error::Error::new(
+            input,
+            ErrorKind::Verify,
+        ))
+    };
+
+    let uuid = input
+        .get(..HYPHENATED_LEN)
+        .and_then(Uuid::parse_hyphenated)
+        .ok_or_else(error)?;
+    let rest = &input[HYPHENATED_LEN..];
+    if rest.starts_with(|chr: char| {
+        chr.is_alphanumeric() || chr == '_' || chr == '-'
+    }) {
+        return Err(error());
+    }
+
+    Ok((rest, uuid))
+}
+
+impl Value<'_> {
+    /// Returns the UUID this value holds, whether it was parsed
+    /// as a `Value::Uuid` or is a `Value::String` holding one in
+    /// any of the forms [`Uuid`]'s `FromStr` reads.
+    pub fn as_uuid(&self) -> Option<Uuid> {
+        match self {
+            Value::Uuid(uuid) => Some(*uuid),
+            Value::String(string) => string.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl From<Uuid> for Value<'_> {
+    fn from(uuid: Uuid) -> Self {
+        Value::Uuid(uuid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{InvalidUuid, Uuid};
+    use crate::{
+        parse_value, parse_value_with, ParserOptions, Value,
+    };
+
+    const UUIDS: [(&str, [u8; 16]); 3] = [
+        (
+            "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            [
+                0x67, 0xe5, 0x50, 0x44, 0x10, 0xb1, 0x42, 0x6f,
+                0x92, 0x47, 0xbb, 0x68, 0x0e, 0x5f, 0xe0, 0xc8,
+            ],
+        ),
+        ("00000000-0000-0000-0000-000000000000", [0; 16]),
+        ("ffffffff-ffff-ffff-ffff-ffffffffffff", [0xff; 16]),
+    ];
+
+    #[test]
+    fn parses_uuids() {
+        for (text, bytes) in UUIDS {
+            let uuid = Uuid(bytes);
+            assert_eq!(text.parse(), Ok(uuid));
+            assert_eq!(uuid.to_string(), text);
+            assert_eq!(text.to_uppercase().parse(), Ok(uuid));
+            assert_eq!(format!("{{{text}}}").parse(), Ok(uuid));
+            assert_eq!(
+                format!("urn:uuid:{text}").parse(),
+                Ok(uuid)
+            );
+            assert_eq!(text.replace('-', "").parse(), Ok(uuid));
+        }
+
+        for text in [
+            "67e55044-10b1-426f-9247-bb680e5fe0c",
+            "67e55044-10b1-426f-9247-bb680e5fe0c8a",
+            "67e5504410b1-426f-9247-bb680e5fe0c8-",
+            "67e55044-10b1-426f-9247-bb680e5fe0cg",
+            "{67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "",
+        ] {
+            assert_eq!(
+                text.parse::<Uuid>(),
+                Err(InvalidUuid),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn parses_uuid_values_when_asked() {
+        let options = ParserOptions::default().uuids(true);
+        let (text, bytes) = UUIDS[0];
+        let uuid = Value::Uuid(Uuid(bytes));
+
+        let cases = [
+            (
+                format!("[{text}, \"{text}\"]"),
+                [uuid.clone(), uuid.clone()],
+            ),
+            (
+                format!(
+                    "[{}, \"{{{text}}}\"]",
+                    text.to_uppercase()
+                ),
+                [
+                    uuid.clone(),
+                    Value::String(format!("{{{text}}}").into()),
+                ],
+            ),
+        ];
+        for (text, expected) in cases {
+            let (rest, value) =
+                parse_value_with(&text, options).unwrap();
+            assert_eq!(rest, "");
+            assert_eq!(
+                value,
+                Value::List(expected.to_vec()),
+                "{text}"
+            );
+            assert_eq!(
+                parse_value_with(&value.to_string(), options),
+                Ok(("", value))
+            );
+        }
+
+        assert_eq!(uuid.to_string(), format!("\"{text}\""));
+        assert!(!matches!(
+            parse_value_with(&format!("{text}0"), options),
+            Ok((_, Value::Uuid(_)))
+        ));
+        assert_eq!(
+            parse_value_with("[1e5, 12345678, abc]", options),
+            parse_value("[1e5, 12345678, abc]")
+        );
+        assert_eq!(
+            parse_value(&format!("\"{text}\""))
+                .unwrap()
+                .1
+                .as_uuid(),
+            uuid.as_uuid()
+        );
+    }
+}
@@ -0,0 +1,354 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{display::sorted, Path, PathSegment, Value};
+
+/// What [`Value::walk`] and [`Value::walk_mut`] do after
+/// visiting a value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Walk {
+    /// Go on to the values in this one, and then the rest.
+    Continue,
+    /// Go on to the rest, leaving out the values in this one.
+    SkipChildren,
+    /// Visit nothing more.
+    Stop,
+}
+
+/// A value yet to be visited: how long the path to the value it
+/// is in is, the segment it is at in that value, if any, and the
+/// value itself.
+type Pending<'v, V> = (usize, Option<PathSegment<'v>>, V);
+
+impl<'a> Value<'a> {
+    /// Calls `f` with this value and every value in it that a
+    /// path leads to, along with the path to each, in the order
+    /// [`Value::find_all`] visits them, until `f` returns
+    /// [`Walk::Stop`].
+    ///
+    /// The walk keeps its own stack rather than recursing, so
+    /// values nested however deep don't overflow the stack, and
+    /// the path given to `f` is the same one each time, changed
+    /// as the walk goes, so `f` has to clone it or write it out
+    /// to keep it.
+    pub fn walk<'v, F>(&'v self, mut f: F)
+    where
+        F: FnMut(&Path<'v>, &'v Value<'a>) -> Walk,
+    {
+        let mut path = Path::default();
+        let mut stack: Vec<Pending<'v, &'v Value<'a>>> =
+            vec![(0, None, self)];
+        while let Some((len, segment, value)) = stack.pop() {
+            path.truncate(len);
+            if let Some(segment) = segment {
+                path.push(segment);
+            }
+            match f(&path, value) {
+                Walk::Continue => {}
+                Walk::SkipChildren => continue,
+                Walk::Stop => return,
+            }
+
+            let len = path.segments().len();
+            let start = stack.len();
+            children(value, |segment, child| {
+                stack.push((len, segment, child))
+            });
+            // The stack is popped from the end, so the first of
+            // them needs to be last
+            stack[start..].reverse();
+        }
+    }
+
+    /// Calls `f` with this value and every value in it like
+    /// [`Value::walk`] does, but so that they can be changed.
+    ///
+    /// Each value is given to `f` before the values in it, which
+    /// are then the ones in what `f` left there, so replacing a
+    /// value with another walks the new one's values, and
+    /// emptying a list walks none.
+    pub fn walk_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Path<'_>, &mut Value<'a>) -> Walk,
+    {
+        let mut path = Path::default();
+        let mut stack: Vec<Pending<'_, &mut Value<'a>>> =
+            vec![(0, None, self)];
+        while let Some((len, segment, value)) = stack.pop() {
+            path.truncate(len);
+            if let Some(segment) = segment {
+                path.push(segment);
+            }
+            match f(&path, value) {
+                Walk::Continue => {}
+                Walk::SkipChildren => continue,
+                Walk::Stop => return,
+            }
+
+            let len = path.segments().len();
+            let start = stack.len();
+            children_mut(value, |segment, child| {
+                stack.push((len, segment, child))
+            });
+            stack[start..].reverse();
+        }
+    }
+}
+
+/// Calls `f` with each value in `value` that a path leads to, in
+/// order, and the segment leading to it, or `None` for the value
+/// in a `Some`, which is at the same path.
+pub(crate) fn children<'v, 'a>(
+    value: &'v Value<'a>,
+    mut f: impl FnMut(Option<PathSegment<'v>>, &'v Value<'a>),
+) {
+    match value {
+        Value::List(elements)
+        | Value::Tuple(elements)
+        | Value::Set(elements)
+        | Value::TupleStruct {
+            fields: elements, ..
+        } => {
+            for (idx, element) in elements.iter().enumerate() {
+                f(Some(PathSegment::Index(idx)), element);
+            }
+        }
+        Value::Struct { fields, .. } => {
+            for (field, value) in fields {
+                f(Some(PathSegment::Key(field)), value);
+            }
+        }
+        Value::Object(object) => {
+            for (key, value) in sorted(object) {
+                f(Some(PathSegment::Key(key)), value);
+            }
+        }
+        Value::Map(entries) => {
+            for (key, value) in entries {
+                if let Some(key) = key.as_str() {
+                    f(Some(PathSegment::Key(key)), value);
+                }
+            }
+        }
+        Value::Optional(Some(inner)) => f(None, inner),
+        _ => {}
+    }
+}
+
+/// Calls `f` with each value in `value` like [`children`] does,
+/// but so that they can be changed.
+fn children_mut<'v, 'a>(
+    value: &'v mut Value<'a>,
+    mut f: impl FnMut(Option<PathSegment<'v>>, &'v mut Value<'a>),
+) {
+    match value {
+        Value::List(elements)
+        | Value::Tuple(elements)
+        | Value::Set(elements)
+        | Value::TupleStruct {
+            fields: elements, ..
+        } => {
+            for (idx, element) in elements.iter_mut().enumerate()
+            {
+                f(Some(PathSegment::Index(idx)), element);
+            }
+        }
+        Value::Struct { fields, .. } => {
+            for (field, value) in fields {
+                f(Some(PathSegment::Key(field)), value);
+            }
+        }
+        Value::Object(object) => {
+            let mut entries: Vec<_> =
+                object.iter_mut().collect();
+            entries.sort_unstable_by_key(|&(key, _)| key);
+            for (key, value) in entries {
+                f(Some(PathSegment::Key(key)), value);
+            }
+        }
+        Value::Map(entries) => {
+            for (key, value) in entries {
+                let key: &Value<'a> = key;
+                if let Some(key) = key.as_str() {
+                    f(Some(PathSegment::Key(key)), value);
+                }
+            }
+        }
+        Value::Optional(Some(inner)) => f(None, inner),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+
+    use crate::{from_str, Value, Walk};
+
+    const DOCUMENT: &str = r#"Report {
+        title: "weekly",
+        sections: [
+            Section { name: "a", lines: ["x", "y"] },
+            Section { name: "b", lines: [] },
+        ],
+        meta: {"z": "last", "a": Some("first"), "n": 1},
+        lookup: {1: "skipped", "key": "kept"},
+        status: Ok("hidden"),
+    }"#;
+
+    #[test]
+    fn collects_string_leaves_with_paths() {
+        let value = from_str(DOCUMENT).unwrap();
+        let mut strings = Vec::new();
+        value.walk(|path, value| {
+            if let Some(string) = value.as_str() {
+                strings.push((path.to_string(), string));
+            }
+            Walk::Continue
+        });
+
+        assert_eq!(
+            strings,
+            [
+                ("title".to_string(), "weekly"),
+                ("sections.0.name".to_string(), "a"),
+                ("sections.0.lines.0".to_string(), "x"),
+                ("sections.0.lines.1".to_string(), "y"),
+                ("sections.1.name".to_string(), "b"),
+                ("meta.a".to_string(), "first"),
+                ("meta.z".to_string(), "last"),
+                ("lookup.key".to_string(), "kept"),
+            ]
+        );
+
+        // The same values as `find_all` finds, in the same order
+        let mut visited = Vec::new();
+        value.walk(|path, value| {
+            visited.push((path.clone(), value));
+            Walk::Continue
+        });
+        assert_eq!(
+            visited,
+            value.find_all(|_| true).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn skips_children_and_stops() {
+        let value = from_str(DOCUMENT).unwrap();
+        let mut paths = Vec::new();
+        value.walk(|path, _| {
+            let written = path.to_string();
+            let walk = match &written[..] {
+                "sections" => Walk::SkipChildren,
+                "meta.a" => Walk::Stop,
+                _ => Walk::Continue,
+            };
+            paths.push(written);
+            walk
+        });
+
+        assert_eq!(
+            paths,
+            ["", "title", "sections", "meta", "meta.a"]
+        );
+    }
+
+    #[test]
+    fn truncates_every_list() {
+        let long = |len: usize| {
+            Value::List(
+                (0..len as i64).map(Value::Integer).collect(),
+            )
+        };
+        let mut value = Value::List(vec![
+            long(25),
+            Value::Optional(Some(Box::new(long(11)))),
+            Value::Tuple(vec![long(3), long(10)]),
+        ]);
+        for idx in 3..15 {
+            value.push(Value::Integer(idx)).unwrap();
+        }
+
+        let mut lengths = Vec::new();
+        value.walk_mut(|path, value| {
+            if let Value::List(elements) = value {
+                elements.truncate(10);
+                lengths.push((path.to_string(), elements.len()));
+            }
+            Walk::Continue
+        });
+
+        assert_eq!(
+            lengths,
+            [
+                (String::new(), 10),
+                ("0".to_string(), 10),
+                ("1".to_string(), 10),
+                ("2.0".to_string(), 3),
+                ("2.1".to_string(), 10),
+            ]
+        );
+        let mut expected = Value::List(vec![
+            long(10),
+            Value::Optional(Some(Box::new(long(10)))),
+            Value::Tuple(vec![long(3), long(10)]),
+        ]);
+        for idx in 3..10 {
+            expected.push(Value::Integer(idx)).unwrap();
+        }
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn walks_what_was_put_in_place() {
+        let mut value =
+            from_str("[[1, 2], {\"a\": [3]}]").unwrap();
+        let mut visited = 0;
+        value.walk_mut(|_, value| {
+            visited += 1;
+            if let Value::Object(_) = value {
+                *value = Value::List(vec![Value::Integer(4)]);
+            } else if let Value::Integer(integer) = value {
+                *integer *= 10;
+            }
+            Walk::Continue
+        });
+
+        assert_eq!(visited, 6);
+        assert_eq!(value, from_str("[[10, 20], [40]]").unwrap());
+    }
+
+    #[test]
+    fn walks_deep_values_without_recursing() {
+        let mut value = Value::Integer(0);
+        for _ in 0..100_000 {
+            value = Value::List(vec![value]);
+        }
+
+        let mut deepest = 0;
+        value.walk(|path, _| {
+            deepest = deepest.max(path.segments().len());
+            Walk::Continue
+        });
+        assert_eq!(deepest, 100_000);
+        value.walk_mut(|_, value| {
+            if let Value::Integer(integer) = value {
+                *integer = 1;
+            }
+            Walk::Continue
+        });
+
+        let mut bottom = None;
+        value.walk(|_, value| {
+            if let Value::Integer(integer) = value {
+                bottom = Some(*integer);
+            }
+            Walk::Continue
+        });
+        assert_eq!(bottom, Some(1));
+    }
+}
@@ -0,0 +1,75 @@
+//! Measures the memory taken while iterating over a long list,
+//! to check that `parse_list_iter` only ever holds the element
+//! it is on, however many there are.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+/// Keeps track of how many bytes are allocated on each thread,
+/// and of the most there were at once, so that the test
+/// harness's own threads don't get in the way.
+struct Counting;
+
+thread_local! {
+    static LIVE: Cell<usize> = const { Cell::new(0) };
+    static PEAK: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let live = LIVE.with(|live| {
+            live.set(live.get() + layout.size());
+            live.get()
+        });
+        PEAK.with(|peak| peak.set(peak.get().max(live)));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.with(|live| {
+            live.set(live.get().saturating_sub(layout.size()))
+        });
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static COUNTING: Counting = Counting;
+
+/// Returns the most bytes `run` had allocated at once, over what
+/// was allocated before it.
+fn peak_bytes(run: impl FnOnce()) -> usize {
+    let before = LIVE.with(Cell::get);
+    PEAK.with(|peak| peak.set(before));
+    run();
+    PEAK.with(Cell::get) - before
+}
+
+#[test]
+fn holds_one_element_at_a_time() {
+    let elements: Vec<String> = (0..100_000)
+        .map(|idx| {
+            format!(
+                "Point {{ x: {idx}, label: Some(\"p\\t{idx}\") }}"
+            )
+        })
+        .collect();
+    let text = format!("[\n{}\n] // done", elements.join(",\n"));
+
+    // The first run sets up the parser's thread-locals
+    tson::parse_list_iter("[1]").unwrap().for_each(drop);
+
+    let mut sum = 0;
+    let iterating = peak_bytes(|| {
+        for element in tson::parse_list_iter(&text).unwrap() {
+            sum += element.unwrap()["x"].as_i64().unwrap();
+        }
+    });
+    assert_eq!(sum, (0..100_000).sum());
+    assert!(iterating < 8 * 1024, "{iterating}");
+
+    let building = peak_bytes(|| drop(tson::from_str(&text)));
+    assert!(building > 1_000 * iterating, "{building}");
+}
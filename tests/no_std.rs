@@ -0,0 +1,69 @@
+//! Uses nothing but `core` and `alloc`, to check that the parser
+//! and `Display` work without `std`. Run it with
+//! `cargo test --no-default-features --test no_std`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{borrow::Cow, boxed::Box, string::ToString, vec};
+
+use tson::{parse_value, ObjectMap, Value};
+
+#[test]
+fn parses_and_writes_nested_values() {
+    let text = r#"Config { name: "a\tb", servers: [Server(1, 'x'), Server(2, 'y')], limits: {"max": Some(15)}, mode: Ok(Red) }"#;
+    let expected = Value::Struct {
+        name: "Config".into(),
+        fields: vec![
+            (
+                "name".into(),
+                Value::String(Cow::Owned("a\tb".to_string())),
+            ),
+            (
+                "servers".into(),
+                Value::List(vec![
+                    Value::TupleStruct {
+                        name: "Server".into(),
+                        fields: vec![
+                            Value::Integer(1),
+                            Value::Char('x'),
+                        ],
+                    },
+                    Value::TupleStruct {
+                        name: "Server".into(),
+                        fields: vec![
+                            Value::Integer(2),
+                            Value::Char('y'),
+                        ],
+                    },
+                ]),
+            ),
+            (
+                "limits".into(),
+                Value::Object(ObjectMap::from([(
+                    "max".into(),
+                    Value::Optional(Some(Box::new(
+                        Value::Integer(15),
+                    ))),
+                )])),
+            ),
+            (
+                "mode".into(),
+                Value::Result(Ok(Box::new(Value::Ident(
+                    "Red".into(),
+                )))),
+            ),
+        ],
+        non_exhaustive: false,
+    };
+
+    let (rest, value) = parse_value(text).unwrap();
+    assert_eq!(rest, "");
+    assert_eq!(value, expected);
+    assert_eq!(value.to_string(), text);
+    assert_eq!(
+        parse_value(&value.to_string_pretty()),
+        Ok(("", value))
+    );
+}
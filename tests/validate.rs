@@ -0,0 +1,86 @@
+//! Counts the allocations made while validating, to check that
+//! `validate` builds nothing, however large the document is, and
+//! that `validate_quick` notes nothing about its errors.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+/// Counts the allocations made on each thread, so that the test
+/// harness's own threads don't get in the way.
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static COUNTING: Counting = Counting;
+
+/// Returns how many allocations `run` made.
+fn allocations(run: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    run();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+fn records(count: usize) -> String {
+    let records: Vec<String> = (0..count)
+        .map(|idx| {
+            format!(
+                "Record {{ id: {idx}, name: \"record\\t{idx}\", \
+                 tags: {{\"a\": [1, 2]}}, owner: Some(User({idx})), \
+                 raw: b\"\\x00\", span: 0..=9 }}"
+            )
+        })
+        .collect();
+    format!("[{}] /* done */", records.join(",\n"))
+}
+
+#[test]
+fn validates_without_building_values() {
+    // The first run sets up the parser's thread-locals
+    let small = records(10);
+    assert_eq!(tson::validate(&small), Ok(()));
+
+    let large = records(2_000);
+    let validating_small =
+        allocations(|| tson::validate(&small).unwrap());
+    let validating_large =
+        allocations(|| tson::validate(&large).unwrap());
+    assert_eq!(validating_small, validating_large);
+    assert!(validating_large < 10, "{validating_large}");
+
+    let parsing = allocations(|| drop(tson::from_str(&large)));
+    assert!(parsing > 2_000, "{parsing}");
+}
+
+#[test]
+fn fails_quickly_allocating_less() {
+    let lines =
+        ["hello world", "key=value", "[1, 2", "Foo { x: 1 y }"];
+    for line in lines {
+        // Sets up the parser's thread-locals, as above
+        assert!(tson::validate_quick(line).is_err());
+        let quick = allocations(|| {
+            let _ = tson::validate_quick(line);
+        });
+        let detailed =
+            allocations(|| drop(tson::validate(line)));
+        // Only the brackets it is inside of take memory
+        assert!(quick <= 2, "{line}: {quick}");
+        assert!(detailed > quick, "{line}: {detailed}");
+    }
+}
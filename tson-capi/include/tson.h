@@ -0,0 +1,117 @@
+/*
+ * The C API of tson, implemented by the tson-capi crate. See
+ * tson-capi/src/lib.rs for what each function does.
+ *
+ * Ownership: tson_parse returns a document, which owns every
+ * value in it and must be released with tson_value_free exactly
+ * once. Every other tson_value pointer, string and byte string
+ * points into a document, must not be freed, and is only valid
+ * until its document is freed. Strings are UTF-8 and not
+ * NUL-terminated.
+ *
+ * Errors: functions that fail return false, NULL or a nonzero
+ * status, and leave a message for tson_last_error_message, which
+ * is kept per thread.
+ *
+ * Maintained by hand, in the layout `cbindgen --lang c` gives.
+ */
+
+#ifndef TSON_H
+#define TSON_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum tson_kind {
+    TSON_KIND_INTEGER,
+    TSON_KIND_FLOAT,
+    TSON_KIND_BOOLEAN,
+    TSON_KIND_STRING,
+    TSON_KIND_CHAR,
+    TSON_KIND_BYTES,
+    TSON_KIND_LIST,
+    TSON_KIND_TUPLE,
+    TSON_KIND_OPTIONAL,
+    TSON_KIND_RESULT,
+    TSON_KIND_OBJECT,
+    TSON_KIND_STRUCT,
+    TSON_KIND_TUPLE_STRUCT,
+    TSON_KIND_MAP,
+    TSON_KIND_SET,
+    TSON_KIND_RANGE,
+    TSON_KIND_DURATION,
+    TSON_KIND_IDENT,
+    /* A datetime, whose text tson_value_format writes. */
+    TSON_KIND_DATETIME,
+    /* A UUID, whose text tson_value_format writes. */
+    TSON_KIND_UUID,
+    /* An IP address, whose text tson_value_format writes. */
+    TSON_KIND_IP_ADDR,
+    /* A socket address, whose text tson_value_format writes. */
+    TSON_KIND_SOCKET_ADDR,
+    /* Returned for a NULL value. */
+    TSON_KIND_INVALID,
+} tson_kind;
+
+/* A parsed value, or a value nested in one. */
+typedef struct tson_value tson_value;
+
+int32_t tson_parse(const char *input, size_t len, tson_value **out);
+
+void tson_value_free(tson_value *value);
+
+const char *tson_last_error_message(void);
+
+tson_kind tson_value_kind(const tson_value *value);
+
+bool tson_value_as_double(const tson_value *value, double *out);
+
+bool tson_value_as_int64(const tson_value *value, int64_t *out);
+
+bool tson_value_as_bool(const tson_value *value, bool *out);
+
+bool tson_value_as_char(const tson_value *value, uint32_t *out);
+
+bool tson_value_as_string(const tson_value *value,
+                          const char **ptr,
+                          size_t *len);
+
+bool tson_value_as_bytes(const tson_value *value,
+                         const uint8_t **ptr,
+                         size_t *len);
+
+size_t tson_value_format(const tson_value *value, char *buf, size_t cap);
+
+bool tson_value_name(const tson_value *value,
+                     const char **ptr,
+                     size_t *len);
+
+size_t tson_list_len(const tson_value *value);
+
+const tson_value *tson_list_get(const tson_value *value, size_t idx);
+
+const tson_value *tson_value_inner(const tson_value *value);
+
+const tson_value *tson_field_get(const tson_value *value,
+                                 const char *name,
+                                 size_t len);
+
+size_t tson_field_count(const tson_value *value);
+
+bool tson_field_name(const tson_value *value,
+                     size_t idx,
+                     const char **ptr,
+                     size_t *len);
+
+const tson_value *tson_field_value(const tson_value *value, size_t idx);
+
+#ifdef __cplusplus
+} /* extern "C" */
+#endif
+
+#endif /* TSON_H */
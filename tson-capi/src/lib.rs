@@ -0,0 +1,740 @@
+//! A C API for tson, declared in `include/tson.h`.
+//!
+//! # Ownership
+//!
+//! [`tson_parse`] copies its input and returns a *document*, a
+//! `tson_value*` that owns the whole parsed tree. It must be
+//! released with [`tson_value_free`], exactly once. Every other
+//! `tson_value*`, such as those returned by [`tson_list_get`],
+//! points into a document: it must not be freed and is only
+//! valid until the document it came from is.
+//!
+//! Strings and byte strings are returned as a pointer and a
+//! length, borrowed from their document in the same way. They
+//! are not NUL-terminated, and strings are UTF-8.
+//!
+//! No function unwinds into C: a panic is caught at the boundary
+//! and reported like any other error.
+//!
+//! # Errors
+//!
+//! Functions that can fail return `false`, `NULL` or a nonzero
+//! status, and leave a message for [`tson_last_error_message`].
+//! Each thread has its own last error, which the next failing
+//! call on that thread replaces.
+
+#![allow(non_camel_case_types)]
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use tson::{ParserOptions, Value};
+
+/// A parsed value, or a value nested in one.
+pub type tson_value = Value<'static>;
+
+/// What kind of value a `tson_value` is. Integers and floats of
+/// every size share a kind.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum tson_kind {
+    TSON_KIND_INTEGER,
+    TSON_KIND_FLOAT,
+    TSON_KIND_BOOLEAN,
+    TSON_KIND_STRING,
+    TSON_KIND_CHAR,
+    TSON_KIND_BYTES,
+    TSON_KIND_LIST,
+    TSON_KIND_TUPLE,
+    TSON_KIND_OPTIONAL,
+    TSON_KIND_RESULT,
+    TSON_KIND_OBJECT,
+    TSON_KIND_STRUCT,
+    TSON_KIND_TUPLE_STRUCT,
+    TSON_KIND_MAP,
+    TSON_KIND_SET,
+    TSON_KIND_RANGE,
+    TSON_KIND_DURATION,
+    TSON_KIND_IDENT,
+    /// A datetime, whose text [`tson_value_format`] writes.
+    TSON_KIND_DATETIME,
+    /// A UUID, whose text [`tson_value_format`] writes.
+    TSON_KIND_UUID,
+    /// An IP address, whose text [`tson_value_format`] writes.
+    TSON_KIND_IP_ADDR,
+    /// A socket address, whose text [`tson_value_format`]
+    /// writes.
+    TSON_KIND_SOCKET_ADDR,
+    /// Returned for a `NULL` value.
+    TSON_KIND_INVALID,
+}
+
+/// A document: the parsed value, and the copy of the input it
+/// borrows from.
+///
+/// `value` comes first so that a pointer to a document is a
+/// pointer to its value, and so that it is dropped before the
+/// input it borrows.
+#[repr(C)]
+struct Document {
+    value: Value<'static>,
+    _input: Box<str>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> =
+        const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    // Messages with a NUL in them are cut short at the first one
+    let message = CString::new(message).unwrap_or_else(|err| {
+        let end = err.nul_position();
+        let mut bytes = err.into_vec();
+        bytes.truncate(end);
+        CString::new(bytes).unwrap()
+    });
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Runs `f`, returning `default` and setting the last error if
+/// it panics.
+fn guard<R>(default: R, f: impl FnOnce() -> R) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| {
+                    payload.downcast_ref::<String>().cloned()
+                })
+                .unwrap_or_else(|| "unknown panic".to_owned());
+            set_last_error(format!("tson panicked: {message}"));
+
+            default
+        }
+    }
+}
+
+/// Returns the value `value` points to, if it isn't `NULL`.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document.
+unsafe fn value_ref<'v>(
+    value: *const tson_value,
+) -> Option<&'v Value<'static>> {
+    unsafe { value.as_ref() }
+}
+
+/// Writes `bytes` to `ptr` and `len`, returning whether there
+/// were any.
+///
+/// # Safety
+///
+/// `ptr` and `len` must be valid for writes.
+unsafe fn write_bytes(
+    bytes: Option<&[u8]>,
+    ptr: *mut *const c_char,
+    len: *mut usize,
+) -> bool {
+    match bytes {
+        Some(bytes) if !ptr.is_null() && !len.is_null() => {
+            unsafe {
+                *ptr = bytes.as_ptr().cast();
+                *len = bytes.len();
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Parses the `len` bytes at `input`, which must hold a single
+/// UTF-8 tson document, and stores the new document in `*out`.
+///
+/// Returns 0 on success. Otherwise returns a nonzero status,
+/// leaves `*out` untouched and sets the last error, which gives
+/// the line and column in `input` of the first character that
+/// couldn't be parsed, as in `at line 1, column 6`.
+///
+/// Strings holding datetimes are parsed as datetimes when the
+/// library is built with the `datetime` feature, and so are
+/// UUIDs with the `uuid` feature and IP and socket addresses
+/// with the `net` feature.
+///
+/// # Safety
+///
+/// `input` must be valid for reads of `len` bytes, and `out`
+/// valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn tson_parse(
+    input: *const c_char,
+    len: usize,
+    out: *mut *mut tson_value,
+) -> i32 {
+    guard(1, || {
+        if (input.is_null() && len > 0) || out.is_null() {
+            set_last_error("tson_parse: NULL argument".into());
+            return 1;
+        }
+        let bytes = match len {
+            0 => &[][..],
+            len => unsafe {
+                slice::from_raw_parts(input.cast(), len)
+            },
+        };
+
+        let input: Box<str> = match std::str::from_utf8(bytes) {
+            Ok(input) => input.into(),
+            Err(err) => {
+                set_last_error(format!(
+                    "invalid UTF-8 at byte {}",
+                    err.valid_up_to()
+                ));
+                return 1;
+            }
+        };
+        // SAFETY: the text is on the heap, so it stays where it
+        // is when the box moves into the document, which drops
+        // the value borrowing it first
+        let text: &'static str =
+            unsafe { &*ptr::from_ref(&*input) };
+
+        let value = match tson::from_slice_with(
+            text.as_bytes(),
+            options(),
+        ) {
+            Ok(value) => value,
+            Err(err) => {
+                set_last_error(format!("invalid tson: {err}"));
+                return 1;
+            }
+        };
+
+        let document = Box::new(Document {
+            value,
+            _input: input,
+        });
+        unsafe { *out = Box::into_raw(document).cast() };
+
+        0
+    })
+}
+
+/// Returns the options [`tson_parse`] parses with, which
+/// recognize every kind of value the library was built to.
+fn options() -> ParserOptions {
+    let options = ParserOptions::default();
+    #[cfg(feature = "datetime")]
+    let options = options.datetimes(true);
+    #[cfg(feature = "uuid")]
+    let options = options.uuids(true);
+    #[cfg(feature = "net")]
+    let options = options.addresses(true);
+    options
+}
+
+/// Frees a document returned by [`tson_parse`], and every value
+/// in it. Does nothing when `value` is `NULL`.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or a document from [`tson_parse`]
+/// that hasn't been freed yet, not a value nested in one.
+#[no_mangle]
+pub unsafe extern "C" fn tson_value_free(
+    value: *mut tson_value,
+) {
+    guard((), || {
+        if !value.is_null() {
+            drop(unsafe {
+                Box::from_raw(value.cast::<Document>())
+            });
+        }
+    })
+}
+
+/// Returns the message of the last error on this thread, as a
+/// NUL-terminated string that stays valid until the next error
+/// on this thread, or `NULL` if there hasn't been one.
+#[no_mangle]
+pub extern "C" fn tson_last_error_message() -> *const c_char {
+    guard(ptr::null(), || {
+        LAST_ERROR.with(|last| {
+            last.borrow()
+                .as_ref()
+                .map_or(ptr::null(), |message| message.as_ptr())
+        })
+    })
+}
+
+/// Returns what kind of value `value` is.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document.
+#[no_mangle]
+pub unsafe extern "C" fn tson_value_kind(
+    value: *const tson_value,
+) -> tson_kind {
+    use tson_kind::*;
+
+    guard(TSON_KIND_INVALID, || {
+        let Some(value) = (unsafe { value_ref(value) }) else {
+            return TSON_KIND_INVALID;
+        };
+
+        match value {
+            Value::Integer(_)
+            | Value::UInt(_)
+            | Value::BigInt(_)
+            | Value::BigUInt(_) => TSON_KIND_INTEGER,
+            Value::Boolean(_) => TSON_KIND_BOOLEAN,
+            Value::String(_) => TSON_KIND_STRING,
+            Value::Char(_) => TSON_KIND_CHAR,
+            Value::Bytes(_) => TSON_KIND_BYTES,
+            Value::List(_) => TSON_KIND_LIST,
+            Value::Tuple(_) => TSON_KIND_TUPLE,
+            Value::Optional(_) => TSON_KIND_OPTIONAL,
+            Value::Result(_) => TSON_KIND_RESULT,
+            Value::Object(_) => TSON_KIND_OBJECT,
+            Value::Struct { .. } => TSON_KIND_STRUCT,
+            Value::TupleStruct { .. } => TSON_KIND_TUPLE_STRUCT,
+            Value::Map(_) => TSON_KIND_MAP,
+            Value::Set(_) => TSON_KIND_SET,
+            Value::Range { .. } => TSON_KIND_RANGE,
+            Value::Float(_) => TSON_KIND_FLOAT,
+            #[cfg(feature = "arbitrary-precision")]
+            Value::RawNumber(_) => TSON_KIND_FLOAT,
+            #[cfg(feature = "duration")]
+            Value::Duration(_) => TSON_KIND_DURATION,
+            #[cfg(feature = "datetime")]
+            Value::DateTime(_) => TSON_KIND_DATETIME,
+            #[cfg(feature = "uuid")]
+            Value::Uuid(_) => TSON_KIND_UUID,
+            #[cfg(feature = "net")]
+            Value::IpAddr(_) => TSON_KIND_IP_ADDR,
+            #[cfg(feature = "net")]
+            Value::SocketAddr(_) => TSON_KIND_SOCKET_ADDR,
+            Value::Ident(_) => TSON_KIND_IDENT,
+        }
+    })
+}
+
+/// Stores any number in `*out` as a double, rounding it if
+/// needed. Returns `false` for other values.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document, and
+/// `out` must be valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn tson_value_as_double(
+    value: *const tson_value,
+    out: *mut f64,
+) -> bool {
+    guard(false, || {
+        match unsafe { value_ref(value) }.and_then(Value::as_f64)
+        {
+            Some(float) if !out.is_null() => {
+                unsafe { *out = float };
+                true
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Stores an integer that fits in an `int64_t` in `*out`.
+/// Returns `false` for other values, including floats.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document, and
+/// `out` must be valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn tson_value_as_int64(
+    value: *const tson_value,
+    out: *mut i64,
+) -> bool {
+    guard(false, || {
+        match unsafe { value_ref(value) }.and_then(Value::as_i64)
+        {
+            Some(integer) if !out.is_null() => {
+                unsafe { *out = integer };
+                true
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Stores a boolean in `*out`. Returns `false` for other values.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document, and
+/// `out` must be valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn tson_value_as_bool(
+    value: *const tson_value,
+    out: *mut bool,
+) -> bool {
+    guard(false, || match unsafe { value_ref(value) } {
+        Some(Value::Boolean(boolean)) if !out.is_null() => {
+            unsafe { *out = *boolean };
+            true
+        }
+        _ => false,
+    })
+}
+
+/// Stores a char's code point in `*out`. Returns `false` for
+/// other values.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document, and
+/// `out` must be valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn tson_value_as_char(
+    value: *const tson_value,
+    out: *mut u32,
+) -> bool {
+    guard(false, || match unsafe { value_ref(value) } {
+        Some(Value::Char(chr)) if !out.is_null() => {
+            unsafe { *out = u32::from(*chr) };
+            true
+        }
+        _ => false,
+    })
+}
+
+/// Stores the UTF-8 text of a string or an identifier in `*ptr`
+/// and `*len`. Returns `false` for other values.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document, and
+/// `ptr` and `len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tson_value_as_string(
+    value: *const tson_value,
+    ptr: *mut *const c_char,
+    len: *mut usize,
+) -> bool {
+    guard(false, || {
+        let text = match unsafe { value_ref(value) } {
+            Some(Value::String(string)) => {
+                Some(string.as_bytes())
+            }
+            Some(Value::Ident(ident)) => Some(ident.as_bytes()),
+            _ => None,
+        };
+
+        unsafe { write_bytes(text, ptr, len) }
+    })
+}
+
+/// Stores the bytes of a byte string in `*ptr` and `*len`.
+/// Returns `false` for other values.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document, and
+/// `ptr` and `len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tson_value_as_bytes(
+    value: *const tson_value,
+    ptr: *mut *const u8,
+    len: *mut usize,
+) -> bool {
+    guard(false, || {
+        let bytes = match unsafe { value_ref(value) } {
+            Some(Value::Bytes(bytes)) => Some(&**bytes),
+            _ => None,
+        };
+
+        unsafe { write_bytes(bytes, ptr.cast(), len) }
+    })
+}
+
+/// Writes the text of a datetime, UUID, IP address or socket
+/// address, as Rust's `Display`
+/// writes it, to `buf` as a NUL-terminated string, cut short to
+/// fit in `cap` bytes the way `snprintf` cuts its output short.
+///
+/// Returns the length of the whole text, not counting the NUL,
+/// so that `cap` must be more than that for none of it to be cut
+/// off, or 0 for other values, for which nothing is written.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document, and
+/// `buf` must be `NULL` or valid for writes of `cap` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tson_value_format(
+    value: *const tson_value,
+    buf: *mut c_char,
+    cap: usize,
+) -> usize {
+    guard(0, || {
+        let Some(text) =
+            unsafe { value_ref(value) }.and_then(text)
+        else {
+            return 0;
+        };
+
+        if !buf.is_null() && cap > 0 {
+            // The text is ASCII, so no character is cut in half
+            let len = text.len().min(cap - 1);
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    text.as_ptr(),
+                    buf.cast(),
+                    len,
+                );
+                *buf.add(len) = 0;
+            }
+        }
+        text.len()
+    })
+}
+
+/// Returns the text [`tson_value_format`] writes for `value`.
+fn text(value: &Value<'static>) -> Option<String> {
+    match value {
+        #[cfg(feature = "datetime")]
+        Value::DateTime(datetime) => Some(datetime.to_string()),
+        #[cfg(feature = "uuid")]
+        Value::Uuid(uuid) => Some(uuid.to_string()),
+        #[cfg(feature = "net")]
+        Value::IpAddr(address) => Some(address.to_string()),
+        #[cfg(feature = "net")]
+        Value::SocketAddr(address) => Some(address.to_string()),
+        _ => None,
+    }
+}
+
+/// Stores the name of a struct or tuple struct in `*ptr` and
+/// `*len`, including any path before it. Returns `false` for
+/// other values.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document, and
+/// `ptr` and `len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tson_value_name(
+    value: *const tson_value,
+    ptr: *mut *const c_char,
+    len: *mut usize,
+) -> bool {
+    guard(false, || {
+        let name = match unsafe { value_ref(value) } {
+            Some(
+                Value::Struct { name, .. }
+                | Value::TupleStruct { name, .. },
+            ) => Some(name.as_bytes()),
+            _ => None,
+        };
+
+        unsafe { write_bytes(name, ptr, len) }
+    })
+}
+
+/// Returns the elements of a list, tuple, set or tuple struct.
+fn elements<'v>(
+    value: &'v Value<'static>,
+) -> &'v [Value<'static>] {
+    match value {
+        Value::List(elements)
+        | Value::Tuple(elements)
+        | Value::Set(elements)
+        | Value::TupleStruct {
+            fields: elements, ..
+        } => elements,
+        _ => &[],
+    }
+}
+
+/// Returns the number of elements of a list, tuple, set or tuple
+/// struct, and 0 for other values.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document.
+#[no_mangle]
+pub unsafe extern "C" fn tson_list_len(
+    value: *const tson_value,
+) -> usize {
+    guard(0, || {
+        unsafe { value_ref(value) }
+            .map_or(0, |value| elements(value).len())
+    })
+}
+
+/// Returns the element at `idx` of a list, tuple, set or tuple
+/// struct, or `NULL` if there is none.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document.
+#[no_mangle]
+pub unsafe extern "C" fn tson_list_get(
+    value: *const tson_value,
+    idx: usize,
+) -> *const tson_value {
+    guard(ptr::null(), || {
+        unsafe { value_ref(value) }
+            .and_then(|value| elements(value).get(idx))
+            .map_or(ptr::null(), ptr::from_ref)
+    })
+}
+
+/// Returns the value inside `Some(..)`, `Ok(..)` or `Err(..)`,
+/// or `NULL` for `None` and other values.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document.
+#[no_mangle]
+pub unsafe extern "C" fn tson_value_inner(
+    value: *const tson_value,
+) -> *const tson_value {
+    guard(ptr::null(), || {
+        match unsafe { value_ref(value) } {
+            Some(
+                Value::Optional(Some(inner))
+                | Value::Result(Ok(inner) | Err(inner)),
+            ) => ptr::from_ref(&**inner),
+            _ => ptr::null(),
+        }
+    })
+}
+
+/// Returns the field of a struct, or the entry of an object,
+/// named by the `len` UTF-8 bytes at `name`, or `NULL` if there
+/// is none.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document, and
+/// `name` must be valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tson_field_get(
+    value: *const tson_value,
+    name: *const c_char,
+    len: usize,
+) -> *const tson_value {
+    guard(ptr::null(), || {
+        if name.is_null() {
+            return ptr::null();
+        }
+        let name = unsafe {
+            slice::from_raw_parts(name.cast::<u8>(), len)
+        };
+        let Ok(name) = std::str::from_utf8(name) else {
+            return ptr::null();
+        };
+
+        let field = match unsafe { value_ref(value) } {
+            Some(Value::Object(object)) => object.get(name),
+            Some(Value::Struct { fields, .. }) => fields
+                .iter()
+                .find(|(field, _)| *field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        };
+
+        field.map_or(ptr::null(), ptr::from_ref)
+    })
+}
+
+/// Returns the number of fields of a struct or entries of an
+/// object, and 0 for other values.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document.
+#[no_mangle]
+pub unsafe extern "C" fn tson_field_count(
+    value: *const tson_value,
+) -> usize {
+    guard(0, || match unsafe { value_ref(value) } {
+        Some(Value::Object(object)) => object.len(),
+        Some(Value::Struct { fields, .. }) => fields.len(),
+        _ => 0,
+    })
+}
+
+/// Returns the name and value of the field at `idx` of a struct
+/// or an object, whose entries are in no particular order, but
+/// the same order every time.
+fn field_at<'v>(
+    value: &'v Value<'static>,
+    idx: usize,
+) -> Option<(&'v str, &'v Value<'static>)> {
+    match value {
+        Value::Object(object) => object
+            .iter()
+            .nth(idx)
+            .map(|(name, value)| (&**name, value)),
+        Value::Struct { fields, .. } => {
+            fields.get(idx).map(|(name, value)| (&**name, value))
+        }
+        _ => None,
+    }
+}
+
+/// Stores the name of the field at `idx` of a struct or an
+/// object in `*ptr` and `*len`, returning `false` if there is
+/// none. Struct fields are in the order they were written in,
+/// object entries in no particular order.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document, and
+/// `ptr` and `len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tson_field_name(
+    value: *const tson_value,
+    idx: usize,
+    ptr: *mut *const c_char,
+    len: *mut usize,
+) -> bool {
+    guard(false, || {
+        let name = unsafe { value_ref(value) }
+            .and_then(|value| field_at(value, idx))
+            .map(|(name, _)| name.as_bytes());
+
+        unsafe { write_bytes(name, ptr, len) }
+    })
+}
+
+/// Returns the value of the field at `idx` of a struct or an
+/// object, in the order [`tson_field_name`] uses, or `NULL` if
+/// there is none.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or point into a live document.
+#[no_mangle]
+pub unsafe extern "C" fn tson_field_value(
+    value: *const tson_value,
+    idx: usize,
+) -> *const tson_value {
+    guard(ptr::null(), || {
+        unsafe { value_ref(value) }
+            .and_then(|value| field_at(value, idx))
+            .map_or(ptr::null(), |(_, value)| {
+                ptr::from_ref(value)
+            })
+    })
+}
@@ -0,0 +1,49 @@
+use std::{env, path::Path, process::Command};
+
+/// Compiles `tests/c/test.c` against `include/tson.h` and the
+/// static library cargo builds next to the test binary, then
+/// runs it, defining `TSON_<FEATURE>` for each feature the
+/// library was built with.
+#[test]
+fn runs_the_c_test() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let exe = env::current_exe().unwrap();
+    let library = exe.with_file_name("libtson_capi.a");
+    assert!(
+        library.exists(),
+        "{} is missing",
+        library.display()
+    );
+
+    let binary = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("tson_c_test");
+    let features = [
+        ("TSON_DATETIME", cfg!(feature = "datetime")),
+        ("TSON_UUID", cfg!(feature = "uuid")),
+        ("TSON_NET", cfg!(feature = "net")),
+    ];
+    let defines = features
+        .into_iter()
+        .filter(|&(_, enabled)| enabled)
+        .map(|(feature, _)| format!("-D{feature}"));
+    let status =
+        Command::new(env::var("CC").unwrap_or("cc".into()))
+            .args(defines)
+            .arg(manifest_dir.join("tests/c/test.c"))
+            .arg("-I")
+            .arg(manifest_dir.join("include"))
+            .arg(&library)
+            .args(["-lpthread", "-ldl", "-lm", "-o"])
+            .arg(&binary)
+            .status()
+            .expect("failed to run the C compiler");
+    assert!(status.success(), "compiling the C test failed");
+
+    let output = Command::new(&binary).output().unwrap();
+    assert!(
+        output.status.success(),
+        "the C test failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(output.stdout, b"ok\n");
+}
@@ -0,0 +1,127 @@
+/* Exercises the C API. Built and run by tests/c.rs. */
+
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "tson.h"
+
+static const char DOCUMENT[] =
+    "Config {\n"
+    "    name: \"log\\tviewer\",\n"
+    "    rate: 2.5,\n"
+    "    servers: [Server(1, 'x'), Server(-2, 'y')],\n"
+    "    extra: {\"key\": Some(b\"\\x00\\x01\")},\n"
+    "    mode: None,\n"
+    "}";
+
+static int string_is(const char *ptr, size_t len, const char *expected) {
+    return len == strlen(expected) && memcmp(ptr, expected, len) == 0;
+}
+
+int main(void) {
+    tson_value *doc = NULL;
+    assert(tson_parse(DOCUMENT, sizeof DOCUMENT - 1, &doc) == 0);
+    assert(tson_value_kind(doc) == TSON_KIND_STRUCT);
+
+    const char *ptr;
+    size_t len;
+    assert(tson_value_name(doc, &ptr, &len));
+    assert(string_is(ptr, len, "Config"));
+    assert(tson_field_count(doc) == 5);
+    assert(tson_field_name(doc, 1, &ptr, &len));
+    assert(string_is(ptr, len, "rate"));
+
+    const tson_value *name = tson_field_get(doc, "name", 4);
+    assert(tson_value_as_string(name, &ptr, &len));
+    assert(string_is(ptr, len, "log\tviewer"));
+
+    double rate;
+    assert(tson_value_as_double(tson_field_value(doc, 1), &rate));
+    assert(rate == 2.5);
+
+    const tson_value *servers = tson_field_get(doc, "servers", 7);
+    assert(tson_value_kind(servers) == TSON_KIND_LIST);
+    assert(tson_list_len(servers) == 2);
+    assert(tson_list_get(servers, 2) == NULL);
+    const tson_value *server = tson_list_get(servers, 1);
+    assert(tson_value_kind(server) == TSON_KIND_TUPLE_STRUCT);
+    int64_t id;
+    assert(tson_value_as_int64(tson_list_get(server, 0), &id));
+    assert(id == -2);
+    uint32_t chr;
+    assert(tson_value_as_char(tson_list_get(server, 1), &chr));
+    assert(chr == 'y');
+
+    const tson_value *extra = tson_field_get(doc, "extra", 5);
+    assert(tson_value_kind(extra) == TSON_KIND_OBJECT);
+    const tson_value *key = tson_value_inner(tson_field_get(extra, "key", 3));
+    const uint8_t *bytes;
+    assert(tson_value_as_bytes(key, &bytes, &len));
+    assert(len == 2 && bytes[0] == 0 && bytes[1] == 1);
+
+    const tson_value *mode = tson_field_get(doc, "mode", 4);
+    assert(tson_value_kind(mode) == TSON_KIND_OPTIONAL);
+    assert(tson_value_inner(mode) == NULL);
+    assert(tson_field_get(doc, "missing", 7) == NULL);
+    assert(!tson_value_as_double(name, &rate));
+    assert(tson_value_kind(NULL) == TSON_KIND_INVALID);
+
+    char text[64];
+    assert(tson_value_format(name, text, sizeof text) == 0);
+
+    tson_value_free(doc);
+    tson_value_free(NULL);
+
+#ifdef TSON_DATETIME
+    static const char DATETIME[] = "\"2024-02-29T12:30:00.5+01:00\"";
+    tson_value *datetime = NULL;
+    assert(tson_parse(DATETIME, sizeof DATETIME - 1, &datetime) == 0);
+    assert(tson_value_kind(datetime) == TSON_KIND_DATETIME);
+    assert(!tson_value_as_string(datetime, &ptr, &len));
+    assert(tson_value_format(datetime, text, sizeof text) == 27);
+    assert(strcmp(text, "2024-02-29T12:30:00.5+01:00") == 0);
+    /* Cut short like snprintf */
+    assert(tson_value_format(datetime, text, 5) == 27);
+    assert(strcmp(text, "2024") == 0);
+    tson_value_free(datetime);
+#endif
+
+#ifdef TSON_UUID
+    static const char UUID[] = "67E55044-10b1-426f-9247-bb680e5fe0c8";
+    tson_value *uuid = NULL;
+    assert(tson_parse(UUID, sizeof UUID - 1, &uuid) == 0);
+    assert(tson_value_kind(uuid) == TSON_KIND_UUID);
+    assert(!tson_value_as_string(uuid, &ptr, &len));
+    assert(tson_value_format(uuid, text, sizeof text) == 36);
+    assert(strcmp(text, "67e55044-10b1-426f-9247-bb680e5fe0c8") == 0);
+    tson_value_free(uuid);
+#endif
+
+#ifdef TSON_NET
+    static const char ADDRESSES[] = "[10.0.0.1, [::1]:8080]";
+    tson_value *addresses = NULL;
+    assert(tson_parse(ADDRESSES, sizeof ADDRESSES - 1, &addresses) == 0);
+    const tson_value *ip = tson_list_get(addresses, 0);
+    assert(tson_value_kind(ip) == TSON_KIND_IP_ADDR);
+    assert(!tson_value_as_string(ip, &ptr, &len));
+    assert(tson_value_format(ip, text, sizeof text) == 8);
+    assert(strcmp(text, "10.0.0.1") == 0);
+    const tson_value *socket = tson_list_get(addresses, 1);
+    assert(tson_value_kind(socket) == TSON_KIND_SOCKET_ADDR);
+    assert(!tson_value_as_string(socket, &ptr, &len));
+    assert(tson_value_format(socket, text, sizeof text) == 10);
+    assert(strcmp(text, "[::1]:8080") == 0);
+    tson_value_free(addresses);
+#endif
+
+    tson_value *invalid = NULL;
+    assert(tson_parse("[1, 2", 5, &invalid) != 0);
+    assert(invalid == NULL);
+    const char *message = tson_last_error_message();
+    assert(message != NULL);
+    assert(strstr(message, "at line 1, column 6") != NULL);
+
+    puts("ok");
+    return 0;
+}
@@ -0,0 +1,436 @@
+//! Derives `tson::FromTson` and `tson::ToTson` for structs and
+//! enums, based on the names of their fields and variants, so
+//! that a type converts to and from the value `tson` reads from
+//! its `Debug` output.
+//!
+//! A struct with named fields is read from a struct of any name
+//! or from an object, a missing `Option` field being `None`. A
+//! tuple struct is read from a tuple struct, a tuple or a list,
+//! and a unit struct from its name or `()`. An enum is read
+//! from the variant of its name, with or without a path before
+//! it, as in `Red`, `Color::Red` or `Shape::Circle { r: 1.0 }`.
+//!
+//! Generic types aren't supported.
+
+use proc_macro::{Delimiter, Spacing, TokenStream, TokenTree};
+
+#[proc_macro_derive(FromTson)]
+pub fn derive_from_tson(input: TokenStream) -> TokenStream {
+    expand(input, from_tson)
+}
+
+#[proc_macro_derive(ToTson)]
+pub fn derive_to_tson(input: TokenStream) -> TokenStream {
+    expand(input, to_tson)
+}
+
+fn expand(
+    input: TokenStream,
+    generate: fn(&Item) -> String,
+) -> TokenStream {
+    let code = match parse_item(input) {
+        Ok(item) => generate(&item),
+        Err(message) => {
+            format!("::core::compile_error!({message:?});")
+        }
+    };
+
+    code.parse().expect("generated invalid code")
+}
+
+/// The type a derive is for.
+struct Item {
+    name: String,
+    kind: Kind,
+}
+
+enum Kind {
+    Struct(Fields),
+    Enum(Vec<Variant>),
+}
+
+struct Variant {
+    name: String,
+    fields: Fields,
+}
+
+enum Fields {
+    /// The identifiers of the fields, as written, so possibly
+    /// raw identifiers like `r#type`.
+    Named(Vec<String>),
+    Unnamed(usize),
+    Unit,
+}
+
+fn parse_item(input: TokenStream) -> Result<Item, String> {
+    let tokens: Vec<_> = input.into_iter().collect();
+    let mut idx = skip_attributes_and_visibility(&tokens, 0);
+
+    let keyword = match tokens.get(idx) {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err("expected a struct or an enum".into()),
+    };
+    let name = match tokens.get(idx + 1) {
+        Some(TokenTree::Ident(ident)) => ident.to_string(),
+        _ => return Err("expected the name of the type".into()),
+    };
+    idx += 2;
+
+    if matches!(tokens.get(idx), Some(TokenTree::Punct(punct)) if punct.as_char() == '<')
+    {
+        return Err(format!(
+            "can't derive tson's traits for the generic type `{name}`"
+        ));
+    }
+
+    let kind = match (keyword.as_str(), tokens.get(idx)) {
+        ("struct", token) => Kind::Struct(parse_fields(token)),
+        ("enum", Some(TokenTree::Group(group)))
+            if group.delimiter() == Delimiter::Brace =>
+        {
+            Kind::Enum(
+                split_by_commas(group.stream())
+                    .iter()
+                    .filter_map(|tokens| parse_variant(tokens))
+                    .collect(),
+            )
+        }
+        _ => {
+            return Err(format!(
+                "can't derive tson's traits for `{name}`, which \
+                 isn't a struct or an enum"
+            ))
+        }
+    };
+
+    Ok(Item { name, kind })
+}
+
+/// Returns the index of the first token after any attributes
+/// and visibility at `idx`.
+fn skip_attributes_and_visibility(
+    tokens: &[TokenTree],
+    mut idx: usize,
+) -> usize {
+    loop {
+        match (tokens.get(idx), tokens.get(idx + 1)) {
+            (
+                Some(TokenTree::Punct(punct)),
+                Some(TokenTree::Group(group)),
+            ) if punct.as_char() == '#'
+                && group.delimiter() == Delimiter::Bracket =>
+            {
+                idx += 2
+            }
+            (Some(TokenTree::Ident(ident)), next)
+                if ident.to_string() == "pub" =>
+            {
+                idx += 1;
+                if matches!(next, Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis)
+                {
+                    idx += 1;
+                }
+            }
+            _ => return idx,
+        }
+    }
+}
+
+/// Reads the fields that start with `token`, the token after
+/// the name of a struct or of a variant.
+fn parse_fields(token: Option<&TokenTree>) -> Fields {
+    match token {
+        Some(TokenTree::Group(group))
+            if group.delimiter() == Delimiter::Brace =>
+        {
+            let names = split_by_commas(group.stream())
+                .iter()
+                .filter_map(|tokens| {
+                    let idx = skip_attributes_and_visibility(
+                        tokens, 0,
+                    );
+                    match tokens.get(idx) {
+                        Some(TokenTree::Ident(ident)) => {
+                            Some(ident.to_string())
+                        }
+                        _ => None,
+                    }
+                })
+                .collect();
+
+            Fields::Named(names)
+        }
+        Some(TokenTree::Group(group))
+            if group.delimiter() == Delimiter::Parenthesis =>
+        {
+            Fields::Unnamed(
+                split_by_commas(group.stream()).len(),
+            )
+        }
+        _ => Fields::Unit,
+    }
+}
+
+fn parse_variant(tokens: &[TokenTree]) -> Option<Variant> {
+    let idx = skip_attributes_and_visibility(tokens, 0);
+    match tokens.get(idx) {
+        Some(TokenTree::Ident(ident)) => Some(Variant {
+            name: ident.to_string(),
+            fields: parse_fields(tokens.get(idx + 1)),
+        }),
+        _ => None,
+    }
+}
+
+/// Splits `stream` at the commas outside of generic arguments,
+/// leaving out empty parts such as after a trailing comma.
+fn split_by_commas(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![vec![]];
+    let mut angle_brackets = 0usize;
+    // Whether the last token was the `-` of `->`
+    let mut after_dash = false;
+
+    for token in stream {
+        let mut dash = false;
+        if let TokenTree::Punct(punct) = &token {
+            match punct.as_char() {
+                ',' if angle_brackets == 0 => {
+                    parts.push(vec![]);
+                    continue;
+                }
+                '<' => angle_brackets += 1,
+                '>' if !after_dash => {
+                    angle_brackets =
+                        angle_brackets.saturating_sub(1)
+                }
+                '-' => dash = punct.spacing() == Spacing::Joint,
+                _ => {}
+            }
+        }
+        after_dash = dash;
+        parts.last_mut().unwrap().push(token);
+    }
+
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// The name a field is written with, without the `r#` of a raw
+/// identifier.
+fn field_name(ident: &str) -> &str {
+    ident.strip_prefix("r#").unwrap_or(ident)
+}
+
+fn from_tson(item: &Item) -> String {
+    let name = &item.name;
+    let body = match &item.kind {
+        Kind::Struct(fields) => read_fields(name, name, fields),
+        Kind::Enum(variants) => {
+            let arms: String = variants
+                .iter()
+                .map(|variant| {
+                    let path =
+                        format!("{name}::{}", variant.name);
+                    format!(
+                        "{:?} => {{ {} }}\n",
+                        field_name(&variant.name),
+                        read_fields(
+                            &path,
+                            &path,
+                            &variant.fields
+                        )
+                    )
+                })
+                .collect();
+
+            format!(
+                "match ::tson::__private::variant_name(value, {name:?})? {{
+                    {arms}
+                    other => ::core::result::Result::Err(
+                        ::tson::__private::unknown_variant({name:?}, other),
+                    ),
+                }}"
+            )
+        }
+    };
+
+    format!(
+        "#[automatically_derived]
+        impl ::tson::FromTson for {name} {{
+            fn from_tson(
+                value: &::tson::Value<'_>,
+            ) -> ::core::result::Result<Self, ::tson::ConvertError> {{
+                {body}
+            }}
+        }}"
+    )
+}
+
+/// Returns the code that builds `constructor`, a struct or a
+/// variant, from `value`.
+fn read_fields(
+    constructor: &str,
+    expected: &str,
+    fields: &Fields,
+) -> String {
+    match fields {
+        Fields::Named(names) => {
+            let fields: String = names
+                .iter()
+                .map(|ident| {
+                    format!(
+                        "{ident}: fields.get({:?})?,",
+                        field_name(ident)
+                    )
+                })
+                .collect();
+
+            format!(
+                "let fields = ::tson::__private::struct_fields(value, {expected:?})?;
+                ::core::result::Result::Ok({constructor} {{ {fields} }})"
+            )
+        }
+        Fields::Unnamed(len) => {
+            let fields: String = (0..*len)
+                .map(|idx| {
+                    format!("::tson::__private::element(fields, {idx})?,")
+                })
+                .collect();
+
+            format!(
+                "let fields = ::tson::__private::tuple_fields(value, {expected:?}, {len})?;
+                ::core::result::Result::Ok({constructor}({fields}))"
+            )
+        }
+        Fields::Unit => format!(
+            "::tson::__private::unit(value, {expected:?})?;
+            ::core::result::Result::Ok({constructor})"
+        ),
+    }
+}
+
+fn to_tson(item: &Item) -> String {
+    let name = &item.name;
+    let body = match &item.kind {
+        Kind::Struct(Fields::Named(names)) => write_fields(
+            name,
+            names.iter().map(|ident| {
+                (ident.as_str(), format!("&self.{ident}"))
+            }),
+        ),
+        Kind::Struct(Fields::Unnamed(len)) => write_elements(
+            name,
+            (0..*len).map(|idx| format!("&self.{idx}")),
+        ),
+        Kind::Struct(Fields::Unit) => write_unit(name),
+        // `match *self {}` is the one match that takes no arms
+        Kind::Enum(variants) if variants.is_empty() => {
+            "match *self {}".to_owned()
+        }
+        Kind::Enum(variants) => {
+            let arms: String = variants
+                .iter()
+                .map(|variant| {
+                    let path =
+                        format!("{name}::{}", variant.name);
+                    let variant_name = &variant.name;
+                    match &variant.fields {
+                        Fields::Named(names) => format!(
+                            "{path} {{ {} }} => {},",
+                            names.join(", "),
+                            write_fields(
+                                variant_name,
+                                names.iter().map(|ident| (
+                                    ident.as_str(),
+                                    ident.clone()
+                                ))
+                            )
+                        ),
+                        Fields::Unnamed(len) => {
+                            let bindings: Vec<_> = (0..*len)
+                                .map(|idx| format!("field{idx}"))
+                                .collect();
+                            format!(
+                                "{path}({}) => {},",
+                                bindings.join(", "),
+                                write_elements(
+                                    variant_name,
+                                    bindings.into_iter()
+                                )
+                            )
+                        }
+                        Fields::Unit => {
+                            format!(
+                                "{path} => {},",
+                                write_unit(variant_name)
+                            )
+                        }
+                    }
+                })
+                .collect();
+
+            format!("match self {{ {arms} }}")
+        }
+    };
+
+    format!(
+        "#[automatically_derived]
+        impl ::tson::ToTson for {name} {{
+            fn to_tson(&self) -> ::tson::Value<'static> {{
+                {body}
+            }}
+        }}"
+    )
+}
+
+/// Returns the code for a `Value::Struct` named `name`, with
+/// the fields named by identifiers and read from expressions.
+fn write_fields<'a>(
+    name: &str,
+    fields: impl Iterator<Item = (&'a str, String)>,
+) -> String {
+    let fields: String = fields
+        .map(|(ident, expr)| {
+            format!(
+                "(::std::borrow::Cow::Borrowed({:?}), \
+                 ::tson::ToTson::to_tson({expr})),",
+                field_name(ident)
+            )
+        })
+        .collect();
+
+    format!(
+        "::tson::Value::Struct {{
+            name: ::std::borrow::Cow::Borrowed({:?}),
+            fields: ::std::vec![{fields}],
+            non_exhaustive: false,
+        }}",
+        field_name(name)
+    )
+}
+
+/// Returns the code for a `Value::TupleStruct` named `name`,
+/// with fields read from expressions.
+fn write_elements(
+    name: &str,
+    fields: impl Iterator<Item = String>,
+) -> String {
+    let fields: String = fields
+        .map(|expr| format!("::tson::ToTson::to_tson({expr}),"))
+        .collect();
+
+    format!(
+        "::tson::Value::TupleStruct {{
+            name: ::std::borrow::Cow::Borrowed({:?}),
+            fields: ::std::vec![{fields}],
+        }}",
+        field_name(name)
+    )
+}
+
+fn write_unit(name: &str) -> String {
+    format!(
+        "::tson::Value::Ident(::std::borrow::Cow::Borrowed({:?}))",
+        field_name(name)
+    )
+}
@@ -0,0 +1,195 @@
+use tson::{
+    parse_document, parse_value, ConvertError, FromTson, ToTson,
+    Value,
+};
+use tson_derive::{FromTson, ToTson};
+
+#[derive(Debug, PartialEq, FromTson, ToTson)]
+struct Config {
+    name: String,
+    retries: u8,
+    timeout: Option<f64>,
+    servers: Vec<Server>,
+    r#mode: Mode,
+}
+
+#[derive(Debug, PartialEq, FromTson, ToTson)]
+pub struct Server {
+    pub(crate) address: (u8, u8, u8, u8),
+    port: u16,
+    #[allow(dead_code)]
+    weight: Weight,
+}
+
+#[derive(Debug, PartialEq, FromTson, ToTson)]
+struct Weight(f32);
+
+#[derive(Debug, PartialEq, FromTson, ToTson)]
+struct Unit;
+
+#[derive(Debug, PartialEq, FromTson, ToTson)]
+enum Mode {
+    Off,
+    Retry(u32, Option<char>),
+    Backoff { base: u64, factor: f32 },
+}
+
+fn config() -> Config {
+    Config {
+        name: "primary".into(),
+        retries: 3,
+        timeout: None,
+        servers: vec![
+            Server {
+                address: (10, 0, 0, 1),
+                port: 8080,
+                weight: Weight(0.5),
+            },
+            Server {
+                address: (10, 0, 0, 2),
+                port: 8081,
+                weight: Weight(1.5),
+            },
+        ],
+        mode: Mode::Backoff {
+            base: 100,
+            factor: 2.0,
+        },
+    }
+}
+
+fn convert<T: FromTson>(text: &str) -> Result<T, ConvertError> {
+    let (rest, value) = parse_value(text).unwrap();
+    assert_eq!(rest, "", "{text}");
+
+    T::from_tson(&value)
+}
+
+#[test]
+fn converts_debug_output() {
+    for text in
+        [format!("{:?}", config()), format!("{:#?}", config())]
+    {
+        assert_eq!(
+            convert::<Config>(&text),
+            Ok(config()),
+            "{text}"
+        );
+    }
+
+    for mode in [
+        Mode::Off,
+        Mode::Retry(5, Some('x')),
+        Mode::Retry(5, None),
+    ] {
+        assert_eq!(
+            convert::<Mode>(&format!("{mode:?}")),
+            Ok(mode)
+        );
+    }
+    assert_eq!(convert::<Unit>("Unit"), Ok(Unit));
+}
+
+#[test]
+fn converts_back_and_forth() {
+    let value = config().to_tson();
+    assert_eq!(value.to_string(), format!("{:?}", config()));
+    assert_eq!(Config::from_tson(&value), Ok(config()));
+
+    assert_eq!(Mode::Off.to_tson(), Value::Ident("Off".into()));
+    assert_eq!(Unit.to_tson(), Value::Ident("Unit".into()));
+}
+
+#[test]
+fn converts_other_shapes() {
+    assert_eq!(
+        convert::<Server>(
+            r#"{"address": [127, 0, 0, 1], "port": 80, "weight": (1,)}"#
+        ),
+        Ok(Server {
+            address: (127, 0, 0, 1),
+            port: 80,
+            weight: Weight(1.),
+        })
+    );
+    assert_eq!(
+        convert::<Mode>("config::Mode::Retry(1, None)"),
+        Ok(Mode::Retry(1, None))
+    );
+}
+
+#[test]
+fn names_the_field_in_errors() {
+    let error = |text: &str| {
+        convert::<Config>(text).unwrap_err().to_string()
+    };
+
+    assert_eq!(
+        error(
+            r#"Config { name: "a", retries: 1, servers: [] }"#
+        ),
+        "missing field `mode`"
+    );
+    assert_eq!(
+        error(
+            r#"Config { name: "a", retries: 300, servers: [], mode: Off }"#
+        ),
+        "retries: 300 is out of range for u8"
+    );
+    assert_eq!(
+        error(
+            r#"Config {
+                name: "a",
+                retries: 1,
+                servers: [
+                    Server { address: (0, 0, 0, 0), port: 1, weight: Weight(1.0) },
+                    Server { address: (0, 0, 0, 0), weight: Weight(1.0) },
+                ],
+                mode: Off,
+            }"#
+        ),
+        "servers[1]: missing field `port`"
+    );
+    assert_eq!(
+        error(
+            r#"Config { name: "a", retries: 1, servers: [], mode: Backoff { base: 1, factor: "2" } }"#
+        ),
+        "mode.factor: expected f32, found a string"
+    );
+    assert_eq!(
+        error(
+            r#"Config { name: "a", retries: 1, servers: [], mode: On }"#
+        ),
+        "mode: unknown variant `On` of Mode"
+    );
+    assert_eq!(
+        error(
+            r#"Config { name: "a", retries: 1, servers: [], mode: Retry(1) }"#
+        ),
+        "mode: expected 2 elements, found 1"
+    );
+    assert_eq!(error("[1, 2]"), "expected Config, found a list");
+}
+
+#[test]
+fn converts_documents() {
+    let document = parse_document(
+        r#"
+        name = "primary"
+        retries = 3
+        // Read as `None` when left out
+        servers = [
+            Server { address: (10, 0, 0, 1), port: 8080, weight: Weight(0.5) },
+            Server { address: (10, 0, 0, 2), port: 8081, weight: Weight(1.5) },
+        ]
+        mode = Backoff { base: 100, factor: 2.0 }
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(document.convert::<Config>(), Ok(config()));
+    assert_eq!(
+        Config::from_tson(&document.into_value()),
+        Ok(config())
+    );
+}